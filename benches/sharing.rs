@@ -0,0 +1,84 @@
+// Throughput of splitting and reconstructing secrets, over a range of secret
+// sizes and (t, n). Run with `cargo bench --bench sharing`, and again with
+// `--features fast-tables` or `--features parallel` to compare. Passing a
+// filter, as in `cargo bench --bench sharing -- 1MiB`, runs only the
+// benchmarks whose name contains it.
+
+use rust_shamir::{construct_shares_with_rng, reconstruct, Dealer};
+use std::env;
+use std::time::{Duration, Instant};
+
+extern crate rand;
+extern crate rand_chacha;
+extern crate rust_shamir;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+// SIZES are the secret sizes benchmarked, with their names.
+const SIZES: &[(&str, usize)] = &[
+    ("32B", 32),
+    ("1KiB", 1 << 10),
+    ("1MiB", 1 << 20),
+    ("100MiB", 100 << 20),
+];
+
+// PARAMS are the (t, n) benchmarked.
+const PARAMS: &[(u8, u8)] = &[(2, 3), (5, 10), (16, 32), (64, 128)];
+
+// MAX_SHARE_BYTES bounds the total size of the shares of one split, so that
+// large secrets are only benchmarked with few shares.
+const MAX_SHARE_BYTES: usize = 1 << 30;
+
+// bench runs `f` repeatedly for about a second, and at least once, and
+// reports the throughput in secret bytes per second.
+fn bench<F: FnMut()>(name: &str, len: usize, mut f: F) {
+    let start = Instant::now();
+    let mut iterations = 0;
+    while iterations == 0 || start.elapsed() < Duration::from_secs(1) {
+        f();
+        iterations += 1;
+    }
+    let per_iter = start.elapsed() / iterations;
+    let mb_per_sec = len as f64 / per_iter.as_secs_f64() / 1e6;
+    println!(
+        "{:<36} {:>12?}/iter {:>10.2} MB/s",
+        name, per_iter, mb_per_sec
+    );
+}
+
+fn main() {
+    // cargo bench passes --bench; anything else is a filter.
+    let filter = env::args().skip(1).find(|a| !a.starts_with("--"));
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    for &(size_name, len) in SIZES {
+        let secret = vec![0xa5u8; len];
+        for &(t, n) in PARAMS {
+            if len * n as usize > MAX_SHARE_BYTES {
+                continue;
+            }
+            let name = |op: &str| format!("{} {} t={} n={}", op, size_name, t, n);
+            let wanted = |name: &str| filter.as_ref().is_none_or(|f| name.contains(f.as_str()));
+
+            let dealer = name("dealer");
+            if wanted(&dealer) {
+                bench(&dealer, len, || {
+                    Dealer::with_rng(t, &secret, &mut rng).unwrap();
+                });
+            }
+            let split = name("split");
+            if wanted(&split) {
+                bench(&split, len, || {
+                    construct_shares_with_rng(t, n, &secret, &mut rng).unwrap();
+                });
+            }
+            let combine = name("combine");
+            if wanted(&combine) {
+                let shares = construct_shares_with_rng(t, n, &secret, &mut rng).unwrap();
+                bench(&combine, len, || {
+                    reconstruct(&shares[..t as usize]).unwrap();
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,215 @@
+// Timing leakage tests in the style of dudect (Reparaz, Balasch and
+// Verbauwhede, "Dude, is my code constant time?"). Each operation is timed on
+// inputs from two classes, a fixed secret and random secrets, in random
+// order, and Welch's t-test checks whether the two timing distributions
+// differ. Run with
+//
+//   cargo bench --features dudect --bench timing
+//
+// which exits non-zero if any operation leaks. |t| above 10 is taken as
+// evidence of a leak; constant-time code typically stays below 5. Cache-timing
+// leaks, such as those of the fast-tables feature, only show up with a cold
+// or contended cache and aren't expected to be caught here.
+
+use rust_shamir::field::{self, Field};
+use rust_shamir::{
+    combine_fixed, construct_shares_with_rng, reconstruct, reconstruct_ct, split_fixed, Dealer,
+    FixedShare, Gf256, AES_POLYNOMIAL,
+};
+use std::hint::black_box;
+use std::process;
+use std::time::Instant;
+
+extern crate rand;
+extern crate rand_chacha;
+extern crate rust_shamir;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+type Gf = Gf256<AES_POLYNOMIAL>;
+
+// SAMPLES is the number of timings taken of each operation.
+const SAMPLES: usize = 100_000;
+
+// THRESHOLD is the |t| above which an operation is reported as leaking.
+const THRESHOLD: f64 = 10.0;
+
+// welch_t returns Welch's t-statistic for the difference between the means of
+// two samples.
+fn welch_t(a: &[f64], b: &[f64]) -> f64 {
+    let stats = |s: &[f64]| {
+        let n = s.len() as f64;
+        let mean = s.iter().sum::<f64>() / n;
+        let var = s.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0);
+        (mean, var / n)
+    };
+    let (ma, va) = stats(a);
+    let (mb, vb) = stats(b);
+    (ma - mb) / (va + vb).sqrt()
+}
+
+// measure times `run` on SAMPLES inputs made by `input`, which is passed
+// whether to make one of the fixed class, and returns the t-statistic. Inputs
+// are prepared before timing starts so that making them isn't measured.
+fn measure<I, P, F>(rng: &mut ChaCha20Rng, mut input: P, run: F) -> f64
+where
+    P: FnMut(bool, &mut ChaCha20Rng) -> I,
+    F: Fn(&I),
+{
+    let inputs: Vec<(bool, I)> = (0..SAMPLES)
+        .map(|_| {
+            let fixed = rng.gen::<bool>();
+            (fixed, input(fixed, rng))
+        })
+        .collect();
+
+    let (mut fixed_t, mut random_t) = (Vec::new(), Vec::new());
+    for (fixed, i) in inputs.iter() {
+        let start = Instant::now();
+        run(black_box(i));
+        let elapsed = start.elapsed().as_nanos() as f64;
+        if *fixed {
+            fixed_t.push(elapsed);
+        } else {
+            random_t.push(elapsed);
+        }
+    }
+
+    // like dudect, drop the slowest timings, which are dominated by
+    // interrupts and preemption rather than by the code under test.
+    for t in [&mut fixed_t, &mut random_t] {
+        t.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        t.truncate(t.len() * 9 / 10);
+    }
+    welch_t(&fixed_t, &random_t)
+}
+
+// report prints the result for one operation and returns whether it passed.
+fn report(name: &str, t: f64) -> bool {
+    let ok = t.abs() < THRESHOLD;
+    println!(
+        "{:<28} t = {:>8.2}  {}",
+        name,
+        t,
+        if ok { "ok" } else { "LEAK" }
+    );
+    ok
+}
+
+fn random_bytes(rng: &mut ChaCha20Rng, len: usize) -> Vec<u8> {
+    (0..len).map(|_| rng.gen()).collect()
+}
+
+fn main() {
+    let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+    let mut ok = true;
+
+    // the fixed classes are zero and one, the values a shortcut would most
+    // likely special-case.
+    for v in [0, 1] {
+        let t = measure(
+            &mut rng,
+            |fixed, rng| {
+                let a = if fixed { v } else { rng.gen() };
+                (Gf256(a), Gf256(rng.gen::<u8>()))
+            },
+            |(a, b): &(Gf, Gf)| {
+                for _ in 0..64 {
+                    black_box(black_box(*a).mul(*b));
+                }
+            },
+        );
+        ok &= report(&format!("mul({}, _)", v), t);
+
+        let t = measure(
+            &mut rng,
+            |fixed, rng| Gf256(if fixed { v } else { rng.gen::<u8>() }),
+            |a: &Gf| {
+                for _ in 0..16 {
+                    black_box(black_box(*a).inv());
+                }
+            },
+        );
+        ok &= report(&format!("inv({})", v), t);
+    }
+
+    let t = measure(
+        &mut rng,
+        |fixed, rng| {
+            let coeff: Vec<Gf> = (0..16)
+                .map(|_| Gf256(if fixed { 0 } else { rng.gen() }))
+                .collect();
+            coeff
+        },
+        |coeff: &Vec<Gf>| {
+            black_box(field::evaluate(coeff, Gf256(7)));
+        },
+    );
+    ok &= report("evaluate", t);
+
+    let t = measure(
+        &mut rng,
+        |fixed, rng| {
+            let secret = if fixed {
+                vec![0; 32]
+            } else {
+                random_bytes(rng, 32)
+            };
+            Dealer::with_rng(5, &secret, rng).unwrap()
+        },
+        |dealer: &Dealer| {
+            black_box(dealer.issue(3).unwrap());
+        },
+    );
+    ok &= report("Dealer::issue", t);
+
+    let t = measure(
+        &mut rng,
+        |fixed, rng| {
+            let secret = if fixed {
+                vec![0; 32]
+            } else {
+                random_bytes(rng, 32)
+            };
+            construct_shares_with_rng(5, 5, &secret, rng).unwrap()
+        },
+        |shares: &Vec<rust_shamir::Share>| {
+            black_box(reconstruct(shares).unwrap());
+        },
+    );
+    ok &= report("reconstruct", t);
+
+    let t = measure(
+        &mut rng,
+        |fixed, rng| {
+            let secret = if fixed {
+                vec![0; 32]
+            } else {
+                random_bytes(rng, 32)
+            };
+            construct_shares_with_rng(5, 5, &secret, rng).unwrap()
+        },
+        |shares: &Vec<rust_shamir::Share>| {
+            black_box(reconstruct_ct(shares).unwrap());
+        },
+    );
+    ok &= report("reconstruct_ct", t);
+
+    let t = measure(
+        &mut rng,
+        |fixed, rng| {
+            let secret = if fixed { [0; 32] } else { rng.gen() };
+            let shares: [FixedShare<32>; 5] = split_fixed(5, &secret, rng).unwrap();
+            shares
+        },
+        |shares: &[FixedShare<32>; 5]| {
+            black_box(combine_fixed(shares).unwrap());
+        },
+    );
+    ok &= report("combine_fixed", t);
+
+    if !ok {
+        process::exit(1);
+    }
+}
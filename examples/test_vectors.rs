@@ -0,0 +1,49 @@
+// Prints the known-answer test vectors in src/test_vectors.rs, as Rust source.
+// Run with `cargo run --example test_vectors`.
+
+extern crate rust_shamir;
+
+use rust_shamir::test_vectors::generate;
+
+// VECTORS are the secrets, (t, n) and seeds the test vectors are made from.
+const VECTORS: &[(&[u8], u8, u8, [u8; 32])] = &[
+    (b"", 2, 3, [0; 32]),
+    (b"\x00", 1, 1, [0; 32]),
+    (b"\xff", 2, 2, [1; 32]),
+    (b"rust-shamir", 2, 3, [2; 32]),
+    (b"correct horse battery staple", 3, 5, [3; 32]),
+    (
+        b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\
+          \x10\x11\x12\x13\x14\x15\x16\x17\x18\x19\x1a\x1b\x1c\x1d\x1e\x1f",
+        5,
+        8,
+        [4; 32],
+    ),
+];
+
+fn bytes(b: &[u8]) -> String {
+    let hex: Vec<String> = b.iter().map(|b| format!("0x{:02x}", b)).collect();
+    format!("&[{}]", hex.join(", "))
+}
+
+fn main() {
+    println!("pub const TEST_VECTORS: &[TestVector] = &[");
+    for &(secret, t, n, seed) in VECTORS {
+        println!("    TestVector {{");
+        println!("        secret: {},", bytes(secret));
+        println!("        threshold: {},", t);
+        println!("        share_count: {},", n);
+        println!("        seed: [{}; 32],", seed[0]);
+        println!("        shares: &[");
+        for share in generate(t, n, secret, seed).unwrap() {
+            println!(
+                "            ({}, {}),",
+                share.index(),
+                bytes(share.as_bytes())
+            );
+        }
+        println!("        ],");
+        println!("    }},");
+    }
+    println!("];");
+}
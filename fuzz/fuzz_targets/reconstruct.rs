@@ -0,0 +1,61 @@
+// Feeds arbitrary share sets to reconstruct and its variants, which must
+// return an error rather than panic on malformed input, and checks that
+// splitting an arbitrary secret and reconstructing it from any t of the
+// shares gives the secret back.
+//
+//   cargo fuzz run reconstruct
+//
+// The input is read as a header byte choosing t and n, followed by share
+// records of one index byte, one length byte and that many payload bytes.
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rust_shamir::{
+    construct_shares_with_rng, reconstruct, reconstruct_consistent, reconstruct_into,
+    reconstruct_robust, recover_share, Share,
+};
+
+// MAX_SHARES bounds the number of shares parsed from one input, to keep each
+// run fast.
+const MAX_SHARES: usize = 32;
+
+fn parse(mut data: &[u8]) -> Vec<Share> {
+    let mut shares = Vec::new();
+    while data.len() >= 2 && shares.len() < MAX_SHARES {
+        let (x, len) = (data[0], data[1] as usize);
+        let payload = &data[2..std::cmp::min(2 + len, data.len())];
+        shares.push(Share::new(x, payload));
+        data = &data[2 + payload.len()..];
+    }
+    shares
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (header, rest) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let t = (header & 0x0f) + 1;
+
+    // arbitrary, likely malformed, shares.
+    let shares = parse(rest);
+    let _ = reconstruct(&shares);
+    let _ = recover_share(&shares, t);
+    let _ = reconstruct_robust(t, &shares);
+    let _ = reconstruct_consistent(t, &shares);
+    let mut out = [0u8; 64];
+    let _ = reconstruct_into(&shares, &mut out);
+
+    // round trip: the rest of the input as a secret.
+    let n = t + (header >> 4);
+    let mut rng = ChaCha20Rng::from_seed([0; 32]);
+    let shares = construct_shares_with_rng(t, n, rest, &mut rng).unwrap();
+    let start = (n - t) as usize;
+    assert_eq!(reconstruct(&shares[start..]).unwrap(), rest);
+    for share in shares.iter() {
+        let decoded = Share::from_bytes(&share.to_bytes()).unwrap();
+        assert_eq!(decoded.as_bytes(), share.as_bytes());
+    }
+});
@@ -0,0 +1,23 @@
+// Feeds arbitrary bytes to Share::from_bytes, which must never panic, and
+// checks that any share it accepts survives a round trip through to_bytes.
+//
+//   cargo fuzz run share_from_bytes
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rust_shamir::Share;
+
+fuzz_target!(|data: &[u8]| {
+    let share = match Share::from_bytes(data) {
+        Ok(share) => share,
+        Err(_) => return,
+    };
+
+    let encoded = share.to_bytes();
+    let decoded = Share::from_bytes(&encoded).expect("re-encoded share must decode");
+    assert_eq!(decoded.index(), share.index());
+    assert_eq!(decoded.threshold(), share.threshold());
+    assert_eq!(decoded.share_count(), share.share_count());
+    assert_eq!(decoded.as_bytes(), share.as_bytes());
+    assert_eq!(decoded.to_bytes(), encoded);
+});
@@ -0,0 +1,81 @@
+/*
+ * shamir.h: C interface to rust-shamir, built with the ffi feature:
+ *
+ *   cargo rustc --release --lib --features ffi --crate-type cdylib
+ *   cargo rustc --release --lib --features ffi --crate-type staticlib
+ *
+ * which produce target/release/librust_shamir.so (or .dylib, .dll) and
+ * librust_shamir.a. The manifest leaves the crate an rlib, so Rust users
+ * don't build the C libraries too. This header is kept by hand in step with
+ * src/ffi.rs.
+ *
+ * Ownership rules:
+ *
+ *   - The caller owns every input and may free it as soon as a call returns.
+ *   - Every shamir_buffer filled in by the library is owned by the caller
+ *     and must be released with shamir_free, which wipes it first. Never
+ *     pass it to free().
+ *   - On error, no output buffer is left allocated.
+ *
+ * Shares are in rust-shamir's canonical binary format, which carries a
+ * checksum and the threshold, so they can be stored and passed back as is.
+ */
+
+#ifndef RUST_SHAMIR_H
+#define RUST_SHAMIR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SHAMIR_OK (0)
+#define SHAMIR_ERR_NULL_POINTER (-1)
+#define SHAMIR_ERR_INVALID_PARAMETERS (-2)
+#define SHAMIR_ERR_NOT_ENOUGH_SHARES (-3)
+#define SHAMIR_ERR_MALFORMED_SHARE (-4)
+#define SHAMIR_ERR_CHECKSUM_MISMATCH (-5)
+#define SHAMIR_ERR_INCONSISTENT_SHARES (-6)
+#define SHAMIR_ERR_INTERNAL (-7)
+
+typedef struct shamir_buffer {
+    uint8_t *data;
+    size_t len;
+} shamir_buffer;
+
+/*
+ * shamir_split splits the secret_len bytes at secret into n shares, any t of
+ * which reconstruct it, and stores them in shares_out, which must point to
+ * an array of n buffers. secret may be NULL if secret_len is 0. Returns
+ * SHAMIR_OK or a negative error code.
+ */
+int shamir_split(const uint8_t *secret, size_t secret_len, uint8_t t, uint8_t n,
+                 shamir_buffer *shares_out);
+
+/*
+ * shamir_combine reconstructs the secret from the count shares in the array
+ * at shares and stores it in *secret_out. Returns SHAMIR_OK or a negative
+ * error code.
+ */
+int shamir_combine(const shamir_buffer *shares, size_t count,
+                   shamir_buffer *secret_out);
+
+/*
+ * shamir_free wipes and releases a buffer filled in by shamir_split or
+ * shamir_combine, and resets it to { NULL, 0 }. Freeing a NULL pointer or an
+ * empty buffer does nothing.
+ */
+void shamir_free(shamir_buffer *buffer);
+
+/*
+ * shamir_strerror returns a static description of an error code.
+ */
+const char *shamir_strerror(int code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUST_SHAMIR_H */
@@ -0,0 +1,379 @@
+// XChaCha20-Poly1305: ChaCha20 and Poly1305 as combined in RFC 8439, with the
+// 192-bit nonce extension of draft-irtf-cfrg-xchacha, built in-crate like
+// sha256.rs. The long nonce makes random nonces safe, so callers never need
+// to keep a counter. The tests check the ChaCha20, Poly1305 and AEAD vectors
+// of RFC 8439 sections 2.3.2, 2.5.2 and 2.8.2, and the HChaCha20 and
+// XChaCha20-Poly1305 vectors of the draft's sections 2.2.1 and A.3.1.
+
+use crate::ConstantTimeEq;
+use std::convert::TryInto;
+use zeroize::{Zeroize, Zeroizing};
+
+pub const KEY_LEN: usize = 32;
+pub const NONCE_LEN: usize = 24;
+pub const TAG_LEN: usize = 16;
+
+const SIGMA: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+// rounds applies the 20 ChaCha rounds to `s`.
+fn rounds(s: &mut [u32; 16]) {
+    for _ in 0..10 {
+        quarter_round(s, 0, 4, 8, 12);
+        quarter_round(s, 1, 5, 9, 13);
+        quarter_round(s, 2, 6, 10, 14);
+        quarter_round(s, 3, 7, 11, 15);
+        quarter_round(s, 0, 5, 10, 15);
+        quarter_round(s, 1, 6, 11, 12);
+        quarter_round(s, 2, 7, 8, 13);
+        quarter_round(s, 3, 4, 9, 14);
+    }
+}
+
+fn words(bytes: &[u8], out: &mut [u32]) {
+    for (w, b) in out.iter_mut().zip(bytes.chunks_exact(4)) {
+        *w = u32::from_le_bytes(b.try_into().unwrap());
+    }
+}
+
+// init returns the initial state for `key`, with the last four words, the
+// counter and nonce, left zero.
+fn init(key: &[u8; KEY_LEN]) -> [u32; 16] {
+    let mut s = [0u32; 16];
+    s[..4].copy_from_slice(&SIGMA);
+    words(key, &mut s[4..12]);
+    s
+}
+
+// chacha20_block returns the keystream block `counter` of the RFC 8439
+// ChaCha20 with `key` and the 96-bit `nonce`.
+fn chacha20_block(key: &[u8; KEY_LEN], nonce: &[u8; 12], counter: u32) -> [u8; 64] {
+    let mut s = init(key);
+    s[12] = counter;
+    words(nonce, &mut s[13..]);
+    let mut x = s;
+    rounds(&mut x);
+    let mut out = [0u8; 64];
+    for (o, (x, s)) in out.chunks_exact_mut(4).zip(x.iter().zip(s.iter())) {
+        o.copy_from_slice(&x.wrapping_add(*s).to_le_bytes());
+    }
+    s.zeroize();
+    x.zeroize();
+    out
+}
+
+// chacha20_xor xors `data` with the keystream starting at block `counter`.
+fn chacha20_xor(key: &[u8; KEY_LEN], nonce: &[u8; 12], counter: u32, data: &mut [u8]) {
+    for (i, chunk) in data.chunks_mut(64).enumerate() {
+        let mut block = chacha20_block(key, nonce, counter.wrapping_add(i as u32));
+        for (d, k) in chunk.iter_mut().zip(block.iter()) {
+            *d ^= k;
+        }
+        block.zeroize();
+    }
+}
+
+// hchacha20 derives a subkey from `key` and the first 16 bytes of an extended
+// nonce.
+fn hchacha20(key: &[u8; KEY_LEN], nonce: &[u8; 16]) -> Zeroizing<[u8; KEY_LEN]> {
+    let mut s = init(key);
+    words(nonce, &mut s[12..]);
+    rounds(&mut s);
+    let mut out = Zeroizing::new([0u8; KEY_LEN]);
+    for (o, w) in out
+        .chunks_exact_mut(4)
+        .zip(s[..4].iter().chain(s[12..].iter()))
+    {
+        o.copy_from_slice(&w.to_le_bytes());
+    }
+    s.zeroize();
+    out
+}
+
+const M44: u64 = (1 << 44) - 1;
+const M42: u64 = (1 << 42) - 1;
+
+// Poly1305 is the one-time authenticator of RFC 8439 section 2.5, with the
+// accumulator in three 44-bit limbs.
+struct Poly1305 {
+    r: [u64; 3],
+    h: [u64; 3],
+    pad: [u64; 2],
+}
+
+impl Poly1305 {
+    fn new(key: &[u8; 32]) -> Poly1305 {
+        let t0 = u64::from_le_bytes(key[..8].try_into().unwrap());
+        let t1 = u64::from_le_bytes(key[8..16].try_into().unwrap());
+        Poly1305 {
+            r: [
+                t0 & 0xffc_0fff_ffff,
+                ((t0 >> 44) | (t1 << 20)) & 0xfff_ffc0_ffff,
+                (t1 >> 24) & 0x00f_ffff_fc0f,
+            ],
+            h: [0; 3],
+            pad: [
+                u64::from_le_bytes(key[16..24].try_into().unwrap()),
+                u64::from_le_bytes(key[24..].try_into().unwrap()),
+            ],
+        }
+    }
+
+    // block adds a 16-byte block to the accumulator; `hibit` is the 2^128
+    // bit, clear only for a padded final block.
+    fn block(&mut self, m: &[u8; 16], hibit: u64) {
+        let [r0, r1, r2] = self.r;
+        let (s1, s2) = (r1 * 20, r2 * 20);
+        let t0 = u64::from_le_bytes(m[..8].try_into().unwrap());
+        let t1 = u64::from_le_bytes(m[8..].try_into().unwrap());
+        let h0 = self.h[0] + (t0 & M44);
+        let h1 = self.h[1] + (((t0 >> 44) | (t1 << 20)) & M44);
+        let h2 = self.h[2] + (((t1 >> 24) & M42) | (hibit << 40));
+
+        let m = |a: u64, b: u64| a as u128 * b as u128;
+        let d0 = m(h0, r0) + m(h1, s2) + m(h2, s1);
+        let mut d1 = m(h0, r1) + m(h1, r0) + m(h2, s2);
+        let mut d2 = m(h0, r2) + m(h1, r1) + m(h2, r0);
+
+        d1 += d0 >> 44;
+        let mut h0 = d0 as u64 & M44;
+        d2 += d1 >> 44;
+        let mut h1 = d1 as u64 & M44;
+        let h2 = d2 as u64 & M42;
+        h0 += (d2 >> 42) as u64 * 5;
+        h1 += h0 >> 44;
+        h0 &= M44;
+        self.h = [h0, h1, h2];
+    }
+
+    // update adds `data`, zero-padded to a multiple of 16 bytes, as the AEAD
+    // construction of RFC 8439 section 2.8 does with its inputs.
+    fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(16) {
+            let mut block = [0u8; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.block(&block, 1);
+            block.zeroize();
+        }
+    }
+
+    fn finalize(mut self) -> [u8; TAG_LEN] {
+        let [mut h0, mut h1, mut h2] = self.h;
+        for _ in 0..2 {
+            h2 += h1 >> 44;
+            h1 &= M44;
+            h0 += (h2 >> 42) * 5;
+            h2 &= M42;
+            h1 += h0 >> 44;
+            h0 &= M44;
+        }
+
+        // compute h - p = h + 5 - 2^130 and keep it if it doesn't underflow.
+        let mut g0 = h0 + 5;
+        let mut g1 = h1 + (g0 >> 44);
+        g0 &= M44;
+        let g2 = (h2 + (g1 >> 44)).wrapping_sub(1 << 42);
+        g1 &= M44;
+        let keep_g = (g2 >> 63).wrapping_sub(1);
+        h0 = (h0 & !keep_g) | (g0 & keep_g);
+        h1 = (h1 & !keep_g) | (g1 & keep_g);
+        h2 = (h2 & !keep_g) | (g2 & keep_g);
+
+        let [p0, p1] = self.pad;
+        h0 += p0 & M44;
+        h1 += (((p0 >> 44) | (p1 << 20)) & M44) + (h0 >> 44);
+        h0 &= M44;
+        h2 += ((p1 >> 24) & M42) + (h1 >> 44);
+        h1 &= M44;
+
+        let lo = h0 | (h1 << 44);
+        let hi = (h1 >> 20) | (h2 << 24);
+        let mut tag = [0u8; TAG_LEN];
+        tag[..8].copy_from_slice(&lo.to_le_bytes());
+        tag[8..].copy_from_slice(&hi.to_le_bytes());
+        self.r.zeroize();
+        self.h.zeroize();
+        self.pad.zeroize();
+        tag
+    }
+}
+
+// poly1305 returns the Poly1305 tag of `msg` under the one-time `key`.
+#[cfg(test)]
+fn poly1305(key: &[u8; 32], msg: &[u8]) -> [u8; TAG_LEN] {
+    let mut p = Poly1305::new(key);
+    let mut chunks = msg.chunks_exact(16);
+    for c in &mut chunks {
+        p.block(c.try_into().unwrap(), 1);
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        let mut block = [0u8; 16];
+        block[..rest.len()].copy_from_slice(rest);
+        block[rest.len()] = 1;
+        p.block(&block, 0);
+    }
+    p.finalize()
+}
+
+// tag computes the AEAD tag over `aad` and `ciphertext`.
+fn tag(key: &[u8; KEY_LEN], nonce: &[u8; 12], aad: &[u8], ciphertext: &[u8]) -> [u8; TAG_LEN] {
+    let mut block = chacha20_block(key, nonce, 0);
+    let mut p = Poly1305::new(block[..32].try_into().unwrap());
+    block.zeroize();
+    p.update_padded(aad);
+    p.update_padded(ciphertext);
+    let mut lengths = [0u8; 16];
+    lengths[..8].copy_from_slice(&(aad.len() as u64).to_le_bytes());
+    lengths[8..].copy_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+    p.block(&lengths, 1);
+    p.finalize()
+}
+
+// chacha20poly1305_seal encrypts `data` in place with the RFC 8439 AEAD and
+// returns its tag.
+fn chacha20poly1305_seal(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8; 12],
+    aad: &[u8],
+    data: &mut [u8],
+) -> [u8; TAG_LEN] {
+    chacha20_xor(key, nonce, 1, data);
+    tag(key, nonce, aad, data)
+}
+
+// chacha20poly1305_open checks `expected` and decrypts `data` in place,
+// returning false, with `data` untouched, if the tag doesn't match.
+fn chacha20poly1305_open(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8; 12],
+    aad: &[u8],
+    data: &mut [u8],
+    expected: &[u8],
+) -> bool {
+    if !tag(key, nonce, aad, data)[..].ct_eq(expected) {
+        return false;
+    }
+    chacha20_xor(key, nonce, 1, data);
+    true
+}
+
+// xchacha_subkey returns the ChaCha20 key and nonce for an extended nonce.
+fn xchacha_subkey(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8; NONCE_LEN],
+) -> (Zeroizing<[u8; KEY_LEN]>, [u8; 12]) {
+    let subkey = hchacha20(key, nonce[..16].try_into().unwrap());
+    let mut n = [0u8; 12];
+    n[4..].copy_from_slice(&nonce[16..]);
+    (subkey, n)
+}
+
+// seal encrypts `plaintext` with XChaCha20-Poly1305, returning the ciphertext
+// followed by the tag.
+pub fn seal(key: &[u8; KEY_LEN], nonce: &[u8; NONCE_LEN], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let (subkey, n) = xchacha_subkey(key, nonce);
+    let mut out = Vec::with_capacity(plaintext.len() + TAG_LEN);
+    out.extend_from_slice(plaintext);
+    let tag = chacha20poly1305_seal(&subkey, &n, aad, &mut out);
+    out.extend_from_slice(&tag);
+    out
+}
+
+// open decrypts the output of seal, returning None if it is too short or
+// fails authentication.
+pub fn open(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8; NONCE_LEN],
+    aad: &[u8],
+    sealed: &[u8],
+) -> Option<Zeroizing<Vec<u8>>> {
+    let len = sealed.len().checked_sub(TAG_LEN)?;
+    let (subkey, n) = xchacha_subkey(key, nonce);
+    let mut out = Zeroizing::new(sealed[..len].to_vec());
+    if chacha20poly1305_open(&subkey, &n, aad, &mut out, &sealed[len..]) {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::hex_decode;
+
+    fn hex<const N: usize>(s: &str) -> [u8; N] {
+        hex_decode(s).unwrap()[..].try_into().unwrap()
+    }
+
+    const SUNSCREEN: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+
+    #[test]
+    fn test_rfc8439_vectors() {
+        // section 2.3.2
+        let key: [u8; 32] = hex("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f");
+        let block = chacha20_block(&key, &hex("000000090000004a00000000"), 1);
+        assert_eq!(
+            block[..],
+            hex_decode("10f1e7e4d13b5915500fdd1fa32071c4c7d1f4c733c068030422aa9ac3d46c4ed2826446079faa0914c2d705d98b02a2b5129cd1de164eb9cbd083e8a2503c4e").unwrap()[..]
+        );
+
+        // section 2.5.2
+        let key = hex("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b");
+        assert_eq!(
+            poly1305(&key, b"Cryptographic Forum Research Group"),
+            hex("a8061dc1305136c6c22b8baf0c0127a9")
+        );
+
+        // section 2.8.2
+        let key = hex("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f");
+        let nonce = hex("070000004041424344454647");
+        let aad = hex::<12>("50515253c0c1c2c3c4c5c6c7");
+        let mut data = SUNSCREEN.to_vec();
+        let tag = chacha20poly1305_seal(&key, &nonce, &aad, &mut data);
+        assert_eq!(tag, hex("1ae10b594f09e26a7e902ecbd0600691"));
+        assert_eq!(data[..16], hex::<16>("d31a8d34648e60db7b86afbc53ef7ec2"));
+        assert!(chacha20poly1305_open(&key, &nonce, &aad, &mut data, &tag));
+        assert_eq!(data, SUNSCREEN);
+    }
+
+    #[test]
+    fn test_xchacha_vectors() {
+        // draft-irtf-cfrg-xchacha-03, sections 2.2.1 and A.3.1
+        let key = hex("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f");
+        assert_eq!(
+            *hchacha20(&key, &hex("000000090000004a0000000031415927")),
+            hex::<32>("82413b4227b27bfed30e42508a877d73a0f9e4d58a74a853c12ec41326d3ecdc")
+        );
+
+        let key = hex("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f");
+        let nonce = hex("404142434445464748494a4b4c4d4e4f5051525354555657");
+        let aad = hex::<12>("50515253c0c1c2c3c4c5c6c7");
+        let sealed = seal(&key, &nonce, &aad, SUNSCREEN);
+        assert_eq!(sealed.len(), SUNSCREEN.len() + TAG_LEN);
+        assert_eq!(
+            sealed[SUNSCREEN.len()..],
+            hex::<16>("c0875924c1c7987947deafd8780acf49")
+        );
+        assert_eq!(*open(&key, &nonce, &aad, &sealed).unwrap(), SUNSCREEN);
+
+        for i in [0, SUNSCREEN.len(), sealed.len() - 1] {
+            let mut bad = sealed.clone();
+            bad[i] ^= 1;
+            assert!(open(&key, &nonce, &aad, &bad).is_none());
+        }
+        assert!(open(&key, &nonce, b"", &sealed).is_none());
+        assert!(open(&key, &nonce, &aad, &sealed[..TAG_LEN - 1]).is_none());
+    }
+}
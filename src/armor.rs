@@ -0,0 +1,324 @@
+// PEM-style ASCII armor for a Share, so shares can be kept in the same files
+// and tooling as certificates and keys:
+//
+//   -----BEGIN SHAMIR SHARE-----
+//   Version: 1
+//   Share-Index: 3
+//   Threshold: 2
+//   Created: 2026-10-16T09:30:00Z
+//   Label: offsite backup
+//
+//   U0hNUgICAwMAAAAH...
+//   -----END SHAMIR SHARE-----
+//
+// The body is the canonical binary format of format.rs in base64, wrapped at
+// 64 characters as in RFC 7468, so it carries its own checksum and metadata.
+// The headers repeat some of it for people reading the file; Share-Index and
+// Threshold must agree with the body. Threshold, Created and Label are
+// optional, and unknown headers are ignored so later versions can add some.
+// Text outside the armor, including other PEM blocks, is ignored.
+
+use crate::text::{base64_decode, base64_encode};
+use crate::{SecretSharingError, Share};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+const BEGIN: &str = "-----BEGIN SHAMIR SHARE-----";
+const END: &str = "-----END SHAMIR SHARE-----";
+const VERSION: u8 = 1;
+const LINE_LEN: usize = 64;
+
+// ArmoredShare is a share with the optional headers of its armor.
+#[derive(Clone, Debug)]
+pub struct ArmoredShare {
+    pub share: Share,
+    pub created: Option<SystemTime>,
+    pub label: Option<String>,
+}
+
+impl ArmoredShare {
+    // new armors `share`, stamped with the current time and labelled with
+    // the share's own label, if it has one.
+    pub fn new(share: Share) -> ArmoredShare {
+        ArmoredShare {
+            label: share.label().map(str::to_string),
+            share,
+            created: Some(SystemTime::now()),
+        }
+    }
+
+    // with_label sets the label, which must fit on the header line.
+    pub fn with_label(mut self, label: &str) -> Result<ArmoredShare, SecretSharingError> {
+        if label.chars().any(char::is_control) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        self.label = Some(label.trim().to_string());
+        Ok(self)
+    }
+
+    // parse_all returns every armored share in `s`, in order, such as a file
+    // of shares or a bundle that also holds certificates.
+    pub fn parse_all(s: &str) -> Result<Vec<ArmoredShare>, SecretSharingError> {
+        let mut out = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find(BEGIN) {
+            let block = &rest[start..];
+            let end = block.find(END).ok_or(SecretSharingError::ShareTruncated)?;
+            out.push(parse_block(&block[BEGIN.len()..end])?);
+            rest = &block[end + END.len()..];
+        }
+        Ok(out)
+    }
+}
+
+// parse_block parses the headers and body between the BEGIN and END lines.
+fn parse_block(block: &str) -> Result<ArmoredShare, SecretSharingError> {
+    let mut lines = block.lines().map(str::trim).skip_while(|l| l.is_empty());
+    let (mut version, mut index, mut threshold, mut created, mut label) =
+        (None, None, None, None, None);
+    for line in lines.by_ref() {
+        if line.is_empty() {
+            break;
+        }
+        let (key, value) = line
+            .split_once(':')
+            .ok_or(SecretSharingError::ShareMalformed)?;
+        let value = value.trim();
+        let slot = match key {
+            "Version" => &mut version,
+            "Share-Index" => &mut index,
+            "Threshold" => &mut threshold,
+            "Created" => &mut created,
+            "Label" => &mut label,
+            _ => continue,
+        };
+        if slot.replace(value).is_some() {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+    }
+
+    let number = |v: &str| {
+        v.parse::<u8>()
+            .map_err(|_| SecretSharingError::ShareMalformed)
+    };
+    match version.map(number).transpose()? {
+        Some(VERSION) => {}
+        Some(v) => return Err(SecretSharingError::UnsupportedShareVersion(v)),
+        None => return Err(SecretSharingError::ShareMalformed),
+    }
+
+    let body: Zeroizing<String> = Zeroizing::new(lines.collect());
+    let share = Share::from_bytes(&base64_decode(&body)?)?;
+    if index.map(number).transpose()? != Some(share.index()) {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    if let Some(t) = threshold.map(number).transpose()? {
+        if share.threshold() != Some(t) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+    }
+    Ok(ArmoredShare {
+        share,
+        created: created.map(parse_time).transpose()?,
+        label: label.map(str::to_string),
+    })
+}
+
+// civil_from_days converts days since 1970-01-01 to a (year, month, day)
+// date in the proleptic Gregorian calendar.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + (m <= 2) as u64, m, d)
+}
+
+// days_from_civil is the inverse of civil_from_days.
+fn days_from_civil(y: u64, m: u64, d: u64) -> u64 {
+    let y = y - (m <= 2) as u64;
+    let era = y / 400;
+    let yoe = y % 400;
+    let mp = if m > 2 { m - 3 } else { m + 9 };
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// format_time formats `t` as an RFC 3339 UTC timestamp to the second.
+pub(crate) fn format_time(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (y, m, d) = civil_from_days(secs / 86_400);
+    let s = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        y,
+        m,
+        d,
+        s / 3600,
+        s / 60 % 60,
+        s % 60
+    )
+}
+
+// parse_time parses a timestamp written by format_time.
+pub(crate) fn parse_time(s: &str) -> Result<SystemTime, SecretSharingError> {
+    let b = s.as_bytes();
+    let field = |r: std::ops::Range<usize>| {
+        s.get(r)
+            .filter(|f| f.bytes().all(|c| c.is_ascii_digit()))
+            .and_then(|f| f.parse::<u64>().ok())
+            .ok_or(SecretSharingError::ShareMalformed)
+    };
+    if b.len() != 20 || b[4] != b'-' || b[7] != b'-' || b[10] != b'T' || b[19] != b'Z' {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    let (y, m, d) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hh, mm, ss) = (field(11..13)?, field(14..16)?, field(17..19)?);
+    if y < 1970 || !(1..=12).contains(&m) || !(1..=31).contains(&d) || hh > 23 || mm > 59 || ss > 59
+    {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    let days = days_from_civil(y, m, d);
+    if civil_from_days(days) != (y, m, d) {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    Ok(UNIX_EPOCH + Duration::from_secs(days * 86_400 + hh * 3600 + mm * 60 + ss))
+}
+
+impl fmt::Display for ArmoredShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", BEGIN)?;
+        writeln!(f, "Version: {}", VERSION)?;
+        writeln!(f, "Share-Index: {}", self.share.index())?;
+        if let Some(t) = self.share.threshold() {
+            writeln!(f, "Threshold: {}", t)?;
+        }
+        if let Some(created) = self.created {
+            writeln!(f, "Created: {}", format_time(created))?;
+        }
+        if let Some(label) = &self.label {
+            writeln!(f, "Label: {}", label)?;
+        }
+        writeln!(f)?;
+        let body = Zeroizing::new(base64_encode(&Zeroizing::new(self.share.to_bytes())));
+        for line in body.as_bytes().chunks(LINE_LEN) {
+            // base64 is ASCII, so every chunk is valid UTF-8.
+            writeln!(f, "{}", std::str::from_utf8(line).map_err(|_| fmt::Error)?)?;
+        }
+        writeln!(f, "{}", END)
+    }
+}
+
+impl FromStr for ArmoredShare {
+    type Err = SecretSharingError;
+
+    // from_str parses the first armored share in `s`.
+    fn from_str(s: &str) -> Result<ArmoredShare, SecretSharingError> {
+        let start = s.find(BEGIN).ok_or(SecretSharingError::ShareMalformed)?;
+        let block = &s[start + BEGIN.len()..];
+        let end = block.find(END).ok_or(SecretSharingError::ShareTruncated)?;
+        parse_block(&block[..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_time() {
+        for &(secs, s) in &[
+            (0, "1970-01-01T00:00:00Z"),
+            (951_825_600, "2000-02-29T12:00:00Z"),
+            (1_792_143_000, "2026-10-16T09:30:00Z"),
+        ] {
+            let t = UNIX_EPOCH + Duration::from_secs(secs);
+            assert_eq!(format_time(t), s);
+            assert_eq!(parse_time(s).unwrap(), t);
+        }
+        for s in &[
+            "2026-02-30T00:00:00Z",
+            "2026-10-16 09:30:00Z",
+            "1969-12-31T23:59:59Z",
+        ] {
+            assert!(parse_time(s).is_err(), "{}", s);
+        }
+    }
+    #[test]
+    fn test_armor_roundtrip() {
+        let shares = construct_shares(2, 3, &[0x5a; 100]).unwrap();
+        let armored = ArmoredShare::new(shares[2].clone())
+            .with_label("offsite backup")
+            .unwrap();
+        let text = armored.to_string();
+        assert!(text.starts_with("-----BEGIN SHAMIR SHARE-----\nVersion: 1\nShare-Index: 3\n"));
+        assert!(text.lines().all(|l| l.len() <= LINE_LEN));
+
+        let parsed: ArmoredShare = format!(
+            "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n{}",
+            text
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(parsed.share.as_bytes(), shares[2].as_bytes());
+        assert_eq!(parsed.share.threshold(), Some(2));
+        assert_eq!(parsed.label.as_deref(), Some("offsite backup"));
+        assert_eq!(
+            parsed.created.map(format_time),
+            armored.created.map(format_time)
+        );
+
+        let bundle: String = shares
+            .iter()
+            .map(|s| ArmoredShare::new(s.clone()).to_string())
+            .collect();
+        let all = ArmoredShare::parse_all(&bundle).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[1].share.index(), 2);
+    }
+    #[test]
+    fn test_armor_errors() {
+        let text = ArmoredShare::new(Share::with_metadata(3, b"key", 2, 3)).to_string();
+        for (from, to, err) in [
+            (
+                "Share-Index: 3",
+                "Share-Index: 4",
+                SecretSharingError::ShareMalformed,
+            ),
+            (
+                "Threshold: 2",
+                "Threshold: 3",
+                SecretSharingError::ShareMalformed,
+            ),
+            (
+                "Version: 1",
+                "Version: 2",
+                SecretSharingError::UnsupportedShareVersion(2),
+            ),
+            (
+                "Version: 1",
+                "Version: 1\nVersion: 1",
+                SecretSharingError::ShareMalformed,
+            ),
+            (END, "", SecretSharingError::ShareTruncated),
+        ] {
+            assert_eq!(
+                text.replacen(from, to, 1).parse::<ArmoredShare>().err(),
+                Some(err),
+                "{}",
+                to
+            );
+        }
+        assert!(ArmoredShare::new(Share::new(1, b"key"))
+            .with_label("two\nlines")
+            .is_err());
+    }
+}
@@ -0,0 +1,201 @@
+// HMAC-authenticated shares. Each share is tagged with HMAC-SHA256 over its
+// canonical encoding, under a key derived from a master key and the share
+// index, so that tampering with a share at rest or in transit is detected
+// before it is combined. The master key is typically held by the dealer or
+// shared among the participants; it does not need to be secret from the
+// shareholders, only from whoever might tamper with the shares.
+//
+// The tag covers the share encoding exactly as it was tagged. A share decoded
+// with from_bytes keeps the bytes it was decoded from, and verifies and
+// re-encodes those, so that tags made before a change to the share format
+// stay valid.
+
+use crate::{kdf, reconstruct, ConstantTimeEq, SecretSharingError, Share, Shares};
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
+
+const MAC_INFO: &[u8] = b"rust-shamir share mac v1";
+pub const TAG_LEN: usize = 32;
+
+// AuthenticatedShare is a share together with its authentication tag.
+#[derive(Clone)]
+pub struct AuthenticatedShare {
+    share: Share,
+    // encoded is the encoding of share that the tag covers.
+    encoded: Zeroizing<Vec<u8>>,
+    tag: [u8; TAG_LEN],
+}
+
+// share_mac_key derives the MAC key for share `x` from the master `key`.
+fn share_mac_key(key: &[u8], x: u8) -> [u8; 32] {
+    let mut prk = kdf::hkdf_extract(&[], key);
+    let mut info = MAC_INFO.to_vec();
+    info.push(x);
+    let mut out = [0u8; 32];
+    kdf::hkdf_expand(&prk, &info, &mut out);
+    prk.zeroize();
+    out
+}
+
+// tag computes the tag of share `x`, encoded as `encoded`.
+fn tag(x: u8, encoded: &[u8], key: &[u8]) -> [u8; TAG_LEN] {
+    let mut mac_key = share_mac_key(key, x);
+    let tag = kdf::hmac(&mac_key, encoded);
+    mac_key.zeroize();
+    tag
+}
+
+impl fmt::Debug for AuthenticatedShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AuthenticatedShare")
+            .field("share", &self.share)
+            .finish()
+    }
+}
+
+impl AuthenticatedShare {
+    // new tags `share` under the master `key`.
+    pub fn new(share: Share, key: &[u8]) -> AuthenticatedShare {
+        let encoded = Zeroizing::new(share.to_bytes());
+        let tag = tag(share.x, &encoded, key);
+        AuthenticatedShare {
+            share,
+            encoded,
+            tag,
+        }
+    }
+
+    // from_parts reassembles an authenticated share from a share and a tag,
+    // e.g. after loading both from storage. It is not verified until `verify`
+    // is called. The tag must cover the share's current encoding; a share
+    // tagged in an older format must be decoded from its bytes with
+    // from_bytes instead.
+    pub fn from_parts(share: Share, tag: [u8; TAG_LEN]) -> AuthenticatedShare {
+        AuthenticatedShare {
+            encoded: Zeroizing::new(share.to_bytes()),
+            share,
+            tag,
+        }
+    }
+
+    pub fn tag(&self) -> &[u8; TAG_LEN] {
+        &self.tag
+    }
+
+    // to_bytes encodes the share in the binary wire format it was tagged in,
+    // followed by the tag.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.encoded.to_vec();
+        out.extend_from_slice(&self.tag);
+        out
+    }
+
+    // from_bytes decodes an authenticated share encoded with to_bytes. It is
+    // not verified until `verify` is called.
+    pub fn from_bytes(data: &[u8]) -> Result<AuthenticatedShare, SecretSharingError> {
+        if data.len() < TAG_LEN {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        let (share, tag) = data.split_at(data.len() - TAG_LEN);
+        let mut t = [0u8; TAG_LEN];
+        t.copy_from_slice(tag);
+        Ok(AuthenticatedShare {
+            share: Share::from_bytes(share)?,
+            encoded: Zeroizing::new(share.to_vec()),
+            tag: t,
+        })
+    }
+
+    // verify checks the tag under the master `key`, returning the share if it
+    // is authentic and ShareTampered otherwise.
+    pub fn verify(&self, key: &[u8]) -> Result<&Share, SecretSharingError> {
+        if !tag(self.share.x, &self.encoded, key).ct_eq(&self.tag) {
+            return Err(SecretSharingError::ShareTampered {
+                index: self.share.x,
+            });
+        }
+        Ok(&self.share)
+    }
+}
+
+// authenticate_shares tags every one of `shares` under the master `key`.
+pub fn authenticate_shares(shares: Shares, key: &[u8]) -> Vec<AuthenticatedShare> {
+    shares
+        .into_iter()
+        .map(|share| AuthenticatedShare::new(share, key))
+        .collect()
+}
+
+// reconstruct_authenticated verifies every share under the master `key` and
+// reconstructs the secret only if all of them are authentic.
+pub fn reconstruct_authenticated(
+    shares: &[AuthenticatedShare],
+    key: &[u8],
+) -> Result<Vec<u8>, SecretSharingError> {
+    let verified = shares
+        .iter()
+        .map(|share| share.verify(key).cloned())
+        .collect::<Result<Shares, SecretSharingError>>()?;
+    reconstruct(&verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_authenticated_roundtrip() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let shares = authenticate_shares(construct_shares(2, 3, &secret).unwrap(), b"key");
+        for share in shares.iter() {
+            let decoded = AuthenticatedShare::from_bytes(&share.to_bytes()).unwrap();
+            assert_eq!(decoded.verify(b"key").unwrap().index(), share.share.index());
+        }
+        assert_eq!(
+            reconstruct_authenticated(&shares[1..], b"key").unwrap(),
+            secret
+        );
+        assert_eq!(
+            reconstruct_authenticated(&shares, b"other key").err(),
+            Some(SecretSharingError::ShareTampered { index: 1 })
+        );
+    }
+    #[test]
+    fn test_tampered() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let mut shares = authenticate_shares(construct_shares(2, 3, &secret).unwrap(), b"key");
+        let mut share = shares[1].share.clone();
+        share.y[0] ^= 0x01;
+        shares[1] = AuthenticatedShare::from_parts(share, shares[1].tag);
+        assert_eq!(
+            reconstruct_authenticated(&shares, b"key").err(),
+            Some(SecretSharingError::ShareTampered { index: 2 })
+        );
+
+        // moving a tag onto another share's data is also detected.
+        let moved = AuthenticatedShare::from_parts(shares[2].share.clone(), shares[0].tag);
+        assert_eq!(
+            moved.verify(b"key").err(),
+            Some(SecretSharingError::ShareTampered { index: 3 })
+        );
+    }
+    #[test]
+    fn test_older_format() {
+        // a share tagged in version 2 of the binary format, before split
+        // identifiers and labels were added to it.
+        let mut encoded = b"SHMR\x02\x02\x03\x01\x00\x00\x00\x02\xaa\xbb".to_vec();
+        let sum = crate::sha256::digest(&encoded);
+        encoded.extend_from_slice(&sum[..4]);
+        let mut bytes = encoded.clone();
+        bytes.extend_from_slice(&tag(1, &encoded, b"key"));
+
+        let share = AuthenticatedShare::from_bytes(&bytes).unwrap();
+        assert_eq!(share.verify(b"key").unwrap().as_bytes(), [0xaa, 0xbb]);
+        assert_eq!(share.to_bytes(), bytes);
+        assert_eq!(
+            share.verify(b"other key").err(),
+            Some(SecretSharingError::ShareTampered { index: 1 })
+        );
+    }
+}
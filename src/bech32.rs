@@ -0,0 +1,261 @@
+// Bech32m encoding of a Share (BIP-350), for shares that are read aloud or
+// typed in by hand. The alphabet leaves out the easily confused 1, b, i and
+// o, the encoding is case-insensitive, and its BCH checksum is guaranteed to
+// detect up to 4 mistyped characters in strings of up to 89 characters, and
+// any other error with probability 1 - 2^-30. A single mistyped character is
+// also located, so the error can say which one to correct:
+//
+//   shamir1<data><checksum>
+//
+// where data is the canonical binary format of format.rs in 5-bit groups.
+// Shares of secrets longer than about 30 bytes exceed the 89 characters the
+// guarantee covers, but are still protected by the probabilistic bound and by
+// the SHA-256 checksum of the binary format.
+
+use crate::{SecretSharingError, Share};
+use zeroize::Zeroizing;
+
+// HRP is the human-readable part, which ends in the separator "1".
+const HRP: &str = "shamir";
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const CHECKSUM_LEN: usize = 6;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+fn polymod(values: &[u8]) -> u32 {
+    const GEN: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk = 1u32;
+    for &v in values {
+        let top = chk >> 25;
+        chk = (chk & 0x1ff_ffff) << 5 ^ v as u32;
+        for (i, g) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut out: Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+    out.push(0);
+    out.extend(hrp.bytes().map(|c| c & 31));
+    out
+}
+
+// verify returns whether `data`, including its checksum, is valid under `hrp`.
+fn verify(hrp: &str, data: &[u8]) -> bool {
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    polymod(&values) == BECH32M_CONST
+}
+
+fn checksum(hrp: &str, data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0; CHECKSUM_LEN]);
+    let p = polymod(&values) ^ BECH32M_CONST;
+    let mut out = [0u8; CHECKSUM_LEN];
+    for (i, c) in out.iter_mut().enumerate() {
+        *c = (p >> (5 * (5 - i)) & 31) as u8;
+    }
+    out
+}
+
+// to_5bit regroups bytes into 5-bit groups, padding the last with zeros.
+fn to_5bit(data: &[u8]) -> Zeroizing<Vec<u8>> {
+    let mut out = Zeroizing::new(Vec::with_capacity((data.len() * 8).div_ceil(5)));
+    let (mut acc, mut bits) = (0u32, 0);
+    for &b in data {
+        acc = (acc << 8 | b as u32) & 0xfff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push((acc >> bits & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        out.push((acc << (5 - bits) & 31) as u8);
+    }
+    out
+}
+
+// from_5bit regroups 5-bit groups into bytes, rejecting padding that isn't
+// zero or is a whole group long.
+fn from_5bit(data: &[u8]) -> Result<Zeroizing<Vec<u8>>, SecretSharingError> {
+    let mut out = Zeroizing::new(Vec::with_capacity(data.len() * 5 / 8));
+    let (mut acc, mut bits) = (0u32, 0);
+    for &v in data {
+        acc = (acc << 5 | v as u32) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || acc & ((1 << bits) - 1) != 0 {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    Ok(out)
+}
+
+// locate_typo returns the position in `data` of the single character that,
+// changed, makes the checksum valid, if there is exactly one.
+fn locate_typo(hrp: &str, data: &mut [u8]) -> Option<usize> {
+    let mut found = None;
+    for i in 0..data.len() {
+        let orig = data[i];
+        for v in 0..32 {
+            if v == orig {
+                continue;
+            }
+            data[i] = v;
+            if verify(hrp, data) {
+                if found.is_some() {
+                    data[i] = orig;
+                    return None;
+                }
+                found = Some(i);
+            }
+        }
+        data[i] = orig;
+    }
+    found
+}
+
+impl Share {
+    // to_bech32 encodes the share as a bech32m string, for transcription by
+    // hand.
+    pub fn to_bech32(&self) -> String {
+        let data = to_5bit(&Zeroizing::new(self.to_bytes()));
+        let sum = checksum(HRP, &data);
+        let mut out = String::with_capacity(HRP.len() + 1 + data.len() + CHECKSUM_LEN);
+        out.push_str(HRP);
+        out.push('1');
+        out.extend(
+            data.iter()
+                .chain(sum.iter())
+                .map(|&v| CHARSET[v as usize] as char),
+        );
+        out
+    }
+
+    // from_bech32 decodes a share encoded by to_bech32, in either case. A
+    // single mistyped or invalid character is reported as ShareTypo, with its
+    // position in `s` counting from 0.
+    pub fn from_bech32(s: &str) -> Result<Share, SecretSharingError> {
+        if s.bytes().any(|c| c.is_ascii_lowercase()) && s.bytes().any(|c| c.is_ascii_uppercase()) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let lower = Zeroizing::new(s.to_ascii_lowercase());
+        let sep = HRP.len();
+        if lower.len() < sep + 1 + CHECKSUM_LEN
+            || !lower.starts_with(HRP)
+            || lower.as_bytes()[sep] != b'1'
+        {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+
+        let start = sep + 1;
+        let mut data = Zeroizing::new(Vec::with_capacity(lower.len() - start));
+        let mut invalid = Vec::new();
+        for (i, c) in lower.bytes().enumerate().skip(start) {
+            match CHARSET.iter().position(|&x| x == c) {
+                Some(v) => data.push(v as u8),
+                None => {
+                    invalid.push(i);
+                    data.push(0);
+                }
+            }
+        }
+        match invalid[..] {
+            [] => {}
+            [i] => return Err(SecretSharingError::ShareTypo { position: i }),
+            _ => return Err(SecretSharingError::ShareMalformed),
+        }
+
+        if !verify(HRP, &data) {
+            return Err(match locate_typo(HRP, &mut data) {
+                Some(i) => SecretSharingError::ShareTypo {
+                    position: start + i,
+                },
+                None => SecretSharingError::ShareChecksumMismatch,
+            });
+        }
+        let bytes = from_5bit(&data[..data.len() - CHECKSUM_LEN])?;
+        Share::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_bech32m_vectors() {
+        // valid bech32m strings from BIP-350.
+        for s in &[
+            "a1lqfn3a",
+            "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx",
+            "split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+        ] {
+            let sep = s.rfind('1').unwrap();
+            let data: Vec<u8> = s[sep + 1..]
+                .bytes()
+                .map(|c| CHARSET.iter().position(|&x| x == c).unwrap() as u8)
+                .collect();
+            assert!(verify(&s[..sep], &data), "{}", s);
+            let n = data.len() - CHECKSUM_LEN;
+            assert_eq!(checksum(&s[..sep], &data[..n]), data[n..]);
+        }
+    }
+    #[test]
+    fn test_bech32_roundtrip() {
+        for share in construct_shares(2, 3, b"hunter2").unwrap() {
+            let encoded = share.to_bech32();
+            assert!(encoded.starts_with("shamir1"));
+            for s in &[encoded.clone(), encoded.to_uppercase()] {
+                let decoded = Share::from_bech32(s).unwrap();
+                assert_eq!(decoded.index(), share.index());
+                assert_eq!(decoded.threshold(), Some(2));
+                assert_eq!(decoded.as_bytes(), share.as_bytes());
+            }
+        }
+    }
+    #[test]
+    fn test_bech32_typos() {
+        let encoded = Share::with_metadata(1, b"key", 2, 3).to_bech32();
+        let at = |i: usize, c: char| {
+            let mut s = encoded.clone();
+            s.replace_range(i..i + 1, &c.to_string());
+            s
+        };
+        let other = |i: usize| if &encoded[i..i + 1] == "q" { 'p' } else { 'q' };
+
+        for &i in &[7, 20, encoded.len() - 1] {
+            assert_eq!(
+                Share::from_bech32(&at(i, other(i))).err(),
+                Some(SecretSharingError::ShareTypo { position: i })
+            );
+        }
+        // b isn't in the alphabet.
+        assert_eq!(
+            Share::from_bech32(&at(12, 'b')).err(),
+            Some(SecretSharingError::ShareTypo { position: 12 })
+        );
+        let mut two = at(9, other(9));
+        two.replace_range(30..31, &other(30).to_string());
+        assert!(Share::from_bech32(&two).is_err());
+        assert_eq!(
+            Share::from_bech32(&encoded.replacen("shamir", "Shamir", 1)).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+    }
+}
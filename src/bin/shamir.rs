@@ -0,0 +1,628 @@
+// shamir is a command-line tool for splitting a secret file into shares and
+// combining shares back into the secret, for people who don't write Rust.
+// Build it with `cargo build --features cli --bin shamir`.
+//
+//   shamir split -t 3 -n 5 secret.bin -o shares/
+//   shamir combine shares/share-*.txt -o secret.bin
+//   shamir recover -o secret.bin
+//   cat key.bin | shamir split -t 2 -n 3 -f base64 | shamir combine
+
+extern crate rust_shamir;
+extern crate zeroize;
+
+use rust_shamir::{construct_shares, reconstruct, Progress, SecretSharingError, Share, ShareSet};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use zeroize::Zeroizing;
+
+const USAGE: &str = "usage:
+  shamir split -t THRESHOLD -n SHARES [-f FORMAT] [-o DIR] [SECRET]
+  shamir combine [-o OUTPUT] [SHARE...]
+  shamir recover -o OUTPUT
+
+split writes SHARES shares of the file SECRET to DIR, as share-01.shamir,
+share-02.shamir, ... in the binary format, or share-01.txt, ... in a text
+FORMAT: hex, base64 or bech32. Any THRESHOLD of them recover the secret.
+
+combine reads shares in any of these formats and writes the secret to
+OUTPUT. recover asks for the shares one at a time instead, checking each as
+it is typed in. No command overwrites existing files.
+
+A missing SECRET or SHARE, or one given as -, is read from stdin, and a
+missing OUTPUT or DIR, or one given as -, is stdout. Shares on stdin and
+stdout are in a text format, one per line; split writes base64 by default.";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Binary,
+    Hex,
+    Base64,
+    Bech32,
+}
+
+impl Format {
+    fn parse(s: &str) -> Result<Format, String> {
+        match s {
+            "binary" => Ok(Format::Binary),
+            "hex" => Ok(Format::Hex),
+            "base64" => Ok(Format::Base64),
+            "bech32" => Ok(Format::Bech32),
+            _ => Err(format!("unknown format {:?}", s)),
+        }
+    }
+
+    // encode returns the share in this format, and the extension of the file
+    // it is written to.
+    fn encode(self, share: &Share) -> (Zeroizing<Vec<u8>>, &'static str) {
+        let text = |s: String| Zeroizing::new(format!("{}\n", s).into_bytes());
+        match self {
+            Format::Binary => (Zeroizing::new(share.to_bytes()), "shamir"),
+            Format::Hex => (text(share.to_hex()), "txt"),
+            Format::Base64 => (text(share.to_base64()), "txt"),
+            Format::Bech32 => (text(share.to_bech32()), "txt"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Split {
+        threshold: u8,
+        shares: u8,
+        format: Format,
+        out: PathBuf,
+        secret: PathBuf,
+    },
+    Combine {
+        out: PathBuf,
+        shares: Vec<PathBuf>,
+    },
+    Recover {
+        out: PathBuf,
+    },
+    Help,
+}
+
+// parse parses the command line, without the program name.
+fn parse(args: &[String]) -> Result<Command, String> {
+    let (command, args) = match args.split_first() {
+        Some((c, rest)) => (c.as_str(), rest),
+        None => return Err("missing command".to_string()),
+    };
+    if command == "-h" || command == "--help" || command == "help" {
+        return Ok(Command::Help);
+    }
+
+    let (mut threshold, mut shares, mut format, mut out) = (None, None, None, None);
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        let number = |v: &str, name: &str| {
+            v.parse::<u8>()
+                .map_err(|_| format!("{} must be a number from 1 to 255", name))
+        };
+        match arg.as_str() {
+            "-t" | "--threshold" => threshold = Some(number(value(arg)?, arg)?),
+            "-n" | "--shares" => shares = Some(number(value(arg)?, arg)?),
+            "-f" | "--format" => format = Some(Format::parse(value(arg)?)?),
+            "-o" | "--output" => out = Some(PathBuf::from(value(arg)?)),
+            "-h" | "--help" => return Ok(Command::Help),
+            "--" => positional.extend(args.by_ref().map(PathBuf::from)),
+            a if a.starts_with('-') && a != "-" => return Err(format!("unknown option {}", a)),
+            a => positional.push(PathBuf::from(a)),
+        }
+    }
+
+    match command {
+        "split" => {
+            if positional.len() > 1 {
+                return Err("split takes one secret file".to_string());
+            }
+            let out = out.unwrap_or_else(|| PathBuf::from("-"));
+            let format = match format {
+                Some(Format::Binary) if is_std(&out) => {
+                    return Err("binary shares can't be written to stdout".to_string())
+                }
+                Some(format) => format,
+                None if is_std(&out) => Format::Base64,
+                None => Format::Binary,
+            };
+            Ok(Command::Split {
+                threshold: threshold.ok_or("missing -t")?,
+                shares: shares.ok_or("missing -n")?,
+                format,
+                out,
+                secret: positional.pop().unwrap_or_else(|| PathBuf::from("-")),
+            })
+        }
+        "combine" => {
+            if threshold.is_some() || shares.is_some() || format.is_some() {
+                return Err("combine takes only -o and share files".to_string());
+            }
+            if positional.is_empty() {
+                positional.push(PathBuf::from("-"));
+            }
+            Ok(Command::Combine {
+                out: out.unwrap_or_else(|| PathBuf::from("-")),
+                shares: positional,
+            })
+        }
+        "recover" => {
+            if threshold.is_some() || shares.is_some() || format.is_some() {
+                return Err("recover takes only -o".to_string());
+            }
+            if !positional.is_empty() {
+                return Err("recover reads shares from the terminal, not files".to_string());
+            }
+            Ok(Command::Recover {
+                out: out.ok_or("missing -o")?,
+            })
+        }
+        c => Err(format!("unknown command {:?}", c)),
+    }
+}
+
+// is_std reports whether `path` is -, standing for stdin or stdout.
+fn is_std(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+// write_output writes `data` to stdout if `path` is -, and to a new file at
+// `path` otherwise.
+fn write_output(path: &Path, data: &[u8]) -> Result<(), String> {
+    let written = if is_std(path) {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(data).and_then(|()| stdout.flush())
+    } else {
+        write_new(path, data)
+    };
+    written.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// write_new writes `data` to a new file at `path`, failing if it exists. On
+// Unix the file is readable by its owner only.
+fn write_new(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+// write_share_lines writes `shares` to `w` in a text format, one per line.
+fn write_share_lines<W: Write>(shares: &[Share], format: Format, w: &mut W) -> io::Result<()> {
+    for share in shares {
+        w.write_all(&format.encode(share).0)?;
+    }
+    w.flush()
+}
+
+// read_share_lines reads shares in any text format from `r`, one per line,
+// skipping blank lines.
+fn read_share_lines<R: BufRead>(mut r: R) -> Result<Vec<Share>, String> {
+    let mut shares = Vec::new();
+    let mut line = Zeroizing::new(String::new());
+    for number in 1.. {
+        line.clear();
+        if r.read_line(&mut line)
+            .map_err(|e| format!("stdin: {}", e))?
+            == 0
+        {
+            break;
+        }
+        if !line.trim().is_empty() {
+            let share = line
+                .parse()
+                .map_err(|e| format!("stdin line {}: {}", number, e))?;
+            shares.push(share);
+        }
+    }
+    Ok(shares)
+}
+
+// read_share reads a share file in the binary format or any text format.
+fn read_share(path: &Path) -> Result<Share, String> {
+    let data = Zeroizing::new(fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?);
+    // the binary format starts with the magic "SHMR"; text never does.
+    let share = if data.starts_with(b"SHMR") {
+        Share::from_bytes(&data)
+    } else {
+        std::str::from_utf8(&data)
+            .map_err(|_| rust_shamir::SecretSharingError::ShareMalformed)
+            .and_then(|s| s.parse())
+    };
+    share.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// read_hidden prints `prompt` to stderr and reads a line from stdin, without
+// echoing it if stdin is a terminal. It returns None at end of input.
+fn read_hidden(prompt: &str) -> io::Result<Option<Zeroizing<String>>> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    let _echo = EchoOff::new();
+    let mut line = Zeroizing::new(String::new());
+    let n = io::stdin().read_line(&mut line)?;
+    Ok(if n == 0 { None } else { Some(line) })
+}
+
+// EchoOff turns off terminal echo on stdin, except for the newline ending a
+// line, until it is dropped.
+#[cfg(unix)]
+struct EchoOff(libc::termios);
+
+#[cfg(unix)]
+impl EchoOff {
+    fn new() -> Option<EchoOff> {
+        unsafe {
+            let mut term = std::mem::zeroed::<libc::termios>();
+            if libc::isatty(libc::STDIN_FILENO) != 1
+                || libc::tcgetattr(libc::STDIN_FILENO, &mut term) != 0
+            {
+                return None;
+            }
+            let saved = EchoOff(term);
+            term.c_lflag &= !libc::ECHO;
+            term.c_lflag |= libc::ECHONL;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &term) != 0 {
+                return None;
+            }
+            Some(saved)
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for EchoOff {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0);
+        }
+    }
+}
+
+// EchoOff does nothing where we don't know how to turn off echo.
+#[cfg(not(unix))]
+struct EchoOff;
+
+#[cfg(not(unix))]
+impl EchoOff {
+    fn new() -> Option<EchoOff> {
+        None
+    }
+}
+
+// recover asks for shares with `read` until it has enough to reconstruct the
+// secret, explaining each step to `w`. Every share is checked as soon as it
+// is entered, so a typo is caught while the paper is still in hand, and
+// added to a ShareSet, which rejects duplicates and shares of another split.
+// Shares that don't record their threshold are taken until an empty line.
+fn recover<R, W>(mut read: R, w: &mut W) -> Result<Zeroizing<Vec<u8>>, String>
+where
+    R: FnMut(&str) -> io::Result<Option<Zeroizing<String>>>,
+    W: Write,
+{
+    let say = |w: &mut W, msg: String| writeln!(w, "{}", msg).map_err(|e| e.to_string());
+    say(
+        w,
+        "Type or paste each share, then press Enter. Shares are not shown as you type.".into(),
+    )?;
+    let mut shares = ShareSet::new();
+    while shares.progress() != Progress::Ready {
+        let line =
+            match read(&format!("Share {}: ", shares.len() + 1)).map_err(|e| e.to_string())? {
+                Some(line) => line,
+                None => return Err("input ended before enough shares were entered".to_string()),
+            };
+        if line.trim().is_empty() {
+            if shares.threshold().is_none() && !shares.is_empty() {
+                break;
+            }
+            continue;
+        }
+        let share = match Share::from_paper(&line) {
+            Ok(share) => share,
+            Err(e) => {
+                say(
+                    w,
+                    format!(
+                        "That share is not valid ({}). Please check it and type it again.",
+                        e
+                    ),
+                )?;
+                continue;
+            }
+        };
+        let index = share.index();
+        match shares.add(share) {
+            Ok(_) => {}
+            Err(SecretSharingError::DuplicateShare { .. }) => {
+                say(
+                    w,
+                    format!(
+                        "Share number {} was already entered. Please use another one.",
+                        index
+                    ),
+                )?;
+                continue;
+            }
+            Err(_) => {
+                say(
+                    w,
+                    "That share belongs to a different secret. Please use another one.".into(),
+                )?;
+                continue;
+            }
+        }
+        say(
+            w,
+            match shares.threshold() {
+                Some(t) => format!("Share accepted: {} of {} entered.", shares.len(), t),
+                None => format!(
+                    "Share accepted: {} entered. Press Enter on an empty line when you have entered them all.",
+                    shares.len()
+                ),
+            },
+        )?;
+    }
+    // a set that can't tell when it is complete holds whatever was entered.
+    let secret = match shares.progress() {
+        Progress::ThresholdUnknown => reconstruct(shares.shares()),
+        _ => shares.combine(),
+    };
+    let secret = Zeroizing::new(secret.map_err(|e| e.to_string())?);
+    say(
+        w,
+        "All shares entered. The secret has been recovered.".into(),
+    )?;
+    Ok(secret)
+}
+
+fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Help => println!("{}", USAGE),
+        Command::Split {
+            threshold,
+            shares,
+            format,
+            out,
+            secret,
+        } => {
+            let mut data = Zeroizing::new(Vec::new());
+            let read = if is_std(&secret) {
+                io::stdin().lock().read_to_end(&mut data).map(|_| ())
+            } else {
+                fs::File::open(&secret).and_then(|mut f| f.read_to_end(&mut data).map(|_| ()))
+            };
+            read.map_err(|e| format!("{}: {}", secret.display(), e))?;
+            let shares = construct_shares(threshold, shares, &data).map_err(|e| e.to_string())?;
+            if is_std(&out) {
+                return write_share_lines(&shares, format, &mut io::stdout().lock())
+                    .map_err(|e| format!("stdout: {}", e));
+            }
+            fs::create_dir_all(&out).map_err(|e| format!("{}: {}", out.display(), e))?;
+            for share in &shares {
+                let (encoded, ext) = format.encode(share);
+                let path = out.join(format!("share-{:02}.{}", share.index(), ext));
+                write_new(&path, &encoded).map_err(|e| format!("{}: {}", path.display(), e))?;
+                eprintln!("wrote {}", path.display());
+            }
+        }
+        Command::Combine { out, shares: paths } => {
+            let mut shares = Vec::new();
+            for path in &paths {
+                if is_std(path) {
+                    shares.extend(read_share_lines(io::stdin().lock())?);
+                } else {
+                    shares.push(read_share(path)?);
+                }
+            }
+            let secret = Zeroizing::new(reconstruct(&shares).map_err(|e| e.to_string())?);
+            write_output(&out, &secret)?;
+        }
+        Command::Recover { out } => {
+            if !is_std(&out) && out.exists() {
+                return Err(format!("{}: already exists", out.display()));
+            }
+            let secret = recover(read_hidden, &mut io::stderr())?;
+            write_output(&out, &secret)?;
+            if !is_std(&out) {
+                eprintln!("wrote {}", out.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match parse(&args) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("shamir: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = run(command) {
+        eprintln!("shamir: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(&args("split -t 3 -n 5 secret.bin -o shares/")),
+            Ok(Command::Split {
+                threshold: 3,
+                shares: 5,
+                format: Format::Binary,
+                out: PathBuf::from("shares/"),
+                secret: PathBuf::from("secret.bin"),
+            })
+        );
+        assert_eq!(
+            parse(&args("combine a.txt b.txt --output s.bin")),
+            Ok(Command::Combine {
+                out: PathBuf::from("s.bin"),
+                shares: vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")],
+            })
+        );
+        assert_eq!(
+            parse(&args("recover -o s.bin")),
+            Ok(Command::Recover {
+                out: PathBuf::from("s.bin"),
+            })
+        );
+        assert_eq!(
+            parse(&args("split -t 2 -n 3")),
+            Ok(Command::Split {
+                threshold: 2,
+                shares: 3,
+                format: Format::Base64,
+                out: PathBuf::from("-"),
+                secret: PathBuf::from("-"),
+            })
+        );
+        assert_eq!(
+            parse(&args("combine")),
+            Ok(Command::Combine {
+                out: PathBuf::from("-"),
+                shares: vec![PathBuf::from("-")],
+            })
+        );
+        assert_eq!(parse(&args("split --help")), Ok(Command::Help));
+        for bad in [
+            "",
+            "frobnicate -o x",
+            "split -t 3 -n 5 a b",
+            "split -t 3 -n 5 -f binary",
+            "split -t 0x3 -n 5 -o d s",
+            "split -t 3 -n 5 -f morse -o d s",
+            "combine -n 2",
+            "combine -t 2 -o s.bin a",
+            "combine -o",
+            "recover",
+            "recover -o s.bin a.txt",
+            "recover -t 2 -o s.bin",
+        ] {
+            assert!(parse(&args(bad)).is_err(), "{}", bad);
+        }
+    }
+    #[test]
+    fn test_split_combine() {
+        let dir = env::temp_dir().join(format!("shamir-cli-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let secret = dir.join("secret.bin");
+        fs::write(&secret, b"the cli secret").unwrap();
+
+        for (i, format) in ["binary", "hex", "base64", "bech32"].iter().enumerate() {
+            let out = dir.join(format!("shares-{}", format));
+            run(parse(&args(&format!(
+                "split -t 2 -n 3 -f {} -o {} {}",
+                format,
+                out.display(),
+                secret.display()
+            )))
+            .unwrap())
+            .unwrap();
+            let ext = if i == 0 { "shamir" } else { "txt" };
+            let recovered = dir.join(format!("recovered-{}", format));
+            run(Command::Combine {
+                out: recovered.clone(),
+                shares: vec![
+                    out.join(format!("share-03.{}", ext)),
+                    out.join(format!("share-01.{}", ext)),
+                ],
+            })
+            .unwrap();
+            assert_eq!(fs::read(&recovered).unwrap(), b"the cli secret");
+            // existing files are never overwritten.
+            assert!(run(Command::Combine {
+                out: recovered,
+                shares: vec![out.join(format!("share-02.{}", ext))],
+            })
+            .is_err());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_recover() {
+        let shares = construct_shares(3, 5, b"for the heirs").unwrap();
+        let other = construct_shares(3, 5, b"something else").unwrap();
+        // the same length, threshold and share count, but another split.
+        let same_shape = construct_shares(3, 5, b"for the heir!").unwrap();
+        let lines = vec![
+            shares[4].to_bech32(),
+            String::new(),
+            "not a share".to_string(),
+            shares[4].to_hex(),
+            other[0].to_base64(),
+            same_shape[3].to_hex(),
+            format!("  {}  ", shares[1]),
+            shares[0].to_base64(),
+            // never read: the threshold has been reached.
+            shares[2].to_hex(),
+        ];
+        let mut input = lines.into_iter();
+        let mut prompts = Vec::new();
+        let mut out = Vec::new();
+        let secret = recover(
+            |prompt: &str| {
+                prompts.push(prompt.to_string());
+                Ok(input.next().map(Zeroizing::new))
+            },
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(&secret[..], b"for the heirs");
+        assert_eq!(input.len(), 1);
+        assert_eq!(prompts.last().unwrap(), "Share 3: ");
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("1 of 3 entered"));
+        assert!(out.contains("3 of 3 entered"));
+        assert!(out.contains("is not valid"));
+        assert!(out.contains("already entered"));
+        assert_eq!(out.matches("different secret").count(), 2);
+
+        let mut input = vec![shares[0].to_hex()].into_iter();
+        assert!(recover(
+            |_: &str| Ok(input.next().map(Zeroizing::new)),
+            &mut Vec::new()
+        )
+        .is_err());
+    }
+    #[test]
+    fn test_share_lines() {
+        let shares = construct_shares(2, 3, b"piped secret").unwrap();
+        for format in [Format::Hex, Format::Base64, Format::Bech32] {
+            let mut out = Vec::new();
+            write_share_lines(&shares, format, &mut out).unwrap();
+            assert_eq!(out.iter().filter(|&&c| c == b'\n').count(), 3);
+            // blank lines and surrounding whitespace are tolerated.
+            out.splice(0..0, b"\n  ".iter().copied());
+            let read = read_share_lines(&out[..]).unwrap();
+            assert_eq!(reconstruct(&read[1..]).unwrap(), b"piped secret");
+        }
+        assert_eq!(
+            read_share_lines(&b"\n\nnot a share\n"[..]).err().unwrap(),
+            "stdin line 3: share is malformed"
+        );
+    }
+}
@@ -0,0 +1,462 @@
+// Deterministic CBOR (RFC 8949 section 4.2.1) encoding of a Share, for
+// hardware tokens and provisioning systems that already speak CBOR, and a
+// COSE_Mac0 envelope (RFC 9052) to authenticate it. A share is a map with
+// small integer keys:
+//
+//   1  version, currently 1
+//   2  share index
+//   3  threshold, if known
+//   4  share count, if known
+//   5  payload, a byte string
+//   6  split identifier, if known
+//   7  label, a text string, if any
+//   8  owner, a text string, if any
+//
+// Integers and lengths take their shortest form and the keys are in
+// ascending order, so a share has exactly one encoding; the decoder rejects
+// any other, as well as indefinite lengths, unknown keys and trailing bytes.
+//
+// The envelope is a tagged COSE_Mac0 with HMAC 256/256 (algorithm 5) under a
+// caller-supplied key, with the share's CBOR as its payload. COSE_Sign1 would
+// need a signature scheme, which the crate doesn't implement.
+
+use crate::{kdf, ConstantTimeEq, SecretSharingError, Share};
+use std::convert::TryFrom;
+use zeroize::Zeroizing;
+
+const VERSION: u64 = 1;
+const KEY_VERSION: u64 = 1;
+const KEY_INDEX: u64 = 2;
+const KEY_THRESHOLD: u64 = 3;
+const KEY_SHARE_COUNT: u64 = 4;
+const KEY_DATA: u64 = 5;
+const KEY_IDENTIFIER: u64 = 6;
+const KEY_LABEL: u64 = 7;
+const KEY_OWNER: u64 = 8;
+
+const MAJOR_UINT: u8 = 0;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+
+// COSE_MAC0_TAG is the CBOR tag of a COSE_Mac0 structure, and ALG_HMAC_256
+// the COSE algorithm identifier of HMAC-SHA256 with a 256-bit tag.
+const COSE_MAC0_TAG: u64 = 17;
+const COSE_HEADER_ALG: u64 = 1;
+const ALG_HMAC_256: u64 = 5;
+const MAC_TAG_LEN: usize = 32;
+
+// write_head writes a data item header in its shortest form.
+fn write_head(out: &mut Vec<u8>, major: u8, v: u64) {
+    let m = major << 5;
+    match v {
+        0..=23 => out.push(m | v as u8),
+        24..=0xff => out.extend_from_slice(&[m | 24, v as u8]),
+        0x100..=0xffff => {
+            out.push(m | 25);
+            out.extend_from_slice(&(v as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(m | 26);
+            out.extend_from_slice(&(v as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(m | 27);
+            out.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+}
+
+fn write_bytes(out: &mut Vec<u8>, b: &[u8]) {
+    write_head(out, MAJOR_BYTES, b.len() as u64);
+    out.extend_from_slice(b);
+}
+
+// Reader decodes data items from the front of a buffer.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    // head reads a data item header, rejecting non-shortest forms and
+    // indefinite lengths.
+    fn head(&mut self) -> Result<(u8, u64), SecretSharingError> {
+        let (&first, rest) = self
+            .data
+            .split_first()
+            .ok_or(SecretSharingError::ShareTruncated)?;
+        let (major, info) = (first >> 5, first & 0x1f);
+        let n = match info {
+            0..=23 => 0,
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            _ => return Err(SecretSharingError::ShareMalformed),
+        };
+        if rest.len() < n {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        let v = match n {
+            0 => info as u64,
+            _ => rest[..n].iter().fold(0u64, |v, &b| v << 8 | b as u64),
+        };
+        let min = match n {
+            0 => 0,
+            1 => 24,
+            _ => 1 << (4 * n),
+        };
+        if v < min {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        self.data = &rest[n..];
+        Ok((major, v))
+    }
+
+    fn expect(&mut self, major: u8) -> Result<u64, SecretSharingError> {
+        match self.head()? {
+            (m, v) if m == major => Ok(v),
+            _ => Err(SecretSharingError::ShareMalformed),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], SecretSharingError> {
+        self.string(MAJOR_BYTES)
+    }
+
+    fn text(&mut self) -> Result<&'a str, SecretSharingError> {
+        std::str::from_utf8(self.string(MAJOR_TEXT)?)
+            .map_err(|_| SecretSharingError::ShareMalformed)
+    }
+
+    // string reads a byte or text string, as given by `major`.
+    fn string(&mut self, major: u8) -> Result<&'a [u8], SecretSharingError> {
+        let len = self.expect(major)?;
+        if (self.data.len() as u64) < len {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        let (b, rest) = self.data.split_at(len as usize);
+        self.data = rest;
+        Ok(b)
+    }
+
+    fn small_uint(&mut self) -> Result<u8, SecretSharingError> {
+        u8::try_from(self.expect(MAJOR_UINT)?).map_err(|_| SecretSharingError::ShareMalformed)
+    }
+
+    fn finish(&self) -> Result<(), SecretSharingError> {
+        if !self.data.is_empty() {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        Ok(())
+    }
+}
+
+// protected_header returns the COSE protected header, {1: 5}, as a byte
+// string's contents.
+fn protected_header() -> Vec<u8> {
+    let mut out = Vec::with_capacity(3);
+    write_head(&mut out, MAJOR_MAP, 1);
+    write_head(&mut out, MAJOR_UINT, COSE_HEADER_ALG);
+    write_head(&mut out, MAJOR_UINT, ALG_HMAC_256);
+    out
+}
+
+// mac_tag computes the COSE_Mac0 tag: the HMAC of the MAC_structure
+// ["MAC0", protected, external_aad, payload], with no external AAD.
+fn mac_tag(key: &[u8], protected: &[u8], payload: &[u8]) -> [u8; MAC_TAG_LEN] {
+    let mut structure = Zeroizing::new(Vec::with_capacity(payload.len() + 16));
+    write_head(&mut structure, MAJOR_ARRAY, 4);
+    write_head(&mut structure, MAJOR_TEXT, 4);
+    structure.extend_from_slice(b"MAC0");
+    write_bytes(&mut structure, protected);
+    write_bytes(&mut structure, &[]);
+    write_bytes(&mut structure, payload);
+    kdf::hmac(key, &structure)
+}
+
+impl Share {
+    // to_cbor encodes the share as deterministic CBOR.
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let fields = 3
+            + self.threshold.is_some() as u64
+            + self.share_count.is_some() as u64
+            + self.identifier.is_some() as u64
+            + self.label.is_some() as u64
+            + self.owner.is_some() as u64;
+        let mut out = Vec::with_capacity(self.y.len() + 16);
+        write_head(&mut out, MAJOR_MAP, fields);
+        write_head(&mut out, MAJOR_UINT, KEY_VERSION);
+        write_head(&mut out, MAJOR_UINT, VERSION);
+        write_head(&mut out, MAJOR_UINT, KEY_INDEX);
+        write_head(&mut out, MAJOR_UINT, self.x as u64);
+        if let Some(t) = self.threshold {
+            write_head(&mut out, MAJOR_UINT, KEY_THRESHOLD);
+            write_head(&mut out, MAJOR_UINT, t as u64);
+        }
+        if let Some(n) = self.share_count {
+            write_head(&mut out, MAJOR_UINT, KEY_SHARE_COUNT);
+            write_head(&mut out, MAJOR_UINT, n as u64);
+        }
+        write_head(&mut out, MAJOR_UINT, KEY_DATA);
+        write_bytes(&mut out, &self.y);
+        if let Some(id) = self.identifier {
+            write_head(&mut out, MAJOR_UINT, KEY_IDENTIFIER);
+            write_head(&mut out, MAJOR_UINT, id as u64);
+        }
+        for (key, text) in [(KEY_LABEL, &self.label), (KEY_OWNER, &self.owner)] {
+            if let Some(text) = text {
+                write_head(&mut out, MAJOR_UINT, key);
+                write_head(&mut out, MAJOR_TEXT, text.len() as u64);
+                out.extend_from_slice(text.as_bytes());
+            }
+        }
+        out
+    }
+
+    // from_cbor decodes a share encoded by to_cbor.
+    pub fn from_cbor(data: &[u8]) -> Result<Share, SecretSharingError> {
+        let mut r = Reader { data };
+        let fields = r.expect(MAJOR_MAP)?;
+        let (mut version, mut x, mut threshold, mut share_count, mut y) =
+            (None, None, None, None, None);
+        let (mut identifier, mut label, mut owner) = (None, None, None);
+        let mut last = None;
+        for _ in 0..fields {
+            let key = r.expect(MAJOR_UINT)?;
+            if last.is_some_and(|last| key <= last) {
+                return Err(SecretSharingError::ShareMalformed);
+            }
+            last = Some(key);
+            match key {
+                KEY_VERSION => version = Some(r.expect(MAJOR_UINT)?),
+                KEY_INDEX => x = Some(r.small_uint()?),
+                KEY_THRESHOLD => threshold = Some(r.small_uint()?),
+                KEY_SHARE_COUNT => share_count = Some(r.small_uint()?),
+                KEY_DATA => y = Some(r.bytes()?),
+                KEY_IDENTIFIER => {
+                    identifier = match u16::try_from(r.expect(MAJOR_UINT)?) {
+                        Ok(0) | Err(_) => return Err(SecretSharingError::ShareMalformed),
+                        Ok(id) => Some(id),
+                    }
+                }
+                KEY_LABEL => label = Some(r.text()?),
+                KEY_OWNER => owner = Some(r.text()?),
+                _ => return Err(SecretSharingError::ShareMalformed),
+            }
+        }
+        r.finish()?;
+
+        match version {
+            Some(VERSION) => {}
+            Some(v) => return Err(SecretSharingError::UnsupportedShareVersion(v.min(255) as u8)),
+            None => return Err(SecretSharingError::ShareMalformed),
+        }
+        let (x, y) = match (x, y) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return Err(SecretSharingError::ShareMalformed),
+        };
+        let mut share = Share {
+            x,
+            y: y.to_vec(),
+            threshold,
+            share_count,
+            identifier,
+            label: None,
+            owner: None,
+        };
+        // the checks of with_label and with_owner, which would also drop an
+        // empty string rather than keep it, so it must not be encoded.
+        for (text, field) in [(label, &mut share.label), (owner, &mut share.owner)] {
+            if let Some(text) = text {
+                *field = crate::check_text(text)?;
+                if field.as_deref() != Some(text) {
+                    return Err(SecretSharingError::ShareMalformed);
+                }
+            }
+        }
+        Ok(share)
+    }
+
+    // to_cose_mac0 wraps the share's CBOR in a COSE_Mac0 envelope,
+    // authenticated with HMAC-SHA256 under `key`.
+    pub fn to_cose_mac0(&self, key: &[u8]) -> Vec<u8> {
+        let payload = Zeroizing::new(self.to_cbor());
+        let protected = protected_header();
+        let tag = mac_tag(key, &protected, &payload);
+
+        let mut out = Vec::with_capacity(payload.len() + MAC_TAG_LEN + 16);
+        write_head(&mut out, MAJOR_TAG, COSE_MAC0_TAG);
+        write_head(&mut out, MAJOR_ARRAY, 4);
+        write_bytes(&mut out, &protected);
+        write_head(&mut out, MAJOR_MAP, 0);
+        write_bytes(&mut out, &payload);
+        write_bytes(&mut out, &tag);
+        out
+    }
+
+    // from_cose_mac0 verifies a COSE_Mac0 envelope made by to_cose_mac0 under
+    // `key` and decodes the share in it. The envelope may be untagged. A tag
+    // that doesn't verify is reported as ShareTampered.
+    pub fn from_cose_mac0(data: &[u8], key: &[u8]) -> Result<Share, SecretSharingError> {
+        let mut r = Reader { data };
+        let mut peek = Reader { data };
+        if peek.head()?.0 == MAJOR_TAG && r.expect(MAJOR_TAG)? != COSE_MAC0_TAG {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if r.expect(MAJOR_ARRAY)? != 4 {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let protected = r.bytes()?;
+        if protected != protected_header().as_slice() || r.expect(MAJOR_MAP)? != 0 {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let payload = r.bytes()?;
+        let tag = r.bytes()?;
+        r.finish()?;
+
+        let share = Share::from_cbor(payload)?;
+        if tag.len() != MAC_TAG_LEN || !mac_tag(key, protected, payload)[..].ct_eq(tag) {
+            return Err(SecretSharingError::ShareTampered { index: share.x });
+        }
+        Ok(share)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_cbor_heads() {
+        // RFC 8949 appendix A.
+        for &(v, enc) in &[
+            (0u64, &[0x00][..]),
+            (23, &[0x17]),
+            (24, &[0x18, 0x18]),
+            (100, &[0x18, 0x64]),
+            (1000, &[0x19, 0x03, 0xe8]),
+            (1_000_000, &[0x1a, 0x00, 0x0f, 0x42, 0x40]),
+            (
+                1_000_000_000_000,
+                &[0x1b, 0x00, 0x00, 0x00, 0xe8, 0xd4, 0xa5, 0x10, 0x00],
+            ),
+        ] {
+            let mut out = Vec::new();
+            write_head(&mut out, MAJOR_UINT, v);
+            assert_eq!(out, enc);
+            assert_eq!(Reader { data: enc }.head().unwrap(), (MAJOR_UINT, v));
+        }
+        // 23 in two bytes, and an indefinite-length byte string.
+        assert!(Reader {
+            data: &[0x18, 0x17]
+        }
+        .head()
+        .is_err());
+        assert!(Reader { data: &[0x5f] }.head().is_err());
+    }
+    #[test]
+    fn test_cbor_share() {
+        let share = Share::with_metadata(3, &[1, 2, 3], 2, 5);
+        let enc = share.to_cbor();
+        assert_eq!(
+            enc,
+            [0xa5, 0x01, 0x01, 0x02, 0x03, 0x03, 0x02, 0x04, 0x05, 0x05, 0x43, 0x01, 0x02, 0x03]
+        );
+        let dec = Share::from_cbor(&enc).unwrap();
+        assert_eq!(dec.index(), 3);
+        assert_eq!(dec.threshold(), Some(2));
+        assert_eq!(dec.share_count(), Some(5));
+        assert_eq!(dec.as_bytes(), [1, 2, 3]);
+
+        let bare = Share::new(1, b"");
+        assert_eq!(bare.to_cbor(), [0xa3, 0x01, 0x01, 0x02, 0x01, 0x05, 0x40]);
+        assert_eq!(Share::from_cbor(&bare.to_cbor()).unwrap().threshold(), None);
+
+        let tagged = Share::new(1, b"").with_identifier(1000);
+        assert_eq!(
+            tagged.to_cbor(),
+            [0xa4, 0x01, 0x01, 0x02, 0x01, 0x05, 0x40, 0x06, 0x19, 0x03, 0xe8]
+        );
+        assert_eq!(
+            Share::from_cbor(&tagged.to_cbor()).unwrap().identifier(),
+            Some(1000)
+        );
+
+        let labelled = Share::new(1, b"").with_owner("Mom").unwrap();
+        assert_eq!(
+            labelled.to_cbor(),
+            [0xa4, 0x01, 0x01, 0x02, 0x01, 0x05, 0x40, 0x08, 0x63, b'M', b'o', b'm']
+        );
+        assert_eq!(
+            Share::from_cbor(&labelled.to_cbor()).unwrap().owner(),
+            Some("Mom")
+        );
+
+        for bad in &[
+            // keys out of order.
+            &[0xa3, 0x02, 0x01, 0x01, 0x01, 0x05, 0x40][..],
+            // an unknown key.
+            &[0xa4, 0x01, 0x01, 0x02, 0x01, 0x05, 0x40, 0x07, 0x00],
+            // an empty label, which has no canonical encoding.
+            &[0xa4, 0x01, 0x01, 0x02, 0x01, 0x05, 0x40, 0x07, 0x60],
+            // an identifier of zero.
+            &[0xa4, 0x01, 0x01, 0x02, 0x01, 0x05, 0x40, 0x06, 0x00],
+            // index too large.
+            &[0xa3, 0x01, 0x01, 0x02, 0x19, 0x01, 0x00, 0x05, 0x40],
+            // trailing bytes.
+            &[0xa3, 0x01, 0x01, 0x02, 0x01, 0x05, 0x40, 0x00],
+        ] {
+            assert_eq!(
+                Share::from_cbor(bad).err(),
+                Some(SecretSharingError::ShareMalformed)
+            );
+        }
+        // a label longer than with_label accepts.
+        let mut long = vec![
+            0xa4, 0x01, 0x01, 0x02, 0x01, 0x05, 0x40, 0x07, 0x79, 0x01, 0x00,
+        ];
+        long.extend_from_slice(&[b'x'; 256]);
+        assert_eq!(
+            Share::from_cbor(&long).err(),
+            Some(SecretSharingError::MetadataTooLong { max: 255 })
+        );
+        assert_eq!(
+            Share::from_cbor(&enc[..enc.len() - 1]).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+    }
+    #[test]
+    fn test_cose_mac0() {
+        let key = b"provisioning key";
+        for share in construct_shares(2, 3, b"cbor secret").unwrap() {
+            let cose = share.to_cose_mac0(key);
+            // tag 17, a 4-array, then the protected header {1: 5}.
+            assert_eq!(&cose[..6], &[0xd1, 0x84, 0x43, 0xa1, 0x01, 0x05]);
+            let dec = Share::from_cose_mac0(&cose, key).unwrap();
+            assert_eq!(dec.as_bytes(), share.as_bytes());
+            // untagged.
+            assert!(Share::from_cose_mac0(&cose[1..], key).is_ok());
+
+            assert_eq!(
+                Share::from_cose_mac0(&cose, b"wrong key").err(),
+                Some(SecretSharingError::ShareTampered {
+                    index: share.index()
+                })
+            );
+            let mut tampered = cose.clone();
+            let n = tampered.len();
+            // the last payload byte, before the tag and its 2-byte header.
+            tampered[n - MAC_TAG_LEN - 3] ^= 1;
+            assert_eq!(
+                Share::from_cose_mac0(&tampered, key).err(),
+                Some(SecretSharingError::ShareTampered {
+                    index: share.index()
+                })
+            );
+        }
+    }
+}
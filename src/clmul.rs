@@ -0,0 +1,137 @@
+// Bulk GF(2^8) multiplication using the carry-less multiply instructions
+// (PCLMULQDQ on x86_64, PMULL on aarch64), selected at runtime. Splitting and
+// reconstructing large secrets spends nearly all its time multiplying long
+// runs of secret bytes by a single public value (a share index or lagrange
+// coefficient), which is what mul_acc does.
+//
+// Four bytes are multiplied at once by spreading them into the 16-bit lanes
+// of a 64-bit word, so that their 15-bit products don't overlap, and then
+// reducing every lane modulo AES_POLYNOMIAL with two further carry-less
+// multiplications. Like the bit-serial code, this runs in constant time.
+
+use crate::gf::GfOps;
+
+// LO selects the low byte of every 16-bit lane.
+const LO: u64 = 0x00ff_00ff_00ff_00ff;
+
+// mul_acc adds src * x to acc, element-wise. acc and src must be the same
+// length.
+pub(crate) fn mul_acc(acc: &mut [u8], src: &[u8], x: u8) {
+    debug_assert_eq!(acc.len(), src.len());
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("pclmulqdq") {
+            // safety: the CPU supports PCLMULQDQ.
+            unsafe { x86::mul_acc(acc, src, x) };
+            return;
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("pmull") {
+            // safety: the CPU supports PMULL.
+            unsafe { arm::mul_acc(acc, src, x) };
+            return;
+        }
+    }
+
+    mul_acc_serial(acc, src, x);
+}
+
+fn mul_acc_serial(acc: &mut [u8], src: &[u8], x: u8) {
+    for (a, s) in acc.iter_mut().zip(src) {
+        *a ^= s.mul(x);
+    }
+}
+
+// spread places the four bytes of `b` in the low byte of each 16-bit lane.
+fn spread(b: u32) -> u64 {
+    let b = b as u64;
+    let b = (b | (b << 16)) & 0x0000_ffff_0000_ffff;
+    (b | (b << 8)) & LO
+}
+
+// gather is the inverse of spread.
+fn gather(v: u64) -> u32 {
+    let v = v & LO;
+    let v = (v | (v >> 8)) & 0x0000_ffff_0000_ffff;
+    (v | (v >> 16)) as u32
+}
+
+// mul_acc_lanes implements mul_acc given a 64x64 carry-less multiplication,
+// of which only the low 64 bits of the product are needed.
+#[inline(always)]
+fn mul_acc_lanes<F: Fn(u64, u64) -> u64>(acc: &mut [u8], src: &[u8], x: u8, clmul: F) {
+    let mut acc_chunks = acc.chunks_exact_mut(4);
+    let mut src_chunks = src.chunks_exact(4);
+    for (a, s) in (&mut acc_chunks).zip(&mut src_chunks) {
+        let mut w = [0u8; 4];
+        w.copy_from_slice(s);
+        let p = clmul(spread(u32::from_le_bytes(w)), x as u64);
+        // x^8 = x^4 + x^3 + x + 1: fold the high byte of every lane back into
+        // the low byte, twice, as the first fold can carry up to 3 bits over.
+        let p = (p & LO) ^ clmul((p >> 8) & LO, 0x1b);
+        let p = (p & LO) ^ clmul((p >> 8) & LO, 0x1b);
+        w.copy_from_slice(a);
+        a.copy_from_slice(&(u32::from_le_bytes(w) ^ gather(p)).to_le_bytes());
+    }
+    mul_acc_serial(acc_chunks.into_remainder(), src_chunks.remainder(), x);
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::{_mm_clmulepi64_si128, _mm_cvtsi128_si64, _mm_set_epi64x};
+
+    #[target_feature(enable = "pclmulqdq")]
+    unsafe fn clmul(a: u64, b: u64) -> u64 {
+        let p = _mm_clmulepi64_si128(_mm_set_epi64x(0, a as i64), _mm_set_epi64x(0, b as i64), 0);
+        _mm_cvtsi128_si64(p) as u64
+    }
+
+    #[target_feature(enable = "pclmulqdq")]
+    pub(super) unsafe fn mul_acc(acc: &mut [u8], src: &[u8], x: u8) {
+        super::mul_acc_lanes(acc, src, x, |a, b| clmul(a, b));
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arm {
+    use std::arch::aarch64::vmull_p64;
+
+    #[target_feature(enable = "neon,aes")]
+    unsafe fn clmul(a: u64, b: u64) -> u64 {
+        vmull_p64(a, b) as u64
+    }
+
+    #[target_feature(enable = "neon,aes")]
+    pub(super) unsafe fn mul_acc(acc: &mut [u8], src: &[u8], x: u8) {
+        super::mul_acc_lanes(acc, src, x, |a, b| clmul(a, b));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_spread_gather() {
+        assert_eq!(spread(0xa1b2_c3d4), 0x00a1_00b2_00c3_00d4);
+        assert_eq!(gather(0xffa1_ffb2_ffc3_ffd4), 0xa1b2_c3d4);
+    }
+    #[test]
+    fn test_mul_acc() {
+        let mut rng = rand::thread_rng();
+        // an odd length exercises the serial tail as well.
+        let src: Vec<u8> = (0..4099).map(|_| rng.gen()).collect();
+        let init: Vec<u8> = (0..4099).map(|_| rng.gen()).collect();
+        for x in 0..=255 {
+            let mut want = init.clone();
+            mul_acc_serial(&mut want, &src, x);
+            let mut got = init.clone();
+            mul_acc(&mut got, &src, x);
+            assert_eq!(got, want, "x = {:#x}", x);
+        }
+    }
+}
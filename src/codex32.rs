@@ -0,0 +1,403 @@
+// codex32 (BIP-93) shares of a 16 to 44 byte secret, such as a BIP-32 master
+// seed. A codex32 string is
+//
+//   ms1 <threshold> <identifier> <share index> <payload> <checksum>
+//
+// in the bech32 alphabet: the threshold is a digit from 2 to 9 (or 0 for an
+// unshared secret), the identifier 4 characters shared by all shares of one
+// secret, the index one character ("s" is the secret itself), the payload the
+// data in 5-bit groups and the checksum 13 characters of a BCH code designed
+// to be computed by hand. Shares are points of polynomials over GF(32), one
+// per character, and since every character but the index is interpolated,
+// the checksum of an interpolated share is valid if those of its sources are.
+//
+// Only the 13-character checksum for strings of up to 93 characters is
+// supported, which limits secrets to 44 bytes; the long checksum for longer
+// secrets is not.
+
+use crate::SecretSharingError;
+use rand::{CryptoRng, Rng, RngCore};
+use zeroize::{Zeroize, Zeroizing};
+
+const HRP: &str = "ms1";
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const HEADER_LEN: usize = 6;
+const CHECKSUM_LEN: usize = 13;
+const MAX_LEN: usize = 93;
+const MIN_SECRET_LEN: usize = 16;
+const MAX_SECRET_LEN: usize = 44;
+
+// SECRET_INDEX is the value of "s", the index of the secret itself.
+const SECRET_INDEX: u8 = 16;
+
+const GEN: [u128; 5] = [
+    0x1_9dc5_00ce_73fd_e210,
+    0x1_bfae_00de_f77f_e529,
+    0x1_fbd9_20ff_fe7b_ee52,
+    0x1_7396_40bd_eee3_fdad,
+    0x7729_a039_cfc7_5f5a,
+];
+const RESIDUE_INIT: u128 = 0x23181b3;
+const RESIDUE_CONST: u128 = 0x1_0ce0_795c_2fd1_e62a;
+
+fn polymod(values: &[u8]) -> u128 {
+    let mut residue = RESIDUE_INIT;
+    for &v in values {
+        let top = residue >> 60;
+        residue = (residue & 0xfff_ffff_ffff_ffff) << 5 ^ v as u128;
+        for (i, g) in GEN.iter().enumerate() {
+            residue ^= g & 0u128.wrapping_sub(top >> i & 1);
+        }
+    }
+    residue
+}
+
+// checksum returns the checksum characters for `data`.
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut values = data.to_vec();
+    values.extend_from_slice(&[0; CHECKSUM_LEN]);
+    let r = polymod(&values) ^ RESIDUE_CONST;
+    values.zeroize();
+    let mut out = [0u8; CHECKSUM_LEN];
+    for (i, c) in out.iter_mut().enumerate() {
+        *c = (r >> (5 * (CHECKSUM_LEN - 1 - i)) & 31) as u8;
+    }
+    out
+}
+
+// mul multiplies in GF(32) as used by bech32, modulo x^5 + x^3 + 1, in
+// constant time.
+fn mul(a: u8, b: u8) -> u8 {
+    let mut a = a as u16;
+    let mut z = 0u16;
+    for i in 0..5 {
+        z ^= 0u16.wrapping_sub((b as u16) >> i & 1) & a;
+        a <<= 1;
+        a ^= 0u16.wrapping_sub(a >> 5 & 1) & 0b101001;
+    }
+    z as u8
+}
+
+// inv computes a^30, the inverse of a in GF(32).
+fn inv(a: u8) -> u8 {
+    let a2 = mul(a, a);
+    let a3 = mul(a2, a);
+    let a6 = mul(a3, a3);
+    let a12 = mul(a6, a6);
+    let a15 = mul(a12, a3);
+    mul(a15, a15)
+}
+
+// Codex32 is a decoded codex32 string: its characters after "ms1", as 5-bit
+// values.
+struct Codex32(Zeroizing<Vec<u8>>);
+
+impl Codex32 {
+    fn parse(s: &str) -> Result<Codex32, SecretSharingError> {
+        if s.bytes().any(|c| c.is_ascii_lowercase()) && s.bytes().any(|c| c.is_ascii_uppercase()) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let lower = Zeroizing::new(s.to_ascii_lowercase());
+        if lower.len() > MAX_LEN || !lower.starts_with(HRP) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let mut data = Zeroizing::new(Vec::with_capacity(lower.len()));
+        for c in lower[HRP.len()..].bytes() {
+            match CHARSET.iter().position(|&x| x == c) {
+                Some(v) => data.push(v as u8),
+                None => return Err(SecretSharingError::ShareMalformed),
+            }
+        }
+        if data.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        if polymod(&data) != RESIDUE_CONST {
+            return Err(SecretSharingError::ShareChecksumMismatch);
+        }
+
+        let share = Codex32(data);
+        let payload_bits = 5 * share.payload().len();
+        if payload_bits % 8 > 4 || payload_bits / 8 < MIN_SECRET_LEN {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        match share.threshold() {
+            0 if share.index() != SECRET_INDEX => Err(SecretSharingError::ShareMalformed),
+            0 | 2..=9 => Ok(share),
+            _ => Err(SecretSharingError::ShareMalformed),
+        }
+    }
+
+    // threshold returns the threshold digit's value, or 255 if it isn't one.
+    fn threshold(&self) -> u8 {
+        match CHARSET[self.0[0] as usize] {
+            c @ b'0'..=b'9' => c - b'0',
+            _ => 255,
+        }
+    }
+
+    fn id(&self) -> &[u8] {
+        &self.0[1..5]
+    }
+
+    fn index(&self) -> u8 {
+        self.0[5]
+    }
+
+    fn payload(&self) -> &[u8] {
+        &self.0[HEADER_LEN..self.0.len() - CHECKSUM_LEN]
+    }
+
+    fn encode(&self) -> String {
+        let mut out = String::from(HRP);
+        out.extend(self.0.iter().map(|&v| CHARSET[v as usize] as char));
+        out
+    }
+
+    // with_payload returns the codex32 string with the given header and
+    // payload, and a valid checksum. The threshold must be 0 or 2 to 9.
+    fn with_payload(threshold: u8, id: &[u8], index: u8, payload: &[u8]) -> Codex32 {
+        let mut data = Zeroizing::new(Vec::with_capacity(
+            HEADER_LEN + payload.len() + CHECKSUM_LEN,
+        ));
+        data.push(CHARSET.iter().position(|&c| c == b'0' + threshold).unwrap() as u8);
+        data.extend_from_slice(id);
+        data.push(index);
+        data.extend_from_slice(payload);
+        let sum = checksum(&data);
+        data.extend_from_slice(&sum);
+        Codex32(data)
+    }
+}
+
+// interpolate returns the share at index `x` of the polynomials through
+// `shares`.
+fn interpolate(shares: &[Codex32], x: u8) -> Codex32 {
+    let mut out = Zeroizing::new(vec![0u8; shares[0].0.len()]);
+    for (i, share) in shares.iter().enumerate() {
+        let (num, den) = shares
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .fold((1, 1), |(num, den), (_, m)| {
+                (mul(num, x ^ m.index()), mul(den, share.index() ^ m.index()))
+            });
+        let l = mul(num, inv(den));
+        for (o, v) in out.iter_mut().zip(share.0.iter()) {
+            *o ^= mul(*v, l);
+        }
+    }
+    Codex32(out)
+}
+
+// codex32_split shares `secret` among `n` participants as codex32 strings
+// with identifier `id` (4 bech32 characters), `t` of which are required to
+// recover it. With t = 0 it returns the secret itself as a single unshared
+// codex32 string.
+pub fn codex32_split<R: CryptoRng + RngCore>(
+    id: &str,
+    t: u8,
+    n: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Vec<String>, SecretSharingError> {
+    if secret.len() < MIN_SECRET_LEN || secret.len() > MAX_SECRET_LEN {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    let id: Vec<u8> = id
+        .to_ascii_lowercase()
+        .bytes()
+        .map(|c| CHARSET.iter().position(|&x| x == c).map(|v| v as u8))
+        .collect::<Option<_>>()
+        .ok_or(SecretSharingError::ShareMalformed)?;
+    if id.len() != 4 {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    // the threshold is written as a single digit, and 1 isn't allowed.
+    if t != 0 && !(2..=9).contains(&t) {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+
+    // the secret in 5-bit groups, zero padded.
+    let mut payload = Zeroizing::new(Vec::with_capacity((8 * secret.len()).div_ceil(5)));
+    let (mut acc, mut bits) = (0u16, 0);
+    for &b in secret {
+        acc = (acc << 8 | b as u16) & 0xfff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            payload.push((acc >> bits & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        payload.push((acc << (5 - bits) & 31) as u8);
+    }
+    acc.zeroize();
+    let secret_share = Codex32::with_payload(t, &id, SECRET_INDEX, &payload);
+
+    if t == 0 {
+        return Ok(vec![secret_share.encode()]);
+    }
+    if n < t {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+    if n > 31 {
+        return Err(SecretSharingError::InvalidShareIndex);
+    }
+
+    // the share indices as BIP-93 assigns them, a, c, d and so on in
+    // alphabetical order, skipping s.
+    let indices: Vec<u8> = b"acdefghjklmnpqrtuvwxyz023456789"
+        .iter()
+        .map(|&c| CHARSET.iter().position(|&x| x == c).unwrap() as u8)
+        .collect();
+    let mut random = Zeroizing::new(vec![0u8; payload.len()]);
+    let mut basis: Vec<Codex32> = indices[..t as usize - 1]
+        .iter()
+        .map(|&x| {
+            for v in random.iter_mut() {
+                *v = rng.gen_range(0, 32);
+            }
+            Codex32::with_payload(t, &id, x, &random)
+        })
+        .collect();
+    basis.push(secret_share);
+
+    Ok(indices[..n as usize]
+        .iter()
+        .map(|&x| interpolate(&basis, x).encode())
+        .collect())
+}
+
+// codex32_combine recovers the secret from at least t codex32 shares of it,
+// or from its unshared codex32 string.
+pub fn codex32_combine(shares: &[&str]) -> Result<Vec<u8>, SecretSharingError> {
+    let shares = shares
+        .iter()
+        .map(|s| Codex32::parse(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = shares.first().ok_or(SecretSharingError::NoSharesProvided)?;
+    if shares.iter().any(|s| {
+        s.threshold() != first.threshold() || s.id() != first.id() || s.0.len() != first.0.len()
+    }) {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    let mut seen = [false; 32];
+    for s in shares.iter() {
+        if seen[s.index() as usize] {
+            return Err(SecretSharingError::DuplicateShare {
+                x: s.index().into(),
+            });
+        }
+        seen[s.index() as usize] = true;
+    }
+
+    let secret_share = match shares.iter().find(|s| s.index() == SECRET_INDEX) {
+        Some(s) => Codex32(s.0.clone()),
+        None => {
+            let need = first.threshold() as usize;
+            if shares.len() < need {
+                return Err(SecretSharingError::NotEnoughShares {
+                    have: shares.len(),
+                    need,
+                });
+            }
+            interpolate(&shares[..need], SECRET_INDEX)
+        }
+    };
+
+    // the payload in bytes; the padding bits are ignored.
+    let payload = secret_share.payload();
+    let mut secret = Vec::with_capacity(5 * payload.len() / 8);
+    let (mut acc, mut bits) = (0u16, 0);
+    for &v in payload {
+        acc = (acc << 5 | v as u16) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            secret.push((acc >> bits) as u8);
+        }
+    }
+    acc.zeroize();
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(b: &[u8]) -> String {
+        b.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_gf32() {
+        for a in 1..32 {
+            assert_eq!(mul(a, inv(a)), 1);
+        }
+        assert_eq!(inv(0), 0);
+    }
+    #[test]
+    fn test_bip93_vector_1() {
+        let secret =
+            codex32_combine(&["ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw"]).unwrap();
+        assert_eq!(hex(&secret), "318c6318c6318c6318c6318c6318c631");
+    }
+    #[test]
+    fn test_bip93_vector_2() {
+        let a = "MS12NAMEA320ZYXWVUTSRQPNMLKJHGFEDCAXRPP870HKKQRM";
+        let c = "MS12NAMECACDEFGHJKLMNPQRSTUVWXYZ023FTR2GDZMPY6PN";
+        let shares = [Codex32::parse(a).unwrap(), Codex32::parse(c).unwrap()];
+        assert_eq!(
+            interpolate(&shares, SECRET_INDEX).encode(),
+            "ms12names6xqguzttxkeqnjsjzv4jv3nz5k3kwgsphuh6evw"
+        );
+        assert_eq!(
+            hex(&codex32_combine(&[a, c]).unwrap()),
+            "d1808e096b35b209ca12132b264662a5"
+        );
+    }
+    #[test]
+    fn test_codex32_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let secret: Vec<u8> = (0..32).map(|_| rng.gen()).collect();
+        let shares = codex32_split("cash", 3, 5, &secret, &mut rng).unwrap();
+        assert_eq!(shares.len(), 5);
+        assert!(shares[0].starts_with("ms13casha"));
+        let subset: Vec<&str> = shares[2..].iter().map(|s| s.as_str()).collect();
+        assert_eq!(codex32_combine(&subset).unwrap(), secret);
+        assert_eq!(
+            codex32_combine(&subset[..2]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+
+        let unshared = codex32_split("cash", 0, 1, &secret, &mut rng).unwrap();
+        assert!(unshared[0].starts_with("ms10cashs"));
+        assert_eq!(codex32_combine(&[&unshared[0]]).unwrap(), secret);
+    }
+    #[test]
+    fn test_codex32_errors() {
+        let a = "ms12namea320zyxwvutsrqpnmlkjhgfedcaxrpp870hkkqrm";
+        assert_eq!(
+            codex32_combine(&[&a.replacen("zyx", "zyz", 1)]).err(),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+        assert_eq!(
+            codex32_combine(&[a, a]).err(),
+            Some(SecretSharingError::DuplicateShare { x: 29 })
+        );
+        assert_eq!(
+            codex32_combine(&["Ms12namea320zyxwvutsrqpnmlkjhgfedcaxrpp870hkkqrm"]).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        assert_eq!(
+            codex32_split("cash", 3, 5, &[0; 15], &mut rand::thread_rng()).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        for t in [1, 10, 208, 255] {
+            assert_eq!(
+                codex32_split("cash", t, 31, &[0; 16], &mut rand::thread_rng()).err(),
+                Some(SecretSharingError::ShareMalformed)
+            );
+        }
+    }
+}
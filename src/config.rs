@@ -0,0 +1,273 @@
+// ShamirConfig gathers the options of a split in one place, as an
+// alternative to choosing between the construct_shares variants:
+//
+//   let mut config = ShamirConfig::new().threshold(3).share_count(5).with_digest(true);
+//   let shares = config.split(secret)?;
+//   let secret = config.combine(&shares[..3])?;
+//
+// The parameters are validated before anything is split.
+
+use crate::padding::unpad;
+use crate::{
+    construct_shares_with_rng, default_rng, reconstruct, secrets_equal, sha256, DefaultRng,
+    Padding, SecretSharingError, Shares,
+};
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use zeroize::{Zeroize, Zeroizing};
+
+// DIGEST_LEN is the length of the digest appended to the secret by
+// with_digest.
+const DIGEST_LEN: usize = 4;
+
+pub struct ShamirConfig<R = DefaultRng> {
+    threshold: u8,
+    share_count: u8,
+    digest: bool,
+    padding: Option<Padding>,
+    rng: R,
+}
+
+impl ShamirConfig<DefaultRng> {
+    // new returns a config using default_rng, no digest and no padding. The
+    // threshold and share count must be set before splitting.
+    pub fn new() -> ShamirConfig<DefaultRng> {
+        ShamirConfig {
+            threshold: 0,
+            share_count: 0,
+            digest: false,
+            padding: None,
+            rng: default_rng(),
+        }
+    }
+}
+
+impl Default for ShamirConfig<DefaultRng> {
+    fn default() -> ShamirConfig<DefaultRng> {
+        ShamirConfig::new()
+    }
+}
+
+impl<R> ShamirConfig<R> {
+    // threshold sets the number of shares required to reconstruct the secret.
+    pub fn threshold(mut self, t: u8) -> ShamirConfig<R> {
+        self.threshold = t;
+        self
+    }
+
+    // share_count sets the number of shares to create.
+    pub fn share_count(mut self, n: u8) -> ShamirConfig<R> {
+        self.share_count = n;
+        self
+    }
+
+    // with_rng draws the polynomial coefficients from `rng` instead of
+    // default_rng.
+    pub fn with_rng<R2: CryptoRng + RngCore>(self, rng: R2) -> ShamirConfig<R2> {
+        ShamirConfig {
+            threshold: self.threshold,
+            share_count: self.share_count,
+            digest: self.digest,
+            padding: self.padding,
+            rng,
+        }
+    }
+
+    // with_os_rng draws the polynomial coefficients directly from the
+    // operating system's CSPRNG, as the os-rng feature does for the whole
+    // crate.
+    pub fn with_os_rng(self) -> ShamirConfig<OsRng> {
+        self.with_rng(OsRng)
+    }
+
+    // with_digest appends a truncated SHA-256 digest to the secret before
+    // splitting, so that combine can tell a correct reconstruction from one
+    // made with wrong, corrupt or too few shares, and returns DigestMismatch
+    // for the latter. Shares made with a digest must be combined with one.
+    pub fn with_digest(mut self, digest: bool) -> ShamirConfig<R> {
+        self.digest = digest;
+        self
+    }
+
+    // with_padding pads the secret (and its digest, if any) before splitting,
+    // so that the length of the shares doesn't reveal the exact length of the
+    // secret. combine removes the padding, so shares made with padding must
+    // be combined with it.
+    pub fn with_padding(mut self, padding: Padding) -> ShamirConfig<R> {
+        self.padding = Some(padding);
+        self
+    }
+
+    // validate checks the parameters: 1 <= t <= n.
+    pub fn validate(&self) -> Result<(), SecretSharingError> {
+        if self.threshold == 0 || self.share_count == 0 {
+            return Err(SecretSharingError::TorNisZero);
+        }
+        if self.threshold > self.share_count {
+            return Err(SecretSharingError::ThresholdExceedsShares);
+        }
+        Ok(())
+    }
+
+    // combine reconstructs the secret from `shares`, removing the padding and
+    // checking and removing the digest if the config has them.
+    pub fn combine(&self, shares: &[crate::Share]) -> Result<Vec<u8>, SecretSharingError> {
+        let mut secret = reconstruct(shares)?;
+        if self.padding.is_some() {
+            if let Err(err) = unpad(&mut secret) {
+                secret.zeroize();
+                // without a digest, bad padding is the only sign of a wrong
+                // reconstruction.
+                return Err(if self.digest {
+                    SecretSharingError::DigestMismatch
+                } else {
+                    err
+                });
+            }
+        }
+        if !self.digest {
+            return Ok(secret);
+        }
+
+        if secret.len() < DIGEST_LEN {
+            secret.zeroize();
+            return Err(SecretSharingError::DigestMismatch);
+        }
+        let len = secret.len() - DIGEST_LEN;
+        let mut digest = sha256::digest(&secret[..len]);
+        let ok = secrets_equal(&digest[..DIGEST_LEN], &secret[len..]);
+        digest.zeroize();
+        if !ok {
+            secret.zeroize();
+            return Err(SecretSharingError::DigestMismatch);
+        }
+        secret[len..].zeroize();
+        secret.truncate(len);
+        Ok(secret)
+    }
+}
+
+impl<R: CryptoRng + RngCore> ShamirConfig<R> {
+    // split validates the config and splits `secret` into shares.
+    pub fn split(&mut self, secret: &[u8]) -> Result<Shares, SecretSharingError> {
+        self.validate()?;
+        if !self.digest && self.padding.is_none() {
+            return construct_shares_with_rng(
+                self.threshold,
+                self.share_count,
+                secret,
+                &mut self.rng,
+            );
+        }
+
+        let mut data = Zeroizing::new(Vec::with_capacity(secret.len() + DIGEST_LEN));
+        data.extend_from_slice(secret);
+        if self.digest {
+            let digest = Zeroizing::new(sha256::digest(secret));
+            data.extend_from_slice(&digest[..DIGEST_LEN]);
+        }
+        if let Some(padding) = &self.padding {
+            data = padding.pad(&data)?;
+        }
+        construct_shares_with_rng(self.threshold, self.share_count, &data, &mut self.rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Share;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_config_split_combine() {
+        let secret = b"correct horse battery staple".to_vec();
+        let mut config = ShamirConfig::new().threshold(3).share_count(5);
+        let shares = config.split(&secret).unwrap();
+        assert_eq!(shares.len(), 5);
+        assert_eq!(config.combine(&shares[1..4]).unwrap(), secret);
+
+        let mut config = ShamirConfig::new()
+            .threshold(2)
+            .share_count(3)
+            .with_rng(ChaCha20Rng::from_seed([7; 32]))
+            .with_digest(true);
+        let shares = config.split(&secret).unwrap();
+        assert_eq!(shares[0].len(), secret.len() + DIGEST_LEN);
+        assert_eq!(config.combine(&shares[..2]).unwrap(), secret);
+
+        let mut os = ShamirConfig::new()
+            .threshold(2)
+            .share_count(3)
+            .with_digest(true)
+            .with_os_rng();
+        let shares = os.split(&secret).unwrap();
+        assert_eq!(os.combine(&shares[1..]).unwrap(), secret);
+
+        // a share without metadata can't be caught by the quorum check, but
+        // the digest catches the resulting garbage.
+        let stripped = Share::new(shares[0].index(), shares[0].as_bytes());
+        assert_eq!(
+            config.combine(&[stripped]).err(),
+            Some(SecretSharingError::DigestMismatch)
+        );
+    }
+    #[test]
+    fn test_config_padding() {
+        let block = Padding::Block(std::num::NonZeroUsize::new(32).unwrap());
+        for digest in [false, true] {
+            let mut config = ShamirConfig::new()
+                .threshold(2)
+                .share_count(3)
+                .with_digest(digest)
+                .with_padding(block.clone());
+            for len in [0, 1, 27, 28, 31, 32, 40] {
+                let secret = vec![0x5a; len];
+                let shares = config.split(&secret).unwrap();
+                assert!(shares[0].len().is_multiple_of(32));
+                assert_eq!(config.combine(&shares[1..]).unwrap(), secret);
+            }
+        }
+
+        let mut config = ShamirConfig::new()
+            .threshold(2)
+            .share_count(2)
+            .with_padding(Padding::Buckets(vec![16, 64]));
+        let short = config.split(b"short").unwrap();
+        let long = config.split(&[1; 20]).unwrap();
+        assert_eq!((short[0].len(), long[0].len()), (16, 64));
+        assert_eq!(
+            config.split(&[1; 64]).err(),
+            Some(SecretSharingError::PayloadTooLarge { max: 63 })
+        );
+
+        // shares whose reconstruction lacks valid padding are rejected.
+        let mut plain = ShamirConfig::new().threshold(2).share_count(2);
+        let shares = plain.split(b"not padded").unwrap();
+        assert_eq!(
+            config.combine(&shares).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+    }
+    #[test]
+    fn test_config_validate() {
+        assert_eq!(
+            ShamirConfig::new().threshold(3).split(b"x").err(),
+            Some(SecretSharingError::TorNisZero)
+        );
+        assert_eq!(
+            ShamirConfig::new()
+                .threshold(4)
+                .share_count(3)
+                .split(b"x")
+                .err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        assert!(ShamirConfig::new()
+            .threshold(255)
+            .share_count(255)
+            .validate()
+            .is_ok());
+    }
+}
@@ -0,0 +1,134 @@
+// Constant-time comparison of secret data. Comparing a reconstructed secret
+// against an expected value with `==` returns as soon as the first byte
+// differs, so how long it takes tells an attacker how much of a guess was
+// right. The comparisons here look at every byte whatever the contents; only
+// the lengths, which are not secret, can affect their running time.
+//
+// This module also holds reconstruct_ct, a reconstruction whose running time
+// depends on nothing secret. The ordinary reconstruct branches only on share
+// indices, thresholds, identifiers and lengths, which are all public: the
+// `m != j` filter and division in lagrange_coefficients compare x-coordinates,
+// and no check looks at a y-value. The y-values themselves are only ever
+// multiplied by the public lagrange basis, with the carry-less multiply
+// instructions where the CPU has them and bit-serial code where it doesn't,
+// both of which run in constant time. What reconstruct can't promise is that
+// this holds in every build: with the fast-tables feature the bit-serial
+// fallback becomes a table lookup indexed by share bytes, and which path runs
+// depends on the CPU. reconstruct_ct always uses the bit-serial multiply, in
+// every build and on every CPU, at the cost of speed.
+
+use crate::field::{lagrange_coefficients, Field};
+use crate::gf::{Gf256, AES_POLYNOMIAL};
+use crate::{check_shares, FixedShare, SecretSharingError, Share};
+use std::hint::black_box;
+
+// ConstantTimeEq is implemented by types holding secret data that can be
+// compared in time independent of their contents.
+pub trait ConstantTimeEq {
+    // ct_eq returns whether `self` and `other` are equal.
+    fn ct_eq(&self, other: &Self) -> bool;
+}
+
+impl ConstantTimeEq for [u8] {
+    fn ct_eq(&self, other: &[u8]) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        let diff = self
+            .iter()
+            .zip(other.iter())
+            .fold(0, |acc, (a, b)| acc | (a ^ b));
+        // keep the compiler from turning the fold into an early exit.
+        black_box(diff) == 0
+    }
+}
+
+impl<const L: usize> ConstantTimeEq for [u8; L] {
+    fn ct_eq(&self, other: &[u8; L]) -> bool {
+        self[..].ct_eq(&other[..])
+    }
+}
+
+impl ConstantTimeEq for Share {
+    // ct_eq compares the index and payload of two shares; the metadata is
+    // not secret and is ignored.
+    fn ct_eq(&self, other: &Share) -> bool {
+        self.x == other.x && self.y.ct_eq(&other.y)
+    }
+}
+
+impl<const L: usize> ConstantTimeEq for FixedShare<L> {
+    fn ct_eq(&self, other: &FixedShare<L>) -> bool {
+        self.index() == other.index() && self.as_bytes().ct_eq(other.as_bytes())
+    }
+}
+
+// secrets_equal returns whether the secrets `a` and `b` are equal, in time
+// independent of their contents.
+pub fn secrets_equal(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b)
+}
+
+// reconstruct_ct is reconstruct for when an attacker may time the
+// reconstruction of shares they don't hold, such as a server unsealing a key
+// from shares its operators submit. Its running time depends only on the
+// number of shares, their indices and their length, whatever features the
+// crate is built with. It returns the same errors as reconstruct.
+pub fn reconstruct_ct(shares: &[Share]) -> Result<Vec<u8>, SecretSharingError> {
+    let len = check_shares(shares)?;
+    let xs: Vec<u8> = shares.iter().map(|share| share.x).collect();
+    let basis = lagrange_coefficients(&xs, 0);
+
+    let mut secret = vec![0u8; len];
+    for (share, &phi) in shares.iter().zip(basis.iter()) {
+        let phi = Gf256::<AES_POLYNOMIAL>(phi);
+        for (s, &y) in secret.iter_mut().zip(share.y.iter()) {
+            *s ^= Gf256(y).mul(phi).0;
+        }
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{construct_shares, reconstruct};
+
+    #[test]
+    fn test_secrets_equal() {
+        assert!(secrets_equal(b"", b""));
+        assert!(secrets_equal(b"secret", b"secret"));
+        assert!(!secrets_equal(b"secret", b"secreT"));
+        assert!(!secrets_equal(b"secret", b"secrets"));
+        assert!([1u8, 2, 3].ct_eq(&[1, 2, 3]));
+    }
+    #[test]
+    fn test_share_ct_eq() {
+        let a = Share::with_metadata(1, &[1, 2, 3], 2, 3);
+        assert!(a.ct_eq(&Share::new(1, &[1, 2, 3])));
+        assert!(!a.ct_eq(&Share::new(2, &[1, 2, 3])));
+        assert!(!a.ct_eq(&Share::new(1, &[1, 2, 4])));
+
+        let f = FixedShare::new(1, [7u8; 4], 2);
+        assert!(f.ct_eq(&FixedShare::new(1, [7; 4], 3)));
+        assert!(!f.ct_eq(&FixedShare::new(1, [8; 4], 2)));
+    }
+    #[test]
+    fn test_reconstruct_ct() {
+        let secret: Vec<u8> = (0..=255).collect();
+        let shares = construct_shares(3, 5, &secret).unwrap();
+        assert_eq!(reconstruct_ct(&shares[1..4]).unwrap(), secret);
+        assert_eq!(
+            reconstruct_ct(&shares[2..]).unwrap(),
+            reconstruct(&shares[2..]).unwrap()
+        );
+        assert_eq!(
+            reconstruct_ct(&shares[..2]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+        assert_eq!(
+            reconstruct_ct(&[]).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+    }
+}
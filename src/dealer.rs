@@ -0,0 +1,356 @@
+// Dealer retains the sharing polynomials of a secret so that further shares
+// can be issued long after the initial split, without re-splitting and
+// invalidating the shares already handed out.
+
+use crate::{clmul, parallel, sha256, Redacted, SecretSharingError, Share};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
+
+// Dealer holds one polynomial of degree t-1 per secret byte. The constant term
+// of each polynomial is the secret byte itself, so a Dealer is exactly as
+// sensitive as the secret; its coefficients are wiped when it is dropped.
+#[derive(Zeroize)]
+#[zeroize(drop)]
+pub struct Dealer {
+    threshold: u8,
+    // identifier is recorded in every issued share. Dealers restored from
+    // version 1 state have none.
+    identifier: Option<u16>,
+    // coefficients of every polynomial, constant term first, stored
+    // contiguously: byte i uses coeff[i*t..(i+1)*t].
+    coeff: Coeffs,
+}
+
+// Coeffs holds a dealer's coefficients: in locked memory with the `mlock`
+// feature, on the heap otherwise.
+#[cfg(feature = "mlock")]
+type Coeffs = crate::LockedBuffer;
+#[cfg(not(feature = "mlock"))]
+type Coeffs = Vec<crate::gf::GF256e>;
+
+// alloc_coeffs returns `len` zeroed coefficients.
+#[cfg(feature = "mlock")]
+fn alloc_coeffs(len: usize) -> Result<Coeffs, SecretSharingError> {
+    crate::LockedBuffer::new(len)
+}
+
+#[cfg(not(feature = "mlock"))]
+fn alloc_coeffs(len: usize) -> Result<Coeffs, SecretSharingError> {
+    Ok(vec![0; len])
+}
+
+// Serialized dealer state. All multi-byte integers are big-endian.
+//
+//   offset  size   field
+//   0       4      magic, "SHMD"
+//   4       1      format version, currently 2
+//   5       1      threshold t
+//   6       2      split identifier
+//   8       4      secret length in bytes
+//   12      len*t  polynomial coefficients
+//   ...     4      checksum: the first 4 bytes of SHA-256 over everything above
+//
+// Version 1 has no identifier, so every later field sits two bytes earlier.
+const MAGIC: [u8; 4] = *b"SHMD";
+const VERSION: u8 = 2;
+const HEADER_LEN: usize = 12;
+const CHECKSUM_LEN: usize = 4;
+
+impl fmt::Debug for Dealer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Dealer")
+            .field("threshold", &self.threshold)
+            .field("coeff", &Redacted(self.coeff.len()))
+            .finish()
+    }
+}
+
+// random_identifier returns a random non-zero split identifier. Zero is left
+// out as the encodings use it for shares without one.
+pub(crate) fn random_identifier<R: CryptoRng + RngCore>(rng: &mut R) -> u16 {
+    (rng.next_u32() % u16::MAX as u32) as u16 + 1
+}
+
+impl Dealer {
+    // new creates a dealer for `secret` with threshold `t`, drawing the
+    // polynomial coefficients from default_rng.
+    pub fn new(t: u8, secret: &[u8]) -> Result<Dealer, SecretSharingError> {
+        Dealer::with_rng(t, secret, &mut crate::default_rng())
+    }
+
+    // with_rng creates a dealer for `secret` with threshold `t`, drawing the
+    // polynomial coefficients from `rng`.
+    pub fn with_rng<R: CryptoRng + RngCore>(
+        t: u8,
+        secret: &[u8],
+        rng: &mut R,
+    ) -> Result<Dealer, SecretSharingError> {
+        if t == 0 {
+            return Err(SecretSharingError::TorNisZero);
+        }
+
+        // draw every coefficient in a single call rather than a byte at a time,
+        // then overwrite the constant terms with the secret.
+        let mut coeff = alloc_coeffs(secret.len() * t as usize)?;
+        rng.fill_bytes(&mut coeff);
+        for (c, b) in coeff.chunks_mut(t as usize).zip(secret) {
+            c[0] = *b;
+        }
+
+        Ok(Dealer {
+            threshold: t,
+            identifier: Some(random_identifier(rng)),
+            coeff,
+        })
+    }
+
+    // threshold returns the number of shares required to reconstruct the
+    // secret.
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    // identifier returns the split identifier recorded in the issued shares.
+    pub fn identifier(&self) -> Option<u16> {
+        self.identifier
+    }
+
+    // issue returns the share for participant `x`. Issuing the same x twice
+    // returns the same share. x = 0 is rejected, as the polynomials evaluate to
+    // the secret there.
+    pub fn issue(&self, x: u8) -> Result<Share, SecretSharingError> {
+        if x == 0 {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+
+        // evaluate the polynomials with Horner's rule, a column of
+        // coefficients at a time, so the multiplications run in bulk.
+        let t = self.threshold as usize;
+        let mut y = vec![0u8; self.coeff.len() / t];
+        parallel::for_each_chunk(&mut y, |offset, y| {
+            let coeff = &self.coeff[offset * t..(offset + y.len()) * t];
+            for (y, c) in y.iter_mut().zip(coeff.iter().skip(t - 1).step_by(t)) {
+                *y = *c;
+            }
+            let mut next = vec![0u8; y.len()];
+            for k in (0..t - 1).rev() {
+                for (n, c) in next.iter_mut().zip(coeff.iter().skip(k).step_by(t)) {
+                    *n = *c;
+                }
+                clmul::mul_acc(&mut next, y, x);
+                y.copy_from_slice(&next);
+            }
+            next.zeroize();
+        });
+
+        Ok(Share {
+            x,
+            y,
+            threshold: Some(self.threshold),
+            share_count: None,
+            identifier: self.identifier,
+            label: None,
+            owner: None,
+        })
+    }
+
+    // to_bytes serializes the dealer state so it can be persisted and later
+    // restored with from_bytes. The output contains the secret in the clear
+    // and must be stored (or encrypted) accordingly; it is wiped when dropped.
+    pub fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        let len = self.coeff.len() / self.threshold as usize;
+        let mut out = Zeroizing::new(Vec::with_capacity(
+            HEADER_LEN + self.coeff.len() + CHECKSUM_LEN,
+        ));
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        out.push(self.threshold);
+        out.extend_from_slice(&self.identifier.unwrap_or(0).to_be_bytes());
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+        out.extend_from_slice(&self.coeff);
+        let sum = sha256::digest(&out);
+        out.extend_from_slice(&sum[..CHECKSUM_LEN]);
+        out
+    }
+
+    // from_bytes restores a dealer serialized with to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<Dealer, SecretSharingError> {
+        let header_len = match data.get(4) {
+            Some(1) => HEADER_LEN - 2,
+            Some(&VERSION) => HEADER_LEN,
+            _ => return Err(SecretSharingError::DealerStateInvalid),
+        };
+        if data.len() < header_len + CHECKSUM_LEN || data[..MAGIC.len()] != MAGIC || data[5] == 0 {
+            return Err(SecretSharingError::DealerStateInvalid);
+        }
+
+        let t = data[5];
+        let identifier = match header_len {
+            HEADER_LEN => match u16::from_be_bytes([data[6], data[7]]) {
+                0 => None,
+                id => Some(id),
+            },
+            _ => None,
+        };
+        let l = &data[header_len - 4..header_len];
+        let len = u32::from_be_bytes([l[0], l[1], l[2], l[3]]) as usize;
+        let body_len = data.len() - header_len - CHECKSUM_LEN;
+        if len.checked_mul(t as usize) != Some(body_len) {
+            return Err(SecretSharingError::DealerStateInvalid);
+        }
+
+        let (body, sum) = data.split_at(header_len + body_len);
+        if sha256::digest(body)[..CHECKSUM_LEN] != *sum {
+            return Err(SecretSharingError::DealerStateInvalid);
+        }
+
+        let mut coeff = alloc_coeffs(body_len)?;
+        coeff.copy_from_slice(&body[header_len..]);
+        Ok(Dealer {
+            threshold: t,
+            identifier,
+            coeff,
+        })
+    }
+}
+
+// SplitIter issues the shares of participants 1..n one at a time, as
+// returned by split_iter, so only the dealer's polynomials and the share
+// being handed out are held in memory.
+#[derive(Debug)]
+pub struct SplitIter {
+    dealer: Dealer,
+    next: u8,
+    n: u8,
+}
+
+impl SplitIter {
+    pub(crate) fn new(dealer: Dealer, n: u8) -> SplitIter {
+        SplitIter { dealer, next: 1, n }
+    }
+}
+
+impl Iterator for SplitIter {
+    type Item = Share;
+
+    fn next(&mut self) -> Option<Share> {
+        if self.next == 0 || self.next > self.n {
+            return None;
+        }
+        // issue only fails for x = 0, which is never reached.
+        let mut share = self.dealer.issue(self.next).ok()?;
+        share.share_count = Some(self.n);
+        // wraps to 0 after share 255, which ends the iteration.
+        self.next = self.next.wrapping_add(1);
+        Some(share)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = match self.next {
+            0 => 0,
+            next => (self.n as usize + 1).saturating_sub(next as usize),
+        };
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for SplitIter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{construct_shares, reconstruct};
+
+    #[test]
+    fn test_issue_later() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let dealer = Dealer::new(3, &secret).unwrap();
+        let early: Vec<Share> = (1..=3).map(|x| dealer.issue(x).unwrap()).collect();
+
+        let restored = Dealer::from_bytes(&dealer.to_bytes()).unwrap();
+        let late = [restored.issue(200).unwrap(), restored.issue(201).unwrap()];
+        assert!(dealer.identifier().is_some());
+        assert_eq!(late[1].identifier(), dealer.identifier());
+        assert_eq!(early[0].identifier(), dealer.identifier());
+
+        assert_eq!(reconstruct(&early).unwrap(), secret);
+        let mixed = vec![
+            Share::new(early[0].index(), early[0].as_bytes()),
+            Share::new(late[0].index(), late[0].as_bytes()),
+            Share::new(late[1].index(), late[1].as_bytes()),
+        ];
+        assert_eq!(reconstruct(&mixed).unwrap(), secret);
+    }
+    #[test]
+    fn test_split_iter() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let mut shares = crate::split_iter(2, 255, &secret).unwrap();
+        assert_eq!(shares.len(), 255);
+        let first = shares.next().unwrap();
+        assert_eq!(first.index(), 1);
+        assert_eq!(first.share_count(), Some(255));
+        assert_eq!(shares.len(), 254);
+        let last = shares.by_ref().last().unwrap();
+        assert_eq!(last.index(), 255);
+        assert!(shares.next().is_none());
+        assert_eq!(reconstruct(&[first, last]).unwrap(), secret);
+    }
+    #[test]
+    fn test_threshold_one() {
+        let secret = vec![0x01, 0x02];
+        let shares = construct_shares(1, 3, &secret).unwrap();
+        for share in shares.iter() {
+            assert_eq!(share.as_bytes(), &secret[..]);
+        }
+    }
+    #[test]
+    fn test_zeroize() {
+        let mut dealer = Dealer::new(3, &[0xaa; 16]).unwrap();
+        dealer.zeroize();
+        assert_eq!(dealer.threshold(), 0);
+        assert!(dealer.coeff.iter().all(|&c| c == 0));
+    }
+    #[test]
+    fn test_issue_zero() {
+        let dealer = Dealer::new(2, &[0x01]).unwrap();
+        assert_eq!(
+            dealer.issue(0).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+    }
+    #[test]
+    fn test_from_bytes_v1() {
+        // a version 1 state has no identifier.
+        let dealer = Dealer::new(2, &[0x01, 0x02]).unwrap();
+        let encoded = dealer.to_bytes();
+        let mut v1 = encoded[..HEADER_LEN + 4].to_vec();
+        v1.drain(6..8);
+        v1[4] = 1;
+        let sum = sha256::digest(&v1);
+        v1.extend_from_slice(&sum[..CHECKSUM_LEN]);
+        let restored = Dealer::from_bytes(&v1).unwrap();
+        assert_eq!(restored.identifier(), None);
+        assert_eq!(restored.issue(1).unwrap().identifier(), None);
+        assert_eq!(
+            restored.issue(1).unwrap().as_bytes(),
+            dealer.issue(1).unwrap().as_bytes()
+        );
+    }
+    #[test]
+    fn test_from_bytes_invalid() {
+        let dealer = Dealer::new(2, &[0x01, 0x02, 0x03]).unwrap();
+        let encoded = dealer.to_bytes();
+        for l in 0..encoded.len() {
+            assert!(Dealer::from_bytes(&encoded[..l]).is_err());
+        }
+        for i in 0..encoded.len() {
+            let mut corrupted = encoded.to_vec();
+            corrupted[i] ^= 0x80;
+            assert_eq!(
+                Dealer::from_bytes(&corrupted).err(),
+                Some(SecretSharingError::DealerStateInvalid)
+            );
+        }
+    }
+}
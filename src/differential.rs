@@ -0,0 +1,165 @@
+// Differential tests against a reference implementation of Shamir's scheme
+// over GF(2^8) with the AES polynomial, the convention shared by this crate
+// and most byte-oriented implementations. sharks and similar crates aren't
+// dependencies, so the reference is written here, as differently from the
+// crate as possible: multiplication by log/antilog tables rather than
+// shift-and-add or carry-less multiplication, textbook polynomial evaluation
+// with explicit powers rather than Horner's rule, and Lagrange interpolation
+// computing every basis polynomial from scratch. A bug in the field or the
+// interpolation would have to be made twice, the same way, to go unnoticed.
+
+use crate::{construct_shares_with_rng, reconstruct, recover_share, Share};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+// Reference is GF(2^8) modulo x^8 + x^4 + x^3 + x + 1, by log/antilog tables
+// with generator 3.
+struct Reference {
+    log: [u8; 256],
+    exp: [u8; 510],
+}
+
+impl Reference {
+    fn new() -> Reference {
+        let mut r = Reference {
+            log: [0; 256],
+            exp: [0; 510],
+        };
+        let mut x: u16 = 1;
+        for i in 0..255 {
+            r.exp[i] = x as u8;
+            r.exp[i + 255] = x as u8;
+            r.log[x as usize] = i as u8;
+            // multiply by the generator, x + 1.
+            x ^= x << 1;
+            if x > 0xff {
+                x ^= 0x11b;
+            }
+        }
+        r
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        assert_ne!(b, 0);
+        if a == 0 {
+            return 0;
+        }
+        self.exp[self.log[a as usize] as usize + 255 - self.log[b as usize] as usize]
+    }
+
+    fn pow(&self, a: u8, e: usize) -> u8 {
+        (0..e).fold(1, |acc, _| self.mul(acc, a))
+    }
+
+    // eval evaluates sum_k coeff[k] * x^k.
+    fn eval(&self, coeff: &[u8], x: u8) -> u8 {
+        coeff
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (k, &c)| acc ^ self.mul(c, self.pow(x, k)))
+    }
+
+    // interpolate returns the value at `at` of the polynomial through
+    // `points`.
+    fn interpolate(&self, points: &[(u8, u8)], at: u8) -> u8 {
+        let mut acc = 0;
+        for (i, &(xi, yi)) in points.iter().enumerate() {
+            let mut num = 1;
+            let mut den = 1;
+            for (j, &(xj, _)) in points.iter().enumerate() {
+                if i != j {
+                    num = self.mul(num, at ^ xj);
+                    den = self.mul(den, xi ^ xj);
+                }
+            }
+            acc ^= self.mul(yi, self.div(num, den));
+        }
+        acc
+    }
+
+    // split shares `secret` at x = 1..=n, byte i with polynomial coeffs[i].
+    fn split(&self, coeffs: &[Vec<u8>], n: u8) -> Vec<(u8, Vec<u8>)> {
+        (1..=n)
+            .map(|x| (x, coeffs.iter().map(|c| self.eval(c, x)).collect()))
+            .collect()
+    }
+
+    fn combine(&self, shares: &[(u8, Vec<u8>)], at: u8) -> Vec<u8> {
+        (0..shares[0].1.len())
+            .map(|i| {
+                let points: Vec<(u8, u8)> = shares.iter().map(|(x, y)| (*x, y[i])).collect();
+                self.interpolate(&points, at)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_reference_field() {
+    let r = Reference::new();
+    // FIPS-197 section 4.2: {57} * {83} = {c1}, {57} * {13} = {fe}.
+    assert_eq!(r.mul(0x57, 0x83), 0xc1);
+    assert_eq!(r.mul(0x57, 0x13), 0xfe);
+    for a in 1..=255 {
+        assert_eq!(r.mul(a, r.div(1, a)), 1);
+    }
+}
+
+#[test]
+fn test_split_here_combine_reference() {
+    let r = Reference::new();
+    let mut rng = ChaCha20Rng::from_seed([5; 32]);
+    for _ in 0..64 {
+        let n = rng.gen_range(1, 20);
+        let t = rng.gen_range(1, n + 1);
+        let secret: Vec<u8> = (0..rng.gen_range(1, 40)).map(|_| rng.gen()).collect();
+        let shares = construct_shares_with_rng(t, n, &secret, &mut rng).unwrap();
+
+        let ours: Vec<(u8, Vec<u8>)> = shares
+            .iter()
+            .map(|s| (s.index(), s.as_bytes().to_vec()))
+            .collect();
+        let k = t as usize;
+        assert_eq!(r.combine(&ours[ours.len() - k..], 0), secret);
+        // every share lies on the same polynomial of degree t-1.
+        for (x, y) in ours.iter() {
+            assert_eq!(r.combine(&ours[..k], *x), *y);
+        }
+    }
+}
+
+#[test]
+fn test_split_reference_combine_here() {
+    let r = Reference::new();
+    let mut rng = ChaCha20Rng::from_seed([6; 32]);
+    for _ in 0..64 {
+        let n = rng.gen_range(1, 20);
+        let t = rng.gen_range(1, n + 1);
+        let secret: Vec<u8> = (0..rng.gen_range(1, 40)).map(|_| rng.gen()).collect();
+        let coeffs: Vec<Vec<u8>> = secret
+            .iter()
+            .map(|&s| {
+                let mut c: Vec<u8> = (0..t).map(|_| rng.gen()).collect();
+                c[0] = s;
+                c
+            })
+            .collect();
+        let theirs: Vec<Share> = r
+            .split(&coeffs, n)
+            .iter()
+            .map(|(x, y)| Share::new(*x, y))
+            .collect();
+
+        let k = t as usize;
+        assert_eq!(reconstruct(&theirs[..k]).unwrap(), secret);
+        let recovered = recover_share(&theirs[n as usize - k..], 1).unwrap();
+        assert_eq!(recovered.as_bytes(), theirs[0].as_bytes());
+    }
+}
@@ -0,0 +1,571 @@
+// Distributed key generation after Pedersen ("A Threshold Cryptosystem
+// without a Trusted Party", EUROCRYPT '91): n participants, numbered 1..n,
+// each deal a random Ed25519 scalar with the Feldman VSS of vss.rs, and every
+// participant adds up the shares it received. The result is a t-of-n sharing
+// of the sum of the dealt scalars, which no participant ever knew.
+//
+// The protocol runs in three steps, relying on an authenticated broadcast
+// channel and authenticated private channels between participants:
+//
+//  1. Every participant creates a DkgParticipant, broadcasts its dealing and
+//     sends each other participant its share_for them. On receiving a
+//     dealing and share, a participant checks them with receive, and
+//     broadcasts a complaint against the dealer if the share doesn't verify
+//     (or never arrives).
+//  2. A dealer answers every complaint against it by broadcasting the
+//     complainant's share. Every participant resolves every complaint with
+//     the answer, if any: a dealer that doesn't answer, or answers with a
+//     share that doesn't verify either, is disqualified.
+//  3. finish adds up the shares of the dealers that weren't disqualified,
+//     and their commitments, coefficient by coefficient, into commitments to
+//     the joint polynomial. Every participant computes the same commitments,
+//     whose C_0 is the joint public key, and every share of the joint secret
+//     verifies against them with verify_share.
+//
+// As Gennaro, Jarecki, Krawczyk and Rabin showed ("Secure Distributed Key
+// Generation for Discrete-Log Based Cryptosystems", 2007), a participant who
+// sees the other dealings before publishing its own can bias the
+// distribution of the public key, by getting itself disqualified on purpose.
+// This doesn't reveal the secret, and suffices for keys used with Schnorr
+// signatures such as Ed25519.
+//
+// The messages travel in the format of message.rs: a dealing's fixed field
+// is the dealer and its payload the encoded commitments, a share's fields
+// are the dealer and recipient and its payload the 32-byte scalar, and a
+// complaint's fields are the complainant and the accused dealer, with no
+// payload.
+
+use crate::ed25519::Point;
+use crate::field::{evaluate, wipe, Field, FieldShare};
+use crate::message::{self, DKG_COMPLAINT, DKG_DEALING, DKG_SHARE};
+use crate::prime::Ed25519Scalar;
+use crate::vss::{base_mul, check_parameters, random_polynomial, scalar_bytes, x_coordinate};
+use crate::{Commitments, Redacted, SecretSharingError};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+use zeroize::Zeroize;
+
+// DkgDealing is a participant's broadcast commitments to its polynomial.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DkgDealing {
+    from: u8,
+    commitments: Commitments,
+}
+
+// DkgShare is a dealer's share for one participant, sent privately, or
+// broadcast to answer a complaint.
+#[derive(Clone, PartialEq)]
+pub struct DkgShare {
+    from: u8,
+    to: u8,
+    value: Ed25519Scalar,
+}
+
+// DkgComplaint is a participant's broadcast complaint that a dealer's share
+// for it didn't verify, or didn't arrive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DkgComplaint {
+    from: u8,
+    against: u8,
+}
+
+// DkgParticipant is one participant's state during the protocol. It holds
+// its own polynomial and the shares it received, which are wiped when it is
+// dropped.
+pub struct DkgParticipant {
+    t: u8,
+    index: u8,
+    coeff: Vec<Ed25519Scalar>,
+    dealings: Vec<Option<Commitments>>,
+    shares: Vec<Option<Ed25519Scalar>>,
+    disqualified: Vec<bool>,
+}
+
+impl fmt::Debug for DkgShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DkgShare")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("value", &Redacted(32))
+            .finish()
+    }
+}
+
+impl fmt::Debug for DkgParticipant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DkgParticipant")
+            .field("index", &self.index)
+            .field("threshold", &self.t)
+            .field("participants", &self.dealings.len())
+            .finish()
+    }
+}
+
+impl Drop for DkgShare {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl Drop for DkgParticipant {
+    fn drop(&mut self) {
+        wipe(&mut self.coeff);
+        for share in self.shares.iter_mut().flatten() {
+            share.zeroize();
+        }
+    }
+}
+
+impl DkgDealing {
+    pub fn sender(&self) -> u8 {
+        self.from
+    }
+
+    // to_bytes encodes the dealing in the format described in message.rs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        message::encode(DKG_DEALING, &[self.from], &self.commitments.to_bytes())
+    }
+
+    // from_bytes decodes a dealing produced by to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<DkgDealing, SecretSharingError> {
+        let (fields, payload) = message::decode(DKG_DEALING, 1, data)?;
+        if fields[0] == 0 {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        Ok(DkgDealing {
+            from: fields[0],
+            commitments: Commitments::from_bytes(payload)?,
+        })
+    }
+}
+
+impl DkgShare {
+    pub fn sender(&self) -> u8 {
+        self.from
+    }
+
+    pub fn recipient(&self) -> u8 {
+        self.to
+    }
+
+    // to_bytes encodes the share in the format described in message.rs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut value = scalar_bytes(&self.value);
+        let out = message::encode(DKG_SHARE, &[self.from, self.to], &value);
+        value.zeroize();
+        out
+    }
+
+    // from_bytes decodes a share produced by to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<DkgShare, SecretSharingError> {
+        let (fields, payload) = message::decode(DKG_SHARE, 2, data)?;
+        if fields.contains(&0) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let value = match payload.len() {
+            32 => Ed25519Scalar::from_bytes(payload).ok_or(SecretSharingError::ShareMalformed)?,
+            _ => return Err(SecretSharingError::ShareMalformed),
+        };
+        Ok(DkgShare {
+            from: fields[0],
+            to: fields[1],
+            value,
+        })
+    }
+}
+
+impl DkgComplaint {
+    pub fn sender(&self) -> u8 {
+        self.from
+    }
+
+    // accused returns the dealer complained against.
+    pub fn accused(&self) -> u8 {
+        self.against
+    }
+
+    // to_bytes encodes the complaint in the format described in message.rs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        message::encode(DKG_COMPLAINT, &[self.from, self.against], &[])
+    }
+
+    // from_bytes decodes a complaint produced by to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<DkgComplaint, SecretSharingError> {
+        let (fields, payload) = message::decode(DKG_COMPLAINT, 2, data)?;
+        if fields.contains(&0) || fields[0] == fields[1] || !payload.is_empty() {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        Ok(DkgComplaint {
+            from: fields[0],
+            against: fields[1],
+        })
+    }
+}
+
+impl DkgParticipant {
+    // new starts participant `index` of a key generation among `n`
+    // participants, any `t` of whom will be able to use the key.
+    pub fn new(t: u8, n: u8, index: u8) -> Result<DkgParticipant, SecretSharingError> {
+        DkgParticipant::new_with_rng(t, n, index, &mut crate::default_rng())
+    }
+
+    // new_with_rng is like new, but draws the participant's polynomial from
+    // `rng`.
+    pub fn new_with_rng<R: CryptoRng + RngCore>(
+        t: u8,
+        n: u8,
+        index: u8,
+        rng: &mut R,
+    ) -> Result<DkgParticipant, SecretSharingError> {
+        check_parameters(t, n)?;
+        if index == 0 || index > n {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        let secret = Ed25519Scalar::random(rng);
+        let mut participant = DkgParticipant {
+            t,
+            index,
+            coeff: random_polynomial(t, secret, rng),
+            dealings: vec![None; n as usize],
+            shares: vec![None; n as usize],
+            disqualified: vec![false; n as usize],
+        };
+        let own = participant.index as usize - 1;
+        participant.dealings[own] = Some(participant.dealing().commitments);
+        participant.shares[own] = Some(participant.share_for(index)?.value);
+        Ok(participant)
+    }
+
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    // dealing returns the commitments to broadcast in step 1.
+    pub fn dealing(&self) -> DkgDealing {
+        DkgDealing {
+            from: self.index,
+            commitments: Commitments(self.coeff.iter().map(base_mul).collect()),
+        }
+    }
+
+    // share_for returns the share to send participant `to` in step 1.
+    pub fn share_for(&self, to: u8) -> Result<DkgShare, SecretSharingError> {
+        self.check_participant(to)?;
+        Ok(DkgShare {
+            from: self.index,
+            to,
+            value: evaluate(&self.coeff, x_coordinate(to)),
+        })
+    }
+
+    // receive checks a dealing and the share its dealer sent this
+    // participant, or None if none arrived. It returns ShareTampered, naming
+    // the dealer, if the share is missing or doesn't verify, in which case
+    // this participant should broadcast a complaint, and UnexpectedMessage
+    // for a share addressed to someone else, or a second dealing from the
+    // same dealer.
+    pub fn receive(
+        &mut self,
+        dealing: &DkgDealing,
+        share: Option<&DkgShare>,
+    ) -> Result<(), SecretSharingError> {
+        self.check_participant(dealing.from)?;
+        let from = dealing.from as usize - 1;
+        if self.dealings[from].is_some()
+            || share.is_some_and(|s| s.from != dealing.from || s.to != self.index)
+        {
+            return Err(SecretSharingError::UnexpectedMessage);
+        }
+        self.dealings[from] = Some(dealing.commitments.clone());
+        match share {
+            Some(share) if self.verify(share) => {
+                self.shares[from] = Some(share.value);
+                Ok(())
+            }
+            _ => Err(SecretSharingError::ShareTampered {
+                index: dealing.from,
+            }),
+        }
+    }
+
+    // complain returns a complaint against dealer `against`, to broadcast in
+    // step 1.
+    pub fn complain(&self, against: u8) -> Result<DkgComplaint, SecretSharingError> {
+        self.check_participant(against)?;
+        if against == self.index {
+            return Err(SecretSharingError::UnexpectedMessage);
+        }
+        Ok(DkgComplaint {
+            from: self.index,
+            against,
+        })
+    }
+
+    // answer returns the share to broadcast in answer to `complaint` in step
+    // 2, or UnexpectedMessage if the complaint is against someone else.
+    pub fn answer(&self, complaint: &DkgComplaint) -> Result<DkgShare, SecretSharingError> {
+        if complaint.against != self.index {
+            return Err(SecretSharingError::UnexpectedMessage);
+        }
+        self.share_for(complaint.from)
+    }
+
+    // resolve settles `complaint` with the accused dealer's broadcast
+    // `answer`, or None if it didn't answer, disqualifying the dealer unless
+    // the answer verifies against its dealing. If this participant made the
+    // complaint, it keeps the answered share.
+    pub fn resolve(
+        &mut self,
+        complaint: &DkgComplaint,
+        answer: Option<&DkgShare>,
+    ) -> Result<(), SecretSharingError> {
+        self.check_participant(complaint.from)?;
+        self.check_participant(complaint.against)?;
+        let against = complaint.against as usize - 1;
+        let answered = answer.filter(|share| {
+            share.from == complaint.against
+                && share.to == complaint.from
+                && self.dealings[against].is_some()
+                && self.verify(share)
+        });
+        match answered {
+            Some(share) if share.to == self.index => self.shares[against] = Some(share.value),
+            Some(_) => {}
+            None => self.disqualified[against] = true,
+        }
+        Ok(())
+    }
+
+    // qualified returns the dealers that haven't been disqualified.
+    pub fn qualified(&self) -> Vec<u8> {
+        (1..=self.dealings.len() as u8)
+            .filter(|&i| !self.disqualified[i as usize - 1])
+            .collect()
+    }
+
+    // finish completes step 3, returning the commitments to the joint
+    // polynomial, which every participant computes alike, and this
+    // participant's share of the joint secret. It returns NotEnoughShares if
+    // a dealing or share of a dealer that wasn't disqualified is missing.
+    pub fn finish(&self) -> Result<(Commitments, FieldShare<Ed25519Scalar>), SecretSharingError> {
+        let qualified = self.qualified();
+        let received = qualified
+            .iter()
+            .filter(|&&i| self.shares[i as usize - 1].is_some())
+            .count();
+        if received < qualified.len() {
+            return Err(SecretSharingError::NotEnoughShares {
+                have: received,
+                need: qualified.len(),
+            });
+        }
+
+        let mut points = vec![Point::IDENTITY; self.t as usize];
+        let mut y = Ed25519Scalar::zero();
+        for i in qualified {
+            let i = i as usize - 1;
+            let dealing = self.dealings[i].as_ref().unwrap();
+            for (acc, c) in points.iter_mut().zip(dealing.0.iter()) {
+                *acc = acc.add(c);
+            }
+            y = y.add(self.shares[i].unwrap());
+        }
+        Ok((
+            Commitments(points),
+            FieldShare {
+                x: x_coordinate(self.index),
+                y: vec![y],
+            },
+        ))
+    }
+
+    // check_participant returns InvalidShareIndex unless `i` numbers one of
+    // the participants.
+    fn check_participant(&self, i: u8) -> Result<(), SecretSharingError> {
+        if i == 0 || i as usize > self.dealings.len() {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        Ok(())
+    }
+
+    // verify checks that `share` lies on its dealer's committed polynomial,
+    // which must be of degree t-1.
+    fn verify(&self, share: &DkgShare) -> bool {
+        match &self.dealings[share.from as usize - 1] {
+            Some(commitments) if commitments.threshold() == self.t => {
+                base_mul(&share.value).compress()
+                    == commitments.expected(x_coordinate(share.to)).compress()
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vss::{reconstruct_verified, verify_share};
+
+    fn participants(t: u8, n: u8) -> Vec<DkgParticipant> {
+        (1..=n)
+            .map(|i| DkgParticipant::new(t, n, i).unwrap())
+            .collect()
+    }
+
+    // exchange runs step 1 between honest participants.
+    fn exchange(participants: &mut [DkgParticipant]) {
+        let dealings: Vec<DkgDealing> = participants.iter().map(|p| p.dealing()).collect();
+        for from in 0..participants.len() {
+            for to in 0..participants.len() {
+                if from != to {
+                    let share = participants[from].share_for(to as u8 + 1).unwrap();
+                    participants[to]
+                        .receive(&dealings[from], Some(&share))
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dkg() {
+        let mut participants = participants(3, 5);
+        exchange(&mut participants);
+        let results: Vec<_> = participants.iter().map(|p| p.finish().unwrap()).collect();
+        let commitments = &results[0].0;
+        assert_eq!(commitments.threshold(), 3);
+        for (c, share) in results.iter() {
+            assert_eq!(c, commitments);
+            verify_share(commitments, share).unwrap();
+        }
+
+        let shares: Vec<_> = results.iter().map(|(_, share)| share.clone()).collect();
+        let secret = reconstruct_verified(commitments, &shares[..3]).unwrap();
+        assert_eq!(
+            reconstruct_verified(commitments, &shares[2..]).unwrap(),
+            secret
+        );
+        assert_eq!(base_mul(&secret).compress(), commitments.public_key());
+        // the joint secret is the sum of the dealt secrets.
+        let sum = participants
+            .iter()
+            .fold(Ed25519Scalar::zero(), |acc, p| acc.add(p.coeff[0]));
+        assert_eq!(secret, sum);
+    }
+    #[test]
+    fn test_dkg_complaints() {
+        let (t, n) = (2, 4);
+        let mut participants = participants(t, n);
+        let dealings: Vec<DkgDealing> = participants.iter().map(|p| p.dealing()).collect();
+        let mut complaints = Vec::new();
+        for from in 0..n as usize {
+            for to in 0..n as usize {
+                if from == to {
+                    continue;
+                }
+                let mut share = participants[from].share_for(to as u8 + 1).unwrap();
+                // dealers 2 and 3 send participant 1 a bad share.
+                if to == 0 && (from == 1 || from == 2) {
+                    share.value = share.value.add(Ed25519Scalar::one());
+                }
+                match participants[to].receive(&dealings[from], Some(&share)) {
+                    Ok(()) => {}
+                    Err(SecretSharingError::ShareTampered { index }) => {
+                        complaints.push(participants[to].complain(index).unwrap())
+                    }
+                    Err(e) => panic!("{:?}", e),
+                }
+            }
+        }
+        assert_eq!(
+            complaints,
+            [
+                DkgComplaint {
+                    from: 1,
+                    against: 2
+                },
+                DkgComplaint {
+                    from: 1,
+                    against: 3
+                },
+            ]
+        );
+
+        // dealer 2 answers its complaint honestly, dealer 3 doesn't.
+        let answer = participants[1].answer(&complaints[0]).unwrap();
+        assert_eq!(
+            participants[2].answer(&complaints[0]).err(),
+            Some(SecretSharingError::UnexpectedMessage)
+        );
+        for p in participants.iter_mut() {
+            p.resolve(&complaints[0], Some(&answer)).unwrap();
+            p.resolve(&complaints[1], None).unwrap();
+        }
+
+        let results: Vec<_> = participants.iter().map(|p| p.finish().unwrap()).collect();
+        for ((c, share), p) in results.iter().zip(participants.iter()) {
+            assert_eq!(p.qualified(), [1, 2, 4]);
+            assert_eq!(c, &results[0].0);
+            verify_share(c, share).unwrap();
+        }
+        let shares: Vec<_> = results.iter().map(|(_, share)| share.clone()).collect();
+        let secret = reconstruct_verified(&results[0].0, &shares[1..3]).unwrap();
+        let sum = [0, 1, 3].iter().fold(Ed25519Scalar::zero(), |acc, &i| {
+            acc.add(participants[i].coeff[0])
+        });
+        assert_eq!(secret, sum);
+    }
+    #[test]
+    fn test_dkg_missing_share() {
+        let mut participants = participants(2, 3);
+        let dealing = participants[1].dealing();
+        let share = participants[1].share_for(1).unwrap();
+        participants[0].receive(&dealing, Some(&share)).unwrap();
+        assert_eq!(
+            participants[0].receive(&dealing, Some(&share)).err(),
+            Some(SecretSharingError::UnexpectedMessage)
+        );
+        assert_eq!(
+            participants[0].finish().err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+        // a dealing of the wrong degree is resolved as a bad share.
+        let other = DkgParticipant::new(3, 3, 3).unwrap();
+        assert_eq!(
+            participants[0]
+                .receive(&other.dealing(), Some(&other.share_for(1).unwrap()))
+                .err(),
+            Some(SecretSharingError::ShareTampered { index: 3 })
+        );
+        let complaint = participants[0].complain(3).unwrap();
+        participants[0]
+            .resolve(&complaint, Some(&other.answer(&complaint).unwrap()))
+            .unwrap();
+        assert_eq!(participants[0].qualified(), [1, 2]);
+        assert!(participants[0].finish().is_ok());
+    }
+    #[test]
+    fn test_dkg_encoding() {
+        let participants = participants(2, 3);
+        let dealing = participants[0].dealing();
+        assert_eq!(DkgDealing::from_bytes(&dealing.to_bytes()), Ok(dealing));
+        let share = participants[0].share_for(2).unwrap();
+        let bytes = share.to_bytes();
+        assert_eq!(DkgShare::from_bytes(&bytes), Ok(share));
+        let complaint = participants[1].complain(1).unwrap();
+        assert_eq!(
+            DkgComplaint::from_bytes(&complaint.to_bytes()),
+            Ok(complaint)
+        );
+
+        assert_eq!(
+            DkgComplaint::from_bytes(&bytes).err(),
+            Some(SecretSharingError::UnexpectedMessage)
+        );
+        let bad = message::encode(DKG_SHARE, &[1, 2], &[0xff; 32]);
+        assert_eq!(
+            DkgShare::from_bytes(&bad).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+    }
+}
@@ -0,0 +1,373 @@
+// Ed25519 signatures (RFC 8032), on the field arithmetic of x25519.rs and
+// SHA-512. Points are kept in extended coordinates (X : Y : Z : T) with
+// x = X/Z, y = Y/Z and xy = T/Z. Signing multiplies by secret scalars, so
+// scalar multiplication and reduction mod l run in constant time.
+
+use crate::sha512::{self, Sha512};
+use crate::x25519::Fe;
+use std::convert::TryInto;
+use zeroize::Zeroize;
+
+pub const PUBLIC_KEY_LEN: usize = 32;
+pub const SIGNATURE_LEN: usize = 64;
+
+// D and D2 are the curve constant d = -121665/121666 and 2d, and SQRT_M1 a
+// square root of -1.
+const D: Fe = Fe([
+    0x34dca135978a3,
+    0x1a8283b156ebd,
+    0x5e7a26001c029,
+    0x739c663a03cbb,
+    0x52036cee2b6ff,
+]);
+const D2: Fe = Fe([
+    0x69b9426b2f159,
+    0x35050762add7a,
+    0x3cf44c0038052,
+    0x6738cc7407977,
+    0x2406d9dc56dff,
+]);
+const SQRT_M1: Fe = Fe([
+    0x61b274a0ea0b0,
+    0xd5a5fc8f189d,
+    0x7ef5e9cbd0c60,
+    0x78595a6804c9e,
+    0x2b8324804fc1d,
+]);
+
+// L is the order of the base point, 2^252 + 27742317777372353535851937790883648493.
+const L: [u64; 4] = [
+    0x5812631a5cf5d3ed,
+    0x14def9dea2f79cd6,
+    0,
+    0x1000000000000000,
+];
+
+// BASE is the encoding of the base point, y = 4/5.
+const BASE: [u8; 32] = [
+    0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+    0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+];
+
+// Point is a point of edwards25519. The VSS schemes of vss.rs commit to
+// polynomials in the prime-order subgroup generated by the base point.
+#[derive(Clone, Copy)]
+pub(crate) struct Point {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+    t: Fe,
+}
+
+impl Point {
+    pub(crate) const IDENTITY: Point = Point {
+        x: Fe::ZERO,
+        y: Fe::ONE,
+        z: Fe::ONE,
+        t: Fe::ZERO,
+    };
+
+    pub(crate) fn base() -> Point {
+        Point::decompress(&BASE).unwrap()
+    }
+
+    // add is the unified addition of RFC 8032 section 5.1.4, which also
+    // doubles.
+    pub(crate) fn add(&self, q: &Point) -> Point {
+        let a = self.y.sub(self.x).mul(q.y.sub(q.x));
+        let b = self.y.add(self.x).mul(q.y.add(q.x));
+        let c = self.t.mul(D2).mul(q.t);
+        let zz = self.z.mul(q.z);
+        let d = zz.add(zz);
+        let (e, f, g, h) = (b.sub(a), d.sub(c), d.add(c), b.add(a));
+        Point {
+            x: e.mul(f),
+            y: g.mul(h),
+            z: f.mul(g),
+            t: e.mul(h),
+        }
+    }
+
+    pub(crate) fn neg(&self) -> Point {
+        Point {
+            x: self.x.neg(),
+            y: self.y,
+            z: self.z,
+            t: self.t.neg(),
+        }
+    }
+
+    // mul returns [k]self for the little-endian scalar `k`, doubling and
+    // adding for every bit so that the time taken doesn't depend on k.
+    pub(crate) fn mul(&self, k: &[u8; 32]) -> Point {
+        let mut r = Point::IDENTITY;
+        for i in (0..256).rev() {
+            r = r.add(&r);
+            let mut s = r.add(self);
+            let bit = ((k[i / 8] >> (i % 8)) & 1) as u64;
+            Fe::cswap(bit, &mut r.x, &mut s.x);
+            Fe::cswap(bit, &mut r.y, &mut s.y);
+            Fe::cswap(bit, &mut r.z, &mut s.z);
+            Fe::cswap(bit, &mut r.t, &mut s.t);
+        }
+        r
+    }
+
+    pub(crate) fn compress(&self) -> [u8; 32] {
+        let zinv = self.z.invert();
+        let mut out = self.y.mul(zinv).to_bytes();
+        out[31] |= (self.x.mul(zinv).is_negative() as u8) << 7;
+        out
+    }
+
+    // decompress decodes a point, rejecting non-canonical encodings and
+    // encodings of no point (RFC 8032 section 5.1.3).
+    pub(crate) fn decompress(b: &[u8; 32]) -> Option<Point> {
+        let y = Fe::from_bytes(b);
+        let mut canonical = *b;
+        canonical[31] &= 0x7f;
+        if y.to_bytes() != canonical {
+            return None;
+        }
+        let sign = b[31] >> 7 == 1;
+
+        let yy = y.square();
+        let u = yy.sub(Fe::ONE);
+        let v = D.mul(yy).add(Fe::ONE);
+        let v3 = v.square().mul(v);
+        let mut x = u.mul(v3).mul(u.mul(v3.square().mul(v)).pow22523());
+        let vxx = v.mul(x.square());
+        if !vxx.ct_eq(u) {
+            if !vxx.ct_eq(u.neg()) {
+                return None;
+            }
+            x = x.mul(SQRT_M1);
+        }
+        if x.ct_eq(Fe::ZERO) && sign {
+            return None;
+        }
+        if x.is_negative() != sign {
+            x = x.neg();
+        }
+        Some(Point {
+            x,
+            y,
+            z: Fe::ONE,
+            t: x.mul(y),
+        })
+    }
+
+    // is_torsion_free reports whether the point is in the subgroup of order
+    // L, rather than having a component in the small subgroup of order 8.
+    #[cfg(feature = "vss")]
+    pub(crate) fn is_torsion_free(&self) -> bool {
+        self.mul(&scalar_bytes(&L)).compress() == Point::IDENTITY.compress()
+    }
+}
+
+// sub_l subtracts L from `r` if r >= L, in constant time.
+fn sub_l(r: &mut [u64; 4]) {
+    let mut diff = [0u64; 4];
+    let mut borrow = 0u64;
+    for i in 0..4 {
+        let (d, b1) = r[i].overflowing_sub(L[i]);
+        let (d, b2) = d.overflowing_sub(borrow);
+        diff[i] = d;
+        borrow = (b1 | b2) as u64;
+    }
+    // keep the difference only if it didn't borrow.
+    let mask = borrow.wrapping_sub(1);
+    for (r, d) in r.iter_mut().zip(diff.iter()) {
+        *r = (*r & !mask) | (d & mask);
+    }
+}
+
+// reduce returns the little-endian 512-bit `wide` mod L, shifting it in one
+// bit at a time.
+fn reduce(wide: &[u64; 8]) -> [u64; 4] {
+    let mut r = [0u64; 4];
+    for i in (0..512).rev() {
+        // r < L < 2^253, so doubling it can't overflow.
+        for j in (1..4).rev() {
+            r[j] = (r[j] << 1) | (r[j - 1] >> 63);
+        }
+        r[0] = (r[0] << 1) | ((wide[i / 64] >> (i % 64)) & 1);
+        sub_l(&mut r);
+    }
+    r
+}
+
+fn words<const N: usize>(b: &[u8]) -> [u64; N] {
+    let mut w = [0u64; N];
+    for (w, c) in w.iter_mut().zip(b.chunks_exact(8)) {
+        *w = u64::from_le_bytes(c.try_into().unwrap());
+    }
+    w
+}
+
+fn scalar_bytes(s: &[u64; 4]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (o, w) in out.chunks_exact_mut(8).zip(s.iter()) {
+        o.copy_from_slice(&w.to_le_bytes());
+    }
+    out
+}
+
+// hash_scalar returns SHA-512 of the concatenated `parts`, mod L.
+pub(crate) fn hash_scalar(parts: &[&[u8]]) -> [u8; 32] {
+    let mut h = Sha512::new();
+    for p in parts {
+        h.update(p);
+    }
+    let mut digest = h.finalize();
+    let out = scalar_bytes(&reduce(&words(&digest)));
+    digest.zeroize();
+    out
+}
+
+// mul_add returns (a * b + c) mod L.
+fn mul_add(a: &[u8; 32], b: &[u8; 32], c: &[u8; 32]) -> [u8; 32] {
+    let (a, b): ([u64; 4], [u64; 4]) = (words(a), words(b));
+    let mut wide = [0u64; 8];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let t = a[i] as u128 * b[j] as u128 + wide[i + j] as u128 + carry;
+            wide[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        wide[i + 4] = carry as u64;
+    }
+    let mut carry = 0u128;
+    for (w, c) in wide
+        .iter_mut()
+        .zip(words::<4>(c).iter().chain([0; 4].iter()))
+    {
+        let t = *w as u128 + *c as u128 + carry;
+        *w = t as u64;
+        carry = t >> 64;
+    }
+    let mut r = reduce(&wide);
+    let out = scalar_bytes(&r);
+    wide.zeroize();
+    r.zeroize();
+    out
+}
+
+// expand returns the clamped secret scalar and the nonce prefix for `seed`.
+fn expand(seed: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    let mut h = sha512::digest(seed);
+    let mut a: [u8; 32] = h[..32].try_into().unwrap();
+    a[0] &= 248;
+    a[31] &= 127;
+    a[31] |= 64;
+    let prefix = h[32..].try_into().unwrap();
+    h.zeroize();
+    (a, prefix)
+}
+
+// public_key returns the public key for the secret key `seed`.
+pub fn public_key(seed: &[u8; 32]) -> [u8; PUBLIC_KEY_LEN] {
+    let (mut a, mut prefix) = expand(seed);
+    let public = Point::base().mul(&a).compress();
+    a.zeroize();
+    prefix.zeroize();
+    public
+}
+
+// sign returns the signature of `msg` under the secret key `seed`.
+pub fn sign(seed: &[u8; 32], msg: &[u8]) -> [u8; SIGNATURE_LEN] {
+    let (mut a, mut prefix) = expand(seed);
+    let base = Point::base();
+    let public = base.mul(&a).compress();
+    let mut r = hash_scalar(&[&prefix, msg]);
+    let big_r = base.mul(&r).compress();
+    let k = hash_scalar(&[&big_r, &public, msg]);
+
+    let mut sig = [0u8; SIGNATURE_LEN];
+    sig[..32].copy_from_slice(&big_r);
+    sig[32..].copy_from_slice(&mul_add(&k, &a, &r));
+    a.zeroize();
+    prefix.zeroize();
+    r.zeroize();
+    sig
+}
+
+// verify checks `sig` over `msg` under `public`, with the cofactorless
+// equation [S]B = R + [k]A.
+pub fn verify(public: &[u8; PUBLIC_KEY_LEN], msg: &[u8], sig: &[u8; SIGNATURE_LEN]) -> bool {
+    let s: [u8; 32] = sig[32..].try_into().unwrap();
+    // S must be canonical: S < L.
+    let mut reduced = words::<4>(&s);
+    sub_l(&mut reduced);
+    if scalar_bytes(&reduced) != s {
+        return false;
+    }
+    let a = match Point::decompress(public) {
+        Some(a) => a,
+        None => return false,
+    };
+    let k = hash_scalar(&[&sig[..32], public, msg]);
+    let check = Point::base().mul(&s).add(&a.neg().mul(&k));
+    check.compress()[..] == sig[..32]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::hex_decode;
+
+    fn hex<const N: usize>(s: &str) -> [u8; N] {
+        hex_decode(s).unwrap()[..].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_rfc8032_vectors() {
+        for (seed, public, msg, sig) in [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                &[][..],
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                &[0x72][..],
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+        ] {
+            let (seed, public, sig) = (hex(seed), hex(public), hex(sig));
+            assert_eq!(public_key(&seed), public);
+            assert_eq!(sign(&seed, msg), sig);
+            assert!(verify(&public, msg, &sig));
+        }
+    }
+    #[test]
+    fn test_verify_rejects() {
+        let seed = [7u8; 32];
+        let public = public_key(&seed);
+        let sig = sign(&seed, b"message");
+        assert!(verify(&public, b"message", &sig));
+        assert!(!verify(&public, b"massage", &sig));
+        assert!(!verify(&public_key(&[8; 32]), b"message", &sig));
+        for i in [0, 40] {
+            let mut bad = sig;
+            bad[i] ^= 1;
+            assert!(!verify(&public, b"message", &bad));
+        }
+
+        // S + L verifies under the same equation, but isn't canonical.
+        let mut malleable = sig;
+        let mut carry = 0u128;
+        let s: [u64; 4] = words(&sig[32..]);
+        let mut sum = [0u64; 4];
+        for i in 0..4 {
+            let t = s[i] as u128 + L[i] as u128 + carry;
+            sum[i] = t as u64;
+            carry = t >> 64;
+        }
+        malleable[32..].copy_from_slice(&scalar_bytes(&sum));
+        assert!(!verify(&public, b"message", &malleable));
+    }
+}
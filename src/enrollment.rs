@@ -0,0 +1,562 @@
+// Enrollment adds a participant at a new x-coordinate without reconstructing
+// the secret or revealing any existing share.
+//
+// The new share is f(new_x) = sum_i l_i * y_i, where l_i are the lagrange
+// basis coefficients of the t helpers' x-coordinates evaluated at new_x.
+// Sending l_i * y_i straight to the new participant would reveal y_i, so the
+// protocol runs in two rounds:
+//
+//   1. every helper i splits its term l_i * y_i into t random additive pieces
+//      and sends piece j to helper j (MaskedContribution);
+//   2. every helper j sums the pieces it received and sends the sum to the new
+//      participant (PartialShare), who adds the t sums to obtain f(new_x).
+//
+// Helpers only ever see uniformly random pieces, and the new participant only
+// sees sums of pieces from every helper. Messages are not authenticated; the
+// transport between participants must provide that.
+
+use crate::gf::{self, GfOps};
+use crate::message::{self, ENROLLMENT_REQUEST, MASKED_CONTRIBUTION, PARTIAL_SHARE};
+use crate::{lagrange_coefficients, Redacted, SecretSharingError, Share};
+use rand::{CryptoRng, Rng, RngCore};
+use std::fmt;
+use zeroize::Zeroize;
+
+// EnrollmentRequest describes an enrollment: the x-coordinate of the new
+// participant and the x-coordinates of the helpers taking part. Every
+// participant must be given the same request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnrollmentRequest {
+    new_x: u8,
+    helpers: Vec<u8>,
+}
+
+// MaskedContribution is a round 1 message from helper `from` to helper `to`.
+#[derive(Zeroize)]
+#[zeroize(drop)]
+pub struct MaskedContribution {
+    from: u8,
+    to: u8,
+    new_x: u8,
+    data: Vec<u8>,
+}
+
+// PartialShare is a round 2 message from helper `from` to the new participant.
+#[derive(Zeroize)]
+#[zeroize(drop)]
+pub struct PartialShare {
+    from: u8,
+    new_x: u8,
+    threshold: Option<u8>,
+    identifier: Option<u16>,
+    data: Vec<u8>,
+}
+
+// Helper is the protocol state of an existing shareholder taking part in an
+// enrollment.
+pub struct Helper<'a> {
+    request: EnrollmentRequest,
+    share: &'a Share,
+    received: Vec<u8>,
+    sum: Vec<u8>,
+}
+
+// Enrollee is the protocol state of the participant being enrolled.
+pub struct Enrollee {
+    request: EnrollmentRequest,
+    received: Vec<u8>,
+    threshold: Option<u8>,
+    identifier: Option<u16>,
+    sum: Vec<u8>,
+}
+
+impl EnrollmentRequest {
+    // new creates a request to enroll `new_x` with the help of the holders of
+    // shares `helpers`. The helpers must be distinct and there must be at least
+    // threshold of them; new_x must not be 0 or one of the helpers.
+    pub fn new(new_x: u8, helpers: &[u8]) -> Result<EnrollmentRequest, SecretSharingError> {
+        if new_x == 0 || helpers.iter().any(|&x| x == 0 || x == new_x) {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        if helpers.is_empty() {
+            return Err(SecretSharingError::NoSharesProvided);
+        }
+        for (i, x) in helpers.iter().enumerate() {
+            if helpers[..i].contains(x) {
+                return Err(SecretSharingError::InvalidShareIndex);
+            }
+        }
+
+        Ok(EnrollmentRequest {
+            new_x,
+            helpers: helpers.to_vec(),
+        })
+    }
+
+    pub fn new_x(&self) -> u8 {
+        self.new_x
+    }
+
+    pub fn helpers(&self) -> &[u8] {
+        &self.helpers
+    }
+
+    // to_bytes encodes the request in the format described in message.rs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        message::encode(ENROLLMENT_REQUEST, &[self.new_x], &self.helpers)
+    }
+
+    // from_bytes decodes a request produced by to_bytes, checking it as new
+    // does.
+    pub fn from_bytes(data: &[u8]) -> Result<EnrollmentRequest, SecretSharingError> {
+        let (fields, helpers) = message::decode(ENROLLMENT_REQUEST, 1, data)?;
+        EnrollmentRequest::new(fields[0], helpers).map_err(|_| SecretSharingError::ShareMalformed)
+    }
+}
+
+impl fmt::Debug for MaskedContribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MaskedContribution")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("new_x", &self.new_x)
+            .field("data", &Redacted(self.data.len()))
+            .finish()
+    }
+}
+
+impl MaskedContribution {
+    pub fn sender(&self) -> u8 {
+        self.from
+    }
+
+    pub fn recipient(&self) -> u8 {
+        self.to
+    }
+
+    // to_bytes encodes the contribution in the format described in
+    // message.rs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let fields = [self.from, self.to, self.new_x];
+        message::encode(MASKED_CONTRIBUTION, &fields, &self.data)
+    }
+
+    // from_bytes decodes a contribution produced by to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<MaskedContribution, SecretSharingError> {
+        let (fields, data) = message::decode(MASKED_CONTRIBUTION, 3, data)?;
+        if fields.contains(&0) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        Ok(MaskedContribution {
+            from: fields[0],
+            to: fields[1],
+            new_x: fields[2],
+            data: data.to_vec(),
+        })
+    }
+}
+
+impl fmt::Debug for PartialShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartialShare")
+            .field("from", &self.from)
+            .field("new_x", &self.new_x)
+            .field("data", &Redacted(self.data.len()))
+            .finish()
+    }
+}
+
+impl PartialShare {
+    pub fn sender(&self) -> u8 {
+        self.from
+    }
+
+    // to_bytes encodes the partial share in the format described in
+    // message.rs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let id = self.identifier.unwrap_or(0).to_be_bytes();
+        let fields = [
+            self.from,
+            self.new_x,
+            self.threshold.unwrap_or(0),
+            id[0],
+            id[1],
+        ];
+        message::encode(PARTIAL_SHARE, &fields, &self.data)
+    }
+
+    // from_bytes decodes a partial share produced by to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<PartialShare, SecretSharingError> {
+        let (fields, data) = message::decode(PARTIAL_SHARE, 5, data)?;
+        if fields[0] == 0 || fields[1] == 0 {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        Ok(PartialShare {
+            from: fields[0],
+            new_x: fields[1],
+            threshold: Some(fields[2]).filter(|&t| t != 0),
+            identifier: Some(u16::from_be_bytes([fields[3], fields[4]])).filter(|&id| id != 0),
+            data: data.to_vec(),
+        })
+    }
+}
+
+impl<'a> Helper<'a> {
+    // new starts the helper side of `request` for the holder of `share`.
+    pub fn new(
+        request: &EnrollmentRequest,
+        share: &'a Share,
+    ) -> Result<Helper<'a>, SecretSharingError> {
+        if !request.helpers.contains(&share.x) {
+            return Err(SecretSharingError::UnexpectedMessage);
+        }
+        if let Some(t) = share.threshold {
+            if request.helpers.len() < t as usize {
+                return Err(SecretSharingError::NotEnoughShares {
+                    have: request.helpers.len(),
+                    need: t as usize,
+                });
+            }
+        }
+
+        Ok(Helper {
+            request: request.clone(),
+            share,
+            received: Vec::new(),
+            sum: vec![0; share.y.len()],
+        })
+    }
+
+    // contribute performs round 1, returning one masked contribution for every
+    // helper (including this one), to be delivered to the helper named by
+    // `recipient()`.
+    pub fn contribute<R: CryptoRng + RngCore>(&self, rng: &mut R) -> Vec<MaskedContribution> {
+        let pos = self
+            .request
+            .helpers
+            .iter()
+            .position(|&x| x == self.share.x)
+            .unwrap();
+        let l = lagrange_coefficients(&self.request.helpers, self.request.new_x)[pos];
+
+        // the last piece is the weighted share minus all the random pieces, so
+        // that the pieces sum to l * y.
+        let mut last: Vec<u8> = self.share.y.iter().map(|y| y.mul(l)).collect();
+        let mut out = Vec::with_capacity(self.request.helpers.len());
+        for &to in &self.request.helpers[1..] {
+            let data: Vec<u8> = (0..last.len()).map(|_| rng.gen()).collect();
+            for (l, d) in last.iter_mut().zip(data.iter()) {
+                *l = l.sub(*d);
+            }
+            out.push(MaskedContribution {
+                from: self.share.x,
+                to,
+                new_x: self.request.new_x,
+                data,
+            });
+        }
+        out.push(MaskedContribution {
+            from: self.share.x,
+            to: self.request.helpers[0],
+            new_x: self.request.new_x,
+            data: last,
+        });
+        out
+    }
+
+    // receive accepts a round 1 contribution addressed to this helper.
+    pub fn receive(&mut self, msg: &MaskedContribution) -> Result<(), SecretSharingError> {
+        if msg.to != self.share.x
+            || msg.new_x != self.request.new_x
+            || !self.request.helpers.contains(&msg.from)
+            || self.received.contains(&msg.from)
+        {
+            return Err(SecretSharingError::UnexpectedMessage);
+        }
+        if msg.data.len() != self.sum.len() {
+            return Err(SecretSharingError::ShareLengthMismatch {
+                index: msg.from,
+                expected: self.sum.len(),
+                actual: msg.data.len(),
+            });
+        }
+
+        for (s, d) in self.sum.iter_mut().zip(msg.data.iter()) {
+            *s = s.add(*d);
+        }
+        self.received.push(msg.from);
+        Ok(())
+    }
+
+    // partial performs round 2 once a contribution from every helper has been
+    // received, returning the message for the new participant.
+    pub fn partial(&self) -> Result<PartialShare, SecretSharingError> {
+        if self.received.len() != self.request.helpers.len() {
+            return Err(SecretSharingError::IncompleteProtocol);
+        }
+
+        Ok(PartialShare {
+            from: self.share.x,
+            new_x: self.request.new_x,
+            threshold: self.share.threshold,
+            identifier: self.share.identifier,
+            data: self.sum.clone(),
+        })
+    }
+}
+
+impl<'a> Drop for Helper<'a> {
+    fn drop(&mut self) {
+        self.sum.zeroize();
+    }
+}
+
+impl Enrollee {
+    // new starts the new participant's side of `request`.
+    pub fn new(request: &EnrollmentRequest) -> Enrollee {
+        Enrollee {
+            request: request.clone(),
+            received: Vec::new(),
+            threshold: None,
+            identifier: None,
+            sum: Vec::new(),
+        }
+    }
+
+    // receive accepts a round 2 partial share from a helper. A helper whose
+    // share is from a different split than the others' is rejected with
+    // InconsistentShares.
+    pub fn receive(&mut self, msg: &PartialShare) -> Result<(), SecretSharingError> {
+        if msg.new_x != self.request.new_x
+            || !self.request.helpers.contains(&msg.from)
+            || self.received.contains(&msg.from)
+        {
+            return Err(SecretSharingError::UnexpectedMessage);
+        }
+        if self
+            .identifier
+            .zip(msg.identifier)
+            .is_some_and(|(a, b)| a != b)
+        {
+            return Err(SecretSharingError::InconsistentShares {
+                suspects: vec![msg.from],
+            });
+        }
+        if self.received.is_empty() {
+            self.sum = vec![0; msg.data.len()];
+        } else if msg.data.len() != self.sum.len() {
+            return Err(SecretSharingError::ShareLengthMismatch {
+                index: msg.from,
+                expected: self.sum.len(),
+                actual: msg.data.len(),
+            });
+        }
+
+        for (s, d) in self.sum.iter_mut().zip(msg.data.iter()) {
+            *s = s.add(*d);
+        }
+        self.threshold = self.threshold.max(msg.threshold);
+        self.identifier = self.identifier.or(msg.identifier);
+        self.received.push(msg.from);
+        Ok(())
+    }
+
+    // finish returns the new participant's share once a partial share from
+    // every helper has been received.
+    pub fn finish(mut self) -> Result<Share, SecretSharingError> {
+        if self.received.len() != self.request.helpers.len() {
+            return Err(SecretSharingError::IncompleteProtocol);
+        }
+
+        Ok(Share {
+            x: self.request.new_x as gf::GF256e,
+            y: std::mem::take(&mut self.sum),
+            threshold: self.threshold,
+            share_count: None,
+            identifier: self.identifier,
+            label: None,
+            owner: None,
+        })
+    }
+}
+
+impl Drop for Enrollee {
+    fn drop(&mut self) {
+        self.sum.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{construct_shares, reconstruct};
+
+    #[test]
+    fn test_enroll() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let shares = construct_shares(3, 5, &secret).unwrap();
+        let mut rng = rand::thread_rng();
+
+        let request = EnrollmentRequest::new(9, &[2, 4, 5]).unwrap();
+        let mut helpers: Vec<Helper> = [1, 3, 4]
+            .iter()
+            .map(|&i| Helper::new(&request, &shares[i]).unwrap())
+            .collect();
+
+        let round1: Vec<MaskedContribution> = helpers
+            .iter()
+            .flat_map(|h| h.contribute(&mut rng))
+            .collect();
+        for msg in round1.iter() {
+            let to = helpers
+                .iter_mut()
+                .find(|h| h.share.x == msg.recipient())
+                .unwrap();
+            to.receive(msg).unwrap();
+        }
+
+        let mut enrollee = Enrollee::new(&request);
+        for h in helpers.iter() {
+            enrollee.receive(&h.partial().unwrap()).unwrap();
+        }
+        let enrolled = enrollee.finish().unwrap();
+        assert_eq!(enrolled.index(), 9);
+        assert_eq!(enrolled.threshold(), Some(3));
+        assert_eq!(enrolled.identifier(), shares[0].identifier());
+
+        let quorum = vec![
+            enrolled,
+            Share::from_bytes(&shares[0].to_bytes()).unwrap(),
+            Share::from_bytes(&shares[2].to_bytes()).unwrap(),
+        ];
+        assert_eq!(reconstruct(&quorum).unwrap(), secret);
+    }
+    #[test]
+    fn test_enroll_rejects_bad_messages() {
+        let shares = construct_shares(2, 3, &[0x01, 0x02]).unwrap();
+        let mut rng = rand::thread_rng();
+
+        assert!(EnrollmentRequest::new(2, &[1, 2]).is_err());
+        assert!(EnrollmentRequest::new(4, &[1, 1]).is_err());
+        assert!(EnrollmentRequest::new(0, &[1, 2]).is_err());
+
+        let request = EnrollmentRequest::new(4, &[1, 2]).unwrap();
+        assert_eq!(
+            Helper::new(&request, &shares[2]).err(),
+            Some(SecretSharingError::UnexpectedMessage)
+        );
+        let short = EnrollmentRequest::new(4, &[1]).unwrap();
+        assert_eq!(
+            Helper::new(&short, &shares[0]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 2 })
+        );
+
+        let mut a = Helper::new(&request, &shares[0]).unwrap();
+        let b = Helper::new(&request, &shares[1]).unwrap();
+        assert_eq!(
+            a.partial().err(),
+            Some(SecretSharingError::IncompleteProtocol)
+        );
+        let msgs = b.contribute(&mut rng);
+        let to_a = msgs.iter().find(|m| m.recipient() == 1).unwrap();
+        let to_b = msgs.iter().find(|m| m.recipient() == 2).unwrap();
+        a.receive(to_a).unwrap();
+        assert_eq!(
+            a.receive(to_a).err(),
+            Some(SecretSharingError::UnexpectedMessage)
+        );
+        assert_eq!(
+            a.receive(to_b).err(),
+            Some(SecretSharingError::UnexpectedMessage)
+        );
+
+        let enrollee = Enrollee::new(&request);
+        assert_eq!(
+            enrollee.finish().err(),
+            Some(SecretSharingError::IncompleteProtocol)
+        );
+
+        // partial shares from helpers holding shares of different splits.
+        let partial = |from, identifier| PartialShare {
+            from,
+            new_x: 4,
+            threshold: Some(2),
+            identifier: Some(identifier),
+            data: vec![0; 2],
+        };
+        let mut enrollee = Enrollee::new(&request);
+        enrollee.receive(&partial(1, 7)).unwrap();
+        assert_eq!(
+            enrollee.receive(&partial(2, 8)).err(),
+            Some(SecretSharingError::InconsistentShares { suspects: vec![2] })
+        );
+        enrollee.receive(&partial(2, 7)).unwrap();
+        assert_eq!(enrollee.finish().unwrap().identifier(), Some(7));
+    }
+    #[test]
+    fn test_enroll_encoding() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let shares = construct_shares(2, 3, &secret).unwrap();
+        let mut rng = rand::thread_rng();
+
+        // every message of the protocol goes through its encoding.
+        let request = EnrollmentRequest::new(7, &[1, 3]).unwrap();
+        let request = EnrollmentRequest::from_bytes(&request.to_bytes()).unwrap();
+        assert_eq!(request.helpers(), [1, 3]);
+        let mut helpers = [
+            Helper::new(&request, &shares[0]).unwrap(),
+            Helper::new(&request, &shares[2]).unwrap(),
+        ];
+        let round1: Vec<Vec<u8>> = helpers
+            .iter()
+            .flat_map(|h| h.contribute(&mut rng))
+            .map(|m| m.to_bytes())
+            .collect();
+        for bytes in round1.iter() {
+            let msg = MaskedContribution::from_bytes(bytes).unwrap();
+            let to = helpers
+                .iter_mut()
+                .find(|h| h.share.x == msg.recipient())
+                .unwrap();
+            to.receive(&msg).unwrap();
+        }
+        let mut enrollee = Enrollee::new(&request);
+        for h in helpers.iter() {
+            let bytes = h.partial().unwrap().to_bytes();
+            enrollee
+                .receive(&PartialShare::from_bytes(&bytes).unwrap())
+                .unwrap();
+        }
+        let enrolled = enrollee.finish().unwrap();
+        assert_eq!(enrolled.threshold(), Some(2));
+        assert_eq!(enrolled.identifier(), shares[0].identifier());
+        assert_eq!(reconstruct(&[enrolled, shares[1].clone()]).unwrap(), secret);
+
+        // malformed messages.
+        let mut bytes = request.to_bytes();
+        assert_eq!(
+            MaskedContribution::from_bytes(&bytes).err(),
+            Some(SecretSharingError::UnexpectedMessage)
+        );
+        bytes[15] ^= 1;
+        assert_eq!(
+            EnrollmentRequest::from_bytes(&bytes).err(),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+        let repeated = message::encode(ENROLLMENT_REQUEST, &[7], &[1, 1]);
+        assert_eq!(
+            EnrollmentRequest::from_bytes(&repeated).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        let from_zero = message::encode(MASKED_CONTRIBUTION, &[0, 1, 7], &[0xaa]);
+        assert_eq!(
+            MaskedContribution::from_bytes(&from_zero).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        let bytes = helpers[0].partial().unwrap().to_bytes();
+        assert_eq!(
+            PartialShare::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+    }
+}
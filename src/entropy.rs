@@ -0,0 +1,55 @@
+// The entropy source of the functions that don't take an RNG, such as
+// construct_shares. By default they use the thread-local RNG, a ChaCha
+// generator seeded from the operating system and kept in userspace. With the
+// os-rng feature they instead read every random byte from the operating
+// system's CSPRNG through getrandom, for deployments whose policy forbids
+// userspace RNG state. That is slower, since every draw is a system call, but
+// leaves no generator state in the process to leak or to be duplicated by a
+// fork.
+//
+// Functions taking an RNG, and ShamirConfig::with_rng and with_os_rng, choose
+// their source explicitly and aren't affected by the feature.
+
+use rand::rngs::OsRng;
+#[cfg(not(feature = "os-rng"))]
+use rand::rngs::ThreadRng;
+
+// DefaultRng is the type of the RNG returned by default_rng.
+#[cfg(not(feature = "os-rng"))]
+pub type DefaultRng = ThreadRng;
+#[cfg(feature = "os-rng")]
+pub type DefaultRng = OsRng;
+
+// default_rng returns the RNG used by the functions that don't take one.
+#[cfg(not(feature = "os-rng"))]
+pub fn default_rng() -> DefaultRng {
+    rand::thread_rng()
+}
+#[cfg(feature = "os-rng")]
+pub fn default_rng() -> DefaultRng {
+    OsRng
+}
+
+// os_rng returns the operating system's CSPRNG, whatever the features.
+pub fn os_rng() -> OsRng {
+    OsRng
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{construct_shares_with_rng, reconstruct};
+    use rand::RngCore;
+
+    #[test]
+    fn test_entropy_sources() {
+        for rng in [&mut default_rng() as &mut dyn RngCore, &mut os_rng()] {
+            let (mut a, mut b) = ([0u8; 32], [0u8; 32]);
+            rng.fill_bytes(&mut a);
+            rng.fill_bytes(&mut b);
+            assert_ne!(a, b);
+        }
+        let shares = construct_shares_with_rng(2, 3, b"from the os", &mut os_rng()).unwrap();
+        assert_eq!(reconstruct(&shares[1..]).unwrap(), b"from the os");
+    }
+}
@@ -0,0 +1,294 @@
+// C interface to splitting and combining, for C, C++, Go (cgo) and Swift
+// programs linking the crate as a shared or static library. The declarations
+// are in include/shamir.h, which is kept by hand alongside this file and
+// gives the cargo rustc --crate-type commands that build the libraries.
+//
+// Ownership is explicit: the caller owns every input, and every buffer the
+// library fills in is allocated by the library and must be released with
+// shamir_free, which wipes it first. Shares are exchanged in the canonical
+// binary format of format.rs, so they carry their own checksum and metadata.
+// Functions return SHAMIR_OK or a negative error code; on error, no output
+// buffer is left allocated. Panics are caught rather than unwound into C.
+
+// The safety requirements of each function are given in its comment and in
+// the header, where C programmers read them.
+#![allow(clippy::missing_safety_doc)]
+
+use crate::{construct_shares, reconstruct, SecretSharingError, Share};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use zeroize::{Zeroize, Zeroizing};
+
+pub const SHAMIR_OK: c_int = 0;
+pub const SHAMIR_ERR_NULL_POINTER: c_int = -1;
+pub const SHAMIR_ERR_INVALID_PARAMETERS: c_int = -2;
+pub const SHAMIR_ERR_NOT_ENOUGH_SHARES: c_int = -3;
+pub const SHAMIR_ERR_MALFORMED_SHARE: c_int = -4;
+pub const SHAMIR_ERR_CHECKSUM_MISMATCH: c_int = -5;
+pub const SHAMIR_ERR_INCONSISTENT_SHARES: c_int = -6;
+pub const SHAMIR_ERR_INTERNAL: c_int = -7;
+
+// ShamirBuffer is a byte buffer passed across the interface. Buffers filled
+// in by the library must be released with shamir_free.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ShamirBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl ShamirBuffer {
+    const EMPTY: ShamirBuffer = ShamirBuffer {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+
+    // from_slice hands a copy of `data` over to C.
+    fn from_slice(data: &[u8]) -> ShamirBuffer {
+        let len = data.len();
+        let data = Box::into_raw(Box::<[u8]>::from(data)) as *mut u8;
+        ShamirBuffer { data, len }
+    }
+}
+
+// error_code returns the code reported for `err`.
+fn error_code(err: &SecretSharingError) -> c_int {
+    use SecretSharingError::*;
+    match err {
+        TorNisZero | ThresholdExceedsShares | TooManyShares => SHAMIR_ERR_INVALID_PARAMETERS,
+        NotEnoughShares { .. } | NoSharesProvided => SHAMIR_ERR_NOT_ENOUGH_SHARES,
+        ShareTruncated
+        | ShareMalformed
+        | UnsupportedShareVersion(_)
+        | InvalidShareIndex
+        | DuplicateShare { .. }
+        | ShareLengthMismatch { .. }
+        | MissingShareForByte { .. } => SHAMIR_ERR_MALFORMED_SHARE,
+        ShareChecksumMismatch => SHAMIR_ERR_CHECKSUM_MISMATCH,
+        InconsistentShares { .. } | DigestMismatch | ShareTampered { .. } => {
+            SHAMIR_ERR_INCONSISTENT_SHARES
+        }
+        _ => SHAMIR_ERR_INTERNAL,
+    }
+}
+
+// input returns the `len` bytes at `data`, which may be null if `len` is 0.
+unsafe fn input<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (data.is_null(), len) {
+        (true, 0) => Some(&[]),
+        (true, _) => None,
+        (false, _) => Some(slice::from_raw_parts(data, len)),
+    }
+}
+
+// guard runs `f`, reporting a panic as SHAMIR_ERR_INTERNAL.
+fn guard<F: FnOnce() -> c_int>(f: F) -> c_int {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(SHAMIR_ERR_INTERNAL)
+}
+
+// shamir_split splits the `secret_len` bytes at `secret` into `n` shares,
+// any `t` of which reconstruct it, and stores them in `shares_out`, which
+// must point to an array of `n` buffers. Each share must be released with
+// shamir_free. `secret` may be null if `secret_len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn shamir_split(
+    secret: *const u8,
+    secret_len: usize,
+    t: u8,
+    n: u8,
+    shares_out: *mut ShamirBuffer,
+) -> c_int {
+    guard(|| {
+        let secret = match input(secret, secret_len) {
+            Some(secret) if !shares_out.is_null() => secret,
+            _ => return SHAMIR_ERR_NULL_POINTER,
+        };
+        let shares = match construct_shares(t, n, secret) {
+            Ok(shares) => shares,
+            Err(err) => return error_code(&err),
+        };
+        let out = slice::from_raw_parts_mut(shares_out, n as usize);
+        for (slot, share) in out.iter_mut().zip(shares.iter()) {
+            *slot = ShamirBuffer::from_slice(&Zeroizing::new(share.to_bytes()));
+        }
+        SHAMIR_OK
+    })
+}
+
+// shamir_combine reconstructs the secret from the `count` shares in the
+// array at `shares` and stores it in `secret_out`, to be released with
+// shamir_free.
+#[no_mangle]
+pub unsafe extern "C" fn shamir_combine(
+    shares: *const ShamirBuffer,
+    count: usize,
+    secret_out: *mut ShamirBuffer,
+) -> c_int {
+    guard(|| {
+        if secret_out.is_null() {
+            return SHAMIR_ERR_NULL_POINTER;
+        }
+        *secret_out = ShamirBuffer::EMPTY;
+        let buffers = match count {
+            0 => &[][..],
+            _ if shares.is_null() => return SHAMIR_ERR_NULL_POINTER,
+            _ => slice::from_raw_parts(shares, count),
+        };
+        let mut decoded = Vec::with_capacity(count);
+        for buffer in buffers {
+            let data = match input(buffer.data, buffer.len) {
+                Some(data) => data,
+                None => return SHAMIR_ERR_NULL_POINTER,
+            };
+            match Share::from_bytes(data) {
+                Ok(share) => decoded.push(share),
+                Err(err) => return error_code(&err),
+            }
+        }
+        match reconstruct(&decoded) {
+            Ok(secret) => {
+                *secret_out = ShamirBuffer::from_slice(&Zeroizing::new(secret));
+                SHAMIR_OK
+            }
+            Err(err) => error_code(&err),
+        }
+    })
+}
+
+// shamir_free wipes and releases a buffer filled in by shamir_split or
+// shamir_combine, and resets it to empty. Freeing a null or empty buffer
+// does nothing.
+#[no_mangle]
+pub unsafe extern "C" fn shamir_free(buffer: *mut ShamirBuffer) {
+    if buffer.is_null() || (*buffer).data.is_null() {
+        return;
+    }
+    let b = &mut *buffer;
+    let data = ptr::slice_from_raw_parts_mut(b.data, b.len);
+    (*data).zeroize();
+    drop(Box::from_raw(data));
+    *b = ShamirBuffer::EMPTY;
+}
+
+// shamir_strerror returns a static, NUL-terminated description of `code`.
+#[no_mangle]
+pub extern "C" fn shamir_strerror(code: c_int) -> *const c_char {
+    let msg: &'static [u8] = match code {
+        SHAMIR_OK => b"success\0",
+        SHAMIR_ERR_NULL_POINTER => b"null pointer argument\0",
+        SHAMIR_ERR_INVALID_PARAMETERS => b"invalid threshold or share count\0",
+        SHAMIR_ERR_NOT_ENOUGH_SHARES => b"not enough shares\0",
+        SHAMIR_ERR_MALFORMED_SHARE => b"malformed share\0",
+        SHAMIR_ERR_CHECKSUM_MISMATCH => b"share checksum mismatch\0",
+        SHAMIR_ERR_INCONSISTENT_SHARES => b"shares are inconsistent\0",
+        SHAMIR_ERR_INTERNAL => b"internal error\0",
+        _ => b"unknown error\0",
+    };
+    msg.as_ptr() as *const c_char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_ffi_roundtrip() {
+        let secret = b"from C";
+        let mut shares: Vec<ShamirBuffer> = (0..3).map(|_| ShamirBuffer::EMPTY).collect();
+        let mut out = ShamirBuffer::EMPTY;
+        unsafe {
+            assert_eq!(
+                shamir_split(secret.as_ptr(), secret.len(), 2, 3, shares.as_mut_ptr()),
+                SHAMIR_OK
+            );
+            assert_eq!(shamir_combine(shares[1..].as_ptr(), 2, &mut out), SHAMIR_OK);
+            assert_eq!(slice::from_raw_parts(out.data, out.len), secret);
+            shamir_free(&mut out);
+            assert!(out.data.is_null());
+            shamir_free(&mut out);
+
+            assert_eq!(
+                shamir_combine(shares.as_ptr(), 1, &mut out),
+                SHAMIR_ERR_NOT_ENOUGH_SHARES
+            );
+            *shares[0].data.add(16) ^= 1;
+            assert_eq!(
+                shamir_combine(shares.as_ptr(), 2, &mut out),
+                SHAMIR_ERR_CHECKSUM_MISMATCH
+            );
+            assert!(out.data.is_null());
+            for share in shares.iter_mut() {
+                shamir_free(share);
+            }
+        }
+    }
+    #[test]
+    fn test_ffi_errors() {
+        let mut shares: Vec<ShamirBuffer> = (0..3).map(|_| ShamirBuffer::EMPTY).collect();
+        let mut out = ShamirBuffer::EMPTY;
+        unsafe {
+            assert_eq!(
+                shamir_split(ptr::null(), 1, 2, 3, shares.as_mut_ptr()),
+                SHAMIR_ERR_NULL_POINTER
+            );
+            assert_eq!(
+                shamir_split(b"x".as_ptr(), 1, 4, 3, shares.as_mut_ptr()),
+                SHAMIR_ERR_INVALID_PARAMETERS
+            );
+            assert!(shares.iter().all(|s| s.data.is_null()));
+            assert_eq!(
+                shamir_combine(ptr::null(), 2, &mut out),
+                SHAMIR_ERR_NULL_POINTER
+            );
+            assert_eq!(
+                shamir_combine(ptr::null(), 0, ptr::null_mut()),
+                SHAMIR_ERR_NULL_POINTER
+            );
+            let garbage = [ShamirBuffer::from_slice(b"not a share")];
+            assert_eq!(
+                shamir_combine(garbage.as_ptr(), 1, &mut out),
+                SHAMIR_ERR_MALFORMED_SHARE
+            );
+            let mut garbage = garbage;
+            shamir_free(&mut garbage[0]);
+            shamir_free(ptr::null_mut());
+        }
+        let msg = unsafe { CStr::from_ptr(shamir_strerror(SHAMIR_ERR_MALFORMED_SHARE)) };
+        assert_eq!(msg.to_str().unwrap(), "malformed share");
+    }
+    #[test]
+    fn test_header_matches() {
+        // the hand-written header must agree with the constants above.
+        let header = include_str!("../include/shamir.h");
+        for (name, value) in [
+            ("SHAMIR_OK", SHAMIR_OK),
+            ("SHAMIR_ERR_NULL_POINTER", SHAMIR_ERR_NULL_POINTER),
+            (
+                "SHAMIR_ERR_INVALID_PARAMETERS",
+                SHAMIR_ERR_INVALID_PARAMETERS,
+            ),
+            ("SHAMIR_ERR_NOT_ENOUGH_SHARES", SHAMIR_ERR_NOT_ENOUGH_SHARES),
+            ("SHAMIR_ERR_MALFORMED_SHARE", SHAMIR_ERR_MALFORMED_SHARE),
+            ("SHAMIR_ERR_CHECKSUM_MISMATCH", SHAMIR_ERR_CHECKSUM_MISMATCH),
+            (
+                "SHAMIR_ERR_INCONSISTENT_SHARES",
+                SHAMIR_ERR_INCONSISTENT_SHARES,
+            ),
+            ("SHAMIR_ERR_INTERNAL", SHAMIR_ERR_INTERNAL),
+        ] {
+            let define = format!("#define {} ({})", name, value);
+            assert!(header.contains(&define), "{}", define);
+        }
+        for f in [
+            "shamir_split(",
+            "shamir_combine(",
+            "shamir_free(",
+            "shamir_strerror(",
+        ] {
+            assert!(header.contains(f), "{}", f);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+// Field abstracts the finite field the sharing polynomials are defined over,
+// so that polynomial evaluation, lagrange interpolation and sharing are
+// written once for every field. The byte-oriented API in the crate root uses
+// GF(2^8), whose elements are plain `u8`s; other fields can be used through
+// `split` and `combine` directly.
+
+use crate::{Redacted, SecretSharingError};
+use rand::{CryptoRng, RngCore};
+use std::fmt::{self, Debug};
+use std::ptr;
+use std::sync::atomic;
+
+pub trait Field: Copy + PartialEq + Debug {
+    // ENCODED_LEN is the length of an element encoded by `to_bytes`.
+    const ENCODED_LEN: usize;
+
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn add(self, x: Self) -> Self;
+    fn sub(self, x: Self) -> Self;
+    fn mul(self, x: Self) -> Self;
+    // inv returns the multiplicative inverse of a non-zero element. The
+    // inverse of zero is unspecified but must not panic.
+    fn inv(self) -> Self;
+    fn div(self, x: Self) -> Self {
+        self.mul(x.inv())
+    }
+
+    // random returns a uniformly random element.
+    fn random<R: CryptoRng + RngCore>(rng: &mut R) -> Self;
+
+    // from_u64 maps the integer `v` to a field element, or returns None if the
+    // field has too few elements to represent it. It is used to assign
+    // distinct x-coordinates to participants 1..n.
+    fn from_u64(v: u64) -> Option<Self>;
+
+    // to_bytes writes the canonical encoding of the element into `out`, which
+    // must be ENCODED_LEN bytes long.
+    fn to_bytes(&self, out: &mut [u8]);
+    // from_bytes decodes an element encoded by to_bytes, returning None for
+    // non-canonical encodings.
+    fn from_bytes(b: &[u8]) -> Option<Self>;
+}
+
+// FieldShare is a participant's share of a secret made up of field
+// elements: one y-value per secret element, all at the same x. The y-values
+// are wiped when it is dropped.
+#[derive(Clone, PartialEq)]
+pub struct FieldShare<F: Field> {
+    pub x: F,
+    pub y: Vec<F>,
+}
+
+impl<F: Field> Drop for FieldShare<F> {
+    fn drop(&mut self) {
+        wipe(&mut self.y);
+    }
+}
+
+impl<F: Field> Debug for FieldShare<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FieldShare")
+            .field("x", &self.x)
+            .field("data", &Redacted(self.y.len()))
+            .finish()
+    }
+}
+
+// evaluate returns the value at `x` of the polynomial with coefficients
+// `coeff`, constant term first, using Horner's rule.
+pub fn evaluate<F: Field>(coeff: &[F], x: F) -> F {
+    coeff.iter().rev().fold(F::zero(), |y, c| y.mul(x).add(*c))
+}
+
+// lagrange_coefficients computes the lagrange basis polynomials for the
+// supplied x-coordinates, evaluated at `x`. Interpolating a set of points that
+// share these x-coordinates is then a dot product of the basis with the
+// y-values.
+pub fn lagrange_coefficients<F: Field>(xs: &[F], x: F) -> Vec<F> {
+    xs.iter()
+        .map(|&j| {
+            xs.iter()
+                .filter(|&&m| m != j)
+                .fold(F::one(), |phi, &m| phi.mul(x.sub(m).div(j.sub(m))))
+        })
+        .collect()
+}
+
+// split shares every element of `secret` with its own random polynomial of
+// degree t-1, returning the shares of participants 1..n.
+pub fn split<F: Field, R: CryptoRng + RngCore>(
+    t: usize,
+    n: usize,
+    secret: &[F],
+    rng: &mut R,
+) -> Result<Vec<FieldShare<F>>, SecretSharingError> {
+    if t == 0 || n == 0 {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if t > n {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+    let xs = (1..=n as u64)
+        .map(F::from_u64)
+        .collect::<Option<Vec<F>>>()
+        .ok_or(SecretSharingError::InvalidShareIndex)?;
+
+    let mut shares: Vec<FieldShare<F>> = xs
+        .iter()
+        .map(|&x| FieldShare {
+            x,
+            y: Vec::with_capacity(secret.len()),
+        })
+        .collect();
+    let mut coeff = vec![F::zero(); t];
+    for s in secret {
+        coeff[0] = *s;
+        for c in coeff[1..].iter_mut() {
+            *c = F::random(rng);
+        }
+        for share in shares.iter_mut() {
+            share.y.push(evaluate(&coeff, share.x));
+        }
+    }
+    wipe(&mut coeff);
+
+    Ok(shares)
+}
+
+// wipe overwrites `v` with zeros. Field elements aren't required to implement
+// Zeroize, so this does what Zeroize would: volatile writes, which the
+// compiler can't drop even though `v` is never read again.
+pub(crate) fn wipe<F: Field>(v: &mut [F]) {
+    for c in v.iter_mut() {
+        // safe: `c` is a valid, aligned reference to an F.
+        unsafe { ptr::write_volatile(c, F::zero()) };
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+// participant returns the participant number v whose x-coordinate, as
+// assigned by split, is `x`, for reporting in errors. It returns 0 for an x
+// that isn't one of the first 65535 participants'.
+pub(crate) fn participant<F: Field>(x: F) -> u16 {
+    (1..=u16::MAX)
+        .find(|&v| F::from_u64(v as u64) == Some(x))
+        .unwrap_or(0)
+}
+
+// check_xs returns InvalidShareIndex if any of the x-coordinates `xs` is
+// zero, where a share would be the secret itself, and DuplicateShare if any
+// appears twice, which would make interpolation divide by zero and return
+// garbage.
+fn check_xs<F: Field>(xs: &[F]) -> Result<(), SecretSharingError> {
+    for (i, &x) in xs.iter().enumerate() {
+        if x == F::zero() {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        if xs[..i].contains(&x) {
+            return Err(SecretSharingError::DuplicateShare { x: participant(x) });
+        }
+    }
+    Ok(())
+}
+
+// combine interpolates the secret from at least t shares produced by split.
+// Like the byte-oriented reconstruct, it rejects a zero or repeated
+// x-coordinate but cannot detect too few or otherwise invalid shares.
+pub fn combine<F: Field>(shares: &[FieldShare<F>]) -> Result<Vec<F>, SecretSharingError> {
+    let xs: Vec<F> = shares.iter().map(|share| share.x).collect();
+    let ys: Vec<&[F]> = shares.iter().map(|share| &share.y[..]).collect();
+    interpolate(&xs, &ys)
+}
+
+// interpolate is combine for shares given as x-coordinates `xs` and the
+// y-values `ys` at each, so that callers holding shares of another type can
+// interpolate them without copying their y-values. The secret is summed in
+// place in the returned vector, leaving no partial sums elsewhere.
+pub(crate) fn interpolate<F: Field>(xs: &[F], ys: &[&[F]]) -> Result<Vec<F>, SecretSharingError> {
+    let sz = match ys.first() {
+        Some(y) => y.len(),
+        None => return Err(SecretSharingError::NoSharesProvided),
+    };
+    check_xs(xs)?;
+    if ys.iter().any(|y| y.len() != sz) {
+        let byte = ys.iter().map(|y| y.len()).min().unwrap_or(0);
+        return Err(SecretSharingError::MissingShareForByte { byte });
+    }
+
+    let basis = lagrange_coefficients(xs, F::zero());
+    let mut secret = vec![F::zero(); sz];
+    for (y, l) in ys.iter().zip(basis.iter()) {
+        for (s, y) in secret.iter_mut().zip(y.iter()) {
+            *s = s.add(y.mul(*l));
+        }
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_combine_gf256() {
+        let secret: Vec<u8> = vec![0xde, 0xad, 0xbe, 0xef];
+        let shares = split(3, 5, &secret, &mut rand::thread_rng()).unwrap();
+        assert_eq!(combine(&shares[1..4]).unwrap(), secret);
+        assert_ne!(combine(&shares[..2]).unwrap(), secret);
+
+        let repeated = [shares[1].clone(), shares[2].clone(), shares[1].clone()];
+        assert_eq!(
+            combine(&repeated).err(),
+            Some(SecretSharingError::DuplicateShare { x: 2 })
+        );
+        let zero = FieldShare {
+            x: 0u8,
+            y: shares[0].y.clone(),
+        };
+        assert_eq!(
+            combine(&[shares[1].clone(), zero]).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+    }
+    #[test]
+    fn test_split_too_many_shares() {
+        let secret: Vec<u8> = vec![0x01];
+        assert_eq!(
+            split(2, 256, &secret, &mut rand::thread_rng()).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+    }
+    #[test]
+    fn test_wipe() {
+        use crate::prime::Secp256k1Scalar;
+
+        let mut v = vec![Secp256k1Scalar::from_u64(7).unwrap(); 3];
+        wipe(&mut v);
+        assert!(v.iter().all(|c| *c == Secp256k1Scalar::zero()));
+        let mut v = vec![0xa5u8; 3];
+        wipe(&mut v);
+        assert_eq!(v, [0, 0, 0]);
+    }
+    #[test]
+    fn test_encoding_gf256() {
+        let mut out = [0u8; 1];
+        0xa5u8.to_bytes(&mut out);
+        assert_eq!(out, [0xa5]);
+        assert_eq!(<u8 as Field>::from_bytes(&out), Some(0xa5));
+    }
+}
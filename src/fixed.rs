@@ -0,0 +1,168 @@
+// Splitting of fixed-size secrets, such as 32-byte keys, using arrays only.
+// Nothing here allocates on the heap, which makes it usable on
+// microcontrollers with no allocator, and means no copy of the secret or its
+// polynomials can be left behind in freed memory: the only copies live on
+// the stack and are wiped before returning.
+
+use crate::gf::{self, GfOps};
+use crate::{Redacted, SecretSharingError};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+use zeroize::Zeroize;
+
+// FixedShare is a participant's share of an L-byte secret.
+#[derive(Clone, Zeroize)]
+#[zeroize(drop)]
+pub struct FixedShare<const L: usize> {
+    x: gf::GF256e,
+    y: [gf::GF256e; L],
+    threshold: u8,
+}
+
+impl<const L: usize> fmt::Debug for FixedShare<L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FixedShare")
+            .field("x", &self.x)
+            .field("data", &Redacted(L))
+            .finish()
+    }
+}
+
+impl<const L: usize> FixedShare<L> {
+    // new creates a share from a previously persisted index, payload and
+    // threshold, as returned by `index`, `as_bytes` and `threshold`.
+    pub fn new(index: u8, data: [u8; L], threshold: u8) -> FixedShare<L> {
+        FixedShare {
+            x: index,
+            y: data,
+            threshold,
+        }
+    }
+
+    pub fn index(&self) -> u8 {
+        self.x
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn as_bytes(&self) -> &[u8; L] {
+        &self.y
+    }
+}
+
+// split_fixed shares the L-byte `secret` among N participants, t of whom are
+// required to reconstruct it, without using the heap.
+pub fn split_fixed<const L: usize, const N: usize, R: CryptoRng + RngCore>(
+    t: u8,
+    secret: &[u8; L],
+    rng: &mut R,
+) -> Result<[FixedShare<L>; N], SecretSharingError> {
+    if t == 0 || N == 0 {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if t as usize > N {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+    if N > 255 {
+        return Err(SecretSharingError::InvalidShareIndex);
+    }
+
+    let mut shares: [FixedShare<L>; N] = std::array::from_fn(|i| FixedShare {
+        x: i as u8 + 1,
+        y: [0; L],
+        threshold: t,
+    });
+    let mut coeff = [0u8; 255];
+    let coeff_t = &mut coeff[..t as usize];
+    for (i, b) in secret.iter().enumerate() {
+        rng.fill_bytes(coeff_t);
+        coeff_t[0] = *b;
+        for share in shares.iter_mut() {
+            share.y[i] = coeff_t.iter().rev().fold(0, |y, c| y.mul(share.x).add(*c));
+        }
+    }
+    coeff.zeroize();
+
+    Ok(shares)
+}
+
+// combine_fixed reconstructs an L-byte secret from shares created by
+// split_fixed, without using the heap.
+pub fn combine_fixed<const L: usize>(
+    shares: &[FixedShare<L>],
+) -> Result<[u8; L], SecretSharingError> {
+    if shares.is_empty() {
+        return Err(SecretSharingError::NoSharesProvided);
+    }
+    let need = shares
+        .iter()
+        .map(|share| share.threshold)
+        .max()
+        .unwrap_or(1) as usize;
+    let mut seen = [false; 256];
+    let mut have = 0;
+    for share in shares {
+        if share.x == 0 {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        if seen[share.x as usize] {
+            return Err(SecretSharingError::DuplicateShare { x: share.x.into() });
+        }
+        seen[share.x as usize] = true;
+        have += 1;
+    }
+    if have < need {
+        return Err(SecretSharingError::NotEnoughShares { have, need });
+    }
+
+    let mut secret = [0u8; L];
+    for share in shares {
+        // the lagrange basis polynomial for this share, evaluated at 0.
+        let phi = shares
+            .iter()
+            .filter(|m| m.x != share.x)
+            .fold(1, |phi: gf::GF256e, m| phi.mul(m.x.div(share.x.sub(m.x))));
+        for (s, y) in secret.iter_mut().zip(share.y.iter()) {
+            *s = s.add(y.mul(phi));
+        }
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_roundtrip() {
+        let key = [0x42u8; 32];
+        let shares: [FixedShare<32>; 5] = split_fixed(3, &key, &mut rand::thread_rng()).unwrap();
+        assert_eq!(shares[4].index(), 5);
+        assert_eq!(combine_fixed(&shares[2..]).unwrap(), key);
+        assert_eq!(combine_fixed(&shares[..]).unwrap(), key);
+
+        let restored = FixedShare::new(shares[0].index(), *shares[0].as_bytes(), 3);
+        let subset = [restored, shares[1].clone(), shares[3].clone()];
+        assert_eq!(combine_fixed(&subset).unwrap(), key);
+    }
+    #[test]
+    fn test_fixed_errors() {
+        let key = [0x42u8; 16];
+        let shares: [FixedShare<16>; 3] = split_fixed(2, &key, &mut rand::thread_rng()).unwrap();
+        assert_eq!(
+            combine_fixed(&shares[..1]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 2 })
+        );
+        let duplicated = [shares[0].clone(), shares[0].clone()];
+        assert_eq!(
+            combine_fixed(&duplicated).err(),
+            Some(SecretSharingError::DuplicateShare { x: 1 })
+        );
+        assert_eq!(
+            split_fixed::<16, 0, _>(2, &key, &mut rand::thread_rng()).err(),
+            Some(SecretSharingError::TorNisZero)
+        );
+    }
+}
@@ -0,0 +1,296 @@
+// Canonical binary encoding of a Share. All multi-byte integers are big-endian.
+//
+//   offset  size  field
+//   0       4     magic, "SHMR"
+//   4       1     format version, currently 4
+//   5       1     threshold (0 if unknown)
+//   6       1     share count (0 if unknown)
+//   7       2     split identifier (0 if unknown)
+//   9       1     share index (x-coordinate)
+//   10      1     label length, l (0 if none)
+//   11      l     label, UTF-8
+//   11+l    1     owner length, o (0 if none)
+//   12+l    o     owner, UTF-8
+//   12+l+o  4     payload length in bytes
+//   16+l+o  len   payload (y-values)
+//   ...     4     checksum: the first 4 bytes of SHA-256 over everything above
+//
+// The label and owner are for people, and only covered by the checksum. In
+// version 3 both are absent, so the payload length sits at offset 10; version
+// 2 also has no split identifier, moving every later field two bytes earlier,
+// and version 1 has no share count byte either, moving them one byte earlier
+// still. Decoders must reject versions they do not understand rather than
+// guessing, so that shares can be exchanged between different versions of the
+// crate.
+
+use super::{SecretSharingError, Share};
+use crate::sha256;
+
+pub const MAGIC: [u8; 4] = *b"SHMR";
+pub const VERSION: u8 = 4;
+
+const CHECKSUM_LEN: usize = 4;
+
+// header_len returns the length of the fixed-size header for `version`, or
+// None if the version is not supported. Version 4 headers are longer by the
+// label and owner.
+fn header_len(version: u8) -> Option<usize> {
+    match version {
+        1 => Some(11),
+        2 => Some(12),
+        3 => Some(14),
+        4 => Some(16),
+        _ => None,
+    }
+}
+
+fn metadata(v: u8) -> Option<u8> {
+    match v {
+        0 => None,
+        v => Some(v),
+    }
+}
+
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let d = sha256::digest(data);
+    [d[0], d[1], d[2], d[3]]
+}
+
+impl Share {
+    // to_bytes encodes the share in the canonical binary wire format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(16 + self.y.len() + CHECKSUM_LEN);
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        out.push(self.threshold.unwrap_or(0));
+        out.push(self.share_count.unwrap_or(0));
+        out.extend_from_slice(&self.identifier.unwrap_or(0).to_be_bytes());
+        out.push(self.x);
+        for text in [&self.label, &self.owner] {
+            let text = text.as_deref().unwrap_or("");
+            out.push(text.len() as u8);
+            out.extend_from_slice(text.as_bytes());
+        }
+        out.extend_from_slice(&(self.y.len() as u32).to_be_bytes());
+        out.extend_from_slice(&self.y);
+        let sum = checksum(&out);
+        out.extend_from_slice(&sum);
+        out
+    }
+
+    // from_bytes decodes a share produced by `to_bytes`, verifying its header
+    // and checksum.
+    pub fn from_bytes(data: &[u8]) -> Result<Share, SecretSharingError> {
+        if data.len() < MAGIC.len() + 1 {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        if data[..MAGIC.len()] != MAGIC {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let mut header_len = match header_len(data[4]) {
+            Some(l) => l,
+            None => return Err(SecretSharingError::UnsupportedShareVersion(data[4])),
+        };
+        if data.len() < header_len + CHECKSUM_LEN {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        let (mut label, mut owner) = (None, None);
+        if data[4] >= 4 {
+            let mut pos = 10;
+            for text in [&mut label, &mut owner] {
+                let l = data[pos] as usize;
+                if data.len() < header_len + l + CHECKSUM_LEN {
+                    return Err(SecretSharingError::ShareTruncated);
+                }
+                // only text that with_label or with_owner would accept as
+                // is, so that decoding and encoding again gives the same bytes.
+                let s = std::str::from_utf8(&data[pos + 1..pos + 1 + l])
+                    .map_err(|_| SecretSharingError::ShareMalformed)?;
+                *text = crate::check_text(s)?;
+                if text.as_deref().unwrap_or("") != s {
+                    return Err(SecretSharingError::ShareMalformed);
+                }
+                pos += 1 + l;
+                header_len += l;
+            }
+        }
+
+        // the fields preceding the share index differ between versions; the
+        // index and length always end the header.
+        let (share_count, identifier, x) = match data[4] {
+            1 => (None, None, data[6]),
+            2 => (metadata(data[6]), None, data[7]),
+            _ => match u16::from_be_bytes([data[7], data[8]]) {
+                0 => (metadata(data[6]), None, data[9]),
+                id => (metadata(data[6]), Some(id), data[9]),
+            },
+        };
+        let l = &data[header_len - 4..header_len];
+        let len = u32::from_be_bytes([l[0], l[1], l[2], l[3]]) as usize;
+        let body_len = data.len() - header_len - CHECKSUM_LEN;
+        if body_len < len {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        if body_len > len {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+
+        let (body, sum) = data.split_at(header_len + len);
+        if checksum(body) != sum {
+            return Err(SecretSharingError::ShareChecksumMismatch);
+        }
+
+        Ok(Share {
+            x,
+            y: body[header_len..].to_vec(),
+            threshold: metadata(data[5]),
+            share_count,
+            identifier,
+            label,
+            owner,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_roundtrip() {
+        let secret = vec![0xca, 0xfe, 0xba, 0xbe];
+        let shares = construct_shares(2, 3, &secret).unwrap();
+        for share in shares.iter() {
+            let decoded = Share::from_bytes(&share.to_bytes()).unwrap();
+            assert_eq!(decoded.index(), share.index());
+            assert_eq!(decoded.threshold(), Some(2));
+            assert_eq!(decoded.share_count(), Some(3));
+            assert_eq!(decoded.identifier(), shares[0].identifier());
+            assert_eq!(decoded.as_bytes(), share.as_bytes());
+        }
+    }
+    #[test]
+    fn test_layout() {
+        let encoded = Share::with_metadata(3, &[0xaa, 0xbb], 2, 4)
+            .with_identifier(0x1234)
+            .to_bytes();
+        assert_eq!(
+            &encoded[..16],
+            b"SHMR\x04\x02\x04\x12\x34\x03\x00\x00\x00\x00\x00\x02"
+        );
+        assert_eq!(&encoded[16..18], &[0xaa, 0xbb]);
+        assert_eq!(encoded.len(), 22);
+        let decoded = Share::from_bytes(&Share::new(3, &[0xaa]).to_bytes()).unwrap();
+        assert_eq!(decoded.identifier(), None);
+    }
+    #[test]
+    fn test_label_owner() {
+        let share = Share::new(3, &[0xaa])
+            .with_label(" Safe deposit box ")
+            .unwrap()
+            .with_owner("Mom")
+            .unwrap();
+        let encoded = share.to_bytes();
+        assert_eq!(
+            &encoded[10..35],
+            b"\x10Safe deposit box\x03Mom\x00\x00\x00\x01"
+        );
+        let decoded = Share::from_bytes(&encoded).unwrap();
+        assert_eq!(decoded.label(), Some("Safe deposit box"));
+        assert_eq!(decoded.owner(), Some("Mom"));
+        assert_eq!(decoded.as_bytes(), &[0xaa]);
+
+        // the label is covered by the checksum.
+        let mut corrupted = encoded.clone();
+        corrupted[11] = b's';
+        assert_eq!(
+            Share::from_bytes(&corrupted).err(),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+        let mut truncated = encoded[..12].to_vec();
+        truncated.extend_from_slice(&[0; 4]);
+        assert_eq!(
+            Share::from_bytes(&truncated).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+    }
+    #[test]
+    fn test_decode_v3() {
+        let mut encoded = b"SHMR\x03\x02\x04\x12\x34\x03\x00\x00\x00\x02\xaa\xbb".to_vec();
+        let sum = checksum(&encoded);
+        encoded.extend_from_slice(&sum);
+
+        let decoded = Share::from_bytes(&encoded).unwrap();
+        assert_eq!(decoded.index(), 3);
+        assert_eq!(decoded.identifier(), Some(0x1234));
+        assert_eq!(decoded.label(), None);
+        assert_eq!(decoded.as_bytes(), &[0xaa, 0xbb]);
+    }
+    #[test]
+    fn test_decode_v2() {
+        let mut encoded = b"SHMR\x02\x02\x04\x03\x00\x00\x00\x02\xaa\xbb".to_vec();
+        let sum = checksum(&encoded);
+        encoded.extend_from_slice(&sum);
+
+        let decoded = Share::from_bytes(&encoded).unwrap();
+        assert_eq!(decoded.index(), 3);
+        assert_eq!(decoded.share_count(), Some(4));
+        assert_eq!(decoded.identifier(), None);
+        assert_eq!(decoded.as_bytes(), &[0xaa, 0xbb]);
+    }
+    #[test]
+    fn test_decode_v1() {
+        let mut encoded = b"SHMR\x01\x02\x03\x00\x00\x00\x02\xaa\xbb".to_vec();
+        let sum = checksum(&encoded);
+        encoded.extend_from_slice(&sum);
+
+        let decoded = Share::from_bytes(&encoded).unwrap();
+        assert_eq!(decoded.index(), 3);
+        assert_eq!(decoded.threshold(), Some(2));
+        assert_eq!(decoded.share_count(), None);
+        assert_eq!(decoded.as_bytes(), &[0xaa, 0xbb]);
+    }
+    #[test]
+    fn test_truncated() {
+        let encoded = Share::new(1, &[1, 2, 3, 4]).to_bytes();
+        for l in 0..encoded.len() {
+            assert_eq!(
+                Share::from_bytes(&encoded[..l]).err(),
+                Some(SecretSharingError::ShareTruncated),
+                "length {}",
+                l
+            );
+        }
+    }
+    #[test]
+    fn test_corrupted() {
+        let encoded = Share::new(1, &[1, 2, 3, 4]).to_bytes();
+        for i in 5..encoded.len() {
+            let mut corrupted = encoded.clone();
+            corrupted[i] ^= 0x01;
+            assert!(Share::from_bytes(&corrupted).is_err(), "byte {}", i);
+        }
+
+        let mut bad_magic = encoded.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(
+            Share::from_bytes(&bad_magic).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+
+        let mut bad_version = encoded.clone();
+        bad_version[4] = 5;
+        assert_eq!(
+            Share::from_bytes(&bad_version).err(),
+            Some(SecretSharingError::UnsupportedShareVersion(5))
+        );
+
+        let mut trailing = encoded;
+        trailing.push(0);
+        assert_eq!(
+            Share::from_bytes(&trailing).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+    }
+}
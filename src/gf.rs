@@ -4,58 +4,233 @@ pub trait GfOps<T> {
     fn mul(self, x: T) -> T;
     fn inv(self) -> T;
     fn div(self, x: T) -> T;
-    fn exp(self, x: T) -> T;
 }
 
+use crate::field;
+use rand::{CryptoRng, Rng, RngCore};
+
 pub type GF256e = u8;
 
+// AES_POLYNOMIAL is the reduction polynomial x^8 + x^4 + x^3 + x + 1 used by
+// AES, and by the byte-oriented API.
+pub const AES_POLYNOMIAL: u16 = 0x11b;
+// RS_POLYNOMIAL is the reduction polynomial x^8 + x^4 + x^3 + x^2 + 1
+// conventionally used by Reed-Solomon codes, and by some other secret sharing
+// implementations.
+pub const RS_POLYNOMIAL: u16 = 0x11d;
+
+// mul multiplies a and b in GF(2^8) with reduction polynomial `poly`, in
+// constant time.
+fn mul(a: u8, b: u8, poly: u16) -> u8 {
+    let mut yj: u16 = a as u16;
+    let mut xj: u16 = b as u16;
+    let mut z: u16 = 0;
+
+    for _ in 0..8 {
+        z ^= (0u16.wrapping_sub(xj & 1)) & yj;
+        xj >>= 1;
+        yj <<= 1;
+        yj ^= 0u16.wrapping_sub(yj >> 8) & poly;
+    }
+
+    z as u8
+}
+
+// With the `fast-tables` feature, the byte-oriented API multiplies with
+// log/antilog tables instead. This is several times faster for bulk
+// splitting, but the table lookups are indexed by secret-dependent values and
+// so leak them through the cache; only enable it where cache-timing attacks
+// are not a concern, e.g. when splitting on a dedicated offline machine.
+#[cfg(feature = "fast-tables")]
+const fn tables() -> ([u8; 256], [u8; 255]) {
+    // 3 generates the multiplicative group of GF(2^8) modulo 0x11b.
+    let mut log = [0u8; 256];
+    let mut exp = [0u8; 255];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x ^= x << 1;
+        if x & 0x100 != 0 {
+            x ^= AES_POLYNOMIAL;
+        }
+        i += 1;
+    }
+    (log, exp)
+}
+
+#[cfg(feature = "fast-tables")]
+const TABLES: ([u8; 256], [u8; 255]) = tables();
+
+#[cfg(feature = "fast-tables")]
+fn mul_table(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    let (log, exp) = &TABLES;
+    exp[(log[a as usize] as usize + log[b as usize] as usize) % 255]
+}
+
+// inv computes a^254, the inverse of a in any GF(2^8).
+fn inv(a: u8, poly: u16) -> u8 {
+    let mut j = mul(a, a, poly);
+    for _ in 0..6 {
+        j = mul(j, a, poly);
+        j = mul(j, j, poly);
+    }
+    j
+}
+
 // fully constant-time mplementation of GfOps for GF(2^8) with reduction
 // polynomial 0x11b.
 impl GfOps<GF256e> for GF256e {
     fn add(self, x: GF256e) -> GF256e {
-        return self ^ x;
+        self ^ x
     }
     fn sub(self, x: GF256e) -> GF256e {
-        return self ^ x;
+        self ^ x
     }
+    #[cfg(not(feature = "fast-tables"))]
     fn mul(self, x: GF256e) -> GF256e {
-        let mut yj: u16 = self as u16;
-        let mut xj: u16 = x as u16;
-        let mut z: u16 = 0;
-
-        for _ in 0..8 {
-            z ^= ((0 as u16).wrapping_sub(xj & 1)) & yj;
-            xj >>= 1;
-            yj <<= 1;
-            yj ^= (0 as u16).wrapping_sub(yj >> 8) & 0x11b;
-        }
+        mul(self, x, AES_POLYNOMIAL)
+    }
+    #[cfg(feature = "fast-tables")]
+    fn mul(self, x: GF256e) -> GF256e {
+        mul_table(self, x)
+    }
+    fn div(self, x: GF256e) -> GF256e {
+        self.mul(x.inv())
+    }
+    fn inv(self) -> GF256e {
+        inv(self, AES_POLYNOMIAL)
+    }
+}
+
+// GF(2^8) is the field used by the byte-oriented API.
+impl field::Field for GF256e {
+    const ENCODED_LEN: usize = 1;
 
-        return z as GF256e;
+    fn zero() -> GF256e {
+        0
+    }
+    fn one() -> GF256e {
+        1
+    }
+    fn add(self, x: GF256e) -> GF256e {
+        GfOps::add(self, x)
+    }
+    fn sub(self, x: GF256e) -> GF256e {
+        GfOps::sub(self, x)
+    }
+    fn mul(self, x: GF256e) -> GF256e {
+        GfOps::mul(self, x)
+    }
+    fn inv(self) -> GF256e {
+        GfOps::inv(self)
     }
     fn div(self, x: GF256e) -> GF256e {
-        return self.mul(x.inv());
-    }
-    fn exp(self, x: GF256e) -> GF256e {
-        let mut r = 1;
-        let mut q: GF256e = 0;
-        for i in 0..255 {
-            let mut mask = i ^ x;
-            mask |=
-                mask << 1 | mask << 2 | mask << 3 | mask << 4 | mask << 5 | mask << 6 | mask << 7;
-            mask |=
-                mask >> 1 | mask >> 2 | mask >> 3 | mask >> 4 | mask >> 5 | mask >> 6 | mask >> 7;
-            q |= r & !mask;
-            r = r.mul(self);
+        GfOps::div(self, x)
+    }
+    fn random<R: CryptoRng + RngCore>(rng: &mut R) -> GF256e {
+        rng.gen()
+    }
+    fn from_u64(v: u64) -> Option<GF256e> {
+        if v > 0xff {
+            return None;
         }
-        return q;
+        Some(v as GF256e)
     }
-    fn inv(self) -> GF256e {
-        let mut j = self.mul(self);
-        for _ in 0..6 {
-            j = j.mul(self);
-            j = j.mul(j);
+    fn to_bytes(&self, out: &mut [u8]) {
+        out[0] = *self;
+    }
+    fn from_bytes(b: &[u8]) -> Option<GF256e> {
+        b.first().copied()
+    }
+}
+
+// poly_mod returns a mod b for polynomials over GF(2), b non-zero.
+const fn poly_mod(mut a: u16, b: u16) -> u16 {
+    let db = 15 - b.leading_zeros();
+    while a != 0 && 15 - a.leading_zeros() >= db {
+        a ^= b << (15 - a.leading_zeros() - db);
+    }
+    a
+}
+
+// is_irreducible returns whether `poly` is a degree 8 polynomial over GF(2)
+// with no factor of degree 1 to 4, and so defines GF(2^8).
+const fn is_irreducible(poly: u16) -> bool {
+    if poly >> 8 != 1 {
+        return false;
+    }
+    // 0b10 to 0b11111 are the polynomials of degree 1 to 4.
+    let mut d = 0b10;
+    while d <= 0b11111 {
+        if poly_mod(poly, d) == 0 {
+            return false;
         }
-        return j;
+        d += 1;
+    }
+    true
+}
+
+// Gf256 is an element of GF(2^8) with the reduction polynomial POLY, for
+// interoperating with implementations that don't use AES_POLYNOMIAL. Secrets
+// are shared with it through `field::split` and `field::combine`; shares are
+// only compatible with those made using the same polynomial. POLY must be an
+// irreducible polynomial of degree 8, such as AES_POLYNOMIAL or
+// RS_POLYNOMIAL: any other doesn't define a field, and interpolation would
+// silently return wrong secrets, so multiplying fails to compile.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Gf256<const POLY: u16>(pub u8);
+
+impl<const POLY: u16> Gf256<POLY> {
+    // IRREDUCIBLE is evaluated, and so fails to compile for a reducible POLY,
+    // by every use of mul and inv.
+    const IRREDUCIBLE: () = assert!(
+        is_irreducible(POLY),
+        "Gf256 requires an irreducible polynomial of degree 8"
+    );
+}
+
+impl<const POLY: u16> field::Field for Gf256<POLY> {
+    const ENCODED_LEN: usize = 1;
+
+    fn zero() -> Gf256<POLY> {
+        Gf256(0)
+    }
+    fn one() -> Gf256<POLY> {
+        Gf256(1)
+    }
+    fn add(self, x: Gf256<POLY>) -> Gf256<POLY> {
+        Gf256(self.0 ^ x.0)
+    }
+    fn sub(self, x: Gf256<POLY>) -> Gf256<POLY> {
+        Gf256(self.0 ^ x.0)
+    }
+    fn mul(self, x: Gf256<POLY>) -> Gf256<POLY> {
+        let () = Self::IRREDUCIBLE;
+        Gf256(mul(self.0, x.0, POLY))
+    }
+    fn inv(self) -> Gf256<POLY> {
+        let () = Self::IRREDUCIBLE;
+        Gf256(inv(self.0, POLY))
+    }
+    fn random<R: CryptoRng + RngCore>(rng: &mut R) -> Gf256<POLY> {
+        Gf256(rng.gen())
+    }
+    fn from_u64(v: u64) -> Option<Gf256<POLY>> {
+        if v > 0xff {
+            return None;
+        }
+        Some(Gf256(v as u8))
+    }
+    fn to_bytes(&self, out: &mut [u8]) {
+        out[0] = self.0;
+    }
+    fn from_bytes(b: &[u8]) -> Option<Gf256<POLY>> {
+        b.first().map(|&b| Gf256(b))
     }
 }
 
@@ -87,12 +262,103 @@ mod tests {
         let a: GF256e = 0xcc;
         assert_eq!(a.mul(a.inv()), 0x1);
     }
-    // TODO: embed correct table values
     #[test]
-    fn test_exp() {
-        assert_eq!((0x02 as GF256e).exp(0x04), 1 << 4);
+    fn test_field_axioms() {
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                assert_eq!(a.mul(b), b.mul(a));
+                // the same as the carry-less product reduced mod 0x11b.
+                let mut p =
+                    (0..8).fold(0u16, |p, i| p ^ (((b as u16 >> i) & 1) * ((a as u16) << i)));
+                for i in (8..15).rev() {
+                    if p >> i & 1 == 1 {
+                        p ^= AES_POLYNOMIAL << (i - 8);
+                    }
+                }
+                assert_eq!(a.mul(b), p as u8);
+            }
+            assert_eq!(a.div(0), 0);
+            if a != 0 {
+                assert_eq!(a.mul(a.inv()), 1);
+                assert_eq!(a.div(a), 1);
+            }
+        }
+        assert_eq!(0u8.inv(), 0);
+    }
+    #[test]
+    fn test_polynomials() {
+        use crate::field::{combine, split, Field};
+
+        assert_eq!(Gf256::<AES_POLYNOMIAL>(0xb6).mul(Gf256(0x53)), Gf256(0x36));
+        assert_eq!(Gf256::<RS_POLYNOMIAL>(0xb6).mul(Gf256(0x53)), Gf256(0xee));
+        assert_eq!(Gf256::<RS_POLYNOMIAL>(0x80).mul(Gf256(0x02)), Gf256(0x1d));
+        for a in 1..=255 {
+            let a = Gf256::<RS_POLYNOMIAL>(a);
+            assert_eq!(a.mul(a.inv()), Gf256::one());
+        }
+
+        let secret: Vec<Gf256<RS_POLYNOMIAL>> = b"interop".iter().map(|&b| Gf256(b)).collect();
+        let shares = split(3, 5, &secret, &mut rand::thread_rng()).unwrap();
+        assert_eq!(combine(&shares[2..]).unwrap(), secret);
+    }
+    #[test]
+    fn test_is_irreducible() {
+        assert!(is_irreducible(AES_POLYNOMIAL));
+        assert!(is_irreducible(RS_POLYNOMIAL));
+        // x^8 + 1 = (x + 1)^8, and 0x1f1 is of degree 8 but has x + 1 as a
+        // factor; 0x8b isn't of degree 8.
+        for poly in [0x100, 0x101, 0x1f1, 0x8b, 0x21b] {
+            assert!(!is_irreducible(poly), "{:#x}", poly);
+        }
+        // there are 30 irreducible polynomials of degree 8 over GF(2).
+        assert_eq!((0x100..0x200).filter(|&p| is_irreducible(p)).count(), 30);
+    }
+    #[cfg(feature = "fast-tables")]
+    #[test]
+    fn test_mul_table() {
+        for a in 0..=255 {
+            for b in 0..=255 {
+                assert_eq!(mul_table(a, b), mul(a, b, AES_POLYNOMIAL));
+            }
+        }
+    }
+}
+
+// Proof harnesses for the Kani model checker, which check the field axioms for
+// every input rather than a sample. Run with `cargo kani`.
+#[cfg(kani)]
+mod verification {
+    use super::*;
+
+    #[kani::proof]
+    fn mul_commutative() {
+        let (a, b): (u8, u8) = (kani::any(), kani::any());
+        assert_eq!(a.mul(b), b.mul(a));
+    }
+
+    #[kani::proof]
+    fn mul_associative() {
+        let (a, b, c): (u8, u8, u8) = (kani::any(), kani::any(), kani::any());
+        assert_eq!(a.mul(b).mul(c), a.mul(b.mul(c)));
+    }
+
+    #[kani::proof]
+    fn mul_distributes_over_add() {
+        let (a, b, c): (u8, u8, u8) = (kani::any(), kani::any(), kani::any());
+        assert_eq!(a.mul(b.add(c)), a.mul(b).add(a.mul(c)));
+    }
+
+    #[kani::proof]
+    fn inv_is_inverse() {
+        let a: u8 = kani::any();
+        kani::assume(a != 0);
+        assert_eq!(a.mul(a.inv()), 1);
+    }
 
-        assert_eq!((0x12 as GF256e).exp(0), 1);
-        assert_eq!((0x12 as GF256e).exp(1), 0x12);
+    // div by zero is defined to return zero, without panicking.
+    #[kani::proof]
+    fn div_by_zero() {
+        let a: u8 = kani::any();
+        assert_eq!(a.div(0), 0);
     }
 }
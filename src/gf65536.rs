@@ -0,0 +1,93 @@
+use crate::field::Field;
+use rand::{CryptoRng, Rng, RngCore};
+
+pub type GF65536e = u16;
+
+// REDUCTION is the irreducible polynomial x^16 + x^5 + x^3 + x + 1.
+const REDUCTION: u32 = 0x1002b;
+
+// fully constant-time implementation of GF(2^16), used when more than 255
+// shares are needed.
+impl Field for GF65536e {
+    const ENCODED_LEN: usize = 2;
+
+    fn zero() -> GF65536e {
+        0
+    }
+    fn one() -> GF65536e {
+        1
+    }
+    fn add(self, x: GF65536e) -> GF65536e {
+        self ^ x
+    }
+    fn sub(self, x: GF65536e) -> GF65536e {
+        self ^ x
+    }
+    fn mul(self, x: GF65536e) -> GF65536e {
+        let mut yj: u32 = self as u32;
+        let mut xj: u32 = x as u32;
+        let mut z: u32 = 0;
+
+        for _ in 0..16 {
+            z ^= 0u32.wrapping_sub(xj & 1) & yj;
+            xj >>= 1;
+            yj <<= 1;
+            yj ^= 0u32.wrapping_sub(yj >> 16) & REDUCTION;
+        }
+
+        z as GF65536e
+    }
+    // inv computes self^(2^16 - 2) by square-and-multiply over the fixed
+    // exponent, whose bits are all ones except the lowest.
+    fn inv(self) -> GF65536e {
+        let mut r: GF65536e = 1;
+        for i in (0..16).rev() {
+            r = r.mul(r);
+            if i != 0 {
+                r = r.mul(self);
+            }
+        }
+        r
+    }
+    fn random<R: CryptoRng + RngCore>(rng: &mut R) -> GF65536e {
+        rng.gen()
+    }
+    fn from_u64(v: u64) -> Option<GF65536e> {
+        if v > 0xffff {
+            return None;
+        }
+        Some(v as GF65536e)
+    }
+    fn to_bytes(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes());
+    }
+    fn from_bytes(b: &[u8]) -> Option<GF65536e> {
+        if b.len() != 2 {
+            return None;
+        }
+        Some(GF65536e::from_be_bytes([b[0], b[1]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul() {
+        let a: GF65536e = 0x8000;
+        // x^15 * x = x^16 = x^5 + x^3 + x + 1.
+        assert_eq!(a.mul(2), 0x002b);
+        assert_eq!(a.mul(1), a);
+        assert_eq!(a.mul(0), 0);
+    }
+    #[test]
+    fn test_inv_all() {
+        // every non-zero element having an inverse shows the reduction
+        // polynomial is irreducible.
+        for a in 1..=0xffffu32 {
+            let a = a as GF65536e;
+            assert_eq!(a.mul(a.inv()), 1, "{:#x}", a);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+// Interoperability with libgfshare and its gfsplit and gfcombine tools.
+// libgfshare shares over GF(2^8) with RS_POLYNOMIAL rather than the AES
+// polynomial, assigns the shares distinct random x-coordinates, and stores
+// each share in a file of its own: gfsplit writes the y-values of share x,
+// with no header, to "<name>.NNN", where NNN is x as three decimal digits,
+// and gfcombine reads x back from the file name.
+//
+// Shares are field shares over GfshareField, so they can't be mixed with the
+// byte-oriented API's; gfshare_split and gfshare_combine produce and consume
+// them, and the file functions read and write gfsplit's files.
+
+use crate::field::{self, FieldShare};
+use crate::gf::{Gf256, RS_POLYNOMIAL};
+use crate::SecretSharingError;
+use rand::seq::SliceRandom;
+use rand::{CryptoRng, RngCore};
+use std::fs;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+// GfshareField is the field libgfshare shares over.
+pub type GfshareField = Gf256<RS_POLYNOMIAL>;
+
+// gfshare_split shares `secret` as gfsplit does: into `n` shares at distinct
+// random x-coordinates, `t` of which are required to reconstruct it.
+pub fn gfshare_split<R: CryptoRng + RngCore>(
+    t: u8,
+    n: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Vec<FieldShare<GfshareField>>, SecretSharingError> {
+    if t == 0 || n == 0 {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if t > n {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+
+    let mut xs: Vec<u8> = (1..=255).collect();
+    xs.shuffle(rng);
+    let mut shares: Vec<FieldShare<GfshareField>> = xs[..n as usize]
+        .iter()
+        .map(|&x| FieldShare {
+            x: Gf256(x),
+            y: Vec::with_capacity(secret.len()),
+        })
+        .collect();
+    let mut coeff = Zeroizing::new(vec![0u8; t as usize]);
+    for &s in secret {
+        rng.fill_bytes(&mut coeff);
+        coeff[0] = s;
+        let mut poly: Vec<GfshareField> = coeff.iter().map(|&c| Gf256(c)).collect();
+        for share in shares.iter_mut() {
+            share.y.push(field::evaluate(&poly, share.x));
+        }
+        field::wipe(&mut poly);
+    }
+    Ok(shares)
+}
+
+// gfshare_combine reconstructs the secret from at least t shares, as
+// gfcombine does. Like gfcombine, it cannot detect too few shares.
+pub fn gfshare_combine(shares: &[FieldShare<GfshareField>]) -> Result<Vec<u8>, SecretSharingError> {
+    Ok(field::combine(shares)?.iter().map(|b| b.0).collect())
+}
+
+// gfshare_file_name returns the name gfsplit gives the share at x in the
+// output for `name`, e.g. secret.txt.042.
+pub fn gfshare_file_name(name: &Path, share: &FieldShare<GfshareField>) -> PathBuf {
+    let mut out = name.as_os_str().to_owned();
+    out.push(format!(".{:03}", share.x.0));
+    PathBuf::from(out)
+}
+
+// write_gfshare_files writes every share to the file gfsplit would, next to
+// `name`, and returns their paths.
+pub fn write_gfshare_files(
+    name: &Path,
+    shares: &[FieldShare<GfshareField>],
+) -> Result<Vec<PathBuf>, SecretSharingError> {
+    shares
+        .iter()
+        .map(|share| {
+            let path = gfshare_file_name(name, share);
+            let data = Zeroizing::new(share.y.iter().map(|b| b.0).collect::<Vec<u8>>());
+            fs::write(&path, &data[..]).map_err(|err| SecretSharingError::Io(err.kind()))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+// read_gfshare_file reads a share written by gfsplit, taking its x-coordinate
+// from the three-digit extension of `path`.
+pub fn read_gfshare_file(path: &Path) -> Result<FieldShare<GfshareField>, SecretSharingError> {
+    let x = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| ext.len() == 3 && ext.bytes().all(|c| c.is_ascii_digit()))
+        .and_then(|ext| ext.parse::<u8>().ok())
+        .ok_or(SecretSharingError::InvalidShareIndex)?;
+    if x == 0 {
+        return Err(SecretSharingError::InvalidShareIndex);
+    }
+    let data = Zeroizing::new(fs::read(path).map_err(|err| SecretSharingError::Io(err.kind()))?);
+    Ok(FieldShare {
+        x: Gf256(x),
+        y: data.iter().map(|&b| Gf256(b)).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share(x: u8, y: &[u8]) -> FieldShare<GfshareField> {
+        FieldShare {
+            x: Gf256(x),
+            y: y.iter().map(|&b| Gf256(b)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_gfshare_vector() {
+        // 0x42 + 0x80x: 0x80 * 2 reduces by 0x11d to 0x1d, not 0x1b as it
+        // would with the AES polynomial.
+        let shares = [share(1, &[0xc2]), share(2, &[0x5f])];
+        assert_eq!(gfshare_combine(&shares).unwrap(), vec![0x42]);
+        assert_eq!(
+            gfshare_combine(&[share(1, &[0xc2]), share(1, &[0x5f])]).err(),
+            Some(SecretSharingError::DuplicateShare { x: 1 })
+        );
+    }
+    #[test]
+    fn test_gfshare_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let secret = b"gfsplit compatible secret";
+        let shares = gfshare_split(3, 5, secret, &mut rng).unwrap();
+        assert_eq!(gfshare_combine(&shares[2..]).unwrap(), secret);
+        assert_ne!(gfshare_combine(&shares[..2]).unwrap(), secret);
+    }
+    #[test]
+    fn test_gfshare_files() {
+        let dir = std::env::temp_dir().join(format!("gfshare-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let name = dir.join("secret.txt");
+
+        let shares = gfshare_split(2, 3, b"files", &mut rand::thread_rng()).unwrap();
+        let paths = write_gfshare_files(&name, &shares).unwrap();
+        assert_eq!(
+            paths[0].file_name().unwrap().to_str().unwrap(),
+            format!("secret.txt.{:03}", shares[0].x.0)
+        );
+        let read: Vec<FieldShare<GfshareField>> = paths[1..]
+            .iter()
+            .map(|p| read_gfshare_file(p).unwrap())
+            .collect();
+        assert_eq!(gfshare_combine(&read).unwrap(), b"files");
+        assert_eq!(
+            read_gfshare_file(&name).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
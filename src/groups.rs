@@ -0,0 +1,275 @@
+// Two-level group sharing, in the style of SLIP-39: the secret is split into
+// group shares with a group threshold, and each group share is split again
+// among the group's members with the group's own threshold. A policy such as
+// "2 of 3 groups, where group 1 is 2-of-3 people, group 2 is 1-of-1 and
+// group 3 is 3-of-5" is dealt by split_groups(secret, 2, &[(2, 3), (1, 1),
+// (3, 5)]). Recovery needs enough members of enough groups.
+//
+// A MemberShare records which group it belongs to alongside the member's
+// share of that group's share. Its encoding:
+//
+//   offset  size  field
+//   0       4     magic, "SHMG"
+//   4       1     format version, currently 1
+//   5       1     group index
+//   6       1     group threshold
+//   7       1     group count
+//   8       len   member share, in the canonical binary format
+//
+// The member share's own checksum covers only itself, so the group fields are
+// checked for consistency on recovery rather than trusted.
+
+use crate::{construct_shares_with_rng, reconstruct, SecretSharingError, Share, Shares};
+use rand::{CryptoRng, RngCore};
+use std::collections::BTreeMap;
+use zeroize::Zeroizing;
+
+const MAGIC: [u8; 4] = *b"SHMG";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 8;
+
+// MemberShare is one member's share in a group sharing.
+#[derive(Clone, Debug)]
+pub struct MemberShare {
+    group: u8,
+    group_threshold: u8,
+    group_count: u8,
+    share: Share,
+}
+
+impl MemberShare {
+    // group returns the index of the member's group, from 1.
+    pub fn group(&self) -> u8 {
+        self.group
+    }
+
+    // group_threshold returns the number of groups needed for recovery.
+    pub fn group_threshold(&self) -> u8 {
+        self.group_threshold
+    }
+
+    pub fn group_count(&self) -> u8 {
+        self.group_count
+    }
+
+    // share returns the member's share of the group share. Its threshold is
+    // the number of members of the group needed for recovery.
+    pub fn share(&self) -> &Share {
+        &self.share
+    }
+
+    // to_bytes encodes the member share in the format described above.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend_from_slice(&[VERSION, self.group, self.group_threshold, self.group_count]);
+        out.extend_from_slice(&Zeroizing::new(self.share.to_bytes()));
+        out
+    }
+
+    // from_bytes decodes a member share produced by to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<MemberShare, SecretSharingError> {
+        if data.len() < MAGIC.len() + 1 {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        if data[..MAGIC.len()] != MAGIC {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if data[4] != VERSION {
+            return Err(SecretSharingError::UnsupportedShareVersion(data[4]));
+        }
+        if data.len() < HEADER_LEN {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        let (group, group_threshold, group_count) = (data[5], data[6], data[7]);
+        if group == 0
+            || group_threshold == 0
+            || group > group_count
+            || group_threshold > group_count
+        {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        Ok(MemberShare {
+            group,
+            group_threshold,
+            group_count,
+            share: Share::from_bytes(&data[HEADER_LEN..])?,
+        })
+    }
+}
+
+// split_groups splits `secret` among groups of members. `groups` gives each
+// group's member threshold and member count, and any `group_threshold` groups
+// in which enough members take part can recover the secret. The result holds
+// each group's member shares, in the order of `groups`.
+pub fn split_groups(
+    secret: &[u8],
+    group_threshold: u8,
+    groups: &[(u8, u8)],
+) -> Result<Vec<Vec<MemberShare>>, SecretSharingError> {
+    split_groups_with_rng(secret, group_threshold, groups, &mut crate::default_rng())
+}
+
+// split_groups_with_rng is like split_groups, but draws the polynomial
+// coefficients from `rng`.
+pub fn split_groups_with_rng<R: CryptoRng + RngCore>(
+    secret: &[u8],
+    group_threshold: u8,
+    groups: &[(u8, u8)],
+    rng: &mut R,
+) -> Result<Vec<Vec<MemberShare>>, SecretSharingError> {
+    if groups.len() > u8::MAX as usize {
+        return Err(SecretSharingError::TooManyShares);
+    }
+    let group_count = groups.len() as u8;
+    let group_shares = construct_shares_with_rng(group_threshold, group_count, secret, rng)?;
+    group_shares
+        .iter()
+        .zip(groups)
+        .map(|(group_share, &(t, n))| {
+            let members = construct_shares_with_rng(t, n, group_share.as_bytes(), rng)?;
+            Ok(members
+                .into_iter()
+                .map(|share| MemberShare {
+                    group: group_share.index(),
+                    group_threshold,
+                    group_count,
+                    share,
+                })
+                .collect())
+        })
+        .collect()
+}
+
+// reconstruct_groups recovers the secret from member shares of any groups.
+// Groups without enough members are ignored; if fewer than the group
+// threshold of groups can be recovered, it returns NotEnoughShares counting
+// groups. Shares that disagree about the group threshold or count are
+// rejected with InconsistentShares, naming the groups involved.
+pub fn reconstruct_groups(shares: &[MemberShare]) -> Result<Vec<u8>, SecretSharingError> {
+    let first = shares.first().ok_or(SecretSharingError::NoSharesProvided)?;
+    let (group_threshold, group_count) = (first.group_threshold, first.group_count);
+    let mut suspects: Vec<u8> = shares
+        .iter()
+        .filter(|m| m.group_threshold != group_threshold || m.group_count != group_count)
+        .map(|m| m.group)
+        .collect();
+    if !suspects.is_empty() {
+        suspects.push(first.group);
+        suspects.sort_unstable();
+        suspects.dedup();
+        return Err(SecretSharingError::InconsistentShares { suspects });
+    }
+
+    let mut members: BTreeMap<u8, Shares> = BTreeMap::new();
+    for m in shares {
+        members.entry(m.group).or_default().push(m.share.clone());
+    }
+    let mut group_shares = Shares::new();
+    for (group, members) in members {
+        match reconstruct(&members) {
+            Ok(y) => group_shares.push(Share::with_metadata(
+                group,
+                &Zeroizing::new(y),
+                group_threshold,
+                group_count,
+            )),
+            Err(SecretSharingError::NotEnoughShares { .. }) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    if group_shares.len() < group_threshold as usize {
+        return Err(SecretSharingError::NotEnoughShares {
+            have: group_shares.len(),
+            need: group_threshold as usize,
+        });
+    }
+    reconstruct(&group_shares)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups() {
+        let secret = b"wallet seed";
+        // 2 of 3 groups: 2-of-3 family members, a 1-of-1 lawyer and 3-of-5
+        // friends.
+        let groups = split_groups(secret, 2, &[(2, 3), (1, 1), (3, 5)]).unwrap();
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [3, 1, 5]);
+        assert_eq!(groups[2][4].group(), 3);
+        assert_eq!(groups[2][4].share().threshold(), Some(3));
+
+        let family = &groups[0];
+        let lawyer = &groups[1];
+        let friends = &groups[2];
+        for quorum in [
+            vec![&family[0], &family[2], &lawyer[0]],
+            vec![&lawyer[0], &friends[1], &friends[3], &friends[4]],
+            // a group short of members doesn't count, but doesn't hurt.
+            vec![
+                &family[1],
+                &lawyer[0],
+                &friends[0],
+                &friends[1],
+                &friends[2],
+            ],
+        ] {
+            let shares: Vec<MemberShare> = quorum
+                .into_iter()
+                .map(|m| MemberShare::from_bytes(&m.to_bytes()).unwrap())
+                .collect();
+            assert_eq!(reconstruct_groups(&shares).unwrap(), secret);
+        }
+
+        let short = [family[0].clone(), lawyer[0].clone(), friends[0].clone()];
+        assert_eq!(
+            reconstruct_groups(&short[1..]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 2 })
+        );
+        assert_eq!(
+            reconstruct_groups(&short).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 2 })
+        );
+    }
+    #[test]
+    fn test_groups_errors() {
+        assert_eq!(
+            split_groups(b"s", 3, &[(1, 1), (1, 1)]).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        assert_eq!(
+            split_groups(b"s", 1, &[(1, 1), (3, 2)]).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        assert_eq!(
+            reconstruct_groups(&[]).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+
+        let groups = split_groups(b"s", 1, &[(1, 1), (1, 1)]).unwrap();
+        let mut bytes = groups[1][0].to_bytes();
+        bytes[6] = 2;
+        let other = MemberShare::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            reconstruct_groups(&[groups[0][0].clone(), other]).err(),
+            Some(SecretSharingError::InconsistentShares {
+                suspects: vec![1, 2]
+            })
+        );
+        bytes[5] = 3;
+        assert_eq!(
+            MemberShare::from_bytes(&bytes).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        bytes[4] = 2;
+        assert_eq!(
+            MemberShare::from_bytes(&bytes).err(),
+            Some(SecretSharingError::UnsupportedShareVersion(2))
+        );
+        assert_eq!(
+            MemberShare::from_bytes(&groups[0][0].to_bytes()[..6]).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+    }
+}
@@ -0,0 +1,358 @@
+// Hierarchical threshold sharing (Tassa, "Hierarchical Threshold Secret
+// Sharing", J. Cryptology 2007). Participants are ranked in levels, level 0
+// the most senior, and a set of them is authorized when, for every level j,
+// it holds at least thresholds[j] members of levels 0 to j. With thresholds
+// [1, 3], any 3 participants may recover the secret as long as at least one
+// of them is from level 0, such as "at least one director among any 3
+// signers".
+//
+// The secret is the constant term of a random polynomial f of degree k-1,
+// where k is the last threshold. A level-0 participant at x receives f(x), as
+// in Shamir's scheme; a participant of level j > 0 receives the
+// thresholds[j-1]-th derivative of f at x. Derivatives shed the low-order
+// coefficients, the secret first, so shares of junior levels alone can never
+// determine it. Recovery solves the linear system the shares give for the
+// coefficients of f (Birkhoff interpolation), which is non-singular exactly
+// for authorized sets.
+//
+// Derivatives need a field of large characteristic, so the scheme works over
+// the prime fields of prime.rs rather than GF(2^8). Participants are given x
+// = 1, 2, ... in level order, which Tassa shows keeps every authorized system
+// non-singular in fields of this size.
+
+use crate::field::{wipe, Field};
+use crate::prime::{Fp, Modulus};
+use crate::{Redacted, SecretSharingError};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+
+// Hierarchy is the cumulative threshold of each level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hierarchy {
+    thresholds: Vec<usize>,
+}
+
+// HierarchicalShare is a participant's share: the derivative of each
+// secret element's polynomial given by its level, at x.
+#[derive(Clone, PartialEq)]
+pub struct HierarchicalShare<M: Modulus> {
+    pub level: usize,
+    pub x: Fp<M>,
+    pub y: Vec<Fp<M>>,
+}
+
+impl<M: Modulus> fmt::Debug for HierarchicalShare<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HierarchicalShare")
+            .field("level", &self.level)
+            .field("x", &self.x)
+            .field("data", &Redacted(self.y.len()))
+            .finish()
+    }
+}
+
+// falling returns i (i-1) ... (i-d+1), the factor the d-th derivative of x^i
+// is multiplied by.
+fn falling<F: Field>(i: usize, d: usize) -> F {
+    (i - d + 1..=i).fold(F::one(), |acc, v| acc.mul(F::from_u64(v as u64).unwrap()))
+}
+
+// row returns the coefficients, constant term first, of the linear form
+// giving the d-th derivative at x of a polynomial with k coefficients.
+fn row<F: Field>(k: usize, d: usize, x: F) -> Vec<F> {
+    let mut out = vec![F::zero(); k];
+    let mut power = F::one();
+    for (i, c) in out.iter_mut().enumerate().skip(d) {
+        *c = falling::<F>(i, d).mul(power);
+        power = power.mul(x);
+    }
+    out
+}
+
+impl Hierarchy {
+    // new returns the hierarchy with the cumulative `thresholds` of levels 0,
+    // 1, ..., which must be non-zero and strictly increasing.
+    pub fn new(thresholds: &[usize]) -> Result<Hierarchy, SecretSharingError> {
+        if thresholds.first().is_none_or(|&t| t == 0) {
+            return Err(SecretSharingError::TorNisZero);
+        }
+        if thresholds.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(SecretSharingError::InvalidHierarchy);
+        }
+        Ok(Hierarchy {
+            thresholds: thresholds.to_vec(),
+        })
+    }
+
+    // threshold returns the number of participants any authorized set has.
+    pub fn threshold(&self) -> usize {
+        self.thresholds[self.thresholds.len() - 1]
+    }
+
+    // order returns the derivative given to participants of `level`.
+    fn order(&self, level: usize) -> usize {
+        match level {
+            0 => 0,
+            _ => self.thresholds[level - 1],
+        }
+    }
+
+    // check_authorized checks that participants with the given levels may
+    // recover the secret, returning the first unmet requirement if not.
+    fn check_authorized<I: Iterator<Item = usize>>(
+        &self,
+        levels: I,
+    ) -> Result<(), SecretSharingError> {
+        let mut counts = vec![0; self.thresholds.len()];
+        for level in levels {
+            if level >= counts.len() {
+                return Err(SecretSharingError::InvalidShareIndex);
+            }
+            counts[level] += 1;
+        }
+        let mut have = 0;
+        for (count, &need) in counts.iter().zip(self.thresholds.iter()) {
+            have += count;
+            if have < need {
+                return Err(SecretSharingError::NotEnoughShares { have, need });
+            }
+        }
+        Ok(())
+    }
+
+    // split shares every element of `secret` among participants, `counts[j]`
+    // of them at level j. Shares are returned in level order. It returns
+    // NotEnoughShares if the participants couldn't form an authorized set.
+    pub fn split<M: Modulus, R: CryptoRng + RngCore>(
+        &self,
+        secret: &[Fp<M>],
+        counts: &[usize],
+        rng: &mut R,
+    ) -> Result<Vec<HierarchicalShare<M>>, SecretSharingError> {
+        if counts.len() != self.thresholds.len() {
+            return Err(SecretSharingError::InvalidHierarchy);
+        }
+        let levels: Vec<usize> = counts
+            .iter()
+            .enumerate()
+            .flat_map(|(level, &n)| std::iter::repeat_n(level, n))
+            .collect();
+        self.check_authorized(levels.iter().copied())?;
+
+        let k = self.threshold();
+        let mut shares = levels
+            .iter()
+            .enumerate()
+            .map(|(i, &level)| {
+                Ok(HierarchicalShare {
+                    level,
+                    x: Fp::from_u64(i as u64 + 1).ok_or(SecretSharingError::InvalidShareIndex)?,
+                    y: Vec::with_capacity(secret.len()),
+                })
+            })
+            .collect::<Result<Vec<_>, SecretSharingError>>()?;
+        let rows: Vec<Vec<Fp<M>>> = shares
+            .iter()
+            .map(|s| row(k, self.order(s.level), s.x))
+            .collect();
+        let mut coeff = vec![Fp::zero(); k];
+        for s in secret {
+            coeff[0] = *s;
+            for c in coeff[1..].iter_mut() {
+                *c = Fp::random(rng);
+            }
+            for (share, row) in shares.iter_mut().zip(rows.iter()) {
+                let y = row
+                    .iter()
+                    .zip(coeff.iter())
+                    .fold(Fp::zero(), |y, (r, c)| y.add(r.mul(*c)));
+                share.y.push(y);
+            }
+        }
+        wipe(&mut coeff);
+        Ok(shares)
+    }
+
+    // combine recovers the secret from the shares of an authorized set. It
+    // returns NotEnoughShares if the set isn't authorized, and
+    // InconsistentShares if the shares' system of equations is singular,
+    // which happens only for shares not dealt by split.
+    pub fn combine<M: Modulus>(
+        &self,
+        shares: &[HierarchicalShare<M>],
+    ) -> Result<Vec<Fp<M>>, SecretSharingError> {
+        let len = match shares.first() {
+            Some(share) => share.y.len(),
+            None => return Err(SecretSharingError::NoSharesProvided),
+        };
+        if shares.iter().any(|share| share.y.len() != len) {
+            let byte = shares.iter().map(|share| share.y.len()).min().unwrap_or(0);
+            return Err(SecretSharingError::MissingShareForByte { byte });
+        }
+        for (i, a) in shares.iter().enumerate() {
+            if shares[..i].iter().any(|b| b.x == a.x) {
+                return Err(SecretSharingError::InvalidShareIndex);
+            }
+        }
+        self.check_authorized(shares.iter().map(|s| s.level))?;
+
+        // Gaussian elimination on the augmented matrix [rows | y], one
+        // column per secret element on the right.
+        let k = self.threshold();
+        let mut m: Vec<Vec<Fp<M>>> = shares
+            .iter()
+            .map(|s| {
+                let mut r = row(k, self.order(s.level), s.x);
+                r.extend_from_slice(&s.y);
+                r
+            })
+            .collect();
+        let mut singular = false;
+        for col in 0..k {
+            let pivot = match (col..m.len()).find(|&r| m[r][col] != Fp::zero()) {
+                Some(pivot) => pivot,
+                None => {
+                    singular = true;
+                    break;
+                }
+            };
+            m.swap(col, pivot);
+            let mut pivot = std::mem::take(&mut m[col]);
+            let inv = pivot[col].inv();
+            for v in pivot.iter_mut() {
+                *v = v.mul(inv);
+            }
+            for r in m.iter_mut().filter(|r| !r.is_empty()) {
+                let factor = r[col];
+                for (v, p) in r.iter_mut().zip(pivot.iter()).skip(col) {
+                    *v = v.sub(p.mul(factor));
+                }
+            }
+            m[col] = pivot;
+        }
+        let secret = match singular {
+            false => Ok(m[0][k..].to_vec()),
+            true => Err(SecretSharingError::InconsistentShares {
+                suspects: Vec::new(),
+            }),
+        };
+        for r in m.iter_mut() {
+            wipe(r);
+        }
+        secret
+    }
+}
+
+// evaluate_derivative returns the d-th derivative at x of the polynomial with
+// coefficients `coeff`, constant term first.
+#[cfg(test)]
+fn evaluate_derivative<F: Field>(coeff: &[F], d: usize, x: F) -> F {
+    let derived: Vec<F> = coeff
+        .iter()
+        .enumerate()
+        .skip(d)
+        .map(|(i, c)| c.mul(falling(i, d)))
+        .collect();
+    crate::field::evaluate(&derived, x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::Secp256k1Scalar;
+
+    fn scalar(v: u64) -> Secp256k1Scalar {
+        Secp256k1Scalar::from_u64(v).unwrap()
+    }
+
+    #[test]
+    fn test_row() {
+        // f = 5 + 3x + 2x^2 + x^3, f'' = 4 + 6x, f''(2) = 16.
+        let coeff = [scalar(5), scalar(3), scalar(2), scalar(1)];
+        let r = row(4, 2, scalar(2));
+        let y = r
+            .iter()
+            .zip(coeff.iter())
+            .fold(Secp256k1Scalar::zero(), |y, (r, c)| y.add(r.mul(*c)));
+        assert_eq!(y, scalar(16));
+        assert_eq!(evaluate_derivative(&coeff, 2, scalar(2)), scalar(16));
+        assert_eq!(evaluate_derivative(&coeff, 0, scalar(2)), scalar(27));
+    }
+    #[test]
+    fn test_hierarchical() {
+        // 2 directors at level 0 and 4 signers at level 1: any 3, at least
+        // one of them a director.
+        let hierarchy = Hierarchy::new(&[1, 3]).unwrap();
+        let mut rng = rand::thread_rng();
+        let secret = vec![Secp256k1Scalar::random(&mut rng), scalar(42)];
+        let shares = hierarchy.split(&secret, &[2, 4], &mut rng).unwrap();
+        assert_eq!(
+            shares.iter().map(|s| s.level).collect::<Vec<_>>(),
+            [0, 0, 1, 1, 1, 1]
+        );
+
+        // every subset of three or more recovers the secret exactly when it
+        // includes a director.
+        for mask in 1u32..64 {
+            let subset: Vec<_> = (0..6)
+                .filter(|i| mask & (1 << i) != 0)
+                .map(|i| shares[i].clone())
+                .collect();
+            let directors = subset.iter().filter(|s| s.level == 0).count();
+            let result = hierarchy.combine(&subset);
+            if subset.len() >= 3 && directors >= 1 {
+                assert_eq!(result.unwrap(), secret, "{:b}", mask);
+            } else {
+                assert!(
+                    matches!(result, Err(SecretSharingError::NotEnoughShares { .. })),
+                    "{:b}",
+                    mask
+                );
+            }
+        }
+    }
+    #[test]
+    fn test_three_levels() {
+        // at least 1 from level 0, 2 from levels 0-1 and 4 in total.
+        let hierarchy = Hierarchy::new(&[1, 2, 4]).unwrap();
+        let mut rng = rand::thread_rng();
+        let secret = vec![scalar(7)];
+        let shares = hierarchy.split(&secret, &[1, 2, 3], &mut rng).unwrap();
+        let pick = |idx: &[usize]| -> Vec<_> { idx.iter().map(|&i| shares[i].clone()).collect() };
+        assert_eq!(hierarchy.combine(&pick(&[0, 1, 3, 4])).unwrap(), secret);
+        assert_eq!(hierarchy.combine(&pick(&[0, 2, 3, 5])).unwrap(), secret);
+        assert_eq!(
+            hierarchy.combine(&pick(&[0, 3, 4, 5])).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 2 })
+        );
+        assert_eq!(
+            hierarchy.combine(&pick(&[1, 2, 3, 4])).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 0, need: 1 })
+        );
+    }
+    #[test]
+    fn test_hierarchy_errors() {
+        assert_eq!(Hierarchy::new(&[]), Err(SecretSharingError::TorNisZero));
+        assert_eq!(Hierarchy::new(&[0, 2]), Err(SecretSharingError::TorNisZero));
+        assert_eq!(
+            Hierarchy::new(&[2, 2]),
+            Err(SecretSharingError::InvalidHierarchy)
+        );
+        let hierarchy = Hierarchy::new(&[1, 3]).unwrap();
+        let mut rng = rand::thread_rng();
+        assert_eq!(
+            hierarchy.split(&[scalar(1)], &[0, 5], &mut rng).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 0, need: 1 })
+        );
+        assert_eq!(
+            hierarchy.split(&[scalar(1)], &[3], &mut rng).err(),
+            Some(SecretSharingError::InvalidHierarchy)
+        );
+        let mut shares = hierarchy.split(&[scalar(1)], &[1, 2], &mut rng).unwrap();
+        shares[2].x = shares[1].x;
+        assert_eq!(
+            hierarchy.combine(&shares).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+    }
+}
@@ -0,0 +1,143 @@
+// Hybrid sharing of large secrets: the secret is encrypted under a fresh
+// random key with XChaCha20-Poly1305, and only the 32-byte key is shared. The
+// shares stay small however large the secret is, and reconstruction is
+// authenticated, since wrong shares give a wrong key and the ciphertext then
+// fails to open. The ciphertext is not secret and can be stored alongside
+// the shares or anywhere else.
+//
+// The ciphertext format:
+//
+//   offset  size  field
+//   0       4     magic, "SHMH"
+//   4       1     format version, currently 1
+//   5       24    nonce
+//   29      len   encrypted secret
+//   29+len  16    Poly1305 tag
+//
+// The magic and version are authenticated as associated data.
+
+use crate::aead::{self, KEY_LEN, NONCE_LEN, TAG_LEN};
+use crate::{construct_shares_with_rng, reconstruct, SecretSharingError, Share, Shares};
+use rand::{CryptoRng, RngCore};
+use std::convert::TryInto;
+use zeroize::Zeroizing;
+
+const MAGIC: [u8; 4] = *b"SHMH";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 5;
+
+// split_large encrypts `secret` under a random key and splits the key into n
+// shares, t of which are required to reconstruct it. It returns the
+// ciphertext and the shares; combine_large needs both.
+pub fn split_large(t: u8, n: u8, secret: &[u8]) -> Result<(Vec<u8>, Shares), SecretSharingError> {
+    split_large_with_rng(t, n, secret, &mut crate::default_rng())
+}
+
+// split_large_with_rng is like split_large, but draws the key, nonce and
+// polynomial coefficients from `rng`.
+pub fn split_large_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    n: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<(Vec<u8>, Shares), SecretSharingError> {
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    rng.fill_bytes(&mut key[..]);
+    let shares = construct_shares_with_rng(t, n, &key[..], rng)?;
+
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    let mut out = Vec::with_capacity(HEADER_LEN + NONCE_LEN + secret.len() + TAG_LEN);
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&aead::seal(&key, &nonce, &out[..HEADER_LEN], secret));
+    Ok((out, shares))
+}
+
+// combine_large reconstructs the key from `shares` and decrypts `ciphertext`
+// with it. It returns DecryptionFailed if the ciphertext was modified or the
+// shares don't reconstruct the key it was encrypted under.
+pub fn combine_large(ciphertext: &[u8], shares: &[Share]) -> Result<Vec<u8>, SecretSharingError> {
+    if ciphertext.len() < HEADER_LEN {
+        return Err(SecretSharingError::ShareTruncated);
+    }
+    if ciphertext[..MAGIC.len()] != MAGIC {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    if ciphertext[4] != VERSION {
+        return Err(SecretSharingError::UnsupportedShareVersion(ciphertext[4]));
+    }
+    if ciphertext.len() < HEADER_LEN + NONCE_LEN + TAG_LEN {
+        return Err(SecretSharingError::ShareTruncated);
+    }
+
+    let key = Zeroizing::new(reconstruct(shares)?);
+    let key: &[u8; KEY_LEN] = key[..]
+        .try_into()
+        .map_err(|_| SecretSharingError::DecryptionFailed)?;
+    let (header, rest) = ciphertext.split_at(HEADER_LEN);
+    let (nonce, sealed) = rest.split_at(NONCE_LEN);
+    let mut secret = aead::open(key, nonce.try_into().unwrap(), header, sealed)
+        .ok_or(SecretSharingError::DecryptionFailed)?;
+    Ok(std::mem::take(&mut *secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_split_large() {
+        let secret: Vec<u8> = (0..100_000u32).map(|i| (i * 31 + 7) as u8).collect();
+        let (ciphertext, shares) =
+            split_large_with_rng(3, 5, &secret, &mut ChaCha20Rng::from_seed([3; 32])).unwrap();
+        assert_eq!(ciphertext.len(), secret.len() + 45);
+        assert!(shares.iter().all(|s| s.len() == KEY_LEN));
+        assert_eq!(combine_large(&ciphertext, &shares[2..]).unwrap(), secret);
+        assert_eq!(combine_large(&ciphertext, &shares[..3]).unwrap(), secret);
+
+        let (empty, shares) = split_large(2, 2, b"").unwrap();
+        assert_eq!(combine_large(&empty, &shares).unwrap(), b"");
+    }
+    #[test]
+    fn test_combine_large_errors() {
+        let (ciphertext, shares) = split_large(2, 3, b"a large secret").unwrap();
+        let (_, other) = split_large(2, 3, b"a large secret").unwrap();
+        let mut tampered = ciphertext.clone();
+        tampered[HEADER_LEN + NONCE_LEN] ^= 1;
+        let mut version = ciphertext.clone();
+        version[4] = 2;
+
+        for (c, s, err) in [
+            (
+                &ciphertext,
+                &other[..2],
+                SecretSharingError::DecryptionFailed,
+            ),
+            (
+                &tampered,
+                &shares[..2],
+                SecretSharingError::DecryptionFailed,
+            ),
+            (
+                &version,
+                &shares[..2],
+                SecretSharingError::UnsupportedShareVersion(2),
+            ),
+            (
+                &ciphertext,
+                &shares[..1],
+                SecretSharingError::NotEnoughShares { have: 1, need: 2 },
+            ),
+        ] {
+            assert_eq!(combine_large(c, s).err(), Some(err));
+        }
+        assert_eq!(
+            combine_large(&ciphertext[..HEADER_LEN + NONCE_LEN + TAG_LEN - 1], &shares).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+    }
+}
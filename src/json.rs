@@ -0,0 +1,475 @@
+// JSON share documents, for storage systems and tooling that work with JSON:
+//
+//   {
+//     "version": 1,
+//     "index": 3,
+//     "threshold": 2,
+//     "share_count": 5,
+//     "identifier": 4660,
+//     "label": "offsite backup",
+//     "owner": "alice",
+//     "created": "2026-10-16T09:30:00Z",
+//     "digest": "sha256:9f86d081...",
+//     "data": "AQID"
+//   }
+//
+// data is the share payload in base64 and digest the SHA-256 of it, checked
+// when the document is read. version, index, digest and data are required.
+//
+// Documents are forward compatible: later versions may only add optional
+// fields, readers ignore fields they don't know, and a reader accepts every
+// version up to its own. A document with a higher version than the reader
+// knows is rejected with UnsupportedShareVersion, as it may depend on fields
+// the reader would ignore.
+
+use crate::armor::{format_time, parse_time};
+use crate::text::{base64_decode, base64_encode, hex_encode};
+use crate::{sha256, SecretSharingError, Share};
+use std::fmt::Write;
+use std::time::SystemTime;
+use zeroize::Zeroizing;
+
+const VERSION: u8 = 1;
+const DIGEST_PREFIX: &str = "sha256:";
+// MAX_DEPTH bounds the nesting of unknown fields' values, so a hostile
+// document can't exhaust the stack.
+const MAX_DEPTH: usize = 32;
+
+// ShareDocument is a share with the metadata of its JSON document.
+#[derive(Clone, Debug)]
+pub struct ShareDocument {
+    pub share: Share,
+    pub label: Option<String>,
+    pub owner: Option<String>,
+    pub created: Option<SystemTime>,
+}
+
+// Value is a parsed JSON value. Numbers are kept as written.
+enum Value {
+    Null,
+    Bool,
+    Number(String),
+    String(Zeroizing<String>),
+    Array,
+    Object(Vec<(String, Value)>),
+}
+
+// Parser is a recursive descent JSON (RFC 8259) parser.
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn ws(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.s.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> Result<(), SecretSharingError> {
+        self.ws();
+        if self.s.get(self.pos) != Some(&c) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, lit: &[u8]) -> Result<(), SecretSharingError> {
+        if !self.s[self.pos..].starts_with(lit) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        self.pos += lit.len();
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, SecretSharingError> {
+        if depth > MAX_DEPTH {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        self.ws();
+        match self.s.get(self.pos) {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => {
+                self.pos += 1;
+                self.ws();
+                if self.s.get(self.pos) == Some(&b']') {
+                    self.pos += 1;
+                    return Ok(Value::Array);
+                }
+                loop {
+                    self.value(depth + 1)?;
+                    self.ws();
+                    match self.s.get(self.pos) {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Value::Array);
+                        }
+                        _ => return Err(SecretSharingError::ShareMalformed),
+                    }
+                }
+            }
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b't') => self.literal(b"true").map(|_| Value::Bool),
+            Some(b'f') => self.literal(b"false").map(|_| Value::Bool),
+            Some(b'n') => self.literal(b"null").map(|_| Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => Err(SecretSharingError::ShareMalformed),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Value, SecretSharingError> {
+        self.eat(b'{')?;
+        let mut fields = Vec::new();
+        self.ws();
+        if self.s.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.ws();
+            let key = self.string()?;
+            self.eat(b':')?;
+            let value = self.value(depth + 1)?;
+            if fields.iter().any(|(k, _)| *k == *key) {
+                return Err(SecretSharingError::ShareMalformed);
+            }
+            fields.push((key.to_string(), value));
+            self.ws();
+            match self.s.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(fields));
+                }
+                _ => return Err(SecretSharingError::ShareMalformed),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, SecretSharingError> {
+        let start = self.pos;
+        let digits = |p: &mut Parser| {
+            let from = p.pos;
+            while p.s.get(p.pos).is_some_and(u8::is_ascii_digit) {
+                p.pos += 1;
+            }
+            p.pos > from
+        };
+        if self.s.get(self.pos) == Some(&b'-') {
+            self.pos += 1;
+        }
+        let int_start = self.pos;
+        if !digits(self) || (self.s[int_start] == b'0' && self.pos - int_start > 1) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if self.s.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            if !digits(self) {
+                return Err(SecretSharingError::ShareMalformed);
+            }
+        }
+        if let Some(b'e' | b'E') = self.s.get(self.pos) {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.s.get(self.pos) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                return Err(SecretSharingError::ShareMalformed);
+            }
+        }
+        // the lexeme is ASCII.
+        Ok(Value::Number(
+            String::from_utf8_lossy(&self.s[start..self.pos]).into_owned(),
+        ))
+    }
+
+    fn hex4(&mut self) -> Result<u32, SecretSharingError> {
+        let hex = self
+            .s
+            .get(self.pos..self.pos + 4)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .filter(|h| h.bytes().all(|c| c.is_ascii_hexdigit()))
+            .ok_or(SecretSharingError::ShareMalformed)?;
+        self.pos += 4;
+        u32::from_str_radix(hex, 16).map_err(|_| SecretSharingError::ShareMalformed)
+    }
+
+    fn string(&mut self) -> Result<Zeroizing<String>, SecretSharingError> {
+        self.eat(b'"')?;
+        let mut out = Zeroizing::new(String::new());
+        loop {
+            let rest = &self.s[self.pos..];
+            // copy the run up to the next quote, escape or control character.
+            let run = rest
+                .iter()
+                .position(|&c| c == b'"' || c == b'\\' || c < 0x20)
+                .ok_or(SecretSharingError::ShareTruncated)?;
+            out.push_str(
+                std::str::from_utf8(&rest[..run])
+                    .map_err(|_| SecretSharingError::ShareMalformed)?,
+            );
+            self.pos += run;
+            match self.s[self.pos] {
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                b'\\' => {
+                    self.pos += 1;
+                    let c = *self
+                        .s
+                        .get(self.pos)
+                        .ok_or(SecretSharingError::ShareTruncated)?;
+                    self.pos += 1;
+                    let ch = match c {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut cp = self.hex4()?;
+                            if (0xd800..0xdc00).contains(&cp) {
+                                self.literal(b"\\u")?;
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(SecretSharingError::ShareMalformed);
+                                }
+                                cp = 0x10000 + ((cp - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(cp).ok_or(SecretSharingError::ShareMalformed)?
+                        }
+                        _ => return Err(SecretSharingError::ShareMalformed),
+                    };
+                    out.push(ch);
+                }
+                _ => return Err(SecretSharingError::ShareMalformed),
+            }
+        }
+    }
+}
+
+// write_string writes `s` as a JSON string.
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl ShareDocument {
+    // new wraps `share` in a document stamped with the current time, taking
+    // its label and owner from the share.
+    pub fn new(share: Share) -> ShareDocument {
+        ShareDocument {
+            label: share.label().map(str::to_string),
+            owner: share.owner().map(str::to_string),
+            share,
+            created: Some(SystemTime::now()),
+        }
+    }
+
+    // to_json encodes the document as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\n");
+        let _ = writeln!(out, "  \"version\": {},", VERSION);
+        let _ = writeln!(out, "  \"index\": {},", self.share.index());
+        if let Some(t) = self.share.threshold() {
+            let _ = writeln!(out, "  \"threshold\": {},", t);
+        }
+        if let Some(n) = self.share.share_count() {
+            let _ = writeln!(out, "  \"share_count\": {},", n);
+        }
+        if let Some(id) = self.share.identifier() {
+            let _ = writeln!(out, "  \"identifier\": {},", id);
+        }
+        for (key, value) in [("label", &self.label), ("owner", &self.owner)] {
+            if let Some(v) = value {
+                let _ = write!(out, "  \"{}\": ", key);
+                write_string(&mut out, v);
+                out.push_str(",\n");
+            }
+        }
+        if let Some(created) = self.created {
+            let _ = writeln!(out, "  \"created\": \"{}\",", format_time(created));
+        }
+        let digest = sha256::digest(self.share.as_bytes());
+        let _ = writeln!(
+            out,
+            "  \"digest\": \"{}{}\",",
+            DIGEST_PREFIX,
+            hex_encode(&digest)
+        );
+        let data = Zeroizing::new(base64_encode(self.share.as_bytes()));
+        let _ = writeln!(out, "  \"data\": \"{}\"", &*data);
+        out.push('}');
+        out
+    }
+
+    // from_json decodes a document written by to_json, of this or any earlier
+    // version.
+    pub fn from_json(s: &str) -> Result<ShareDocument, SecretSharingError> {
+        let mut p = Parser {
+            s: s.as_bytes(),
+            pos: 0,
+        };
+        let fields = match p.object(0)? {
+            Value::Object(fields) => fields,
+            _ => return Err(SecretSharingError::ShareMalformed),
+        };
+        p.ws();
+        if p.pos != p.s.len() {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+
+        let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+        let number = |key: &str| match get(key) {
+            None => Ok(None),
+            Some(Value::Number(n)) => n
+                .parse::<u8>()
+                .map(Some)
+                .map_err(|_| SecretSharingError::ShareMalformed),
+            Some(_) => Err(SecretSharingError::ShareMalformed),
+        };
+        let string = |key: &str| match get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.to_string())),
+            Some(_) => Err(SecretSharingError::ShareMalformed),
+        };
+
+        match number("version")? {
+            Some(0) => return Err(SecretSharingError::ShareMalformed),
+            Some(v) if v > VERSION => return Err(SecretSharingError::UnsupportedShareVersion(v)),
+            Some(_) => {}
+            None => return Err(SecretSharingError::ShareMalformed),
+        }
+        let index = number("index")?.ok_or(SecretSharingError::ShareMalformed)?;
+        let data = match get("data") {
+            Some(Value::String(d)) => base64_decode(d)?,
+            _ => return Err(SecretSharingError::ShareMalformed),
+        };
+        let digest = string("digest")?.ok_or(SecretSharingError::ShareMalformed)?;
+        let expected = format!("{}{}", DIGEST_PREFIX, hex_encode(&sha256::digest(&data)));
+        if !digest.eq_ignore_ascii_case(&expected) {
+            return Err(SecretSharingError::DigestMismatch);
+        }
+
+        let mut share = Share::new(index, &data);
+        share.threshold = number("threshold")?;
+        share.share_count = number("share_count")?;
+        share.identifier = match get("identifier") {
+            None => None,
+            Some(Value::Number(n)) => match n.parse::<u16>() {
+                Ok(0) | Err(_) => return Err(SecretSharingError::ShareMalformed),
+                Ok(id) => Some(id),
+            },
+            Some(_) => return Err(SecretSharingError::ShareMalformed),
+        };
+        Ok(ShareDocument {
+            share,
+            label: string("label")?,
+            owner: string("owner")?,
+            created: string("created")?.as_deref().map(parse_time).transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_json_roundtrip() {
+        let shares = construct_shares(2, 5, b"json secret").unwrap();
+        let mut doc = ShareDocument::new(shares[2].clone());
+        doc.label = Some("offsite \"backup\"\n".to_string());
+        doc.owner = Some("bob \u{1f511}".to_string());
+        let json = doc.to_json();
+        assert!(json.contains("\"version\": 1,\n  \"index\": 3,\n  \"threshold\": 2,"));
+
+        let parsed = ShareDocument::from_json(&json).unwrap();
+        assert_eq!(parsed.share.as_bytes(), shares[2].as_bytes());
+        assert_eq!(parsed.share.index(), 3);
+        assert_eq!(parsed.share.threshold(), Some(2));
+        assert_eq!(parsed.share.share_count(), Some(5));
+        assert_eq!(parsed.share.identifier(), shares[2].identifier());
+        assert_eq!(parsed.label, doc.label);
+        assert_eq!(parsed.owner, doc.owner);
+        assert_eq!(
+            parsed.created.map(format_time),
+            doc.created.map(format_time)
+        );
+    }
+    #[test]
+    fn test_json_compatibility() {
+        // a minimal version 1 document, with fields from some later version.
+        let json = r#"{"data": "AQID", "index": 7, "version": 1,
+            "digest": "sha256:039058c6f2c0cb492c533b0a4d14ef77cc0f78abccced5287d84a1a2011cfb81",
+            "future": {"nested": [1, 2.5e3, -0, true, null, "é🔑"]}}"#;
+        let doc = ShareDocument::from_json(json).unwrap();
+        assert_eq!(doc.share.index(), 7);
+        assert_eq!(doc.share.as_bytes(), [1, 2, 3]);
+        assert_eq!(doc.share.threshold(), None);
+        assert!(doc.label.is_none() && doc.created.is_none());
+
+        assert_eq!(
+            ShareDocument::from_json(&json.replacen("\"version\": 1", "\"version\": 2", 1)).err(),
+            Some(SecretSharingError::UnsupportedShareVersion(2))
+        );
+    }
+    #[test]
+    fn test_json_errors() {
+        let json = ShareDocument::new(Share::new(1, b"key")).to_json();
+        for (from, to, err) in [
+            (
+                "\"data\": \"",
+                "\"data\": \"AAAA",
+                SecretSharingError::DigestMismatch,
+            ),
+            (
+                "\"index\": 1",
+                "\"index\": 256",
+                SecretSharingError::ShareMalformed,
+            ),
+            (
+                "\"index\": 1",
+                "\"index\": \"1\"",
+                SecretSharingError::ShareMalformed,
+            ),
+            (
+                "\"index\": 1",
+                "\"index\": 1, \"index\": 1",
+                SecretSharingError::ShareMalformed,
+            ),
+            ("{", "{\"x\": 01, ", SecretSharingError::ShareMalformed),
+            ("}", "} {}", SecretSharingError::ShareMalformed),
+        ] {
+            assert_eq!(
+                ShareDocument::from_json(&json.replacen(from, to, 1)).err(),
+                Some(err),
+                "{}",
+                to
+            );
+        }
+        let deep = format!("{{\"x\": {}{}}}", "[".repeat(100), "]".repeat(100));
+        assert!(ShareDocument::from_json(&deep).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+// HMAC-SHA256 (RFC 2104), HKDF-SHA256 (RFC 5869), PBKDF2-HMAC-SHA256
+// (RFC 8018) and scrypt (RFC 7914), built on the in-crate SHA-256
+// implementation. PBKDF2 and scrypt are tested against the vectors of RFC
+// 7914 sections 11 and 12.
+
+use crate::sha256::{Sha256, BLOCK_LEN, DIGEST_LEN};
+use std::convert::TryInto;
+use zeroize::Zeroize;
+
+// Hmac is an incremental HMAC-SHA256 computation.
+#[derive(Clone)]
+pub struct Hmac {
+    inner: Sha256,
+    outer: Sha256,
+}
+
+impl Hmac {
+    pub fn new(key: &[u8]) -> Hmac {
+        let mut k = [0u8; BLOCK_LEN];
+        if key.len() > BLOCK_LEN {
+            k[..DIGEST_LEN].copy_from_slice(&crate::sha256::digest(key));
+        } else {
+            k[..key.len()].copy_from_slice(key);
+        }
+
+        let mut pad = [0u8; BLOCK_LEN];
+        let mut inner = Sha256::new();
+        for (p, k) in pad.iter_mut().zip(k.iter()) {
+            *p = k ^ 0x36;
+        }
+        inner.update(&pad);
+        let mut outer = Sha256::new();
+        for (p, k) in pad.iter_mut().zip(k.iter()) {
+            *p = k ^ 0x5c;
+        }
+        outer.update(&pad);
+
+        k.zeroize();
+        pad.zeroize();
+        Hmac { inner, outer }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> [u8; DIGEST_LEN] {
+        let mut outer = self.outer;
+        outer.update(&self.inner.finalize());
+        outer.finalize()
+    }
+}
+
+// hmac returns HMAC-SHA256(key, data).
+pub fn hmac(key: &[u8], data: &[u8]) -> [u8; DIGEST_LEN] {
+    let mut h = Hmac::new(key);
+    h.update(data);
+    h.finalize()
+}
+
+// hkdf_extract derives a pseudorandom key from input keying material `ikm`
+// and an optional `salt`.
+pub fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> [u8; DIGEST_LEN] {
+    hmac(salt, ikm)
+}
+
+// hkdf_expand fills `out` with output keying material derived from `prk` and
+// the context string `info`. `out` must be at most 255 * 32 bytes long.
+pub fn hkdf_expand(prk: &[u8], info: &[u8], out: &mut [u8]) {
+    assert!(out.len() <= 255 * DIGEST_LEN);
+
+    let mut t = [0u8; DIGEST_LEN];
+    for (i, chunk) in out.chunks_mut(DIGEST_LEN).enumerate() {
+        let mut h = Hmac::new(prk);
+        if i > 0 {
+            h.update(&t);
+        }
+        h.update(info);
+        h.update(&[i as u8 + 1]);
+        t = h.finalize();
+        chunk.copy_from_slice(&t[..chunk.len()]);
+    }
+    t.zeroize();
+}
+
+// pbkdf2 fills `out` with PBKDF2-HMAC-SHA256 of `password` and `salt` with
+// `rounds` iterations.
+pub fn pbkdf2(password: &[u8], salt: &[u8], rounds: u32, out: &mut [u8]) {
+    let prf = Hmac::new(password);
+    for (i, chunk) in out.chunks_mut(DIGEST_LEN).enumerate() {
+        let mut h = prf.clone();
+        h.update(salt);
+        h.update(&(i as u32 + 1).to_be_bytes());
+        let mut u = h.finalize();
+        let mut t = u;
+        for _ in 1..rounds {
+            let mut h = prf.clone();
+            h.update(&u);
+            u = h.finalize();
+            for (t, u) in t.iter_mut().zip(u.iter()) {
+                *t ^= u;
+            }
+        }
+        chunk.copy_from_slice(&t[..chunk.len()]);
+        u.zeroize();
+        t.zeroize();
+    }
+}
+
+// salsa20_8 applies the Salsa20/8 core to the 64-byte block `b` in place.
+fn salsa20_8(b: &mut [u32]) {
+    let mut x = [0u32; 16];
+    x.copy_from_slice(b);
+    let mut qr = |a: usize, b: usize, c: usize, d: usize| {
+        x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+        x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+        x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+        x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+    };
+    for _ in 0..4 {
+        qr(0, 4, 8, 12);
+        qr(5, 9, 13, 1);
+        qr(10, 14, 2, 6);
+        qr(15, 3, 7, 11);
+        qr(0, 1, 2, 3);
+        qr(5, 6, 7, 4);
+        qr(10, 11, 8, 9);
+        qr(15, 12, 13, 14);
+    }
+    for (b, x) in b.iter_mut().zip(x.iter()) {
+        *b = b.wrapping_add(*x);
+    }
+    x.zeroize();
+}
+
+// block_mix is scryptBlockMix with Salsa20/8: it mixes the 2r 64-byte blocks
+// of `b` into `out`.
+fn block_mix(b: &[u32], out: &mut [u32]) {
+    let r2 = b.len() / 16;
+    let mut x = [0u32; 16];
+    x.copy_from_slice(&b[b.len() - 16..]);
+    for (i, block) in b.chunks_exact(16).enumerate() {
+        for (x, b) in x.iter_mut().zip(block.iter()) {
+            *x ^= b;
+        }
+        salsa20_8(&mut x);
+        // even blocks go to the first half of the output, odd to the second.
+        let j = i / 2 + (i % 2) * (r2 / 2);
+        out[16 * j..16 * (j + 1)].copy_from_slice(&x);
+    }
+    x.zeroize();
+}
+
+// ro_mix is scryptROMix, mixing `b` in place with a table of `n` copies.
+fn ro_mix(b: &mut [u32], n: usize) {
+    let len = b.len();
+    let mut v = vec![0u32; len * n];
+    let mut y = vec![0u32; len];
+    for i in 0..n {
+        v[len * i..len * (i + 1)].copy_from_slice(b);
+        block_mix(b, &mut y);
+        b.copy_from_slice(&y);
+    }
+    for _ in 0..n {
+        let j = (b[len - 16] as usize) & (n - 1);
+        for (b, v) in b.iter_mut().zip(v[len * j..len * (j + 1)].iter()) {
+            *b ^= v;
+        }
+        block_mix(b, &mut y);
+        b.copy_from_slice(&y);
+    }
+    v.zeroize();
+    y.zeroize();
+}
+
+// scrypt fills `out` with scrypt of `password` and `salt` with cost 2^log_n,
+// block size `r` and parallelism `p`. The parameters must already have been
+// checked: it uses 128 * r * (2^log_n + p) bytes of memory.
+pub fn scrypt(password: &[u8], salt: &[u8], log_n: u8, r: u32, p: u32, out: &mut [u8]) {
+    let block_len = 128 * r as usize;
+    let mut b = vec![0u8; block_len * p as usize];
+    pbkdf2(password, salt, 1, &mut b);
+
+    let mut words = vec![0u32; block_len / 4];
+    for chunk in b.chunks_exact_mut(block_len) {
+        for (w, c) in words.iter_mut().zip(chunk.chunks_exact(4)) {
+            *w = u32::from_le_bytes(c.try_into().unwrap());
+        }
+        ro_mix(&mut words, 1 << log_n);
+        for (w, c) in words.iter().zip(chunk.chunks_exact_mut(4)) {
+            c.copy_from_slice(&w.to_le_bytes());
+        }
+    }
+    pbkdf2(password, &b, 1, out);
+    words.zeroize();
+    b.zeroize();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(b: &[u8]) -> String {
+        b.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_hmac_rfc4231() {
+        assert_eq!(
+            hex(&hmac(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            hex(&hmac(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+    #[test]
+    fn test_hmac_long_key() {
+        let key = [0xaa; 131];
+        assert_eq!(
+            hex(&hmac(
+                &key,
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+    #[test]
+    fn test_hkdf_rfc5869() {
+        let salt: Vec<u8> = (0..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        let prk = hkdf_extract(&salt, &[0x0b; 22]);
+        assert_eq!(
+            hex(&prk),
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"
+        );
+
+        let mut okm = [0u8; 42];
+        hkdf_expand(&prk, &info, &mut okm);
+        assert_eq!(
+            hex(&okm),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+    }
+    #[test]
+    fn test_pbkdf2_scrypt_rfc7914() {
+        let mut out = [0u8; 64];
+        pbkdf2(b"passwd", b"salt", 1, &mut out);
+        assert_eq!(
+            hex(&out),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+
+        scrypt(b"", b"", 4, 1, 1, &mut out);
+        assert_eq!(
+            hex(&out),
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"
+        );
+        scrypt(b"password", b"NaCl", 10, 8, 16, &mut out);
+        assert_eq!(
+            hex(&out),
+            "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+        );
+    }
+}
@@ -3,150 +3,1080 @@
 // field GF(2^8). (t,n) are configurable; t is the minimum threshold required to
 // rebuild the secret and n is the number of shares to distribute.
 
+mod aead;
+mod armor;
+mod auth;
+mod bech32;
+mod cbor;
+mod clmul;
+mod codex32;
+mod config;
+mod ct;
+mod dealer;
+#[cfg(test)]
+mod differential;
+#[cfg(feature = "vss")]
+mod dkg;
+mod ed25519;
+mod enrollment;
+mod entropy;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod field;
+mod fixed;
+mod format;
 mod gf;
+pub mod gf65536;
+mod gfshare;
+mod groups;
+mod hierarchical;
+mod hybrid;
+mod json;
+mod kdf;
+#[cfg(feature = "mlock")]
+mod locked;
+mod message;
+mod multi;
+mod padding;
+mod paper;
+mod parallel;
+mod params;
+mod passphrase;
+mod policy;
+pub mod prime;
+#[cfg(test)]
+mod properties;
+#[cfg(feature = "vss")]
+mod pvss;
+#[cfg(feature = "qr")]
+mod qr;
+mod ramp;
+mod refresh;
+mod reshare;
+mod robust;
+mod sealed;
+mod sha256;
+mod sha512;
+mod shareset;
+mod signed;
+mod store;
+mod stream;
+pub mod test_vectors;
+mod text;
+mod uri;
+mod vault;
+#[cfg(feature = "vss")]
+mod vss;
+mod weighted;
+mod wide;
+mod x25519;
 
 extern crate rand;
+extern crate rand_chacha;
 extern crate zeroize;
 
+pub use armor::ArmoredShare;
+pub use auth::{authenticate_shares, reconstruct_authenticated, AuthenticatedShare};
+pub use codex32::{codex32_combine, codex32_split};
+pub use config::ShamirConfig;
+pub use ct::{reconstruct_ct, secrets_equal, ConstantTimeEq};
+use dealer::random_identifier;
+pub use dealer::{Dealer, SplitIter};
+#[cfg(feature = "vss")]
+pub use dkg::{DkgComplaint, DkgDealing, DkgParticipant, DkgShare};
+pub use enrollment::{Enrollee, EnrollmentRequest, Helper, MaskedContribution, PartialShare};
+pub use entropy::{default_rng, os_rng, DefaultRng};
+use field::lagrange_coefficients;
+pub use fixed::{combine_fixed, split_fixed, FixedShare};
 use gf::GfOps;
-use rand::Rng;
-use std::cmp;
-use zeroize::Zeroize;
+pub use gf::{Gf256, AES_POLYNOMIAL, RS_POLYNOMIAL};
+pub use gfshare::{
+    gfshare_combine, gfshare_file_name, gfshare_split, read_gfshare_file, write_gfshare_files,
+    GfshareField,
+};
+pub use groups::{reconstruct_groups, split_groups, split_groups_with_rng, MemberShare};
+pub use hierarchical::{HierarchicalShare, Hierarchy};
+pub use hybrid::{combine_large, split_large, split_large_with_rng};
+pub use json::ShareDocument;
+#[cfg(feature = "mlock")]
+pub use locked::LockedBuffer;
+pub use multi::{
+    reconstruct_multi, reconstruct_one, split_multi, split_multi_with_rng, MultiShare,
+};
+pub use padding::Padding;
+pub use paper::PaperBackup;
+pub use params::{
+    construct_shares_checked, construct_shares_checked_with_rng, ShareCount, Threshold,
+};
+pub use passphrase::{protect_shares, unlock_shares, ProtectedShare, ScryptParams};
+pub use policy::{reconstruct_policy, split_policy, split_policy_with_rng, Policy, PolicyShare};
+#[cfg(feature = "vss")]
+pub use pvss::{
+    deal_pvss, deal_pvss_with_rng, reconstruct_pvss, PvssDealing, PvssKey, PvssPublicKey, PvssShare,
+};
+#[cfg(feature = "qr")]
+pub use qr::QrCode;
+pub use ramp::{
+    construct_shares_ramp, construct_shares_ramp_with_rng, reconstruct_ramp, RampShare,
+};
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+pub use refresh::{
+    apply_refresh, refresh_contribution, refresh_shares, refresh_shares_with_rng, RefreshDelta,
+};
+pub use reshare::{combine_reshare, reshare_contribution, ReshareDelta};
+pub use robust::{reconstruct_consistent, reconstruct_robust};
+pub use sealed::{RecipientKey, RecipientPublicKey, SealedShare};
+pub use shareset::{Progress, ShareSet};
+pub use signed::{reconstruct_signed, sign_shares, DealerKey, DealerPublicKey, SignedShare};
+use std::fmt;
+pub use store::{combine_from_paths, split_to_dir, DirShareStore, MemoryShareStore, ShareStore};
+pub use stream::{combine_stream, split_stream};
+pub use vault::{vault_combine, vault_split, vault_split_with_rng};
+#[cfg(feature = "vss")]
+pub use vss::{
+    reconstruct_pedersen, reconstruct_verified, split_pedersen, split_pedersen_with_rng,
+    split_verifiable, split_verifiable_with_rng, verify_pedersen_share, verify_share, Commitments,
+    PedersenCommitments,
+};
+pub use weighted::{reconstruct_weighted, split_weighted, split_weighted_with_rng, Participant};
+pub use wide::{
+    construct_shares_wide, construct_shares_wide_with_rng, reconstruct_wide, WideShare,
+};
+use zeroize::{Zeroize, Zeroizing};
 
-// SharePoint defines a share for a particular byte. It is a point (x, y) on the
-// sharing polynomial.
-#[derive(Zeroize)]
+// Share is a single participant's share of a secret. Every byte of the secret
+// is shared with its own polynomial, but all of a participant's points lie at
+// the same x, so it is stored once alongside the packed y-values (one per
+// secret byte). The (t, n) parameters the share was created with are carried
+// along when known, as is the identifier of the split it came from, which is
+// random and common to all of that split's shares so that shares of
+// different splits aren't mistakenly combined.
+#[derive(Clone, Zeroize)]
 #[zeroize(drop)]
-pub struct SharePoint {
+pub struct Share {
     x: gf::GF256e,
-    y: gf::GF256e,
+    y: Vec<gf::GF256e>,
+    threshold: Option<u8>,
+    share_count: Option<u8>,
+    identifier: Option<u16>,
+    label: Option<String>,
+    owner: Option<String>,
+}
+
+// check_text checks a share's label or owner, returning it trimmed, or None
+// if it is empty.
+fn check_text(text: &str) -> Result<Option<String>, SecretSharingError> {
+    if text.chars().any(char::is_control) {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    let text = text.trim();
+    if text.len() > u8::MAX as usize {
+        return Err(SecretSharingError::MetadataTooLong {
+            max: u8::MAX as usize,
+        });
+    }
+    Ok(Some(text.to_string()).filter(|t| !t.is_empty()))
+}
+
+// Redacted stands in for secret data in Debug output, showing only its
+// length, so that logging a share with `{:?}` doesn't leak it.
+pub(crate) struct Redacted(pub(crate) usize);
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[REDACTED; {}]", self.0)
+    }
+}
+
+impl fmt::Debug for Share {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Share")
+            .field("x", &self.x)
+            .field("data", &Redacted(self.y.len()))
+            .finish()
+    }
+}
+
+impl Share {
+    // new creates a share from a previously persisted index and payload, as
+    // returned by `index` and `as_bytes`.
+    pub fn new(index: u8, data: &[u8]) -> Share {
+        Share {
+            x: index,
+            y: data.to_vec(),
+            threshold: None,
+            share_count: None,
+            identifier: None,
+            label: None,
+            owner: None,
+        }
+    }
+
+    // with_metadata creates a share like `new`, additionally recording the
+    // threshold `t` and share count `n` it was created with.
+    pub fn with_metadata(index: u8, data: &[u8], t: u8, n: u8) -> Share {
+        Share {
+            x: index,
+            y: data.to_vec(),
+            threshold: Some(t),
+            share_count: Some(n),
+            identifier: None,
+            label: None,
+            owner: None,
+        }
+    }
+
+    // with_identifier returns the share recording that it belongs to the
+    // split with identifier `id`, such as one returned by `identifier` before
+    // the share was persisted.
+    pub fn with_identifier(mut self, id: u16) -> Share {
+        self.identifier = Some(id);
+        self
+    }
+
+    // index returns the x-coordinate of the share, which identifies the
+    // participant it was issued to.
+    pub fn index(&self) -> u8 {
+        self.x
+    }
+
+    // len returns the length of the share payload, which is the same as the
+    // length of the shared secret.
+    pub fn len(&self) -> usize {
+        self.y.len()
+    }
+
+    // threshold returns the number of shares required to reconstruct the
+    // secret, if it was recorded when the share was created.
+    pub fn threshold(&self) -> Option<u8> {
+        self.threshold
+    }
+
+    // share_count returns the number of shares that were distributed, if it was
+    // recorded when the share was created.
+    pub fn share_count(&self) -> Option<u8> {
+        self.share_count
+    }
+
+    // with_label returns the share labelled with what it protects or where it
+    // is kept, such as "Safe deposit box". The label is carried in the
+    // encodings to help people tell shares apart, but isn't part of the
+    // payload, and is only protected by the checksum. It must be a single
+    // line of at most 255 bytes.
+    pub fn with_label(mut self, label: &str) -> Result<Share, SecretSharingError> {
+        self.label = check_text(label)?;
+        Ok(self)
+    }
+
+    // with_owner returns the share recording the person or device holding
+    // it, such as "Mom", under the same rules as with_label.
+    pub fn with_owner(mut self, owner: &str) -> Result<Share, SecretSharingError> {
+        self.owner = check_text(owner)?;
+        Ok(self)
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    // identifier returns the identifier of the split the share came from, if
+    // it was recorded. Shares from splits made before identifiers were added
+    // have none.
+    pub fn identifier(&self) -> Option<u16> {
+        self.identifier
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.y.is_empty()
+    }
+
+    // as_bytes returns the share payload: the y-value of each secret byte's
+    // polynomial at `index`.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.y
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum SecretSharingError {
     TorNisZero,
-    MissingShareForByte,
+    // MissingShareForByte is returned for field and wide shares of different
+    // lengths: at least one of them has no value for byte `byte`.
+    MissingShareForByte {
+        byte: usize,
+    },
+    ShareTruncated,
+    ShareMalformed,
+    UnsupportedShareVersion(u8),
+    ShareChecksumMismatch,
+    NotEnoughShares {
+        have: usize,
+        need: usize,
+    },
+    InvalidShareIndex,
+    DealerStateInvalid,
+    UnexpectedMessage,
+    IncompleteProtocol,
+    TooManyCorruptShares {
+        byte: usize,
+    },
+    InconsistentShares {
+        suspects: Vec<u8>,
+    },
+    ShareTampered {
+        index: u8,
+    },
+    ChunkOutOfOrder {
+        expected: u64,
+        got: u64,
+    },
+    Io(std::io::ErrorKind),
+    BufferTooSmall {
+        need: usize,
+    },
+    ThresholdExceedsShares,
+    DigestMismatch,
+    DuplicateShare {
+        x: u16,
+    },
+    NoSharesProvided,
+    ShareLengthMismatch {
+        index: u8,
+        expected: usize,
+        actual: usize,
+    },
+    // ShareTypo is returned for a transcribed share with a single wrong
+    // character, at `position`.
+    ShareTypo {
+        position: usize,
+    },
+    // PayloadTooLarge is returned when a share is too long for an encoding
+    // with a fixed capacity, such as a QR code, which holds at most `max`
+    // bytes.
+    PayloadTooLarge {
+        max: usize,
+    },
+    // DecryptionFailed is returned when a ciphertext fails authentication:
+    // it was tampered with, or the key reconstructed from the shares is wrong.
+    DecryptionFailed,
+    // WrongPassphrase is returned when a passphrase-protected share fails to
+    // decrypt: the passphrase is wrong or the share was tampered with.
+    WrongPassphrase,
+    // InvalidKdfParameters is returned for key derivation parameters that are
+    // out of range, including ones read from a share.
+    InvalidKdfParameters,
+    // InvalidPublicKey is returned for a public key of small order, which
+    // would give a shared secret known to everyone.
+    InvalidPublicKey,
+    // TooManyShares is returned when more than 255 shares would be needed,
+    // since every share takes one of the 255 nonzero indices.
+    TooManyShares,
+    // PolicyNotSatisfied is returned when the shares given for an access
+    // policy don't satisfy it.
+    PolicyNotSatisfied,
+    // InvalidPolicy is returned for a policy expression with a syntax error
+    // at byte `position`.
+    InvalidPolicy {
+        position: usize,
+    },
+    // InvalidHierarchy is returned for hierarchical thresholds that don't
+    // strictly increase, or participant counts given for the wrong number of
+    // levels.
+    InvalidHierarchy,
+    // PackingExceedsThreshold is returned for a ramp sharing packing more
+    // secret bytes into each polynomial than its threshold allows.
+    PackingExceedsThreshold,
+    // RangeOutOfBounds is returned for a byte range extending past the end of
+    // a secret `len` bytes long.
+    RangeOutOfBounds {
+        len: usize,
+    },
+    // ThresholdUnknown is returned when shares are to be combined but neither
+    // they nor the caller give the threshold, so it can't be checked.
+    ThresholdUnknown,
+    // MetadataTooLong is returned for a share label or owner longer than
+    // `max` bytes once trimmed.
+    MetadataTooLong {
+        max: usize,
+    },
 }
 
-pub type Shares = Vec<SharePoint>;
+impl fmt::Display for SecretSharingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use SecretSharingError::*;
+        match self {
+            TorNisZero => write!(f, "threshold and share count must be non-zero"),
+            MissingShareForByte { byte } => {
+                write!(
+                    f,
+                    "shares differ in length: byte {} is missing from a share",
+                    byte
+                )
+            }
+            ShareTruncated => write!(f, "share is truncated"),
+            ShareMalformed => write!(f, "share is malformed"),
+            UnsupportedShareVersion(v) => write!(f, "unsupported share format version {}", v),
+            ShareChecksumMismatch => write!(f, "share checksum mismatch"),
+            NotEnoughShares { have, need } => write!(
+                f,
+                "not enough shares: have {} distinct, need {}",
+                have, need
+            ),
+            InvalidShareIndex => write!(f, "invalid share index"),
+            DealerStateInvalid => write!(f, "dealer state is invalid"),
+            UnexpectedMessage => write!(f, "unexpected protocol message"),
+            IncompleteProtocol => write!(f, "protocol is incomplete"),
+            TooManyCorruptShares { byte } => {
+                write!(f, "too many corrupt shares to correct byte {}", byte)
+            }
+            InconsistentShares { suspects } => {
+                write!(f, "shares are inconsistent; suspects: {:?}", suspects)
+            }
+            ShareTampered { index } => write!(f, "share {} failed authentication", index),
+            ChunkOutOfOrder { expected, got } => {
+                write!(f, "expected chunk {}, got chunk {}", expected, got)
+            }
+            Io(kind) => write!(f, "i/o error: {:?}", kind),
+            BufferTooSmall { need } => write!(f, "buffer too small: need {} bytes", need),
+            ThresholdExceedsShares => write!(f, "threshold exceeds share count"),
+            DigestMismatch => write!(f, "reconstructed secret does not match its digest"),
+            DuplicateShare { x } => write!(f, "share {} was supplied more than once", x),
+            NoSharesProvided => write!(f, "no shares provided"),
+            ShareLengthMismatch {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "share {} is {} bytes long, expected {}",
+                index, actual, expected
+            ),
+            ShareTypo { position } => write!(f, "share has a typo at character {}", position),
+            PayloadTooLarge { max } => write!(f, "too large to encode: at most {} bytes", max),
+            DecryptionFailed => write!(f, "ciphertext failed authentication"),
+            WrongPassphrase => write!(f, "wrong passphrase"),
+            InvalidKdfParameters => write!(f, "invalid key derivation parameters"),
+            InvalidPublicKey => write!(f, "invalid public key"),
+            TooManyShares => write!(f, "more than 255 shares needed"),
+            PolicyNotSatisfied => write!(f, "shares do not satisfy the access policy"),
+            InvalidPolicy { position } => write!(f, "invalid policy at byte {}", position),
+            InvalidHierarchy => write!(f, "invalid hierarchy"),
+            PackingExceedsThreshold => write!(f, "packing exceeds threshold"),
+            RangeOutOfBounds { len } => write!(f, "range exceeds the secret's {} bytes", len),
+            ThresholdUnknown => write!(f, "threshold unknown"),
+            MetadataTooLong { max } => {
+                write!(f, "label or owner too long: at most {} bytes", max)
+            }
+        }
+    }
+}
 
-// share_value shares a single `secret_byte` with Shamir's using parameters
-// (t,n). An entirely random polynomial is created with degree t-1 such that `t`
-// shares are required to reconstruct the secret.
-fn share_value(t: u8, n: u8, secret_byte: &u8) -> Shares {
-    let mut rng = rand::thread_rng();
+impl std::error::Error for SecretSharingError {}
 
-    // pull random coefficients for the polynomial.
-    // since we're operating in GF(2^8), the coefficients are conveniently byte-aligned.
-    let coeff: Vec<(gf::GF256e, gf::GF256e)> = vec![0; n as usize]
-        .iter()
-        .enumerate()
-        .map(|(i, _)| (rng.gen(), cmp::max((t - 1).saturating_sub(i as u8), 1)))
-        .collect();
+pub type Shares = Vec<Share>;
 
-    // construct the polynomial
-    // f(x) = mx^t-1 + m2x^t-2 ... + b
-    let p = |x: gf::GF256e| {
-        coeff
-            .iter()
-            .fold(0, |y, m| y.add(m.0.mul(x.exp(m.1))))
-            .add(*secret_byte)
-    };
+// evaluate returns the value at `x` of the polynomial with coefficients
+// `coeff`, constant term first, using Horner's rule: t-1 multiplications
+// rather than computing every power of x.
+fn evaluate(coeff: &[gf::GF256e], x: gf::GF256e) -> gf::GF256e {
+    coeff.iter().rev().fold(0, |y, c| y.mul(x).add(*c))
+}
 
-    // split the secret for x = 1..n
-    vec![0; n as usize]
-        .iter()
-        .enumerate()
-        .map(|(i, _)| SharePoint {
-            x: i as gf::GF256e + 1,
-            y: p(i as gf::GF256e + 1),
+// construct_shares creates a new Share of the supplied `secret`. It returns a
+// Vec<Share>, where each share belongs to participant 1 -> n. t shares are
+// required to reconstruct the secret. `secret` is an arbitrary size byte
+// slice.
+pub fn construct_shares(t: u8, n: u8, secret: &[u8]) -> Result<Shares, SecretSharingError> {
+    construct_shares_with_rng(t, n, secret, &mut default_rng())
+}
+
+// construct_shares_with_rng is like construct_shares, but draws the polynomial
+// coefficients from `rng` instead of default_rng. The RNG must be
+// cryptographically secure: anyone able to predict its output can recover the
+// secret from a single share.
+pub fn construct_shares_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    n: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Shares, SecretSharingError> {
+    Ok(split_iter_with_rng(t, n, secret, rng)?.collect())
+}
+
+// split_iter is like construct_shares, but returns an iterator that generates
+// the shares on demand, so shares can be streamed to their recipients without
+// holding all n in memory at once. Only the sharing polynomials are kept,
+// and they are wiped when the iterator is dropped.
+pub fn split_iter(t: u8, n: u8, secret: &[u8]) -> Result<SplitIter, SecretSharingError> {
+    split_iter_with_rng(t, n, secret, &mut default_rng())
+}
+
+// split_iter_with_rng is like split_iter, but draws the polynomial
+// coefficients from `rng`.
+pub fn split_iter_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    n: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<SplitIter, SecretSharingError> {
+    if t == 0 || n == 0 {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if t > n {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+
+    // each byte of the secret gets an entirely random polynomial of degree t-1,
+    // such that `t` shares are required to reconstruct it.
+    Ok(SplitIter::new(Dealer::with_rng(t, secret, rng)?, n))
+}
+
+// construct_shares_at is like construct_shares, but issues the shares at the
+// x-coordinates `xs` rather than 1..n, one share per coordinate in the order
+// given. This lets participants keep stable IDs, such as employee numbers,
+// and interoperate with tools that index shares differently. The coordinates
+// must be non-zero and distinct; n is the number of coordinates.
+pub fn construct_shares_at(t: u8, xs: &[u8], secret: &[u8]) -> Result<Shares, SecretSharingError> {
+    construct_shares_at_with_rng(t, xs, secret, &mut default_rng())
+}
+
+// construct_shares_at_with_rng is like construct_shares_at, but draws the
+// polynomial coefficients from `rng`.
+pub fn construct_shares_at_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    xs: &[u8],
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Shares, SecretSharingError> {
+    if t == 0 || xs.is_empty() {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if t as usize > xs.len() {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+    check_indices(xs)?;
+
+    // 255 distinct non-zero coordinates at most, so the count fits in a u8.
+    let n = xs.len() as u8;
+    let dealer = Dealer::with_rng(t, secret, rng)?;
+    xs.iter()
+        .map(|&x| {
+            let mut share = dealer.issue(x)?;
+            share.share_count = Some(n);
+            Ok(share)
         })
         .collect()
 }
 
-// construct_shares creates a new Share of the supplied `secret`. It returns a
-// Vec<Share>, where each vec of shares belings to participant 1 -> n. t shares
-// are required to reconstruct the secret. `secret` is an arbitrary size byte
-// slice.
-pub fn construct_shares(t: u8, n: u8, secret: &[u8]) -> Result<Vec<Shares>, SecretSharingError> {
+// construct_shares_batch splits each of `secrets` independently into n shares
+// with threshold t, returning the shares of secrets[i] at index i, just as
+// construct_shares would. It is much faster than calling construct_shares in
+// a loop when splitting many secrets: the coefficients of every secret are
+// allocated and drawn from the RNG at once, and each participant's shares of
+// all the secrets are evaluated in a single pass.
+pub fn construct_shares_batch(
+    t: u8,
+    n: u8,
+    secrets: &[&[u8]],
+) -> Result<Vec<Shares>, SecretSharingError> {
+    construct_shares_batch_with_rng(t, n, secrets, &mut default_rng())
+}
+
+// construct_shares_batch_with_rng is like construct_shares_batch, but draws
+// the polynomial coefficients from `rng`.
+pub fn construct_shares_batch_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    n: u8,
+    secrets: &[&[u8]],
+    rng: &mut R,
+) -> Result<Vec<Shares>, SecretSharingError> {
     if t == 0 || n == 0 {
         return Err(SecretSharingError::TorNisZero);
     }
+    if t > n {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
 
-    let mut shares: Vec<Shares> = Vec::new();
-    for _ in 0..n {
-        shares.push(Vec::new());
+    // every byte has its own polynomial whichever secret it belongs to, so
+    // sharing the concatenation and cutting each share back apart gives the
+    // same shares as sharing the secrets one by one.
+    // each secret is still a split of its own, with its own identifier.
+    let dealer = Dealer::with_rng(t, &Zeroizing::new(secrets.concat()), rng)?;
+    let ids: Vec<u16> = secrets.iter().map(|_| random_identifier(rng)).collect();
+    let mut batch: Vec<Shares> = secrets
+        .iter()
+        .map(|_| Vec::with_capacity(n as usize))
+        .collect();
+    for x in 1..=n {
+        let share = dealer.issue(x)?;
+        let mut rest = share.as_bytes();
+        for ((shares, secret), &id) in batch.iter_mut().zip(secrets).zip(ids.iter()) {
+            let (y, tail) = rest.split_at(secret.len());
+            shares.push(Share::with_metadata(x, y, t, n).with_identifier(id));
+            rest = tail;
+        }
     }
+    Ok(batch)
+}
 
-    let shares =
-        secret
-            .iter()
-            .map(|b| share_value(t, n, b))
-            .fold(shares, |mut s, share_bytes| {
-                for (i, b) in share_bytes.iter().enumerate() {
-                    s[i].push(SharePoint { x: b.x, y: b.y });
-                }
-                s
-            });
-
-    Ok(shares)
-}
-
-// lagrange_interpolate computes the lagrange polynomial from the supplied
-// shares, then returns the value of the interpolated polynomial at `x`.
-fn lagrange_interpolate(shares: Vec<&SharePoint>, x: gf::GF256e) -> gf::GF256e {
-    shares.iter().fold(0 as gf::GF256e, |y, j| {
-        let phi = shares
-            .iter()
-            .filter(|m| m.x != j.x)
-            .fold(1 as gf::GF256e, |phi, m| {
-                phi.mul(x.sub(m.x).div(j.x.sub(m.x)))
-            });
+// DETERMINISTIC_INFO is the HKDF context string for deterministic sharing.
+// Changing it changes every share produced by construct_shares_deterministic.
+const DETERMINISTIC_INFO: &[u8] = b"rust-shamir deterministic coefficients v1";
 
-        y.add(j.y.mul(phi))
-    })
+// construct_shares_deterministic is like construct_shares, but derives the
+// polynomial coefficients from `secret` and `seed` using HKDF-SHA256, so the
+// same secret, seed and (t,n) always produce the same shares. This makes
+// backups idempotent and allows a lost share to be regenerated without
+// keeping any dealer state. The seed must be protected like the secret:
+// anyone holding it and a single share can test guesses of the secret.
+pub fn construct_shares_deterministic(
+    t: u8,
+    n: u8,
+    secret: &[u8],
+    seed: &[u8],
+) -> Result<Shares, SecretSharingError> {
+    let mut prk = kdf::hkdf_extract(seed, secret);
+    let mut info = DETERMINISTIC_INFO.to_vec();
+    info.extend_from_slice(&[t, n]);
+    let mut key = [0u8; 32];
+    kdf::hkdf_expand(&prk, &info, &mut key);
+    let mut rng = ChaCha20Rng::from_seed(key);
+    prk.zeroize();
+    key.zeroize();
+
+    construct_shares_with_rng(t, n, secret, &mut rng)
 }
 
-fn reconstruct_value(shares: Vec<&SharePoint>) -> gf::GF256e {
-    lagrange_interpolate(shares, 0)
+// insecure_deterministic_split is like construct_shares, but draws the
+// polynomial coefficients (and the split identifier) from a ChaCha20 RNG,
+// rand_chacha's ChaCha20Rng, seeded with `seed`. The same arguments always give
+// the same shares, byte for byte, which is what reproducible test vectors and
+// integration tests need; see the test_vectors module for the exact
+// derivation. Unlike construct_shares_deterministic, the seed alone determines
+// the coefficients, so anyone who knows or guesses it can recover the secret
+// from a single share. Never use it for real secrets.
+pub fn insecure_deterministic_split(
+    seed: [u8; 32],
+    t: u8,
+    n: u8,
+    secret: &[u8],
+) -> Result<Shares, SecretSharingError> {
+    construct_shares_with_rng(t, n, secret, &mut ChaCha20Rng::from_seed(seed))
 }
 
-// reconstruct takes a slice of shares and attempts to reconstruct the shared
-// secret. The reconstruction is not verifiable; reconstructing invalid shares
-// will return an invalid secret, not an error.
-pub fn reconstruct(shares: Vec<Shares>) -> Result<Vec<u8>, SecretSharingError> {
-    // ensure the blobs are the same length
-    let sz = shares[0].len();
-    let all_same_len = shares.iter().all(|share| share.len() == sz);
-    if !all_same_len {
-        return Err(SecretSharingError::MissingShareForByte);
+// check_indices returns InvalidShareIndex if any of the share indices `xs` is
+// zero, which would expose the secret rather than a share of it, and
+// DuplicateShare if any appears twice, which would make interpolation divide
+// by zero.
+fn check_indices<'a, I: IntoIterator<Item = &'a u8>>(xs: I) -> Result<(), SecretSharingError> {
+    let mut seen = [false; 256];
+    for &x in xs {
+        if x == 0 {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        if seen[x as usize] {
+            return Err(SecretSharingError::DuplicateShare { x: x.into() });
+        }
+        seen[x as usize] = true;
     }
+    Ok(())
+}
 
-    let result = vec![0; sz as usize]
+// check_identifiers returns InconsistentShares, naming the shares involved,
+// if `shares` come from more than one split. Shares without an identifier
+// can't be checked and are assumed to match.
+fn check_identifiers(shares: &[Share]) -> Result<(), SecretSharingError> {
+    let id = match shares.iter().find_map(|share| share.identifier) {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    if shares
         .iter()
-        .enumerate()
-        .map(|(i, _)| {
-            let byte_shares = shares.iter().map(|share| &share[i]);
-            reconstruct_value(byte_shares.collect())
-        })
+        .all(|share| share.identifier.unwrap_or(id) == id)
+    {
+        return Ok(());
+    }
+    let mut suspects: Vec<u8> = shares
+        .iter()
+        .filter(|share| share.identifier.is_some())
+        .map(|share| share.x)
         .collect();
+    suspects.sort_unstable();
+    Err(SecretSharingError::InconsistentShares { suspects })
+}
+
+// check_lengths returns ShareLengthMismatch, naming the first offending
+// share, unless all `shares` are as long as the first.
+fn check_lengths(shares: &[Share]) -> Result<(), SecretSharingError> {
+    let expected = shares.first().map_or(0, |share| share.y.len());
+    match shares.iter().find(|share| share.y.len() != expected) {
+        Some(share) => Err(SecretSharingError::ShareLengthMismatch {
+            index: share.x,
+            expected,
+            actual: share.y.len(),
+        }),
+        None => Ok(()),
+    }
+}
+
+// check_quorum returns NotEnoughShares if `shares` provably cannot reach the
+// threshold recorded in them. Only distinct share indices count toward the
+// quorum, since passing the same share twice adds no information. Shares
+// without metadata cannot be checked and are assumed sufficient.
+fn check_quorum(shares: &[Share]) -> Result<(), SecretSharingError> {
+    let need = match shares.iter().filter_map(|share| share.threshold).max() {
+        Some(t) => t as usize,
+        None => return Ok(()),
+    };
+
+    let mut seen = [false; 256];
+    let mut have = 0;
+    for share in shares {
+        if !seen[share.x as usize] {
+            seen[share.x as usize] = true;
+            have += 1;
+        }
+    }
+
+    if have < need {
+        return Err(SecretSharingError::NotEnoughShares { have, need });
+    }
+    Ok(())
+}
+
+// reconstruct takes a slice of shares and attempts to reconstruct the shared
+// secret. The reconstruction is not verifiable; reconstructing invalid shares
+// will return an invalid secret, not an error. If the shares record the
+// threshold they were created with and it is not met, NotEnoughShares is
+// returned instead. Like every function in this crate that accepts shares, it
+// returns an error rather than panicking on malformed input, so shares from
+// untrusted sources can be passed to it directly.
+pub fn reconstruct(shares: &[Share]) -> Result<Vec<u8>, SecretSharingError> {
+    interpolate_at(shares, 0)
+}
+
+// recover_share regenerates the share for participant `new_x` from a quorum of
+// existing shares, by evaluating the interpolated polynomials at `new_x`
+// rather than at 0. This lets a quorum re-issue a replacement share for a lost
+// device without ever computing the secret itself.
+pub fn recover_share(shares: &[Share], new_x: u8) -> Result<Share, SecretSharingError> {
+    if new_x == 0 {
+        return Err(SecretSharingError::InvalidShareIndex);
+    }
 
+    let y = interpolate_at(shares, new_x)?;
+    Ok(Share {
+        x: new_x,
+        y,
+        threshold: shares.iter().filter_map(|share| share.threshold).max(),
+        share_count: None,
+        identifier: shares.iter().find_map(|share| share.identifier),
+        label: None,
+        owner: None,
+    })
+}
+
+// reconstruct_range is like reconstruct, but recovers only the `len` bytes of
+// the secret starting at `offset`. Since every byte has its own polynomial,
+// the rest of the secret is never computed, so a fragment such as a key
+// fingerprint can be checked without materializing the whole secret. If the
+// range extends past the end of the secret, RangeOutOfBounds is returned.
+pub fn reconstruct_range(
+    shares: &[Share],
+    offset: usize,
+    len: usize,
+) -> Result<Vec<u8>, SecretSharingError> {
+    let sz = check_shares(shares)?;
+    match offset.checked_add(len) {
+        Some(end) if end <= sz => {}
+        _ => return Err(SecretSharingError::RangeOutOfBounds { len: sz }),
+    }
+    let mut out = vec![0u8; len];
+    interpolate_range(shares, 0, offset, &mut out);
+    Ok(out)
+}
+
+// reconstruct_into is like reconstruct, but writes the secret into the start
+// of `out` and returns its length, rather than allocating it on the heap. This
+// lets the caller keep the secret in memory it controls, e.g. a locked page,
+// and wipe it as soon as it is no longer needed. If `out` is too short,
+// BufferTooSmall is returned and `out` is left untouched.
+pub fn reconstruct_into(shares: &[Share], out: &mut [u8]) -> Result<usize, SecretSharingError> {
+    interpolate_into(shares, 0, out)
+}
+
+// reconstruct_locked is like reconstruct, but returns the secret in
+// page-locked memory that is wiped when dropped.
+#[cfg(feature = "mlock")]
+pub fn reconstruct_locked(shares: &[Share]) -> Result<LockedBuffer, SecretSharingError> {
+    let mut out = LockedBuffer::new(shares.first().map_or(0, |share| share.y.len()))?;
+    interpolate_into(shares, 0, &mut out)?;
+    Ok(out)
+}
+
+// interpolate_at interpolates the per-byte polynomials from `shares` and
+// returns their values at `x`.
+fn interpolate_at(shares: &[Share], x: gf::GF256e) -> Result<Vec<u8>, SecretSharingError> {
+    let mut result = vec![0u8; shares.first().map_or(0, |share| share.y.len())];
+    interpolate_into(shares, x, &mut result)?;
     Ok(result)
 }
 
+// interpolate_into is like interpolate_at, but writes the values into `out`,
+// returning how many were written.
+fn interpolate_into(
+    shares: &[Share],
+    x: gf::GF256e,
+    out: &mut [u8],
+) -> Result<usize, SecretSharingError> {
+    let sz = check_shares(shares)?;
+    if out.len() < sz {
+        return Err(SecretSharingError::BufferTooSmall { need: sz });
+    }
+    interpolate_range(shares, x, 0, &mut out[..sz]);
+    Ok(sz)
+}
+
+// check_shares checks that `shares` can be interpolated together, returning
+// their length.
+fn check_shares(shares: &[Share]) -> Result<usize, SecretSharingError> {
+    if shares.is_empty() {
+        return Err(SecretSharingError::NoSharesProvided);
+    }
+    check_quorum(shares)?;
+    check_indices(shares.iter().map(|share| &share.x))?;
+    check_identifiers(shares)?;
+
+    // ensure the blobs are the same length
+    check_lengths(shares)?;
+    Ok(shares[0].y.len())
+}
+
+// interpolate_range writes the values at `x` of the polynomials of bytes
+// offset..offset+out.len() into `out`. The shares must have been checked
+// with check_shares.
+fn interpolate_range(shares: &[Share], x: gf::GF256e, offset: usize, out: &mut [u8]) {
+    let xs: Vec<gf::GF256e> = shares.iter().map(|share| share.x).collect();
+    let basis = lagrange_coefficients(&xs, x);
+
+    for b in out.iter_mut() {
+        *b = 0;
+    }
+    parallel::for_each_chunk(out, |start, out| {
+        let start = offset + start;
+        for (share, phi) in shares.iter().zip(basis.iter()) {
+            clmul::mul_acc(out, &share.y[start..start + out.len()], *phi);
+        }
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    fn vec_eq<T: PartialEq>(a: &Vec<T>, b: &Vec<T>) -> bool {
-        let matchcount = a.iter().zip(b.iter()).filter(|&(a, b)| a == b).count();
-        matchcount == a.len() && matchcount == b.len()
-    }
 
+    #[test]
+    fn test_reconstruct_into() {
+        let secret = vec![0xfe, 0xff, 0xaf, 0xbe];
+        let shares = construct_shares(2, 3, &secret).unwrap();
+        let mut out = [0xaa; 8];
+        assert_eq!(reconstruct_into(&shares[1..], &mut out).unwrap(), 4);
+        assert_eq!(out[..4], secret[..]);
+        assert_eq!(out[4..], [0xaa; 4]);
+
+        let mut short = [0u8; 3];
+        assert_eq!(
+            reconstruct_into(&shares, &mut short).err(),
+            Some(SecretSharingError::BufferTooSmall { need: 4 })
+        );
+        assert_eq!(short, [0u8; 3]);
+    }
+    #[test]
+    fn test_reconstruct_range() {
+        let secret: Vec<u8> = (0..=255).collect();
+        let shares = construct_shares(3, 5, &secret).unwrap();
+        assert_eq!(
+            reconstruct_range(&shares[2..], 100, 20).unwrap(),
+            &secret[100..120]
+        );
+        assert_eq!(
+            reconstruct_range(&shares[..3], 250, 6).unwrap(),
+            &secret[250..]
+        );
+        assert_eq!(reconstruct_range(&shares[..3], 256, 0).unwrap(), b"");
+        assert_eq!(
+            reconstruct_range(&shares[..3], 250, 7).err(),
+            Some(SecretSharingError::RangeOutOfBounds { len: 256 })
+        );
+        assert_eq!(
+            reconstruct_range(&shares[..3], usize::MAX, 1).err(),
+            Some(SecretSharingError::RangeOutOfBounds { len: 256 })
+        );
+        assert_eq!(
+            reconstruct_range(&shares[..2], 0, 1).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+    }
+    #[cfg(feature = "mlock")]
+    #[test]
+    fn test_reconstruct_locked() {
+        let secret = vec![0xfe, 0xff, 0xaf, 0xbe];
+        let shares = construct_shares(2, 3, &secret).unwrap();
+        assert_eq!(reconstruct_locked(&shares[1..]).unwrap()[..], secret[..]);
+        assert_eq!(
+            reconstruct_locked(&[]).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+    }
     #[test]
     fn test_share_construct() {
         let secret = vec![0xfe, 0xff, 0xaf, 0xbe];
         let shares = construct_shares(3, 5, &secret).unwrap();
         assert_eq!(shares.len(), 5);
+        for (i, share) in shares.iter().enumerate() {
+            assert_eq!(share.index(), i as u8 + 1);
+            assert_eq!(share.len(), secret.len());
+        }
+    }
+    #[test]
+    fn test_share_construct_with_rng() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let secret = vec![0xfe, 0xff, 0xaf, 0xbe];
+        let a = construct_shares_with_rng(3, 5, &secret, &mut StdRng::seed_from_u64(7)).unwrap();
+        let b = construct_shares_with_rng(3, 5, &secret, &mut StdRng::seed_from_u64(7)).unwrap();
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert_eq!(a.as_bytes(), b.as_bytes());
+        }
+        assert_eq!(reconstruct(&a[2..]).unwrap(), secret);
+    }
+    #[test]
+    fn test_share_construct_at() {
+        let secret = vec![0xfe, 0xff, 0xaf, 0xbe];
+        let shares = construct_shares_at(2, &[17, 250, 3], &secret).unwrap();
+        assert_eq!(
+            shares.iter().map(Share::index).collect::<Vec<_>>(),
+            [17, 250, 3]
+        );
+        assert_eq!(shares[1].share_count(), Some(3));
+        assert_eq!(reconstruct(&shares[1..]).unwrap(), secret);
+        assert_eq!(
+            reconstruct(&[shares[2].clone(), shares[0].clone()]).unwrap(),
+            secret
+        );
+
+        assert_eq!(
+            construct_shares_at(2, &[4, 0], &secret).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+        assert_eq!(
+            construct_shares_at(2, &[4, 9, 4], &secret).err(),
+            Some(SecretSharingError::DuplicateShare { x: 4 })
+        );
+        assert_eq!(
+            construct_shares_at(3, &[4, 9], &secret).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        assert_eq!(
+            construct_shares_at(1, &[], &secret).err(),
+            Some(SecretSharingError::TorNisZero)
+        );
+    }
+    #[test]
+    fn test_share_construct_batch() {
+        let secrets: [&[u8]; 3] = [b"first key", b"", b"third"];
+        let batch = construct_shares_batch(2, 3, &secrets).unwrap();
+        assert_eq!(batch.len(), 3);
+        for (shares, secret) in batch.iter().zip(secrets.iter()) {
+            assert_eq!(shares.len(), 3);
+            assert_eq!(shares[2].index(), 3);
+            assert_eq!(shares[0].threshold(), Some(2));
+            assert!(shares
+                .iter()
+                .all(|s| s.identifier() == shares[0].identifier()));
+            assert_eq!(reconstruct(&shares[1..]).unwrap(), *secret);
+        }
+        assert_eq!(construct_shares_batch(2, 3, &[]).unwrap().len(), 0);
+        assert_eq!(
+            construct_shares_batch(4, 3, &secrets).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+    }
+    #[test]
+    fn test_share_construct_deterministic() {
+        let secret = vec![0xfe, 0xff, 0xaf, 0xbe];
+        let a = construct_shares_deterministic(3, 5, &secret, b"salt").unwrap();
+        let b = construct_shares_deterministic(3, 5, &secret, b"salt").unwrap();
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert_eq!(a.as_bytes(), b.as_bytes());
+        }
+        assert_eq!(reconstruct(&a[..3]).unwrap(), secret);
+
+        let c = construct_shares_deterministic(3, 5, &secret, b"pepper").unwrap();
+        assert_ne!(a[0].as_bytes(), c[0].as_bytes());
+        let d = construct_shares_deterministic(3, 5, &[0xfe, 0xff, 0xaf, 0xbf], b"salt").unwrap();
+        assert_ne!(a[0].as_bytes()[..3], d[0].as_bytes()[..3]);
+    }
+    #[test]
+    fn test_insecure_deterministic_split() {
+        let secret = b"reproducible";
+        let a = insecure_deterministic_split([9; 32], 2, 3, secret).unwrap();
+        let b = insecure_deterministic_split([9; 32], 2, 3, secret).unwrap();
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert_eq!(a.to_bytes(), b.to_bytes());
+        }
+        assert_eq!(reconstruct(&a[1..]).unwrap(), secret);
+        let c = insecure_deterministic_split([8; 32], 2, 3, secret).unwrap();
+        assert_ne!(a[0].as_bytes(), c[0].as_bytes());
+
+        // the seed is all it takes to recover the secret from one share.
+        let guessed = insecure_deterministic_split([9; 32], 2, 3, &[0; 12]).unwrap();
+        let recovered: Vec<u8> = guessed[0]
+            .as_bytes()
+            .iter()
+            .zip(a[0].as_bytes())
+            .map(|(g, a)| g ^ a)
+            .collect();
+        assert_eq!(recovered, secret);
+    }
+    #[test]
+    fn test_recover_share() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let shares = construct_shares(3, 5, &secret).unwrap();
+
+        let recovered = recover_share(&shares[1..4], 1).unwrap();
+        assert_eq!(recovered.index(), 1);
+        assert_eq!(recovered.threshold(), Some(3));
+        assert_eq!(recovered.as_bytes(), shares[0].as_bytes());
+
+        let fresh = recover_share(&shares[..3], 42).unwrap();
+        let quorum = vec![fresh, recover_share(&shares[2..], 43).unwrap(), recovered];
+        assert_eq!(reconstruct(&quorum).unwrap(), secret);
+
+        assert_eq!(
+            recover_share(&shares[..2], 6).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+        assert_eq!(
+            recover_share(&shares, 0).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+    }
+    #[test]
+    fn test_share_persist_roundtrip() {
+        let secret = vec![0xca, 0xfe, 0xba, 0xbe];
+        let shares = construct_shares(2, 3, &secret).unwrap();
+        let restored: Shares = shares
+            .iter()
+            .skip(1)
+            .map(|share| Share::new(share.index(), share.as_bytes()))
+            .collect();
+        assert_eq!(reconstruct(&restored).unwrap(), secret);
     }
     #[test]
     fn test_share_construct_reconstruct() {
@@ -165,8 +1095,8 @@ mod tests {
         let shares = construct_shares(3, 5, &secret).unwrap();
         assert_eq!(shares.len(), 5);
 
-        let reconstructed = reconstruct(shares).unwrap();
-        assert!(vec_eq(&reconstructed, &secret));
+        let reconstructed = reconstruct(&shares).unwrap();
+        assert_eq!(reconstructed, secret);
     }
     #[test]
     fn test_share_construct_reconstruct_shares_omitting() {
@@ -186,15 +1116,216 @@ mod tests {
         for _ in 0..todelete {
             shares.pop();
         }
-        let reconstructed = reconstruct(shares);
-        assert!(vec_eq(&reconstructed.unwrap(), &secret));
+        let reconstructed = reconstruct(&shares);
+        assert_eq!(reconstructed.unwrap(), secret);
 
         todelete = 3;
         shares = construct_shares(t, n, &secret).unwrap();
         for _ in 0..todelete {
             shares.pop();
         }
-        let reconstructed_bad = reconstruct(shares);
-        assert!(!vec_eq(&reconstructed_bad.unwrap(), &secret));
+        let reconstructed_bad = reconstruct(&shares);
+        assert_eq!(
+            reconstructed_bad.err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+
+        // without metadata, too few shares silently produce the wrong secret.
+        let stripped: Shares = shares
+            .iter()
+            .map(|share| Share::new(share.index(), share.as_bytes()))
+            .collect();
+        assert_ne!(reconstruct(&stripped).unwrap(), secret);
+    }
+    #[test]
+    fn test_reconstruct_below_threshold() {
+        let secret = vec![0x01, 0x02, 0x03];
+        for &(t, n) in [(2, 2), (2, 5), (3, 3), (5, 8)].iter() {
+            let shares = construct_shares(t, n, &secret).unwrap();
+            for have in 1..t as usize {
+                assert_eq!(
+                    reconstruct(&shares[..have]).err(),
+                    Some(SecretSharingError::NotEnoughShares {
+                        have,
+                        need: t as usize
+                    })
+                );
+            }
+            assert_eq!(reconstruct(&shares[..t as usize]).unwrap(), secret);
+        }
+    }
+    #[test]
+    fn test_reconstruct_duplicates_do_not_count() {
+        let secret = vec![0x01, 0x02, 0x03];
+        let shares = construct_shares(3, 5, &secret).unwrap();
+        let supplied = vec![
+            Share::from_bytes(&shares[0].to_bytes()).unwrap(),
+            Share::from_bytes(&shares[0].to_bytes()).unwrap(),
+        ];
+        assert_eq!(
+            reconstruct(&supplied).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 3 })
+        );
+
+        // a share that lacks metadata still counts toward the recorded quorum.
+        let mixed = vec![
+            Share::from_bytes(&shares[0].to_bytes()).unwrap(),
+            Share::from_bytes(&shares[1].to_bytes()).unwrap(),
+            Share::new(shares[2].index(), shares[2].as_bytes()),
+        ];
+        assert_eq!(reconstruct(&mixed).unwrap(), secret);
+    }
+    #[test]
+    fn test_threshold_exceeds_shares() {
+        let secret = vec![0x01, 0x02, 0x03];
+        assert_eq!(
+            construct_shares(5, 3, &secret).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        assert_eq!(
+            construct_shares(2, 1, &secret).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        assert_eq!(
+            construct_shares(255, 254, &secret).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        assert_eq!(
+            construct_shares_deterministic(4, 3, &secret, b"seed").err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+
+        // t == n is the largest valid threshold.
+        assert_eq!(construct_shares(1, 1, &secret).unwrap().len(), 1);
+        let shares = construct_shares(255, 255, &secret).unwrap();
+        assert_eq!(reconstruct(&shares).unwrap(), secret);
+    }
+    #[test]
+    fn test_reconstruct_invalid_indices() {
+        let secret = vec![0x01, 0x02, 0x03];
+        let shares = construct_shares(2, 3, &secret).unwrap();
+
+        // enough distinct shares, but one of them passed twice.
+        let duplicated = vec![shares[0].clone(), shares[1].clone(), shares[0].clone()];
+        assert_eq!(
+            reconstruct(&duplicated).err(),
+            Some(SecretSharingError::DuplicateShare { x: 1 })
+        );
+
+        let zero = vec![Share::new(0, shares[0].as_bytes()), shares[1].clone()];
+        assert_eq!(
+            reconstruct(&zero).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+    }
+    #[test]
+    fn test_reconstruct_mixed_splits() {
+        let secret = vec![0xfe, 0xff, 0xaf, 0xbe];
+        let a = construct_shares(2, 3, &secret).unwrap();
+        let mut b = construct_shares(2, 3, &secret).unwrap();
+        while b[0].identifier() == a[0].identifier() {
+            b = construct_shares(2, 3, &secret).unwrap();
+        }
+        assert_eq!(
+            reconstruct(&[a[0].clone(), b[1].clone()]).err(),
+            Some(SecretSharingError::InconsistentShares {
+                suspects: vec![1, 2]
+            })
+        );
+        // shares without an identifier, such as ones persisted by index and
+        // payload alone, are still accepted.
+        let bare = Share::new(b[1].index(), b[1].as_bytes());
+        assert_ne!(reconstruct(&[a[0].clone(), bare]).unwrap(), secret);
+        let bare = Share::new(a[1].index(), a[1].as_bytes());
+        assert_eq!(reconstruct(&[a[0].clone(), bare]).unwrap(), secret);
+    }
+    #[test]
+    fn test_share_label() {
+        let shares = construct_shares(2, 3, b"secret").unwrap();
+        let labelled: Shares = shares
+            .iter()
+            .zip(["Mom", "Safe deposit box", ""])
+            .map(|(s, owner)| s.clone().with_owner(owner).unwrap())
+            .collect();
+        assert_eq!(labelled[0].owner(), Some("Mom"));
+        assert_eq!(labelled[2].owner(), None);
+        // labels aren't part of the payload, so differing ones don't matter.
+        assert_eq!(reconstruct(&labelled[1..]).unwrap(), b"secret");
+
+        let share = shares[0].clone().with_label("  Safe deposit box ").unwrap();
+        assert_eq!(share.label(), Some("Safe deposit box"));
+        assert_eq!(
+            shares[0].clone().with_label("two\nlines").err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        assert_eq!(
+            shares[0].clone().with_owner(&"x".repeat(256)).err(),
+            Some(SecretSharingError::MetadataTooLong { max: 255 })
+        );
+        // surrounding whitespace doesn't count.
+        let owner = format!(" {} ", "x".repeat(255));
+        assert!(shares[0].clone().with_owner(&owner).is_ok());
+    }
+    #[test]
+    fn test_reconstruct_empty() {
+        assert_eq!(
+            reconstruct(&[]).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+        assert_eq!(
+            recover_share(&[], 1).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+        assert_eq!(
+            reconstruct_into(&[], &mut []).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+        assert_eq!(
+            reconstruct_robust(2, &[]).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+        assert_eq!(
+            combine_fixed::<32>(&[]).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+    }
+    #[test]
+    fn test_error_context() {
+        let mut shares = construct_shares(2, 3, &[1, 2, 3, 4]).unwrap();
+        shares[1].y.pop();
+        let err = reconstruct(&shares[..2]).unwrap_err();
+        assert_eq!(
+            err,
+            SecretSharingError::ShareLengthMismatch {
+                index: 2,
+                expected: 4,
+                actual: 3
+            }
+        );
+        assert_eq!(err.to_string(), "share 2 is 3 bytes long, expected 4");
+
+        let err: Box<dyn std::error::Error> =
+            Box::new(SecretSharingError::NotEnoughShares { have: 1, need: 2 });
+        assert_eq!(
+            err.to_string(),
+            "not enough shares: have 1 distinct, need 2"
+        );
+    }
+    #[test]
+    fn test_debug_redacted() {
+        let share = Share::new(3, &[0xaa; 32]);
+        assert_eq!(
+            format!("{:?}", share),
+            "Share { x: 3, data: [REDACTED; 32] }"
+        );
+
+        let dealer = Dealer::new(2, &[0xaa; 4]).unwrap();
+        assert_eq!(
+            format!("{:?}", dealer),
+            "Dealer { threshold: 2, coeff: [REDACTED; 8] }"
+        );
+        let shares: [FixedShare<16>; 2] =
+            split_fixed(2, &[0xaa; 16], &mut rand::thread_rng()).unwrap();
+        assert!(!format!("{:?}", shares).contains("170"));
     }
 }
@@ -0,0 +1,129 @@
+// With the `mlock` feature, secret buffers are allocated in page-locked
+// memory, so that the operating system can't write them to swap where they
+// would outlive the process. Each buffer gets whole pages of its own: locks
+// don't nest, so unlocking a buffer that shared a page with another would
+// unlock both.
+//
+// The amount of memory a process may lock is limited (see RLIMIT_MEMLOCK);
+// allocations beyond the limit fail with an Io error rather than silently
+// falling back to unlocked memory.
+
+#[cfg(not(unix))]
+compile_error!("the mlock feature is only supported on unix");
+
+use crate::{Redacted, SecretSharingError};
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::slice;
+use zeroize::Zeroize;
+
+// LockedBuffer is a fixed-length byte buffer in page-locked memory. It is
+// wiped and unlocked when dropped.
+pub struct LockedBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+    layout: Layout,
+}
+
+// safe: LockedBuffer owns its allocation, like a Box<[u8]>.
+unsafe impl Send for LockedBuffer {}
+unsafe impl Sync for LockedBuffer {}
+
+fn page_size() -> usize {
+    // safe: sysconf has no preconditions.
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        n if n > 0 => n as usize,
+        _ => 4096,
+    }
+}
+
+impl LockedBuffer {
+    // new allocates a zeroed, locked buffer of `len` bytes.
+    pub fn new(len: usize) -> Result<LockedBuffer, SecretSharingError> {
+        let page = page_size();
+        let size = std::cmp::max(len, 1)
+            .checked_add(page - 1)
+            .ok_or(SecretSharingError::Io(io::ErrorKind::OutOfMemory))?
+            / page
+            * page;
+        let layout = Layout::from_size_align(size, page)
+            .map_err(|_| SecretSharingError::Io(io::ErrorKind::OutOfMemory))?;
+
+        // safe: layout has a non-zero size.
+        let ptr = match NonNull::new(unsafe { alloc::alloc_zeroed(layout) }) {
+            Some(ptr) => ptr,
+            None => alloc::handle_alloc_error(layout),
+        };
+        // safe: ptr points to `size` bytes we own.
+        if unsafe { libc::mlock(ptr.as_ptr() as *const libc::c_void, size) } != 0 {
+            let err = io::Error::last_os_error();
+            // safe: ptr was allocated above with this layout.
+            unsafe { alloc::dealloc(ptr.as_ptr(), layout) };
+            return Err(SecretSharingError::Io(err.kind()));
+        }
+
+        Ok(LockedBuffer { ptr, len, layout })
+    }
+}
+
+impl Deref for LockedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // safe: the first `len` bytes of the allocation are initialized.
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for LockedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // safe: as for deref, and we hold the only reference.
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Zeroize for LockedBuffer {
+    fn zeroize(&mut self) {
+        self[..].zeroize();
+    }
+}
+
+impl Drop for LockedBuffer {
+    fn drop(&mut self) {
+        self.zeroize();
+        // safe: ptr was allocated and locked in new with this layout.
+        unsafe {
+            libc::munlock(self.ptr.as_ptr() as *const libc::c_void, self.layout.size());
+            alloc::dealloc(self.ptr.as_ptr(), self.layout);
+        }
+    }
+}
+
+impl fmt::Debug for LockedBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&Redacted(self.len), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locked_buffer() {
+        let mut buf = LockedBuffer::new(100).unwrap();
+        assert_eq!(buf.len(), 100);
+        assert!(buf.iter().all(|&b| b == 0));
+        buf[99] = 0xaa;
+        assert_eq!(buf[99], 0xaa);
+        assert_eq!(buf.ptr.as_ptr() as usize % page_size(), 0);
+        assert_eq!(format!("{:?}", buf), "[REDACTED; 100]");
+
+        buf.zeroize();
+        assert_eq!(buf[99], 0);
+        assert_eq!(LockedBuffer::new(0).unwrap().len(), 0);
+    }
+}
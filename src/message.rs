@@ -0,0 +1,168 @@
+// Binary encoding of the messages exchanged by the enrollment, dealerless
+// refresh, resharing, PVSS and key generation protocols. All multi-byte
+// integers are big-endian.
+//
+//   offset  size  field
+//   0       4     magic, "SHMM"
+//   4       1     format version, currently 1
+//   5       1     message kind, one of the constants below
+//   6       f     fixed fields of the kind, f bytes
+//   6+f     4     payload length in bytes
+//   10+f    len   payload
+//   ...     4     checksum: the first 4 bytes of SHA-256 over everything above
+//
+// The fixed fields and payload of each kind:
+//
+//   kind  message            fixed fields                               payload
+//   1     EnrollmentRequest  new x                                      helper xs
+//   2     MaskedContribution from, to, new x                            data
+//   3     PartialShare       from, new x, threshold (0 if unknown),     data
+//                            split identifier (2 bytes, 0 if unknown)
+//   4     RefreshDelta       from, to                                   data
+//   5     ReshareDelta       from, to, threshold, share count,          data
+//                            old split identifier (2 bytes, 0 if
+//                            unknown), contribution to the new split
+//                            identifier (2 bytes)
+//   6     PvssDealing        threshold, share count                     commitments,
+//                                                                       then each
+//                                                                       share's E_i,
+//                                                                       c and r
+//   7     PvssShare          participant number                         S_i, c, r
+//   8     DkgDealing         dealer                                     commitments
+//   9     DkgShare           dealer, recipient                          share
+//   10    DkgComplaint       complainant, accused dealer                (empty)
+//
+// Like shares, messages are only protected against accidental corruption;
+// the transport must authenticate them.
+
+use crate::{sha256, SecretSharingError};
+
+const MAGIC: [u8; 4] = *b"SHMM";
+const VERSION: u8 = 1;
+const CHECKSUM_LEN: usize = 4;
+
+pub(crate) const ENROLLMENT_REQUEST: u8 = 1;
+pub(crate) const MASKED_CONTRIBUTION: u8 = 2;
+pub(crate) const PARTIAL_SHARE: u8 = 3;
+pub(crate) const REFRESH_DELTA: u8 = 4;
+pub(crate) const RESHARE_DELTA: u8 = 5;
+#[cfg(feature = "vss")]
+pub(crate) const PVSS_DEALING: u8 = 6;
+#[cfg(feature = "vss")]
+pub(crate) const PVSS_SHARE: u8 = 7;
+#[cfg(feature = "vss")]
+pub(crate) const DKG_DEALING: u8 = 8;
+#[cfg(feature = "vss")]
+pub(crate) const DKG_SHARE: u8 = 9;
+#[cfg(feature = "vss")]
+pub(crate) const DKG_COMPLAINT: u8 = 10;
+
+// encode returns the message of kind `kind` with the given fixed fields and
+// payload.
+pub(crate) fn encode(kind: u8, fields: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(10 + fields.len() + payload.len() + CHECKSUM_LEN);
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    out.push(kind);
+    out.extend_from_slice(fields);
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    out.extend_from_slice(payload);
+    let sum = sha256::digest(&out);
+    out.extend_from_slice(&sum[..CHECKSUM_LEN]);
+    out
+}
+
+// decode checks that `data` is a message of kind `kind` with `n` bytes of
+// fixed fields, returning the fields and the payload. A message of another
+// kind is UnexpectedMessage.
+pub(crate) fn decode(
+    kind: u8,
+    n: usize,
+    data: &[u8],
+) -> Result<(&[u8], &[u8]), SecretSharingError> {
+    if data.len() < MAGIC.len() + 2 {
+        return Err(SecretSharingError::ShareTruncated);
+    }
+    if data[..MAGIC.len()] != MAGIC {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    if data[4] != VERSION {
+        return Err(SecretSharingError::UnsupportedShareVersion(data[4]));
+    }
+    if data[5] != kind {
+        return Err(SecretSharingError::UnexpectedMessage);
+    }
+    let header_len = 10 + n;
+    if data.len() < header_len + CHECKSUM_LEN {
+        return Err(SecretSharingError::ShareTruncated);
+    }
+    let l = &data[header_len - 4..header_len];
+    let len = u32::from_be_bytes([l[0], l[1], l[2], l[3]]) as usize;
+    let body_len = data.len() - header_len - CHECKSUM_LEN;
+    if body_len < len {
+        return Err(SecretSharingError::ShareTruncated);
+    }
+    if body_len > len {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+
+    let (body, sum) = data.split_at(header_len + len);
+    if sha256::digest(body)[..CHECKSUM_LEN] != *sum {
+        return Err(SecretSharingError::ShareChecksumMismatch);
+    }
+    Ok((&body[6..6 + n], &body[header_len..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message() {
+        let encoded = encode(RESHARE_DELTA, &[1, 2, 3, 4], &[0xaa, 0xbb]);
+        assert_eq!(
+            &encoded[..16],
+            b"SHMM\x01\x05\x01\x02\x03\x04\x00\x00\x00\x02\xaa\xbb"
+        );
+        assert_eq!(encoded.len(), 20);
+        assert_eq!(
+            decode(RESHARE_DELTA, 4, &encoded),
+            Ok((&[1u8, 2, 3, 4][..], &[0xaau8, 0xbb][..]))
+        );
+
+        assert_eq!(
+            decode(REFRESH_DELTA, 2, &encoded),
+            Err(SecretSharingError::UnexpectedMessage)
+        );
+        for len in [0, 5, 13, 19] {
+            assert_eq!(
+                decode(RESHARE_DELTA, 4, &encoded[..len]),
+                Err(SecretSharingError::ShareTruncated)
+            );
+        }
+        let mut longer = encoded.clone();
+        longer.push(0);
+        assert_eq!(
+            decode(RESHARE_DELTA, 4, &longer),
+            Err(SecretSharingError::ShareMalformed)
+        );
+        let corrupt = |i: usize, b: u8| {
+            let mut bytes = encoded.clone();
+            bytes[i] ^= b;
+            decode(RESHARE_DELTA, 4, &bytes).err()
+        };
+        assert_eq!(corrupt(0, 1), Some(SecretSharingError::ShareMalformed));
+        assert_eq!(
+            corrupt(4, 3),
+            Some(SecretSharingError::UnsupportedShareVersion(2))
+        );
+        assert_eq!(
+            corrupt(14, 1),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+        assert_eq!(
+            corrupt(19, 1),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+    }
+}
@@ -0,0 +1,343 @@
+// Multi-secret sharing: several related secrets, such as a key and its chain
+// code, split so that each participant holds a single MultiShare from which
+// any t participants recover all of them. Each secret is shared with its own
+// random polynomials, so the sharings are independent, and is stored under a
+// label, so a share of one secret can never be interpolated as another's. The
+// labels are not secret. The encoding:
+//
+//   offset  size  field
+//   0       4     magic, "SHMM"
+//   4       1     format version, currently 1
+//   5       1     share index
+//   6       1     threshold
+//   7       1     share count
+//   8       1     number of secrets
+//   9       ...   for each secret: the label length l (1 byte), the label
+//                 (l bytes, UTF-8), the payload length len (4 bytes) and
+//                 the payload (len bytes)
+//   ...     4     checksum: the first 4 bytes of SHA-256 over everything above
+//
+// The checksum covers the labels, which are compared across shares on
+// recovery.
+
+use crate::{construct_shares_with_rng, reconstruct, sha256, SecretSharingError, Share, Shares};
+use rand::{CryptoRng, RngCore};
+use std::convert::TryInto;
+
+const MAGIC: [u8; 4] = *b"SHMM";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 9;
+const CHECKSUM_LEN: usize = 4;
+
+// MultiShare is a participant's share of every secret of a multi-secret
+// sharing, in the order they were given to split_multi.
+#[derive(Clone, Debug)]
+pub struct MultiShare {
+    secrets: Vec<(String, Share)>,
+}
+
+impl MultiShare {
+    pub fn index(&self) -> u8 {
+        self.secrets[0].1.index()
+    }
+
+    pub fn threshold(&self) -> Option<u8> {
+        self.secrets[0].1.threshold()
+    }
+
+    // labels returns the labels of the shared secrets.
+    pub fn labels(&self) -> Vec<&str> {
+        self.secrets
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect()
+    }
+
+    // secret returns the participant's share of the secret labelled `label`,
+    // which reconstruct accepts like any other share.
+    pub fn secret(&self, label: &str) -> Option<&Share> {
+        self.secrets
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, share)| share)
+    }
+
+    // to_bytes encodes the share in the format described above.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let first = &self.secrets[0].1;
+        let mut out = MAGIC.to_vec();
+        out.extend_from_slice(&[
+            VERSION,
+            first.index(),
+            first.threshold().unwrap_or(0),
+            first.share_count().unwrap_or(0),
+            self.secrets.len() as u8,
+        ]);
+        for (label, share) in &self.secrets {
+            out.push(label.len() as u8);
+            out.extend_from_slice(label.as_bytes());
+            out.extend_from_slice(&(share.len() as u32).to_be_bytes());
+            out.extend_from_slice(share.as_bytes());
+        }
+        let sum = sha256::digest(&out);
+        out.extend_from_slice(&sum[..CHECKSUM_LEN]);
+        out
+    }
+
+    // from_bytes decodes a share produced by to_bytes, verifying its checksum.
+    pub fn from_bytes(data: &[u8]) -> Result<MultiShare, SecretSharingError> {
+        if data.len() < MAGIC.len() + 1 {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        if data[..MAGIC.len()] != MAGIC {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if data[4] != VERSION {
+            return Err(SecretSharingError::UnsupportedShareVersion(data[4]));
+        }
+        if data.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        let (x, t, n, count) = (data[5], data[6], data[7], data[8]);
+        if x == 0 {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        if count == 0 || t > n {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let mut rest = &data[HEADER_LEN..];
+        let mut take = |n: usize| {
+            if rest.len() < n {
+                return Err(SecretSharingError::ShareTruncated);
+            }
+            let (head, tail) = rest.split_at(n);
+            rest = tail;
+            Ok(head)
+        };
+        let mut secrets = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let label_len = take(1)?[0] as usize;
+            let label = std::str::from_utf8(take(label_len)?)
+                .map_err(|_| SecretSharingError::ShareMalformed)?
+                .to_string();
+            let len = u32::from_be_bytes(take(4)?.try_into().unwrap()) as usize;
+            let payload = take(len)?;
+            let share = match t {
+                0 => Share::new(x, payload),
+                _ => Share::with_metadata(x, payload, t, n),
+            };
+            secrets.push((label, share));
+        }
+        let sum = take(CHECKSUM_LEN)?;
+        if !rest.is_empty() {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if sha256::digest(&data[..data.len() - CHECKSUM_LEN])[..CHECKSUM_LEN] != *sum {
+            return Err(SecretSharingError::ShareChecksumMismatch);
+        }
+        check_labels(secrets.iter().map(|(label, _)| label.as_str()))?;
+        Ok(MultiShare { secrets })
+    }
+}
+
+// check_labels checks that `labels` are distinct and short enough to encode.
+fn check_labels<'a, I: Iterator<Item = &'a str>>(labels: I) -> Result<(), SecretSharingError> {
+    let mut seen: Vec<&str> = Vec::new();
+    for label in labels {
+        if label.len() > u8::MAX as usize || seen.contains(&label) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        seen.push(label);
+    }
+    Ok(())
+}
+
+// split_multi splits each of `secrets`, given as labels and values, into n
+// shares with threshold t, and returns each participant's shares of all of
+// them as one MultiShare. Labels must be distinct and at most 255 bytes long,
+// or it returns ShareMalformed; more than 255 secrets give TooManyShares.
+pub fn split_multi(
+    t: u8,
+    n: u8,
+    secrets: &[(&str, &[u8])],
+) -> Result<Vec<MultiShare>, SecretSharingError> {
+    split_multi_with_rng(t, n, secrets, &mut crate::default_rng())
+}
+
+// split_multi_with_rng is like split_multi, but draws the polynomial
+// coefficients from `rng`.
+pub fn split_multi_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    n: u8,
+    secrets: &[(&str, &[u8])],
+    rng: &mut R,
+) -> Result<Vec<MultiShare>, SecretSharingError> {
+    if secrets.is_empty() {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if secrets.len() > u8::MAX as usize {
+        return Err(SecretSharingError::TooManyShares);
+    }
+    check_labels(secrets.iter().map(|&(label, _)| label))?;
+
+    let mut shares: Vec<MultiShare> = (0..n)
+        .map(|_| MultiShare {
+            secrets: Vec::with_capacity(secrets.len()),
+        })
+        .collect();
+    for &(label, secret) in secrets {
+        let split = construct_shares_with_rng(t, n, secret, rng)?;
+        for (multi, share) in shares.iter_mut().zip(split) {
+            multi.secrets.push((label.to_string(), share));
+        }
+    }
+    Ok(shares)
+}
+
+// reconstruct_multi recovers every secret from at least t multi-shares,
+// returning them with their labels in the order they were split. Shares that
+// don't agree on the labels are rejected with InconsistentShares, naming
+// their indices.
+pub fn reconstruct_multi(
+    shares: &[MultiShare],
+) -> Result<Vec<(String, Vec<u8>)>, SecretSharingError> {
+    let first = shares.first().ok_or(SecretSharingError::NoSharesProvided)?;
+    let labels = first.labels();
+    let mut suspects: Vec<u8> = shares
+        .iter()
+        .filter(|s| s.labels() != labels)
+        .map(MultiShare::index)
+        .collect();
+    if !suspects.is_empty() {
+        suspects.push(first.index());
+        suspects.sort_unstable();
+        suspects.dedup();
+        return Err(SecretSharingError::InconsistentShares { suspects });
+    }
+
+    (0..labels.len())
+        .map(|i| {
+            let secret: Shares = shares.iter().map(|s| s.secrets[i].1.clone()).collect();
+            Ok((labels[i].to_string(), reconstruct(&secret)?))
+        })
+        .collect()
+}
+
+// reconstruct_one recovers only the secret labelled `label` from at least t
+// multi-shares. Shares without it are rejected with InconsistentShares.
+pub fn reconstruct_one(shares: &[MultiShare], label: &str) -> Result<Vec<u8>, SecretSharingError> {
+    if shares.is_empty() {
+        return Err(SecretSharingError::NoSharesProvided);
+    }
+    let secret: Vec<Option<&Share>> = shares.iter().map(|s| s.secret(label)).collect();
+    let suspects: Vec<u8> = shares
+        .iter()
+        .zip(secret.iter())
+        .filter(|(_, share)| share.is_none())
+        .map(|(s, _)| s.index())
+        .collect();
+    if !suspects.is_empty() {
+        return Err(SecretSharingError::InconsistentShares { suspects });
+    }
+    let secret: Shares = secret.iter().flatten().map(|&s| s.clone()).collect();
+    reconstruct(&secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multi() {
+        let key = [0x11u8; 32];
+        let chain_code = [0x22u8; 32];
+        let shares = split_multi(2, 3, &[("key", &key), ("chain code", &chain_code)]).unwrap();
+        assert_eq!(shares.len(), 3);
+        assert_eq!(shares[2].index(), 3);
+        assert_eq!(shares[0].labels(), ["key", "chain code"]);
+
+        let restored: Vec<MultiShare> = shares[1..]
+            .iter()
+            .map(|s| MultiShare::from_bytes(&s.to_bytes()).unwrap())
+            .collect();
+        assert_eq!(
+            reconstruct_multi(&restored).unwrap(),
+            [
+                ("key".to_string(), key.to_vec()),
+                ("chain code".to_string(), chain_code.to_vec())
+            ]
+        );
+        assert_eq!(
+            reconstruct_one(&[shares[0].clone(), shares[2].clone()], "chain code").unwrap(),
+            chain_code
+        );
+        let single: Shares = shares[..2]
+            .iter()
+            .map(|s| s.secret("key").unwrap().clone())
+            .collect();
+        assert_eq!(reconstruct(&single).unwrap(), key);
+        assert_eq!(
+            reconstruct_multi(&shares[..1]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 2 })
+        );
+    }
+    #[test]
+    fn test_multi_separation() {
+        // the same value under two labels is shared independently.
+        let shares = split_multi(2, 2, &[("a", b"same"), ("b", b"same")]).unwrap();
+        assert_ne!(
+            shares[0].secret("a").unwrap().as_bytes(),
+            shares[0].secret("b").unwrap().as_bytes()
+        );
+
+        let other = split_multi(2, 2, &[("b", b"same"), ("a", b"same")]).unwrap();
+        assert_eq!(
+            reconstruct_multi(&[shares[0].clone(), other[1].clone()]).err(),
+            Some(SecretSharingError::InconsistentShares {
+                suspects: vec![1, 2]
+            })
+        );
+        assert_eq!(
+            reconstruct_one(&shares, "c").err(),
+            Some(SecretSharingError::InconsistentShares {
+                suspects: vec![1, 2]
+            })
+        );
+    }
+    #[test]
+    fn test_multi_errors() {
+        assert_eq!(
+            split_multi(2, 3, &[]).err(),
+            Some(SecretSharingError::TorNisZero)
+        );
+        assert_eq!(
+            split_multi(2, 3, &[("a", b"x"), ("a", b"y")]).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        assert_eq!(
+            split_multi(4, 3, &[("a", b"x")]).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        assert_eq!(
+            reconstruct_multi(&[]).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+
+        let mut bytes = split_multi(2, 3, &[("a", b"x")]).unwrap()[0].to_bytes();
+        let len = bytes.len();
+        assert_eq!(
+            MultiShare::from_bytes(&bytes[..len - 1]).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+        bytes[10] = b'b';
+        assert_eq!(
+            MultiShare::from_bytes(&bytes).err(),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+        bytes[4] = 2;
+        assert_eq!(
+            MultiShare::from_bytes(&bytes).err(),
+            Some(SecretSharingError::UnsupportedShareVersion(2))
+        );
+    }
+}
@@ -0,0 +1,101 @@
+// Padding of the secret before splitting, for secrets whose length is itself
+// sensitive: shares are exactly as long as the data they share, so without
+// padding a single share reveals the length of the secret.
+//
+// The padding is that of ISO/IEC 7816-4: a 0x80 byte followed by as many zero
+// bytes as needed. At least one byte is always added, so it can be removed
+// unambiguously: the padded data ends at its last non-zero byte, which must be
+// the 0x80. Padding is applied by ShamirConfig::with_padding.
+
+use crate::SecretSharingError;
+use std::num::NonZeroUsize;
+use zeroize::Zeroizing;
+
+const MARKER: u8 = 0x80;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Padding {
+    // Block pads to the next multiple of the block size, hiding the length
+    // to within a block.
+    Block(NonZeroUsize),
+    // Buckets pads to the smallest of the given sizes that fits, so the
+    // length reveals only which bucket the secret falls in. A secret too
+    // long for every bucket is rejected.
+    Buckets(Vec<usize>),
+}
+
+impl Padding {
+    // padded_len returns the length `len` bytes of data are padded to.
+    fn padded_len(&self, len: usize) -> Result<usize, SecretSharingError> {
+        match self {
+            Padding::Block(b) => Ok((len / b.get() + 1) * b.get()),
+            Padding::Buckets(sizes) => sizes.iter().filter(|&&s| s > len).min().copied().ok_or(
+                SecretSharingError::PayloadTooLarge {
+                    max: sizes.iter().max().map_or(0, |m| m.saturating_sub(1)),
+                },
+            ),
+        }
+    }
+
+    // pad returns `data` padded.
+    pub(crate) fn pad(&self, data: &[u8]) -> Result<Zeroizing<Vec<u8>>, SecretSharingError> {
+        let mut out = Zeroizing::new(vec![0u8; self.padded_len(data.len())?]);
+        out[..data.len()].copy_from_slice(data);
+        out[data.len()] = MARKER;
+        Ok(out)
+    }
+}
+
+// unpad removes the padding from `data` in place. It reads every byte whatever
+// the length of the padding, so its timing doesn't reveal the length of the
+// secret.
+pub(crate) fn unpad(data: &mut Vec<u8>) -> Result<(), SecretSharingError> {
+    // scanning from the front, remember the position and value of the last
+    // non-zero byte.
+    let (mut last, mut value) = (0usize, 0u8);
+    for (i, &b) in data.iter().enumerate() {
+        let nonzero = 0usize.wrapping_sub((b != 0) as usize);
+        last = (i & nonzero) | (last & !nonzero);
+        value = (b & nonzero as u8) | (value & !nonzero as u8);
+    }
+    if value != MARKER {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    for b in data[last..].iter_mut() {
+        *b = 0;
+    }
+    data.truncate(last);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padding() {
+        let block = Padding::Block(NonZeroUsize::new(16).unwrap());
+        for len in [0, 1, 15, 16, 17, 100] {
+            let data = vec![0u8; len];
+            let mut padded = block.pad(&data).unwrap().to_vec();
+            assert!(padded.len() > len && padded.len().is_multiple_of(16));
+            unpad(&mut padded).unwrap();
+            assert_eq!(padded, data);
+        }
+
+        let buckets = Padding::Buckets(vec![64, 32, 256]);
+        assert_eq!(buckets.pad(&[7; 31]).unwrap().len(), 32);
+        assert_eq!(buckets.pad(&[7; 32]).unwrap().len(), 64);
+        assert_eq!(
+            buckets.pad(&[7; 256]).err(),
+            Some(SecretSharingError::PayloadTooLarge { max: 255 })
+        );
+
+        for bad in [&[][..], &[1, 2, 0], &[0x80, 1]] {
+            assert_eq!(
+                unpad(&mut bad.to_vec()).err(),
+                Some(SecretSharingError::ShareMalformed)
+            );
+        }
+    }
+}
@@ -0,0 +1,243 @@
+// Printable paper backups of a Share, to be printed, laminated and stored
+// somewhere safe. The share is written out as uppercase hex in groups of four
+// characters, eight groups to a line, which is easy to read aloud and to type
+// back in: only 0-9 and A-F appear, and the last eight characters are the
+// share's checksum, so a typo is caught as soon as the share is entered. The
+// page also gives the share index, the threshold, the creation date and
+// recovery instructions for whoever finds it, as plain text or as HTML.
+//
+// Share::from_paper reads the groups back, ignoring spaces and line breaks.
+
+use crate::armor::format_time;
+use crate::text::hex_encode;
+use crate::{SecretSharingError, Share};
+use std::fmt::Write;
+use std::time::SystemTime;
+use zeroize::Zeroizing;
+
+const GROUP_LEN: usize = 4;
+const GROUPS_PER_LINE: usize = 8;
+
+// PaperBackup is a share laid out for printing.
+#[derive(Clone, Debug)]
+pub struct PaperBackup {
+    pub share: Share,
+    pub created: Option<SystemTime>,
+    pub label: Option<String>,
+}
+
+impl PaperBackup {
+    // new lays out `share`, dated today and labelled with the share's own
+    // label, if it has one.
+    pub fn new(share: Share) -> PaperBackup {
+        PaperBackup {
+            label: share.label().map(str::to_string),
+            share,
+            created: Some(SystemTime::now()),
+        }
+    }
+
+    // with_label sets a label saying what the secret is, which must fit on
+    // one line.
+    pub fn with_label(mut self, label: &str) -> Result<PaperBackup, SecretSharingError> {
+        if label.chars().any(char::is_control) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        self.label = Some(label.trim().to_string());
+        Ok(self)
+    }
+
+    // lines returns the share's hex groups, a line of them at a time.
+    fn lines(&self) -> Zeroizing<Vec<String>> {
+        let hex = Zeroizing::new(hex_encode(&Zeroizing::new(self.share.to_bytes())));
+        let groups: Vec<&str> = hex
+            .as_bytes()
+            .chunks(GROUP_LEN)
+            // hex is ASCII, so every chunk is valid UTF-8.
+            .map(|g| std::str::from_utf8(g).unwrap())
+            .collect();
+        Zeroizing::new(
+            groups
+                .chunks(GROUPS_PER_LINE)
+                .map(|line| line.join(" ").to_ascii_uppercase())
+                .collect(),
+        )
+    }
+
+    // title returns the heading of the page, such as "Share 3 of 5". Shares
+    // issued at custom x-coordinates may have indices above the count, which
+    // are shown alone.
+    fn title(&self) -> String {
+        match self.share.share_count() {
+            Some(n) if self.share.index() <= n => format!("Share {} of {}", self.share.index(), n),
+            _ => format!("Share {}", self.share.index()),
+        }
+    }
+
+    // details returns the lines describing the share, above its groups.
+    fn details(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        if let Some(label) = &self.label {
+            out.push(format!("Label: {}", label));
+        }
+        out.push(match (self.share.threshold(), self.share.share_count()) {
+            (Some(t), Some(n)) => format!("Any {} of the {} shares recover the secret.", t, n),
+            (Some(t), None) => format!("Any {} shares recover the secret.", t),
+            _ => "The number of shares needed to recover the secret was not recorded.".into(),
+        });
+        if let Some(created) = self.created {
+            out.push(format!("Created: {}", &format_time(created)[..10]));
+        }
+        out
+    }
+
+    // instructions returns the recovery instructions, one step per line.
+    fn instructions(&self) -> Vec<String> {
+        let collect = match self.share.threshold() {
+            Some(t) => format!(
+                "Collect {} different shares of this secret, this one included.",
+                t
+            ),
+            None => "Collect as many different shares of this secret as you can find.".into(),
+        };
+        vec![
+            collect,
+            "Run `shamir recover -o secret.bin` with the shamir tool, from the \
+             rust-shamir project."
+                .into(),
+            "When asked, type in each share's groups of characters in order. \
+             Spaces and line breaks don't matter, and letters may be typed in \
+             lower case."
+                .into(),
+            "The last eight characters of each share are its checksum: if a share is \
+             rejected, look for a mistyped character and type it in again."
+                .into(),
+            "Once enough shares are in, the secret is written to secret.bin.".into(),
+        ]
+    }
+
+    // to_text renders the page as plain text.
+    pub fn to_text(&self) -> Zeroizing<String> {
+        let mut out = Zeroizing::new(String::new());
+        let title = self.title().to_uppercase();
+        let _ = writeln!(out, "{}\n{}\n", title, "=".repeat(title.len()));
+        for line in self.details() {
+            let _ = writeln!(out, "{}", line);
+        }
+        out.push('\n');
+        for line in self.lines().iter() {
+            let _ = writeln!(out, "    {}", line);
+        }
+        let _ = writeln!(out, "\nHOW TO RECOVER THE SECRET\n");
+        for (i, step) in self.instructions().iter().enumerate() {
+            let _ = writeln!(out, "{}. {}", i + 1, step);
+        }
+        out
+    }
+
+    // to_html renders the page as a standalone HTML document, set in a large
+    // monospaced font for printing.
+    pub fn to_html(&self) -> Zeroizing<String> {
+        let mut out = Zeroizing::new(String::new());
+        let title = escape(&self.title());
+        let _ = write!(
+            out,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{}</title>\n<style>\n\
+             body {{ font-family: sans-serif; margin: 2cm; }}\n\
+             pre {{ font-size: 20pt; line-height: 1.6; border: 2px solid; padding: 0.5cm; }}\n\
+             </style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, title
+        );
+        for line in self.details() {
+            let _ = writeln!(out, "<p>{}</p>", escape(&line));
+        }
+        out.push_str("<pre>\n");
+        for line in self.lines().iter() {
+            let _ = writeln!(out, "{}", line);
+        }
+        out.push_str("</pre>\n<h2>How to recover the secret</h2>\n<ol>\n");
+        for step in self.instructions() {
+            let _ = writeln!(out, "<li>{}</li>", escape(&step));
+        }
+        out.push_str("</ol>\n</body>\n</html>\n");
+        out
+    }
+}
+
+// escape escapes `s` for HTML text.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+impl Share {
+    // from_paper parses a share typed in from a paper backup, or in any of
+    // the formats FromStr accepts, ignoring all whitespace.
+    pub fn from_paper(s: &str) -> Result<Share, SecretSharingError> {
+        let compact: Zeroizing<String> = Zeroizing::new(s.split_whitespace().collect());
+        compact.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_paper_text() {
+        let shares = construct_shares(3, 5, &[0xa5; 40]).unwrap();
+        let mut paper = PaperBackup::new(shares[2].clone())
+            .with_label("family photos key")
+            .unwrap();
+        paper.created = Some(UNIX_EPOCH + Duration::from_secs(1_792_143_000));
+        let text = paper.to_text();
+        assert!(text.starts_with("SHARE 3 OF 5\n============\n\nLabel: family photos key\n"));
+        assert!(text.contains("Any 3 of the 5 shares recover the secret.\nCreated: 2026-10-16\n"));
+        assert!(text.contains("\n    5348 4D52 0403 05"));
+        assert!(text.contains("1. Collect 3 different shares"));
+
+        // the groups typed back in, however they are spaced, give the share.
+        let groups: String = text
+            .lines()
+            .filter(|l| l.starts_with("    "))
+            .map(|l| format!("{}\n", l.to_lowercase()))
+            .collect();
+        assert_eq!(groups.lines().count(), 4);
+        let typed = Share::from_paper(&groups).unwrap();
+        assert_eq!(typed.as_bytes(), shares[2].as_bytes());
+
+        let mut typo = groups.into_bytes();
+        let i = typo.iter().position(|&c| c == b'\n').unwrap() + 5;
+        typo[i] = if typo[i] == b'0' { b'1' } else { b'0' };
+        assert_eq!(
+            Share::from_paper(std::str::from_utf8(&typo).unwrap()).err(),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+    }
+    #[test]
+    fn test_paper_html() {
+        let paper = PaperBackup::new(Share::new(7, b"key"))
+            .with_label("<b>Tom & Jerry</b>")
+            .unwrap();
+        let html = paper.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>Share 7</h1>"));
+        assert!(html.contains("<p>Label: &lt;b&gt;Tom &amp; Jerry&lt;/b&gt;</p>"));
+        assert!(html.contains("<li>Collect as many"));
+        assert!(PaperBackup::new(Share::new(1, b"key"))
+            .with_label("two\nlines")
+            .is_err());
+    }
+}
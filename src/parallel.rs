@@ -0,0 +1,72 @@
+// With the `parallel` feature, splitting and reconstruction process large
+// secrets in chunks on multiple threads. Every byte of the secret is shared
+// independently, so the chunks need no coordination. Threads are scoped to
+// each call rather than pooled, which costs little next to the work on a
+// chunk of at least MIN_CHUNK bytes.
+
+// MIN_CHUNK is the smallest chunk worth handing to its own thread.
+#[cfg(feature = "parallel")]
+const MIN_CHUNK: usize = 1 << 16;
+
+// for_each_chunk splits `out` into chunks and calls `f` with the offset of
+// each chunk into `out` and the chunk itself. Without the `parallel` feature
+// it is called once, for the whole of `out`.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn for_each_chunk<F: Fn(usize, &mut [u8]) + Sync>(out: &mut [u8], f: F) {
+    f(0, out);
+}
+
+#[cfg(feature = "parallel")]
+pub(crate) fn for_each_chunk<F: Fn(usize, &mut [u8]) + Sync>(out: &mut [u8], f: F) {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for_each_chunk_on(threads, out, f);
+}
+
+// for_each_chunk_on is for_each_chunk with up to `threads` threads.
+#[cfg(feature = "parallel")]
+fn for_each_chunk_on<F: Fn(usize, &mut [u8]) + Sync>(threads: usize, out: &mut [u8], f: F) {
+    let chunk = std::cmp::max(out.len().div_ceil(threads), MIN_CHUNK);
+    if out.len() <= chunk {
+        f(0, out);
+        return;
+    }
+
+    let f = &f;
+    std::thread::scope(|s| {
+        for (i, c) in out.chunks_mut(chunk).enumerate() {
+            s.spawn(move || f(i * chunk, c));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_each_chunk() {
+        let mut out = vec![0u8; (1 << 20) + 3];
+        for_each_chunk(&mut out, |offset, c| {
+            for (i, b) in c.iter_mut().enumerate() {
+                *b = ((offset + i) % 251) as u8;
+            }
+        });
+        assert!(out.iter().enumerate().all(|(i, &b)| b == (i % 251) as u8));
+    }
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_for_each_chunk_threads() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = AtomicUsize::new(0);
+        let mut out = vec![0u8; (1 << 20) + 3];
+        for_each_chunk_on(4, &mut out, |offset, c| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            for (i, b) in c.iter_mut().enumerate() {
+                *b = ((offset + i) % 251) as u8;
+            }
+        });
+        assert!(out.iter().enumerate().all(|(i, &b)| b == (i % 251) as u8));
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
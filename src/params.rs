@@ -0,0 +1,107 @@
+// Validated sharing parameters. A Threshold can only be constructed together
+// with the ShareCount it applies to, and only if 1 <= t <= n <= 255, so the
+// functions taking one cannot fail because of their parameters.
+
+use crate::{construct_shares_with_rng, SecretSharingError, Shares};
+use rand::{CryptoRng, RngCore};
+use std::convert::TryFrom;
+
+// ShareCount is a number of shares, n, between 1 and 255.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ShareCount(u8);
+
+impl ShareCount {
+    pub fn new(n: u8) -> Result<ShareCount, SecretSharingError> {
+        if n == 0 {
+            return Err(SecretSharingError::TorNisZero);
+        }
+        Ok(ShareCount(n))
+    }
+
+    pub fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<u8> for ShareCount {
+    type Error = SecretSharingError;
+
+    fn try_from(n: u8) -> Result<ShareCount, SecretSharingError> {
+        ShareCount::new(n)
+    }
+}
+
+// Threshold is the number of shares, t, required to reconstruct a secret
+// split into a given ShareCount of shares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Threshold {
+    t: u8,
+    n: ShareCount,
+}
+
+impl Threshold {
+    pub fn new(t: u8, n: ShareCount) -> Result<Threshold, SecretSharingError> {
+        if t == 0 {
+            return Err(SecretSharingError::TorNisZero);
+        }
+        if t > n.get() {
+            return Err(SecretSharingError::ThresholdExceedsShares);
+        }
+        Ok(Threshold { t, n })
+    }
+
+    pub fn get(self) -> u8 {
+        self.t
+    }
+
+    pub fn share_count(self) -> ShareCount {
+        self.n
+    }
+}
+
+// construct_shares_checked is like construct_shares, but takes validated
+// parameters and so cannot fail.
+pub fn construct_shares_checked(threshold: Threshold, secret: &[u8]) -> Shares {
+    construct_shares_checked_with_rng(threshold, secret, &mut crate::default_rng())
+}
+
+// construct_shares_checked_with_rng is like construct_shares_checked, but
+// draws the polynomial coefficients from `rng`.
+pub fn construct_shares_checked_with_rng<R: CryptoRng + RngCore>(
+    threshold: Threshold,
+    secret: &[u8],
+    rng: &mut R,
+) -> Shares {
+    construct_shares_with_rng(threshold.t, threshold.n.get(), secret, rng)
+        .expect("validated parameters")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reconstruct;
+
+    #[test]
+    fn test_params() {
+        assert_eq!(ShareCount::new(0), Err(SecretSharingError::TorNisZero));
+        assert_eq!(ShareCount::try_from(255).unwrap().get(), 255);
+
+        let n = ShareCount::new(5).unwrap();
+        assert_eq!(Threshold::new(0, n), Err(SecretSharingError::TorNisZero));
+        assert_eq!(
+            Threshold::new(6, n),
+            Err(SecretSharingError::ThresholdExceedsShares)
+        );
+        let t = Threshold::new(5, n).unwrap();
+        assert_eq!(t.get(), 5);
+        assert_eq!(t.share_count(), n);
+    }
+    #[test]
+    fn test_construct_shares_checked() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let t = Threshold::new(2, ShareCount::new(3).unwrap()).unwrap();
+        let shares = construct_shares_checked(t, &secret);
+        assert_eq!(shares.len(), 3);
+        assert_eq!(reconstruct(&shares[1..]).unwrap(), secret);
+    }
+}
@@ -0,0 +1,326 @@
+// Passphrase-protected shares, so that holding t shares isn't enough to
+// recover the secret without also knowing the passphrase. A share is
+// encrypted with XChaCha20-Poly1305 under a key derived from the passphrase
+// with scrypt and a random salt. All multi-byte integers are big-endian:
+//
+//   offset  size  field
+//   0       4     magic, "SHMP"
+//   4       1     format version, currently 1
+//   5       1     key derivation function, 1 for scrypt
+//   6       1     scrypt log2(N)
+//   7       4     scrypt r
+//   11      4     scrypt p
+//   15      16    salt
+//   31      24    nonce
+//   55      len   encrypted share, in the canonical binary format
+//   55+len  16    Poly1305 tag
+//
+// Everything before the encrypted share is authenticated as associated data.
+// The share index is encrypted along with the rest of the share.
+
+use crate::aead::{self, KEY_LEN, NONCE_LEN, TAG_LEN};
+use crate::{kdf, SecretSharingError, Share, Shares};
+use rand::{CryptoRng, RngCore};
+use std::convert::TryInto;
+use zeroize::Zeroizing;
+
+const MAGIC: [u8; 4] = *b"SHMP";
+const VERSION: u8 = 1;
+const KDF_SCRYPT: u8 = 1;
+const SALT_LEN: usize = 16;
+const HEADER_LEN: usize = 31 + NONCE_LEN;
+
+// MAX_MEMORY bounds the memory scrypt may use, so that a share can't make
+// unlock allocate without limit: 1 GiB for its table of N blocks, enough for
+// N = 2^20 with r = 8, and 1 MiB more for its p blocks.
+const MAX_MEMORY: u128 = (1 << 30) + (1 << 20);
+
+// MAX_WORK bounds N * r * p, the number of 128-byte block mixes scrypt
+// performs, so that a share can't make unlock run for hours. It is 128 times
+// the default's.
+const MAX_WORK: u128 = 1 << 25;
+
+// ScryptParams are the scrypt cost parameters: N = 2^log_n, the block size r
+// and the parallelism p. Memory use is 128 * r * (N + p) bytes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScryptParams {
+    log_n: u8,
+    r: u32,
+    p: u32,
+}
+
+impl ScryptParams {
+    // new checks the parameters against RFC 7914, MAX_MEMORY and MAX_WORK.
+    pub fn new(log_n: u8, r: u32, p: u32) -> Result<ScryptParams, SecretSharingError> {
+        // log_n < 64 keeps N, and so the products below, well within a u128.
+        let n = 1u128 << log_n.min(63);
+        let (r128, p128) = (r as u128, p as u128);
+        if log_n == 0
+            || log_n >= 64
+            || r == 0
+            || p == 0
+            || (log_n as u64) >= 16 * r as u64
+            || r as u64 * p as u64 >= 1 << 30
+            || 128 * r128 * (n + p128) > MAX_MEMORY
+            || n * r128 * p128 > MAX_WORK
+        {
+            return Err(SecretSharingError::InvalidKdfParameters);
+        }
+        Ok(ScryptParams { log_n, r, p })
+    }
+
+    pub fn log_n(&self) -> u8 {
+        self.log_n
+    }
+
+    pub fn r(&self) -> u32 {
+        self.r
+    }
+
+    pub fn p(&self) -> u32 {
+        self.p
+    }
+
+    fn derive_key(&self, passphrase: &[u8], salt: &[u8]) -> Zeroizing<[u8; KEY_LEN]> {
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        kdf::scrypt(passphrase, salt, self.log_n, self.r, self.p, &mut key[..]);
+        key
+    }
+}
+
+impl Default for ScryptParams {
+    // default returns N = 2^15, r = 8, p = 1, using 32 MiB, as recommended
+    // for interactive use.
+    fn default() -> ScryptParams {
+        ScryptParams {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+// ProtectedShare is a share encrypted under a passphrase.
+#[derive(Clone, Debug)]
+pub struct ProtectedShare {
+    params: ScryptParams,
+    salt: [u8; SALT_LEN],
+    nonce: [u8; NONCE_LEN],
+    sealed: Vec<u8>,
+}
+
+impl Share {
+    // protect encrypts the share under `passphrase`.
+    pub fn protect(&self, passphrase: &[u8], params: &ScryptParams) -> ProtectedShare {
+        self.protect_with_rng(passphrase, params, &mut crate::default_rng())
+    }
+
+    // protect_with_rng is like protect, but draws the salt and nonce from
+    // `rng`.
+    pub fn protect_with_rng<R: CryptoRng + RngCore>(
+        &self,
+        passphrase: &[u8],
+        params: &ScryptParams,
+        rng: &mut R,
+    ) -> ProtectedShare {
+        let mut share = ProtectedShare {
+            params: *params,
+            salt: [0; SALT_LEN],
+            nonce: [0; NONCE_LEN],
+            sealed: Vec::new(),
+        };
+        rng.fill_bytes(&mut share.salt);
+        rng.fill_bytes(&mut share.nonce);
+        let key = params.derive_key(passphrase, &share.salt);
+        let plaintext = Zeroizing::new(self.to_bytes());
+        share.sealed = aead::seal(&key, &share.nonce, &share.header(), &plaintext);
+        share
+    }
+}
+
+impl ProtectedShare {
+    pub fn params(&self) -> &ScryptParams {
+        &self.params
+    }
+
+    fn header(&self) -> [u8; HEADER_LEN] {
+        let mut h = [0u8; HEADER_LEN];
+        h[..4].copy_from_slice(&MAGIC);
+        h[4] = VERSION;
+        h[5] = KDF_SCRYPT;
+        h[6] = self.params.log_n;
+        h[7..11].copy_from_slice(&self.params.r.to_be_bytes());
+        h[11..15].copy_from_slice(&self.params.p.to_be_bytes());
+        h[15..31].copy_from_slice(&self.salt);
+        h[31..].copy_from_slice(&self.nonce);
+        h
+    }
+
+    // unlock decrypts the share with `passphrase`, returning WrongPassphrase
+    // if it doesn't decrypt.
+    pub fn unlock(&self, passphrase: &[u8]) -> Result<Share, SecretSharingError> {
+        let key = self.params.derive_key(passphrase, &self.salt);
+        let plaintext = aead::open(&key, &self.nonce, &self.header(), &self.sealed)
+            .ok_or(SecretSharingError::WrongPassphrase)?;
+        Share::from_bytes(&plaintext)
+    }
+
+    // to_bytes encodes the protected share in the format described above.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.sealed.len());
+        out.extend_from_slice(&self.header());
+        out.extend_from_slice(&self.sealed);
+        out
+    }
+
+    // from_bytes decodes a protected share produced by to_bytes. The
+    // passphrase is only checked by unlock.
+    pub fn from_bytes(data: &[u8]) -> Result<ProtectedShare, SecretSharingError> {
+        if data.len() < MAGIC.len() + 1 {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        if data[..MAGIC.len()] != MAGIC {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if data[4] != VERSION {
+            return Err(SecretSharingError::UnsupportedShareVersion(data[4]));
+        }
+        if data.len() < HEADER_LEN + TAG_LEN {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        if data[5] != KDF_SCRYPT {
+            return Err(SecretSharingError::InvalidKdfParameters);
+        }
+        let u32_at = |i: usize| u32::from_be_bytes(data[i..i + 4].try_into().unwrap());
+        Ok(ProtectedShare {
+            params: ScryptParams::new(data[6], u32_at(7), u32_at(11))?,
+            salt: data[15..31].try_into().unwrap(),
+            nonce: data[31..HEADER_LEN].try_into().unwrap(),
+            sealed: data[HEADER_LEN..].to_vec(),
+        })
+    }
+}
+
+// protect_shares encrypts every share under the same passphrase, each with
+// its own salt.
+pub fn protect_shares(
+    shares: &[Share],
+    passphrase: &[u8],
+    params: &ScryptParams,
+) -> Vec<ProtectedShare> {
+    shares
+        .iter()
+        .map(|s| s.protect(passphrase, params))
+        .collect()
+}
+
+// unlock_shares decrypts shares protected with protect_shares, ready to be
+// combined.
+pub fn unlock_shares(
+    shares: &[ProtectedShare],
+    passphrase: &[u8],
+) -> Result<Shares, SecretSharingError> {
+    shares.iter().map(|s| s.unlock(passphrase)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{construct_shares, reconstruct};
+
+    // cheap parameters, so the tests don't spend their time in scrypt.
+    fn params() -> ScryptParams {
+        ScryptParams::new(4, 1, 1).unwrap()
+    }
+
+    #[test]
+    fn test_protect_unlock() {
+        let secret = b"correct horse battery staple";
+        let shares = construct_shares(2, 3, secret).unwrap();
+        let protected = protect_shares(&shares, b"hunter2", &params());
+        assert_ne!(protected[0].salt, protected[1].salt);
+
+        let decoded: Vec<ProtectedShare> = protected
+            .iter()
+            .map(|p| ProtectedShare::from_bytes(&p.to_bytes()).unwrap())
+            .collect();
+        let unlocked = unlock_shares(&decoded[1..], b"hunter2").unwrap();
+        assert_eq!(unlocked[0].index(), 2);
+        assert_eq!(unlocked[0].threshold(), Some(2));
+        assert_eq!(reconstruct(&unlocked).unwrap(), secret);
+
+        // shares may each have their own passphrase.
+        let alice = shares[0].protect(b"alice", &params());
+        assert_eq!(
+            alice.unlock(b"alice").unwrap().as_bytes(),
+            shares[0].as_bytes()
+        );
+        assert_eq!(
+            alice.unlock(b"bob").err(),
+            Some(SecretSharingError::WrongPassphrase)
+        );
+    }
+    #[test]
+    fn test_protected_share_errors() {
+        let share = Share::with_metadata(1, b"key", 2, 3).protect(b"pw", &params());
+        let bytes = share.to_bytes();
+        // tampering with the header or the ciphertext fails like a wrong
+        // passphrase.
+        for i in [20, 40, bytes.len() - 1] {
+            let mut bad = bytes.clone();
+            bad[i] ^= 1;
+            assert_eq!(
+                ProtectedShare::from_bytes(&bad)
+                    .unwrap()
+                    .unlock(b"pw")
+                    .err(),
+                Some(SecretSharingError::WrongPassphrase)
+            );
+        }
+
+        let mut huge = bytes.clone();
+        huge[6] = 40;
+        let mut version = bytes.clone();
+        version[4] = 2;
+        for (data, err) in [
+            (&huge[..], SecretSharingError::InvalidKdfParameters),
+            (&version[..], SecretSharingError::UnsupportedShareVersion(2)),
+            (&bytes[..HEADER_LEN], SecretSharingError::ShareTruncated),
+            (&bytes[1..], SecretSharingError::ShareMalformed),
+        ] {
+            assert_eq!(ProtectedShare::from_bytes(data).err(), Some(err));
+        }
+
+        // scrypt's p blocks of 128 * r bytes count against the memory limit,
+        // and the total work is bounded too.
+        let mut wide = bytes.clone();
+        wide[7..15].copy_from_slice(&[0, 0, 0, 1, 0x3f, 0xff, 0xff, 0xff]);
+        assert_eq!(
+            ProtectedShare::from_bytes(&wide).err(),
+            Some(SecretSharingError::InvalidKdfParameters)
+        );
+
+        assert!(ScryptParams::new(20, 8, 1).is_ok());
+        assert!(ScryptParams::new(20, 8, 4).is_ok());
+        for (log_n, r, p) in [
+            (0, 8, 1),
+            (21, 8, 1),
+            (14, 0, 1),
+            (14, 8, 0),
+            (16, 1, 1),
+            (1, 8, 1 << 21),
+            (20, 8, 5),
+            (64, 8, 1),
+            (255, 16, 1),
+        ] {
+            assert_eq!(
+                ScryptParams::new(log_n, r, p).err(),
+                Some(SecretSharingError::InvalidKdfParameters)
+            );
+        }
+        assert_eq!(
+            ScryptParams::default(),
+            ScryptParams::new(15, 8, 1).unwrap()
+        );
+    }
+}
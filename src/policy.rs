@@ -0,0 +1,693 @@
+// General monotone access structures, as trees of AND, OR and threshold
+// gates over named participants, realized by nested Shamir sharing: a k-of-n
+// gate splits the value it receives into n shares with threshold k and hands
+// share i to its i-th child, AND is n-of-n and OR is 1-of-n. Each leaf
+// receives the value that reaches it, so "(alice AND bob) OR any 3 of the ops
+// team" is
+//
+//   Policy::Or(vec![
+//       Policy::And(vec![Policy::leaf("alice"), Policy::leaf("bob")]),
+//       Policy::Threshold(3, ops_team),
+//   ])
+//
+// A participant named by several leaves receives a PolicyShare for each. The
+// path of a PolicyShare gives the child index taken at each gate from the
+// root down to its leaf, so recovery needs the policy as well as the shares.
+// The policy is not secret. The encoding:
+//
+//   offset   size  field
+//   0        4     magic, "SHMA"
+//   4        1     format version, currently 1
+//   5        1     leaf name length, n
+//   6        n     leaf name, UTF-8
+//   6+n      1     path length, p
+//   7+n      p     path
+//   7+n+p    4     payload length in bytes
+//   11+n+p   len   payload
+//   ...      4     checksum: the first 4 bytes of SHA-256 over everything above
+
+use crate::{construct_shares_with_rng, reconstruct, sha256, Redacted, SecretSharingError, Share};
+use rand::{CryptoRng, RngCore};
+use std::convert::TryInto;
+use std::fmt;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+const MAGIC: [u8; 4] = *b"SHMA";
+const VERSION: u8 = 1;
+const CHECKSUM_LEN: usize = 4;
+
+// Policy is a monotone access structure over named participants.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Policy {
+    Leaf(String),
+    And(Vec<Policy>),
+    Or(Vec<Policy>),
+    Threshold(u8, Vec<Policy>),
+}
+
+impl Policy {
+    // leaf returns the policy satisfied by participant `name` alone.
+    pub fn leaf(name: &str) -> Policy {
+        Policy::Leaf(name.to_string())
+    }
+
+    // gate returns the threshold and children of a gate, or None for a leaf.
+    fn gate(&self) -> Option<(usize, &[Policy])> {
+        match self {
+            Policy::Leaf(_) => None,
+            Policy::And(children) => Some((children.len(), children)),
+            Policy::Or(children) => Some((1, children)),
+            Policy::Threshold(k, children) => Some((*k as usize, children)),
+        }
+    }
+
+    // validate checks that every gate can be realized: it has between 1 and
+    // 255 children and a threshold between 1 and its number of children.
+    // Leaf names must be at most 255 bytes long, and the tree at most 255
+    // gates deep.
+    pub fn validate(&self) -> Result<(), SecretSharingError> {
+        self.validate_at(0)
+    }
+
+    fn validate_at(&self, depth: usize) -> Result<(), SecretSharingError> {
+        let (k, children) = match self.gate() {
+            Some(gate) => gate,
+            None => {
+                return match self {
+                    Policy::Leaf(name) if name.len() <= u8::MAX as usize => Ok(()),
+                    _ => Err(SecretSharingError::ShareMalformed),
+                }
+            }
+        };
+        if depth >= u8::MAX as usize || children.len() > u8::MAX as usize {
+            return Err(SecretSharingError::TooManyShares);
+        }
+        if k == 0 || children.is_empty() {
+            return Err(SecretSharingError::TorNisZero);
+        }
+        if k > children.len() {
+            return Err(SecretSharingError::ThresholdExceedsShares);
+        }
+        children.iter().try_for_each(|c| c.validate_at(depth + 1))
+    }
+
+    // is_satisfied_by reports whether the participants in `names` together
+    // satisfy the policy.
+    pub fn is_satisfied_by(&self, names: &[&str]) -> bool {
+        match (self, self.gate()) {
+            (Policy::Leaf(name), _) => names.contains(&name.as_str()),
+            (_, Some((k, children))) => {
+                children.iter().filter(|c| c.is_satisfied_by(names)).count() >= k
+            }
+            (_, None) => false,
+        }
+    }
+}
+
+// PolicyShare is the piece of a policy sharing given to one leaf.
+#[derive(Clone)]
+pub struct PolicyShare {
+    leaf: String,
+    path: Vec<u8>,
+    data: Zeroizing<Vec<u8>>,
+}
+
+impl fmt::Debug for PolicyShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PolicyShare")
+            .field("leaf", &self.leaf)
+            .field("path", &self.path)
+            .field("data", &Redacted(self.data.len()))
+            .finish()
+    }
+}
+
+impl PolicyShare {
+    // leaf returns the name of the participant the share belongs to.
+    pub fn leaf(&self) -> &str {
+        &self.leaf
+    }
+
+    // path returns the child indices leading from the root to the share's
+    // leaf.
+    pub fn path(&self) -> &[u8] {
+        &self.path
+    }
+
+    // to_bytes encodes the share in the format described above.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        out.push(self.leaf.len() as u8);
+        out.extend_from_slice(self.leaf.as_bytes());
+        out.push(self.path.len() as u8);
+        out.extend_from_slice(&self.path);
+        out.extend_from_slice(&(self.data.len() as u32).to_be_bytes());
+        out.extend_from_slice(&self.data);
+        let sum = sha256::digest(&out);
+        out.extend_from_slice(&sum[..CHECKSUM_LEN]);
+        out
+    }
+
+    // from_bytes decodes a share produced by to_bytes, verifying its checksum.
+    pub fn from_bytes(data: &[u8]) -> Result<PolicyShare, SecretSharingError> {
+        if data.len() < MAGIC.len() + 1 {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        if data[..MAGIC.len()] != MAGIC {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if data[4] != VERSION {
+            return Err(SecretSharingError::UnsupportedShareVersion(data[4]));
+        }
+        let mut rest = &data[5..];
+        let mut take = |n: usize| {
+            if rest.len() < n {
+                return Err(SecretSharingError::ShareTruncated);
+            }
+            let (head, tail) = rest.split_at(n);
+            rest = tail;
+            Ok(head)
+        };
+        let name_len = take(1)?[0] as usize;
+        let leaf = std::str::from_utf8(take(name_len)?)
+            .map_err(|_| SecretSharingError::ShareMalformed)?
+            .to_string();
+        let path_len = take(1)?[0] as usize;
+        let path = take(path_len)?.to_vec();
+        let len = u32::from_be_bytes(take(4)?.try_into().unwrap()) as usize;
+        let payload = take(len)?;
+        let sum = take(CHECKSUM_LEN)?;
+        if !rest.is_empty() {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if path.contains(&0) {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        if sha256::digest(&data[..data.len() - CHECKSUM_LEN])[..CHECKSUM_LEN] != *sum {
+            return Err(SecretSharingError::ShareChecksumMismatch);
+        }
+        Ok(PolicyShare {
+            leaf,
+            path,
+            data: Zeroizing::new(payload.to_vec()),
+        })
+    }
+}
+
+// split_policy splits `secret` according to `policy`, returning a share for
+// every leaf in depth-first order.
+pub fn split_policy(
+    policy: &Policy,
+    secret: &[u8],
+) -> Result<Vec<PolicyShare>, SecretSharingError> {
+    split_policy_with_rng(policy, secret, &mut crate::default_rng())
+}
+
+// split_policy_with_rng is like split_policy, but draws the polynomial
+// coefficients from `rng`.
+pub fn split_policy_with_rng<R: CryptoRng + RngCore>(
+    policy: &Policy,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Vec<PolicyShare>, SecretSharingError> {
+    policy.validate()?;
+    let mut out = Vec::new();
+    deal(policy, secret, &mut Vec::new(), &mut out, rng)?;
+    Ok(out)
+}
+
+// deal shares `value` below the node at `path`.
+fn deal<R: CryptoRng + RngCore>(
+    node: &Policy,
+    value: &[u8],
+    path: &mut Vec<u8>,
+    out: &mut Vec<PolicyShare>,
+    rng: &mut R,
+) -> Result<(), SecretSharingError> {
+    let (k, children) = match (node, node.gate()) {
+        (Policy::Leaf(name), _) => {
+            out.push(PolicyShare {
+                leaf: name.clone(),
+                path: path.clone(),
+                data: Zeroizing::new(value.to_vec()),
+            });
+            return Ok(());
+        }
+        (_, Some(gate)) => gate,
+        (_, None) => unreachable!(),
+    };
+    let shares = construct_shares_with_rng(k as u8, children.len() as u8, value, rng)?;
+    for (child, share) in children.iter().zip(shares.iter()) {
+        path.push(share.index());
+        deal(child, share.as_bytes(), path, out, rng)?;
+        path.pop();
+    }
+    Ok(())
+}
+
+// reconstruct_policy recovers the secret from `shares` dealt under `policy`.
+// Shares for other leaves or paths are ignored. It returns PolicyNotSatisfied
+// if the shares don't satisfy the policy.
+pub fn reconstruct_policy(
+    policy: &Policy,
+    shares: &[PolicyShare],
+) -> Result<Vec<u8>, SecretSharingError> {
+    policy.validate()?;
+    match recover(policy, shares, &mut Vec::new())? {
+        Some(mut secret) => Ok(std::mem::take(&mut *secret)),
+        None => Err(SecretSharingError::PolicyNotSatisfied),
+    }
+}
+
+// recover returns the value dealt to the node at `path`, or None if `shares`
+// don't satisfy it.
+fn recover(
+    node: &Policy,
+    shares: &[PolicyShare],
+    path: &mut Vec<u8>,
+) -> Result<Option<Zeroizing<Vec<u8>>>, SecretSharingError> {
+    let (k, children) = match (node, node.gate()) {
+        (Policy::Leaf(name), _) => {
+            return Ok(shares
+                .iter()
+                .find(|s| s.leaf == *name && s.path == *path)
+                .map(|s| s.data.clone()))
+        }
+        (_, Some(gate)) => gate,
+        (_, None) => unreachable!(),
+    };
+    let mut found = Vec::with_capacity(k);
+    for (i, child) in children.iter().enumerate() {
+        if found.len() == k {
+            break;
+        }
+        let x = i as u8 + 1;
+        path.push(x);
+        let value = recover(child, shares, path)?;
+        path.pop();
+        if let Some(value) = value {
+            found.push(Share::new(x, &value));
+        }
+    }
+    if found.len() < k {
+        return Ok(None);
+    }
+    Ok(Some(Zeroizing::new(reconstruct(&found)?)))
+}
+
+// Policies can also be written as text, for CLIs and config files:
+//
+//   2-of-(alice, bob, carol) AND dave
+//   (alice AND bob) OR 3-of-(bob, carol, dave, erin)
+//
+// Names are runs of letters, digits and "_", "-", ".", "@". AND binds more
+// tightly than OR, and both keywords are case-insensitive. A k-of-n gate is
+// written "k-of-(...)" with its n children separated by commas. Display
+// writes a policy back in the same syntax.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Open,
+    Close,
+    Comma,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || "_-.@".contains(c)
+}
+
+// tokenize splits `s` into tokens, each with its byte offset.
+fn tokenize(s: &str) -> Result<Vec<(usize, Token<'_>)>, SecretSharingError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            c if c.is_whitespace() => continue,
+            c if is_name_char(c) => {
+                let mut end = i + c.len_utf8();
+                while let Some(&(j, c)) = chars.peek() {
+                    if !is_name_char(c) {
+                        break;
+                    }
+                    end = j + c.len_utf8();
+                    chars.next();
+                }
+                Token::Word(&s[i..end])
+            }
+            _ => return Err(SecretSharingError::InvalidPolicy { position: i }),
+        };
+        tokens.push((i, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    pos: usize,
+    len: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).map(|&(_, t)| t)
+    }
+
+    // error returns InvalidPolicy at the current token, or at the end.
+    fn error(&self) -> SecretSharingError {
+        let position = self.tokens.get(self.pos).map_or(self.len, |&(i, _)| i);
+        SecretSharingError::InvalidPolicy { position }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), SecretSharingError> {
+        if self.peek() != Some(token) {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // keyword consumes the next token if it is the keyword `k`.
+    fn keyword(&mut self, k: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(k) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // open consumes an opening parenthesis. Nesting is limited to the depth
+    // validate accepts, so a hostile string can't exhaust the stack.
+    fn open(&mut self) -> Result<(), SecretSharingError> {
+        if self.depth >= u8::MAX as usize {
+            return Err(self.error());
+        }
+        self.expect(Token::Open)?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    // close consumes a closing parenthesis.
+    fn close(&mut self) -> Result<(), SecretSharingError> {
+        self.expect(Token::Close)?;
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Policy, SecretSharingError> {
+        let mut children = vec![self.and()?];
+        while self.keyword("or") {
+            children.push(self.and()?);
+        }
+        Ok(match children.len() {
+            1 => children.remove(0),
+            _ => Policy::Or(children),
+        })
+    }
+
+    fn and(&mut self) -> Result<Policy, SecretSharingError> {
+        let mut children = vec![self.term()?];
+        while self.keyword("and") {
+            children.push(self.term()?);
+        }
+        Ok(match children.len() {
+            1 => children.remove(0),
+            _ => Policy::And(children),
+        })
+    }
+
+    fn term(&mut self) -> Result<Policy, SecretSharingError> {
+        let word = match self.peek() {
+            Some(Token::Open) => {
+                self.open()?;
+                let policy = self.or()?;
+                self.close()?;
+                return Ok(policy);
+            }
+            Some(Token::Word(w)) => w,
+            _ => return Err(self.error()),
+        };
+        if word.eq_ignore_ascii_case("and") || word.eq_ignore_ascii_case("or") {
+            return Err(self.error());
+        }
+        let k = match word.strip_suffix("-of-") {
+            Some(k) => k,
+            None => {
+                self.pos += 1;
+                return Ok(Policy::leaf(word));
+            }
+        };
+        let k = match k.parse::<u8>() {
+            Ok(n) if k.bytes().all(|c| c.is_ascii_digit()) => n,
+            _ => return Err(self.error()),
+        };
+        self.pos += 1;
+        self.open()?;
+        let mut children = vec![self.or()?];
+        while self.peek() == Some(Token::Comma) {
+            self.pos += 1;
+            children.push(self.or()?);
+        }
+        self.close()?;
+        Ok(Policy::Threshold(k, children))
+    }
+}
+
+impl FromStr for Policy {
+    type Err = SecretSharingError;
+
+    // from_str parses a policy in the syntax above. It returns InvalidPolicy
+    // with the byte offset of a syntax error, and the errors of validate for
+    // a policy that can't be realized.
+    fn from_str(s: &str) -> Result<Policy, SecretSharingError> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            len: s.len(),
+            depth: 0,
+        };
+        let policy = parser.or()?;
+        if parser.peek().is_some() {
+            return Err(parser.error());
+        }
+        policy.validate()?;
+        Ok(policy)
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // AND and OR gates nested in each other are parenthesized.
+        let join = |f: &mut fmt::Formatter, children: &[Policy], sep: &str| {
+            for (i, child) in children.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(sep)?;
+                }
+                match child {
+                    Policy::And(_) | Policy::Or(_) => write!(f, "({})", child)?,
+                    _ => write!(f, "{}", child)?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Policy::Leaf(name) => f.write_str(name),
+            Policy::And(children) => join(f, children, " AND "),
+            Policy::Or(children) => join(f, children, " OR "),
+            Policy::Threshold(k, children) => {
+                write!(f, "{}-of-(", k)?;
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", child)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Policy {
+        // (alice AND bob) OR any 3 of the ops team, which bob is also on.
+        Policy::Or(vec![
+            Policy::And(vec![Policy::leaf("alice"), Policy::leaf("bob")]),
+            Policy::Threshold(
+                3,
+                ["bob", "carol", "dave", "erin"]
+                    .iter()
+                    .map(|n| Policy::leaf(n))
+                    .collect(),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = example();
+        let secret = b"root CA key";
+        let shares = split_policy(&policy, secret).unwrap();
+        assert_eq!(shares.len(), 6);
+        assert_eq!(shares[1].leaf(), "bob");
+        assert_eq!(shares[1].path(), [1, 2]);
+        assert_eq!(shares[2].path(), [2, 1]);
+
+        let holding = |names: &[&str]| -> Vec<PolicyShare> {
+            shares
+                .iter()
+                .filter(|s| names.contains(&s.leaf()))
+                .map(|s| PolicyShare::from_bytes(&s.to_bytes()).unwrap())
+                .collect()
+        };
+        for names in [
+            &["alice", "bob"][..],
+            &["bob", "carol", "erin"],
+            &["carol", "dave", "erin"],
+            &["alice", "bob", "carol", "dave", "erin"],
+        ] {
+            assert!(policy.is_satisfied_by(names));
+            assert_eq!(
+                reconstruct_policy(&policy, &holding(names)).unwrap(),
+                secret
+            );
+        }
+        for names in [&["alice", "carol", "dave"][..], &["bob", "dave"], &[]] {
+            assert!(!policy.is_satisfied_by(names));
+            assert_eq!(
+                reconstruct_policy(&policy, &holding(names)).err(),
+                Some(SecretSharingError::PolicyNotSatisfied)
+            );
+        }
+    }
+    #[test]
+    fn test_policy_errors() {
+        let leaves = || vec![Policy::leaf("a"), Policy::leaf("b")];
+        for (policy, err) in [
+            (
+                Policy::Threshold(3, leaves()),
+                SecretSharingError::ThresholdExceedsShares,
+            ),
+            (
+                Policy::Threshold(0, leaves()),
+                SecretSharingError::TorNisZero,
+            ),
+            (
+                Policy::Or(vec![Policy::leaf("a"), Policy::And(vec![])]),
+                SecretSharingError::TorNisZero,
+            ),
+            (
+                Policy::Leaf("x".repeat(256)),
+                SecretSharingError::ShareMalformed,
+            ),
+        ] {
+            assert_eq!(split_policy(&policy, b"s").err(), Some(err));
+        }
+
+        let shares = split_policy(&Policy::And(leaves()), b"secret").unwrap();
+        let bytes = shares[1].to_bytes();
+        let mut bad = bytes.clone();
+        bad[bytes.len() - CHECKSUM_LEN - 1] ^= 1;
+        assert_eq!(
+            PolicyShare::from_bytes(&bad).err(),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+        assert_eq!(
+            PolicyShare::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+        // a single leaf holds the secret itself.
+        let alone = split_policy(&Policy::leaf("a"), b"secret").unwrap();
+        assert_eq!(alone[0].path(), [0u8; 0]);
+        assert_eq!(
+            reconstruct_policy(&Policy::leaf("a"), &alone).unwrap(),
+            b"secret"
+        );
+    }
+    #[test]
+    fn test_policy_syntax() {
+        assert_eq!(
+            "(alice AND bob) or 3-of-(bob, carol, dave, erin)".parse(),
+            Ok(example())
+        );
+        assert_eq!(
+            "2-of-(alice, bob, carol) AND dave".parse(),
+            Ok(Policy::And(vec![
+                Policy::Threshold(
+                    2,
+                    vec![
+                        Policy::leaf("alice"),
+                        Policy::leaf("bob"),
+                        Policy::leaf("carol")
+                    ]
+                ),
+                Policy::leaf("dave"),
+            ]))
+        );
+        // AND binds more tightly than OR.
+        assert_eq!(
+            "a OR b AND c".parse(),
+            Ok(Policy::Or(vec![
+                Policy::leaf("a"),
+                Policy::And(vec![Policy::leaf("b"), Policy::leaf("c")]),
+            ]))
+        );
+        for text in [
+            "ops-lead@example.com",
+            "(a OR b) AND 1-of-(c AND d, e OR f, 2-of-(g, h))",
+            "(a AND b AND c) OR d",
+        ] {
+            let policy: Policy = text.parse().unwrap();
+            assert_eq!(policy.to_string(), text);
+            assert_eq!(policy.to_string().parse(), Ok(policy));
+        }
+
+        for (text, position) in [
+            ("", 0),
+            ("alice AND", 9),
+            ("alice bob", 6),
+            ("(alice OR bob", 13),
+            ("2-of- alice", 6),
+            ("x-of-(a, b)", 0),
+            ("2-of-(a, b,)", 11),
+            ("alice & bob", 6),
+            ("AND", 0),
+        ] {
+            assert_eq!(
+                text.parse::<Policy>(),
+                Err(SecretSharingError::InvalidPolicy { position }),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(
+            "3-of-(a, b)".parse::<Policy>(),
+            Err(SecretSharingError::ThresholdExceedsShares)
+        );
+    }
+
+    #[test]
+    fn test_policy_depth() {
+        let nested = |n: usize| format!("{}a{}", "(".repeat(n), ")".repeat(n));
+        assert_eq!(nested(255).parse(), Ok(Policy::leaf("a")));
+        assert_eq!(
+            nested(256).parse::<Policy>(),
+            Err(SecretSharingError::InvalidPolicy { position: 255 })
+        );
+        assert_eq!(
+            nested(1_000_000).parse::<Policy>(),
+            Err(SecretSharingError::InvalidPolicy { position: 255 })
+        );
+    }
+}
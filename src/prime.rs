@@ -0,0 +1,483 @@
+// Prime fields with moduli of up to 256 bits, for sharing secrets that are
+// themselves field elements, such as elliptic curve private keys. Sharing a
+// private key as a single scalar rather than byte-wise keeps the shares usable
+// by threshold signing protocols, which operate on the scalars directly.
+//
+// Elements are held in Montgomery form as four 64-bit limbs, least
+// significant first. All arithmetic is constant-time; only the exponent in
+// `inv` is branched on, and it is the public modulus.
+
+use crate::field::Field;
+use rand::{CryptoRng, RngCore};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use zeroize::Zeroize;
+
+// Modulus describes a prime field. The constants are checked by the tests.
+pub trait Modulus: Copy + PartialEq + Debug {
+    // P is the prime modulus, least significant limb first.
+    const P: [u64; 4];
+    // INV is -P^-1 mod 2^64.
+    const INV: u64;
+    // R2 is 2^512 mod P, used to convert into Montgomery form.
+    const R2: [u64; 4];
+    // LITTLE_ENDIAN selects the byte order of encoded elements, to match the
+    // conventions of the curve the field belongs to.
+    const LITTLE_ENDIAN: bool = false;
+}
+
+// Fp is an element of the prime field described by `M`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Fp<M: Modulus>([u64; 4], PhantomData<M>);
+
+// Secp256k1Order is the order of the secp256k1 group, the field Bitcoin and
+// Ethereum private keys live in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Secp256k1Order;
+
+impl Modulus for Secp256k1Order {
+    const P: [u64; 4] = [
+        0xbfd2_5e8c_d036_4141,
+        0xbaae_dce6_af48_a03b,
+        0xffff_ffff_ffff_fffe,
+        0xffff_ffff_ffff_ffff,
+    ];
+    const INV: u64 = 0x4b0d_ff66_5588_b13f;
+    const R2: [u64; 4] = [
+        0x896c_f214_67d7_d140,
+        0x7414_96c2_0e7c_f878,
+        0xe697_f5e4_5bcd_07c6,
+        0x9d67_1cd5_81c6_9bc5,
+    ];
+}
+
+// Secp256k1Scalar is a secp256k1 private key or other scalar, encoded as 32
+// big-endian bytes.
+pub type Secp256k1Scalar = Fp<Secp256k1Order>;
+
+// Ed25519Order is the order l of the prime-order subgroup of curve25519,
+// shared by Ed25519 and Ristretto255.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ed25519Order;
+
+impl Modulus for Ed25519Order {
+    const P: [u64; 4] = [
+        0x5812_631a_5cf5_d3ed,
+        0x14de_f9de_a2f7_9cd6,
+        0x0000_0000_0000_0000,
+        0x1000_0000_0000_0000,
+    ];
+    const INV: u64 = 0xd2b5_1da3_1254_7e1b;
+    const R2: [u64; 4] = [
+        0xa406_11e3_449c_0f01,
+        0xd00e_1ba7_6885_9347,
+        0xceec_73d2_17f5_be65,
+        0x0399_411b_7c30_9a3d,
+    ];
+    const LITTLE_ENDIAN: bool = true;
+}
+
+// Ed25519Scalar is an Ed25519 or Ristretto255 scalar, encoded as 32
+// little-endian bytes as in RFC 8032.
+pub type Ed25519Scalar = Fp<Ed25519Order>;
+
+// Bls12381Order is the order r of the BLS12-381 G1 and G2 subgroups.
+#[cfg(feature = "bls12_381")]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Bls12381Order;
+
+#[cfg(feature = "bls12_381")]
+impl Modulus for Bls12381Order {
+    const P: [u64; 4] = [
+        0xffff_ffff_0000_0001,
+        0x53bd_a402_fffe_5bfe,
+        0x3339_d808_09a1_d805,
+        0x73ed_a753_299d_7d48,
+    ];
+    const INV: u64 = 0xffff_fffe_ffff_ffff;
+    const R2: [u64; 4] = [
+        0xc999_e990_f3f2_9c6d,
+        0x2b6c_edcb_8792_5c23,
+        0x05d3_1496_7254_398f,
+        0x0748_d9d9_9f59_ff11,
+    ];
+}
+
+// Bls12381Scalar is a BLS12-381 secret key or other scalar, encoded as 32
+// big-endian bytes as in the IETF BLS signature draft.
+#[cfg(feature = "bls12_381")]
+pub type Bls12381Scalar = Fp<Bls12381Order>;
+
+// adc returns a + b + carry, and the carry out.
+fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+// sbb returns a - b - borrow, and the borrow out.
+fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+    (t as u64, (t >> 127) as u64)
+}
+
+// mac returns a + b * c + carry, and the carry out.
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + (b as u128) * (c as u128) + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+// sub_borrow returns a - b, and whether it borrowed.
+fn sub_borrow(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], u64) {
+    let mut d = [0u64; 4];
+    let mut borrow = 0;
+    for i in 0..4 {
+        let (v, bo) = sbb(a[i], b[i], borrow);
+        d[i] = v;
+        borrow = bo;
+    }
+    (d, borrow)
+}
+
+impl<M: Modulus> Fp<M> {
+    // reduce returns hi * 2^256 + a, less P if it is at least P. The input
+    // must be less than 2P.
+    fn reduce(a: [u64; 4], hi: u64) -> [u64; 4] {
+        let (d, borrow) = sub_borrow(&a, &M::P);
+        let mask = 0u64.wrapping_sub(hi | (borrow ^ 1));
+        let mut r = [0u64; 4];
+        for i in 0..4 {
+            r[i] = (d[i] & mask) | (a[i] & !mask);
+        }
+        r
+    }
+
+    // montgomery_mul returns a * b / 2^256 mod P, for a * b < 2^256 * P.
+    fn montgomery_mul(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+        let mut t = [0u64; 6];
+        for ai in a.iter() {
+            let mut c = 0;
+            for j in 0..4 {
+                let (v, carry) = mac(t[j], *ai, b[j], c);
+                t[j] = v;
+                c = carry;
+            }
+            let (v, carry) = adc(t[4], c, 0);
+            t[4] = v;
+            t[5] = carry;
+
+            let m = t[0].wrapping_mul(M::INV);
+            let (_, mut c) = mac(t[0], m, M::P[0], 0);
+            for j in 1..4 {
+                let (v, carry) = mac(t[j], m, M::P[j], c);
+                t[j - 1] = v;
+                c = carry;
+            }
+            let (v, carry) = adc(t[4], c, 0);
+            t[3] = v;
+            t[4] = t[5] + carry;
+        }
+        Self::reduce([t[0], t[1], t[2], t[3]], t[4])
+    }
+
+    fn from_limbs(limbs: &[u64; 4]) -> Fp<M> {
+        Fp(Self::montgomery_mul(limbs, &M::R2), PhantomData)
+    }
+
+    fn to_limbs(self) -> [u64; 4] {
+        Self::montgomery_mul(&self.0, &[1, 0, 0, 0])
+    }
+
+    // from_bytes_mod_order interprets 32 bytes as an integer in the field's
+    // byte order and reduces it modulo P. Unlike `Field::from_bytes` it
+    // accepts every input, which makes it suitable for hash outputs.
+    pub fn from_bytes_mod_order(b: &[u8; 32]) -> Fp<M> {
+        Self::from_limbs(&Self::decode(b))
+    }
+
+    // decode reads 32 bytes in the field's byte order into limbs, without
+    // reducing them.
+    fn decode(b: &[u8]) -> [u64; 4] {
+        let mut limbs = [0u64; 4];
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let mut w = [0u8; 8];
+            if M::LITTLE_ENDIAN {
+                w.copy_from_slice(&b[8 * i..8 * i + 8]);
+                *limb = u64::from_le_bytes(w);
+            } else {
+                w.copy_from_slice(&b[24 - 8 * i..32 - 8 * i]);
+                *limb = u64::from_be_bytes(w);
+            }
+        }
+        limbs
+    }
+}
+
+impl Fp<Ed25519Order> {
+    // from_clamped derives the scalar of an Ed25519 private key from the
+    // first half of its expanded form, SHA-512(seed)[..32]. The bytes are
+    // clamped as in RFC 8032 and the result reduced modulo l; the clamped
+    // integer itself may exceed l, but as the base point has order l the
+    // reduced scalar yields the same public key and signatures. Shares, and
+    // the secret reconstructed from them, are of the reduced scalar.
+    pub fn from_clamped(b: &[u8; 32]) -> Ed25519Scalar {
+        let mut clamped = *b;
+        clamped[0] &= 248;
+        clamped[31] &= 127;
+        clamped[31] |= 64;
+        let s = Self::from_bytes_mod_order(&clamped);
+        clamped.zeroize();
+        s
+    }
+}
+
+impl<M: Modulus> Field for Fp<M> {
+    const ENCODED_LEN: usize = 32;
+
+    fn zero() -> Fp<M> {
+        Fp([0; 4], PhantomData)
+    }
+    fn one() -> Fp<M> {
+        Self::from_limbs(&[1, 0, 0, 0])
+    }
+    fn add(self, x: Fp<M>) -> Fp<M> {
+        let mut s = [0u64; 4];
+        let mut carry = 0;
+        for (s, (a, b)) in s.iter_mut().zip(self.0.iter().zip(x.0.iter())) {
+            let (v, c) = adc(*a, *b, carry);
+            *s = v;
+            carry = c;
+        }
+        Fp(Self::reduce(s, carry), PhantomData)
+    }
+    fn sub(self, x: Fp<M>) -> Fp<M> {
+        let (d, borrow) = sub_borrow(&self.0, &x.0);
+        let mask = 0u64.wrapping_sub(borrow);
+        let mut r = [0u64; 4];
+        let mut carry = 0;
+        for i in 0..4 {
+            let (v, c) = adc(d[i], M::P[i] & mask, carry);
+            r[i] = v;
+            carry = c;
+        }
+        Fp(r, PhantomData)
+    }
+    fn mul(self, x: Fp<M>) -> Fp<M> {
+        Fp(Self::montgomery_mul(&self.0, &x.0), PhantomData)
+    }
+    // inv computes self^(P-2) by square-and-multiply over the bits of the
+    // public exponent.
+    fn inv(self) -> Fp<M> {
+        let (e, _) = sub_borrow(&M::P, &[2, 0, 0, 0]);
+        let mut r = Self::one();
+        for i in (0..256).rev() {
+            r = r.mul(r);
+            if (e[i / 64] >> (i % 64)) & 1 == 1 {
+                r = r.mul(self);
+            }
+        }
+        r
+    }
+    // random draws uniformly by rejection sampling: candidates are masked to
+    // the bit length of P, and redrawn if they are not less than P.
+    fn random<R: CryptoRng + RngCore>(rng: &mut R) -> Fp<M> {
+        let mask = u64::MAX >> M::P[3].leading_zeros();
+        let mut b = [0u8; 32];
+        loop {
+            rng.fill_bytes(&mut b);
+            let mut limbs = Self::decode(&b);
+            limbs[3] &= mask;
+            let (_, borrow) = sub_borrow(&limbs, &M::P);
+            if borrow == 1 {
+                b.zeroize();
+                return Self::from_limbs(&limbs);
+            }
+        }
+    }
+    fn from_u64(v: u64) -> Option<Fp<M>> {
+        Some(Self::from_limbs(&[v, 0, 0, 0]))
+    }
+    fn to_bytes(&self, out: &mut [u8]) {
+        let limbs = self.to_limbs();
+        for (i, limb) in limbs.iter().enumerate() {
+            if M::LITTLE_ENDIAN {
+                out[8 * i..8 * i + 8].copy_from_slice(&limb.to_le_bytes());
+            } else {
+                out[24 - 8 * i..32 - 8 * i].copy_from_slice(&limb.to_be_bytes());
+            }
+        }
+    }
+    fn from_bytes(b: &[u8]) -> Option<Fp<M>> {
+        if b.len() != 32 {
+            return None;
+        }
+        let limbs = Self::decode(b);
+        let (_, borrow) = sub_borrow(&limbs, &M::P);
+        if borrow == 0 {
+            return None;
+        }
+        Some(Self::from_limbs(&limbs))
+    }
+}
+
+impl<M: Modulus> Zeroize for Fp<M> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::{combine, split};
+
+    fn bytes(hex: &str) -> [u8; 32] {
+        let mut b = [0u8; 32];
+        for (i, v) in b.iter_mut().enumerate() {
+            *v = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        b
+    }
+
+    fn scalar<M: Modulus>(hex: &str) -> Fp<M> {
+        Fp::<M>::from_bytes(&bytes(hex)).unwrap()
+    }
+
+    // check_constants verifies INV and R2 against P.
+    fn check_constants<M: Modulus>() {
+        assert_eq!(M::P[0].wrapping_mul(M::INV), u64::MAX);
+        // add is plain modular addition on the limbs, so doubling 1 512 times
+        // yields 2^512 mod P.
+        let mut r2 = Fp::<M>([1, 0, 0, 0], PhantomData);
+        for _ in 0..512 {
+            r2 = r2.add(r2);
+        }
+        assert_eq!(r2.0, M::R2);
+    }
+
+    #[test]
+    fn test_secp256k1_constants() {
+        check_constants::<Secp256k1Order>();
+    }
+    #[test]
+    fn test_secp256k1_arithmetic() {
+        let a: Secp256k1Scalar =
+            scalar("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+        let b: Secp256k1Scalar =
+            scalar("5a1b2c3d4e5f60718293a4b5c6d7e8f90112233445566778899aabbccddeeff0");
+        assert_eq!(
+            a.mul(b),
+            scalar("6bf9707b868d566cf5cf5d0495d6fce3bd3c331daa64d2d98f8289d925935c60")
+        );
+        assert_eq!(
+            a.inv(),
+            scalar("3a6a20d25567276e640946e6e40550724b73eedbcfa16331fa0c57295958f570")
+        );
+        assert_eq!(a.add(b).sub(b), a);
+        assert_eq!(a.sub(a), Secp256k1Scalar::zero());
+
+        let minus_one = Secp256k1Scalar::zero().sub(Secp256k1Scalar::one());
+        assert_eq!(minus_one.mul(minus_one), Secp256k1Scalar::one());
+        assert_eq!(
+            minus_one.add(Secp256k1Scalar::one()),
+            Secp256k1Scalar::zero()
+        );
+    }
+    #[test]
+    fn test_secp256k1_encoding() {
+        let a: Secp256k1Scalar =
+            scalar("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+        let mut out = [0u8; 32];
+        a.to_bytes(&mut out);
+        assert_eq!(Secp256k1Scalar::from_bytes(&out), Some(a));
+
+        // the order itself, and anything above it, is not canonical.
+        let mut n = [0u8; 32];
+        Secp256k1Scalar::zero()
+            .sub(Secp256k1Scalar::one())
+            .to_bytes(&mut n);
+        n[31] += 1;
+        assert_eq!(Secp256k1Scalar::from_bytes(&n), None);
+        assert_eq!(
+            Secp256k1Scalar::from_bytes_mod_order(&n),
+            Secp256k1Scalar::zero()
+        );
+        assert_eq!(Secp256k1Scalar::from_bytes(&[0xff; 32]), None);
+        assert_eq!(Secp256k1Scalar::from_bytes(&out[1..]), None);
+    }
+    #[test]
+    fn test_secp256k1_split_combine() {
+        let mut rng = rand::thread_rng();
+        let key = Secp256k1Scalar::random(&mut rng);
+        let shares = split(3, 5, &[key], &mut rng).unwrap();
+        assert_eq!(combine(&shares[2..]).unwrap(), vec![key]);
+        assert_ne!(combine(&shares[..2]).unwrap(), vec![key]);
+    }
+    #[test]
+    fn test_ed25519_constants() {
+        check_constants::<Ed25519Order>();
+    }
+    #[test]
+    fn test_ed25519_arithmetic() {
+        let a: Ed25519Scalar =
+            scalar("057889b6eebcad5a06414d936b0ddd5392d6b16757215c6b1675ba45d8a9af09");
+        let b: Ed25519Scalar =
+            scalar("4fcc11fd38bc3ed148578016db41b798f8e8d7c6b5a4938271605f4e3d2c1b0a");
+        assert_eq!(
+            a.mul(b),
+            scalar("ea236b7576568f1a137caf2dca6a40a99cb69fd5d2e9a0182895e6226f373500")
+        );
+        assert_eq!(
+            a.inv(),
+            scalar("ca77d592f07e692bc41d52d9901b4a9974a2d98c35f8a8e116ea6ae7e859db0d")
+        );
+
+        // l encodes little-endian, and is rejected.
+        let l = bytes("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010");
+        assert_eq!(Ed25519Scalar::from_bytes(&l), None);
+        assert_eq!(
+            Ed25519Scalar::from_bytes_mod_order(&l),
+            Ed25519Scalar::zero()
+        );
+    }
+    #[test]
+    fn test_ed25519_clamped() {
+        // the first half of SHA-512 of the RFC 8032 test 1 secret key, whose
+        // clamped scalar exceeds l.
+        let h = bytes("357c83864f2833cb427a2ef1c00a013cfdff2768d980c0a3a520f006904de90f");
+        let s = Ed25519Scalar::from_clamped(&h);
+        assert_eq!(
+            s,
+            scalar("7c2cac12e69be96ae9065065462385e8fcff2768d980c0a3a520f006904de90f")
+        );
+
+        let mut rng = rand::thread_rng();
+        let shares = split(2, 3, &[s], &mut rng).unwrap();
+        assert_eq!(combine(&shares[1..]).unwrap(), vec![s]);
+    }
+    #[cfg(feature = "bls12_381")]
+    #[test]
+    fn test_bls12_381_arithmetic() {
+        check_constants::<Bls12381Order>();
+
+        let a: Bls12381Scalar =
+            scalar("55c202851c1cf7ce3822494f5e0ffe8dfa931fd836ea3f137b8a622c120f6720");
+        let b: Bls12381Scalar =
+            scalar("5a1b2c3d4e5f60718293a4b5c6d7e8f90112233445566778899aabbccddeeff0");
+        assert_eq!(
+            a.mul(b),
+            scalar("32d1c8e017cf092ce32bab58a1e0bb9b84681267bc68a84463da7e893e5da7e2")
+        );
+        assert_eq!(
+            a.inv(),
+            scalar("1a68a6ecf0b3d54dd935c1ecf414ad2d40fd69bc04c4e8723869058577fd6ebf")
+        );
+
+        let r = bytes("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001");
+        assert_eq!(Bls12381Scalar::from_bytes(&r), None);
+
+        let mut rng = rand::thread_rng();
+        let key = Bls12381Scalar::random(&mut rng);
+        let shares = split(3, 4, &[key], &mut rng).unwrap();
+        assert_eq!(combine(&shares[1..]).unwrap(), vec![key]);
+    }
+}
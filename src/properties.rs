@@ -0,0 +1,101 @@
+// Property tests: random (t, n, secret) combinations, drawn from a seeded RNG
+// so that a failure reproduces, checked against the properties every split
+// must have whatever its parameters. proptest isn't a dependency, so cases
+// are generated by hand and a failing case is reported by its number rather
+// than shrunk.
+
+use crate::{construct_shares_with_rng, reconstruct, Share};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+// CASES is the number of random cases checked per property.
+const CASES: usize = 256;
+
+// params returns a random threshold, share count and secret.
+fn params(rng: &mut ChaCha20Rng) -> (u8, u8, Vec<u8>) {
+    let n = rng.gen_range(1, 32);
+    let t = rng.gen_range(1, n + 1);
+    let len = rng.gen_range(0, 64);
+    let secret = (0..len).map(|_| rng.gen()).collect();
+    (t, n, secret)
+}
+
+// without_metadata strips the (t, n) recorded in `shares`, so that too few of
+// them are interpolated rather than rejected.
+fn without_metadata(shares: &[Share]) -> Vec<Share> {
+    shares
+        .iter()
+        .map(|share| Share::new(share.index(), share.as_bytes()))
+        .collect()
+}
+
+#[test]
+fn prop_any_t_shares_reconstruct() {
+    let mut rng = ChaCha20Rng::from_seed([1; 32]);
+    for case in 0..CASES {
+        let (t, n, secret) = params(&mut rng);
+        let mut shares = construct_shares_with_rng(t, n, &secret, &mut rng).unwrap();
+        shares.shuffle(&mut rng);
+        let k = rng.gen_range(t, n + 1) as usize;
+        assert_eq!(
+            reconstruct(&shares[..k]).unwrap(),
+            secret,
+            "case {}: t={} n={} k={}",
+            case,
+            t,
+            n,
+            k
+        );
+    }
+}
+
+#[test]
+fn prop_share_encoding_roundtrips() {
+    let mut rng = ChaCha20Rng::from_seed([2; 32]);
+    for case in 0..CASES {
+        let (t, n, secret) = params(&mut rng);
+        for share in construct_shares_with_rng(t, n, &secret, &mut rng).unwrap() {
+            let encoded = share.to_bytes();
+            let decoded = Share::from_bytes(&encoded).unwrap();
+            assert_eq!(decoded.index(), share.index(), "case {}", case);
+            assert_eq!(decoded.as_bytes(), share.as_bytes(), "case {}", case);
+            assert_eq!(decoded.threshold(), Some(t), "case {}", case);
+            assert_eq!(decoded.share_count(), Some(n), "case {}", case);
+            assert_eq!(decoded.to_bytes(), encoded, "case {}", case);
+        }
+    }
+}
+
+// prop_fewer_than_t_shares_are_uniform checks that t-1 shares say nothing
+// about the secret: the value they interpolate at 0 must be uniformly
+// distributed, whatever the secret. A polynomial of too low a degree would
+// leak the secret here, by interpolating it from t-1 points.
+#[test]
+fn prop_fewer_than_t_shares_are_uniform() {
+    const SAMPLES: usize = 256 * 64;
+
+    let mut rng = ChaCha20Rng::from_seed([3; 32]);
+    for &(t, n) in &[(2, 2), (3, 5), (5, 9), (16, 31)] {
+        let secret = [0x42u8; 16];
+        let mut counts = [0usize; 256];
+        for _ in 0..SAMPLES / secret.len() {
+            let mut shares = construct_shares_with_rng(t, n, &secret, &mut rng).unwrap();
+            shares.shuffle(&mut rng);
+            let shares = without_metadata(&shares[..t as usize - 1]);
+            let guess = reconstruct(&shares).unwrap();
+            for b in guess {
+                counts[b as usize] += 1;
+            }
+        }
+
+        // chi-squared with 255 degrees of freedom: the 1e-6 critical value is
+        // about 372.
+        let expected = (SAMPLES / 256) as f64;
+        let chi2: f64 = counts
+            .iter()
+            .map(|&c| (c as f64 - expected).powi(2) / expected)
+            .sum();
+        assert!(chi2 < 372.0, "t={} n={}: chi2 = {}", t, n, chi2);
+    }
+}
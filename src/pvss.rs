@@ -0,0 +1,519 @@
+// Publicly verifiable secret sharing, after Schoenmakers ("A Simple Publicly
+// Verifiable Secret Sharing Scheme", CRYPTO '99), in the prime-order
+// subgroup of edwards25519. Every participant i has a key pair (y_i, Y_i =
+// [y_i]B). The dealer picks a random polynomial p of degree t-1, publishes
+// commitments C_j = [a_j]H to its coefficients (H is the second generator of
+// vss.rs), and encrypts participant i's share as E_i = [p(i)]Y_i, with a
+// proof that log_H X_i = log_Y_i E_i for X_i = sum_j [i^j]C_j = [p(i)]H.
+// Anyone, not just the participants, can check from the published dealing
+// that every participant received a share of the same polynomial.
+//
+// To reconstruct, participant i decrypts S_i = [1/y_i]E_i = [p(i)]B and
+// publishes it with a proof that log_B Y_i = log_S_i E_i, so anyone can check
+// that it was decrypted correctly. Any t of them are interpolated in the
+// exponent to [p(0)]B, and the shared secret is SHA-512 of its encoding,
+// truncated to 32 bytes: PVSS shares a random key chosen by the dealing, not
+// a value chosen by the dealer, which can be used to encrypt one.
+//
+// The proofs are non-interactive Chaum-Pedersen proofs (c, r): for a random
+// w, c hashes the four points and [w]g1, [w]g2, and r = w - c*alpha. The
+// dealing and decrypted shares travel in the message format of message.rs.
+// A dealing's fixed fields are its threshold and share count, and its
+// payload the commitments followed by each share's E_i, c and r; a decrypted
+// share's fixed field is the participant number, and its payload S_i, c and
+// r.
+
+use crate::ed25519::{hash_scalar, Point};
+use crate::field::{lagrange_coefficients, Field};
+use crate::message::{self, PVSS_DEALING, PVSS_SHARE};
+use crate::prime::Ed25519Scalar;
+use crate::sha512;
+use crate::vss::{
+    base_mul, check_parameters, mul, pedersen_generator, random_polynomial, x_coordinate,
+    Commitments,
+};
+use crate::SecretSharingError;
+use rand::{CryptoRng, RngCore};
+use std::convert::TryInto;
+use std::fmt;
+use zeroize::Zeroize;
+
+const POINT_LEN: usize = 32;
+const PROOF_LEN: usize = 64;
+const DLEQ_DOMAIN: &[u8] = b"rust-shamir pvss dleq";
+const SECRET_DOMAIN: &[u8] = b"rust-shamir pvss secret";
+
+// PvssKey is a participant's decryption key. It is wiped when dropped.
+#[derive(Clone)]
+pub struct PvssKey(Ed25519Scalar);
+
+// PvssPublicKey is the key dealers encrypt a participant's share to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PvssPublicKey([u8; POINT_LEN]);
+
+// Dleq is a proof that two pairs of points have the same discrete logarithm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Dleq {
+    c: Ed25519Scalar,
+    r: Ed25519Scalar,
+}
+
+// PvssDealing is a dealer's published commitments and encrypted shares.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PvssDealing {
+    commitments: Commitments,
+    shares: Vec<([u8; POINT_LEN], Dleq)>,
+}
+
+// PvssShare is a participant's decrypted share, published for
+// reconstruction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PvssShare {
+    index: u8,
+    value: [u8; POINT_LEN],
+    proof: Dleq,
+}
+
+impl Drop for PvssKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for PvssKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("PvssKey").field(&self.public_key()).finish()
+    }
+}
+
+impl PvssKey {
+    // generate returns a new random key.
+    pub fn generate() -> PvssKey {
+        PvssKey::generate_with_rng(&mut crate::default_rng())
+    }
+
+    // generate_with_rng is like generate, but draws the key from `rng`.
+    pub fn generate_with_rng<R: CryptoRng + RngCore>(rng: &mut R) -> PvssKey {
+        loop {
+            let y = Ed25519Scalar::random(rng);
+            if y != Ed25519Scalar::zero() {
+                return PvssKey(y);
+            }
+        }
+    }
+
+    pub fn public_key(&self) -> PvssPublicKey {
+        PvssPublicKey(base_mul(&self.0).compress())
+    }
+}
+
+impl PvssPublicKey {
+    // from_bytes returns the public key encoded in `b`, or ShareMalformed if
+    // it isn't a point of the prime-order subgroup.
+    pub fn from_bytes(b: &[u8; POINT_LEN]) -> Result<PvssPublicKey, SecretSharingError> {
+        point(b)?;
+        Ok(PvssPublicKey(*b))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; POINT_LEN] {
+        &self.0
+    }
+
+    fn point(&self) -> Point {
+        Point::decompress(&self.0).unwrap()
+    }
+}
+
+// point decodes a point of the prime-order subgroup.
+fn point(b: &[u8]) -> Result<Point, SecretSharingError> {
+    b.try_into()
+        .ok()
+        .and_then(Point::decompress)
+        .filter(Point::is_torsion_free)
+        .ok_or(SecretSharingError::ShareMalformed)
+}
+
+// scalar decodes a canonically encoded scalar.
+fn scalar(b: &[u8]) -> Result<Ed25519Scalar, SecretSharingError> {
+    Ed25519Scalar::from_bytes(b).ok_or(SecretSharingError::ShareMalformed)
+}
+
+impl Dleq {
+    fn challenge(points: &[&Point]) -> Ed25519Scalar {
+        let encoded: Vec<[u8; POINT_LEN]> = points.iter().map(|p| p.compress()).collect();
+        let mut parts: Vec<&[u8]> = vec![DLEQ_DOMAIN];
+        parts.extend(encoded.iter().map(|p| &p[..]));
+        Ed25519Scalar::from_bytes(&hash_scalar(&parts)).unwrap()
+    }
+
+    // prove proves that h1 = [alpha]g1 and h2 = [alpha]g2.
+    fn prove<R: CryptoRng + RngCore>(
+        g1: &Point,
+        h1: &Point,
+        g2: &Point,
+        h2: &Point,
+        alpha: &Ed25519Scalar,
+        rng: &mut R,
+    ) -> Dleq {
+        let mut w = Ed25519Scalar::random(rng);
+        let c = Dleq::challenge(&[g1, h1, g2, h2, &mul(g1, &w), &mul(g2, &w)]);
+        let r = w.sub(c.mul(*alpha));
+        w.zeroize();
+        Dleq { c, r }
+    }
+
+    // verify checks that the proof shows log_g1 h1 = log_g2 h2.
+    fn verify(&self, g1: &Point, h1: &Point, g2: &Point, h2: &Point) -> bool {
+        let a1 = mul(g1, &self.r).add(&mul(h1, &self.c));
+        let a2 = mul(g2, &self.r).add(&mul(h2, &self.c));
+        Dleq::challenge(&[g1, h1, g2, h2, &a1, &a2]) == self.c
+    }
+
+    fn to_bytes(self, out: &mut Vec<u8>) {
+        let mut b = [0u8; 32];
+        for s in [self.c, self.r] {
+            s.to_bytes(&mut b);
+            out.extend_from_slice(&b);
+        }
+    }
+
+    fn from_bytes(b: &[u8]) -> Result<Dleq, SecretSharingError> {
+        Ok(Dleq {
+            c: scalar(&b[..32])?,
+            r: scalar(&b[32..PROOF_LEN])?,
+        })
+    }
+}
+
+// deal_pvss deals a random secret to the holders of `keys`, any `t` of whom
+// can reconstruct it. It returns the dealing to publish and the secret, as
+// reconstruct_pvss will return it; participant i is the holder of keys[i-1].
+pub fn deal_pvss(
+    t: u8,
+    keys: &[PvssPublicKey],
+) -> Result<(PvssDealing, Vec<u8>), SecretSharingError> {
+    deal_pvss_with_rng(t, keys, &mut crate::default_rng())
+}
+
+// deal_pvss_with_rng is like deal_pvss, but draws the secret, polynomial and
+// proofs from `rng`.
+pub fn deal_pvss_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    keys: &[PvssPublicKey],
+    rng: &mut R,
+) -> Result<(PvssDealing, Vec<u8>), SecretSharingError> {
+    if keys.len() > u8::MAX as usize {
+        return Err(SecretSharingError::TooManyShares);
+    }
+    check_parameters(t, keys.len() as u8)?;
+
+    let h = pedersen_generator();
+    let mut secret = Ed25519Scalar::random(rng);
+    let mut coeff = random_polynomial(t, secret, rng);
+    let commitments = Commitments(coeff.iter().map(|a| mul(&h, a)).collect());
+    let shares = keys
+        .iter()
+        .zip(1..)
+        .map(|(key, i)| {
+            let x = x_coordinate(i);
+            let mut y = crate::field::evaluate(&coeff, x);
+            let key = key.point();
+            let encrypted = mul(&key, &y);
+            let proof = Dleq::prove(&h, &commitments.expected(x), &key, &encrypted, &y, rng);
+            y.zeroize();
+            (encrypted.compress(), proof)
+        })
+        .collect();
+    let derived = derive_secret(&base_mul(&secret));
+    crate::field::wipe(&mut coeff);
+    secret.zeroize();
+
+    Ok((
+        PvssDealing {
+            commitments,
+            shares,
+        },
+        derived,
+    ))
+}
+
+// derive_secret returns the secret shared by a dealing whose constant term
+// in the exponent is `s`.
+fn derive_secret(s: &Point) -> Vec<u8> {
+    let mut parts = SECRET_DOMAIN.to_vec();
+    parts.extend_from_slice(&s.compress());
+    let mut digest = sha512::digest(&parts);
+    let secret = digest[..32].to_vec();
+    digest.zeroize();
+    secret
+}
+
+impl PvssDealing {
+    pub fn threshold(&self) -> u8 {
+        self.commitments.threshold()
+    }
+
+    pub fn share_count(&self) -> u8 {
+        self.shares.len() as u8
+    }
+
+    // verify checks the dealing against the participants' `keys`, in the
+    // order they were dealt to. It returns ShareTampered, naming the first
+    // participant whose encrypted share isn't consistent with the
+    // commitments, and UnexpectedMessage if the number of keys differs.
+    pub fn verify(&self, keys: &[PvssPublicKey]) -> Result<(), SecretSharingError> {
+        if keys.len() != self.shares.len() {
+            return Err(SecretSharingError::UnexpectedMessage);
+        }
+        let h = pedersen_generator();
+        for ((key, (encrypted, proof)), i) in keys.iter().zip(self.shares.iter()).zip(1..) {
+            let x = x_coordinate(i);
+            let encrypted = Point::decompress(encrypted).unwrap();
+            if !proof.verify(&h, &self.commitments.expected(x), &key.point(), &encrypted) {
+                return Err(SecretSharingError::ShareTampered { index: i });
+            }
+        }
+        Ok(())
+    }
+
+    // decrypt decrypts the share of participant `index`, who holds `key`,
+    // for publishing. The dealing should have been verified first.
+    pub fn decrypt(&self, index: u8, key: &PvssKey) -> Result<PvssShare, SecretSharingError> {
+        self.decrypt_with_rng(index, key, &mut crate::default_rng())
+    }
+
+    // decrypt_with_rng is like decrypt, but draws the proof from `rng`.
+    pub fn decrypt_with_rng<R: CryptoRng + RngCore>(
+        &self,
+        index: u8,
+        key: &PvssKey,
+        rng: &mut R,
+    ) -> Result<PvssShare, SecretSharingError> {
+        let encrypted = match self.shares.get((index as usize).wrapping_sub(1)) {
+            Some((encrypted, _)) => Point::decompress(encrypted).unwrap(),
+            None => return Err(SecretSharingError::InvalidShareIndex),
+        };
+        let mut inverse = key.0.inv();
+        let value = mul(&encrypted, &inverse);
+        inverse.zeroize();
+        let public = base_mul(&key.0);
+        let proof = Dleq::prove(&Point::base(), &public, &value, &encrypted, &key.0, rng);
+        Ok(PvssShare {
+            index,
+            value: value.compress(),
+            proof,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut payload = self.commitments.to_bytes();
+        for (encrypted, proof) in self.shares.iter() {
+            payload.extend_from_slice(encrypted);
+            proof.to_bytes(&mut payload);
+        }
+        let fields = [self.threshold(), self.share_count()];
+        message::encode(PVSS_DEALING, &fields, &payload)
+    }
+
+    // from_bytes decodes a dealing produced by to_bytes. It checks that the
+    // dealing is well formed, not that it is consistent; see verify.
+    pub fn from_bytes(data: &[u8]) -> Result<PvssDealing, SecretSharingError> {
+        let (fields, payload) = message::decode(PVSS_DEALING, 2, data)?;
+        let (t, n) = (fields[0] as usize, fields[1] as usize);
+        let share_len = POINT_LEN + PROOF_LEN;
+        if t == 0 || t > n || payload.len() != t * POINT_LEN + n * share_len {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let (commitments, shares) = payload.split_at(t * POINT_LEN);
+        let shares = shares
+            .chunks_exact(share_len)
+            .map(|s| {
+                point(&s[..POINT_LEN])?;
+                Ok((
+                    s[..POINT_LEN].try_into().unwrap(),
+                    Dleq::from_bytes(&s[POINT_LEN..])?,
+                ))
+            })
+            .collect::<Result<_, SecretSharingError>>()?;
+        Ok(PvssDealing {
+            commitments: Commitments::from_bytes(commitments)?,
+            shares,
+        })
+    }
+}
+
+impl PvssShare {
+    // index returns the number of the participant the share belongs to.
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    // verify checks that the share was correctly decrypted from `dealing`
+    // by the holder of `key`, returning ShareTampered if not.
+    pub fn verify(
+        &self,
+        dealing: &PvssDealing,
+        key: &PvssPublicKey,
+    ) -> Result<(), SecretSharingError> {
+        let encrypted = match dealing.shares.get((self.index as usize).wrapping_sub(1)) {
+            Some((encrypted, _)) => Point::decompress(encrypted).unwrap(),
+            None => return Err(SecretSharingError::InvalidShareIndex),
+        };
+        let value = Point::decompress(&self.value).unwrap();
+        if !self
+            .proof
+            .verify(&Point::base(), &key.point(), &value, &encrypted)
+        {
+            return Err(SecretSharingError::ShareTampered { index: self.index });
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut payload = self.value.to_vec();
+        self.proof.to_bytes(&mut payload);
+        message::encode(PVSS_SHARE, &[self.index], &payload)
+    }
+
+    // from_bytes decodes a share produced by to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<PvssShare, SecretSharingError> {
+        let (fields, payload) = message::decode(PVSS_SHARE, 1, data)?;
+        if fields[0] == 0 || payload.len() != POINT_LEN + PROOF_LEN {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        point(&payload[..POINT_LEN])?;
+        Ok(PvssShare {
+            index: fields[0],
+            value: payload[..POINT_LEN].try_into().unwrap(),
+            proof: Dleq::from_bytes(&payload[POINT_LEN..])?,
+        })
+    }
+}
+
+// reconstruct_pvss checks every one of the decrypted `shares` of `dealing`
+// against the participants' `keys`, as PvssShare::verify does, and
+// reconstructs the secret from them. It returns NotEnoughShares for fewer
+// than the dealing's threshold, and DuplicateShare for two shares of the
+// same participant.
+pub fn reconstruct_pvss(
+    dealing: &PvssDealing,
+    keys: &[PvssPublicKey],
+    shares: &[PvssShare],
+) -> Result<Vec<u8>, SecretSharingError> {
+    let t = dealing.threshold() as usize;
+    if shares.len() < t {
+        return Err(SecretSharingError::NotEnoughShares {
+            have: shares.len(),
+            need: t,
+        });
+    }
+    for (i, share) in shares.iter().enumerate() {
+        if shares[..i].iter().any(|s| s.index == share.index) {
+            return Err(SecretSharingError::DuplicateShare {
+                x: share.index as u16,
+            });
+        }
+        let key = match keys.get((share.index as usize).wrapping_sub(1)) {
+            Some(key) => key,
+            None => return Err(SecretSharingError::InvalidShareIndex),
+        };
+        share.verify(dealing, key)?;
+    }
+
+    let xs: Vec<Ed25519Scalar> = shares.iter().map(|s| x_coordinate(s.index)).collect();
+    let basis = lagrange_coefficients(&xs, Ed25519Scalar::zero());
+    let secret = shares
+        .iter()
+        .zip(basis.iter())
+        .fold(Point::IDENTITY, |acc, (share, l)| {
+            acc.add(&mul(&Point::decompress(&share.value).unwrap(), l))
+        });
+    Ok(derive_secret(&secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn participants(n: usize) -> (Vec<PvssKey>, Vec<PvssPublicKey>) {
+        let keys: Vec<PvssKey> = (0..n).map(|_| PvssKey::generate()).collect();
+        let public = keys.iter().map(PvssKey::public_key).collect();
+        (keys, public)
+    }
+
+    #[test]
+    fn test_pvss() {
+        let (keys, public) = participants(5);
+        let (dealing, secret) = deal_pvss(3, &public).unwrap();
+        assert_eq!(secret.len(), 32);
+        assert_eq!((dealing.threshold(), dealing.share_count()), (3, 5));
+
+        // anyone can check the published dealing.
+        let dealing = PvssDealing::from_bytes(&dealing.to_bytes()).unwrap();
+        dealing.verify(&public).unwrap();
+
+        let shares: Vec<PvssShare> = (1..=5)
+            .map(|i| {
+                let share = dealing.decrypt(i, &keys[i as usize - 1]).unwrap();
+                PvssShare::from_bytes(&share.to_bytes()).unwrap()
+            })
+            .collect();
+        assert_eq!(
+            reconstruct_pvss(&dealing, &public, &shares[..3]).unwrap(),
+            secret
+        );
+        assert_eq!(
+            reconstruct_pvss(&dealing, &public, &shares[2..]).unwrap(),
+            secret
+        );
+        assert_eq!(
+            reconstruct_pvss(&dealing, &public, &shares[3..]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+        let repeated = [shares[0].clone(), shares[1].clone(), shares[0].clone()];
+        assert_eq!(
+            reconstruct_pvss(&dealing, &public, &repeated).err(),
+            Some(SecretSharingError::DuplicateShare { x: 1 })
+        );
+    }
+    #[test]
+    fn test_pvss_rejects_cheating() {
+        let (keys, public) = participants(3);
+        let (dealing, _) = deal_pvss(2, &public).unwrap();
+
+        // a dealer swapping in a share of another dealing.
+        let (other, _) = deal_pvss(2, &public).unwrap();
+        let mut cheat = dealing.clone();
+        cheat.shares[1] = other.shares[1];
+        assert_eq!(
+            cheat.verify(&public).err(),
+            Some(SecretSharingError::ShareTampered { index: 2 })
+        );
+        // a dealing checked against the wrong keys.
+        let swapped = [public[1], public[0], public[2]];
+        assert_eq!(
+            dealing.verify(&swapped).err(),
+            Some(SecretSharingError::ShareTampered { index: 1 })
+        );
+
+        // a participant publishing a share decrypted with the wrong key.
+        let good = dealing.decrypt(1, &keys[0]).unwrap();
+        let bad = dealing.decrypt(2, &keys[0]).unwrap();
+        assert_eq!(
+            reconstruct_pvss(&dealing, &public, &[good, bad]).err(),
+            Some(SecretSharingError::ShareTampered { index: 2 })
+        );
+
+        let mut bytes = dealing.decrypt(3, &keys[2]).unwrap().to_bytes();
+        let len = bytes.len();
+        bytes[len - 5] ^= 1;
+        assert_eq!(
+            PvssShare::from_bytes(&bytes).err(),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+        assert_eq!(
+            PvssPublicKey::from_bytes(&[0; 32]).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+    }
+}
@@ -0,0 +1,719 @@
+// QR code rendering of a Share's URI (see uri.rs), for paper backups. The
+// encoder follows ISO/IEC 18004: byte mode, error correction level M (about
+// 15% of the symbol can be damaged), the smallest version from 1 to 40 that
+// fits, and the mask with the lowest penalty score. It only encodes; any QR
+// scanner reads the codes back, and Share::from_uri parses what it reads.
+//
+// Codes are rendered as SVG, or as a 1-bit grayscale PNG with uncompressed
+// deflate blocks, so neither needs an image or compression library. Both
+// include the 4-module quiet zone scanners require.
+
+use crate::{SecretSharingError, Share};
+use zeroize::{Zeroize, Zeroizing};
+
+// ECC_CODEWORDS and BLOCKS give, for versions 1 to 40 at level M, the error
+// correction codewords per block and the number of blocks.
+const ECC_CODEWORDS: [usize; 40] = [
+    10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+const BLOCKS: [usize; 40] = [
+    1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23, 25,
+    26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+// FORMAT_M is level M's 2-bit indicator in the format information.
+const FORMAT_M: u32 = 0;
+const QUIET_ZONE: usize = 4;
+
+// QrCode is a square grid of modules, true for dark. The modules encode the
+// share, so they are wiped when dropped.
+#[derive(Zeroize)]
+#[zeroize(drop)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+// raw_modules returns the number of modules of a version-`ver` symbol that
+// hold data and error correction codewords.
+fn raw_modules(ver: usize) -> usize {
+    let mut n = (16 * ver + 128) * ver + 64;
+    if ver >= 2 {
+        let align = ver / 7 + 2;
+        n -= (25 * align - 10) * align - 55;
+        if ver >= 7 {
+            n -= 36;
+        }
+    }
+    n
+}
+
+fn data_codewords(ver: usize) -> usize {
+    raw_modules(ver) / 8 - ECC_CODEWORDS[ver - 1] * BLOCKS[ver - 1]
+}
+
+// gf_mul multiplies in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1, the field
+// of QR codes' Reed-Solomon codes.
+fn gf_mul(a: u8, b: u8) -> u8 {
+    let mut z: u16 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11d);
+        z ^= ((b as u16 >> i) & 1) * a as u16;
+    }
+    z as u8
+}
+
+// rs_divisor returns the Reed-Solomon generator polynomial of `degree`,
+// without its leading coefficient, highest power first.
+fn rs_divisor(degree: usize) -> Vec<u8> {
+    let mut out = vec![0u8; degree];
+    out[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            out[j] = gf_mul(out[j], root);
+            if j + 1 < degree {
+                out[j] ^= out[j + 1];
+            }
+        }
+        root = gf_mul(root, 2);
+    }
+    out
+}
+
+// rs_remainder returns the error correction codewords of `data`.
+fn rs_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; divisor.len()];
+    for &b in data {
+        let factor = b ^ out.remove(0);
+        out.push(0);
+        for (o, &d) in out.iter_mut().zip(divisor) {
+            *o ^= gf_mul(d, factor);
+        }
+    }
+    out
+}
+
+// alignment_positions returns the row and column coordinates of the centres
+// of the alignment patterns of version `ver`.
+fn alignment_positions(ver: usize) -> Vec<usize> {
+    if ver == 1 {
+        return Vec::new();
+    }
+    let n = ver / 7 + 2;
+    let step = if ver == 32 {
+        26
+    } else {
+        (ver * 4 + n * 2 + 1) / (n * 2 - 2) * 2
+    };
+    let size = 4 * ver + 17;
+    let mut out: Vec<usize> = (0..n - 1).map(|i| size - 7 - i * step).collect();
+    out.push(6);
+    out.reverse();
+    out
+}
+
+// format_bits returns the 15 bits of format information for `mask`.
+fn format_bits(mask: u32) -> u32 {
+    let data = FORMAT_M << 3 | mask;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    (data << 10 | rem) ^ 0x5412
+}
+
+// version_bits returns the 18 bits of version information for `ver`.
+fn version_bits(ver: usize) -> u32 {
+    let mut rem = ver as u32;
+    for _ in 0..12 {
+        rem = (rem << 1) ^ ((rem >> 11) * 0x1f25);
+    }
+    (ver as u32) << 12 | rem
+}
+
+// pad_data returns the data codewords of a version-`ver` symbol holding
+// `data` in byte mode.
+fn pad_data(data: &[u8], ver: usize) -> Zeroizing<Vec<u8>> {
+    let capacity = data_codewords(ver);
+    let count_bits = if ver <= 9 { 8 } else { 16 };
+
+    // mode indicator 0100 (byte mode), the length, the data, a terminator of
+    // up to 4 zero bits, then padding to a byte and alternating pad bytes.
+    let mut bits: Zeroizing<Vec<bool>> = Zeroizing::new(Vec::with_capacity(capacity * 8));
+    let mut push = |v: u32, n: usize| (0..n).rev().for_each(|i| bits.push(v >> i & 1 == 1));
+    push(0b0100, 4);
+    push(data.len() as u32, count_bits);
+    for &b in data {
+        push(b as u32, 8);
+    }
+    let terminator = (capacity * 8 - bits.len()).min(4);
+    bits.extend(std::iter::repeat_n(false, terminator));
+    while !bits.len().is_multiple_of(8) {
+        bits.push(false);
+    }
+    let mut bytes: Zeroizing<Vec<u8>> = Zeroizing::new(
+        bits.chunks(8)
+            .map(|c| c.iter().fold(0u8, |b, &bit| b << 1 | bit as u8))
+            .collect(),
+    );
+    for pad in [0xec, 0x11].iter().cycle() {
+        if bytes.len() >= capacity {
+            break;
+        }
+        bytes.push(*pad);
+    }
+    bytes
+}
+
+// codewords returns the data codewords `bytes` of a version-`ver` symbol,
+// with their error correction codewords, in the order they are placed.
+fn codewords(bytes: &[u8], ver: usize) -> Zeroizing<Vec<u8>> {
+    // split into blocks, the first `short` of them one codeword shorter, and
+    // interleave them codeword by codeword, then their ECC the same way.
+    let blocks = BLOCKS[ver - 1];
+    let ecc_len = ECC_CODEWORDS[ver - 1];
+    let raw = raw_modules(ver) / 8;
+    let short = blocks - raw % blocks;
+    let short_len = raw / blocks - ecc_len;
+    let divisor = rs_divisor(ecc_len);
+    let mut data_blocks = Vec::with_capacity(blocks);
+    let mut ecc_blocks = Vec::with_capacity(blocks);
+    let mut k = 0;
+    for i in 0..blocks {
+        let len = short_len + (i >= short) as usize;
+        let block = &bytes[k..k + len];
+        ecc_blocks.push(rs_remainder(block, &divisor));
+        data_blocks.push(block);
+        k += len;
+    }
+    let mut out = Zeroizing::new(Vec::with_capacity(raw));
+    for i in 0..=short_len {
+        for block in data_blocks.iter() {
+            if let Some(&b) = block.get(i) {
+                out.push(b);
+            }
+        }
+    }
+    for i in 0..ecc_len {
+        for ecc in ecc_blocks.iter() {
+            out.push(ecc[i]);
+        }
+    }
+    out
+}
+
+impl QrCode {
+    // encode encodes `data` in byte mode at level M, in the smallest version
+    // that fits.
+    pub fn encode(data: &[u8]) -> Result<QrCode, SecretSharingError> {
+        let ver = (1..=40)
+            .find(|&v| {
+                let count_bits = if v <= 9 { 8 } else { 16 };
+                data.len() < 1 << count_bits
+                    && 4 + count_bits + 8 * data.len() <= 8 * data_codewords(v)
+            })
+            .ok_or(SecretSharingError::PayloadTooLarge {
+                max: data_codewords(40) - 3,
+            })?;
+
+        let mut qr = QrCode::blank(ver);
+        qr.draw_codewords(&codewords(&pad_data(data, ver), ver));
+
+        // try every mask and keep the one with the lowest penalty.
+        let mut best = (u32::MAX, 0);
+        for mask in 0..8 {
+            qr.apply_mask(mask);
+            qr.draw_format(mask);
+            let penalty = qr.penalty();
+            if penalty < best.0 {
+                best = (penalty, mask);
+            }
+            qr.apply_mask(mask);
+        }
+        qr.apply_mask(best.1);
+        qr.draw_format(best.1);
+        Ok(qr)
+    }
+
+    // blank returns a version-`ver` symbol with only its function patterns.
+    fn blank(ver: usize) -> QrCode {
+        let size = 4 * ver + 17;
+        let mut qr = QrCode {
+            size,
+            modules: vec![false; size * size],
+            function: vec![false; size * size],
+        };
+        qr.draw_function_patterns(ver);
+        qr
+    }
+
+    // size returns the width and height of the code in modules, without the
+    // quiet zone.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // module returns whether the module at column x and row y is dark.
+    pub fn module(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, ver: usize) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        // finder patterns with their separators, in three corners.
+        for &(cx, cy) in &[(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4i32..=4 {
+                for dx in -4i32..=4 {
+                    let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                    if (0..size as i32).contains(&x) && (0..size as i32).contains(&y) {
+                        let dist = dx.abs().max(dy.abs());
+                        self.set_function(x as usize, y as usize, dist != 2 && dist != 4);
+                    }
+                }
+            }
+        }
+
+        let align = alignment_positions(ver);
+        let last = align.len().saturating_sub(1);
+        for (i, &cx) in align.iter().enumerate() {
+            for (j, &cy) in align.iter().enumerate() {
+                if (i, j) == (0, 0) || (i, j) == (0, last) || (i, j) == (last, 0) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        let (x, y) = ((cx as i32 + dx) as usize, (cy as i32 + dy) as usize);
+                        self.set_function(x, y, dx.abs().max(dy.abs()) != 1);
+                    }
+                }
+            }
+        }
+
+        // reserve the format information, drawn once the mask is known.
+        self.draw_format(0);
+
+        if ver >= 7 {
+            let bits = version_bits(ver);
+            for i in 0..18 {
+                let dark = bits >> i & 1 == 1;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                self.set_function(a, b, dark);
+                self.set_function(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format(&mut self, mask: u32) {
+        let bits = format_bits(mask);
+        let bit = |i: usize| bits >> i & 1 == 1;
+        let size = self.size;
+
+        // around the top left finder pattern.
+        for i in 0..6 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        // split between the other two, beside the always dark module.
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    // draw_codewords places `data` in the zigzag of two-module columns from
+    // the bottom right, skipping function patterns.
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vert in 0..size {
+                let y = if upward { size - 1 - vert } else { vert };
+                for x in [right, right - 1] {
+                    if !self.function[y * size + x] && i < data.len() * 8 {
+                        self.modules[y * size + x] = data[i / 8] >> (7 - i % 8) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    // apply_mask inverts the data modules selected by `mask`; applying it
+    // twice undoes it.
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let i = y * self.size + x;
+                self.modules[i] ^= invert && !self.function[i];
+            }
+        }
+    }
+
+    // penalty scores the symbol by the four rules of ISO/IEC 18004 section
+    // 7.8.3: long runs, 2x2 blocks, finder-like patterns and imbalance of
+    // dark and light modules.
+    fn penalty(&self) -> u32 {
+        let size = self.size;
+        let mut score = 0;
+        for horizontal in [true, false] {
+            for a in 0..size {
+                let line: Vec<bool> = (0..size)
+                    .map(|b| {
+                        if horizontal {
+                            self.module(b, a)
+                        } else {
+                            self.module(a, b)
+                        }
+                    })
+                    .collect();
+
+                let mut run = 1;
+                for b in 1..=size {
+                    if b < size && line[b] == line[b - 1] {
+                        run += 1;
+                        continue;
+                    }
+                    if run >= 5 {
+                        score += 3 + (run - 5) as u32;
+                    }
+                    run = 1;
+                }
+
+                // 1:1:3:1:1 with four light modules on either side, the
+                // quiet zone counting as light.
+                let mut padded = vec![false; QUIET_ZONE];
+                padded.extend_from_slice(&line);
+                padded.extend(std::iter::repeat_n(false, QUIET_ZONE));
+                let finder = [true, false, true, true, true, false, true];
+                for w in padded.windows(11) {
+                    if (w[..7] == finder && w[7..].iter().all(|&m| !m))
+                        || (w[4..] == finder && w[..4].iter().all(|&m| !m))
+                    {
+                        score += 40;
+                    }
+                }
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let m = self.module(x, y);
+                if m == self.module(x + 1, y)
+                    && m == self.module(x, y + 1)
+                    && m == self.module(x + 1, y + 1)
+                {
+                    score += 3;
+                }
+            }
+        }
+
+        let total = size * size;
+        let dark = self.modules.iter().filter(|&&m| m).count();
+        let k = (dark * 20)
+            .abs_diff(total * 10)
+            .div_ceil(total)
+            .saturating_sub(1);
+        score + 10 * k as u32
+    }
+
+    // to_svg renders the code as an SVG image, one unit per module.
+    pub fn to_svg(&self) -> String {
+        let full = self.size + 2 * QUIET_ZONE;
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.module(x, y) {
+                    if !path.is_empty() {
+                        path.push(' ');
+                    }
+                    path.push_str(&format!("M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE));
+                }
+            }
+        }
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" viewBox=\"0 0 {0} {0}\" stroke=\"none\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n\
+             <path d=\"{1}\" fill=\"#000000\"/>\n\
+             </svg>\n",
+            full, path
+        )
+    }
+
+    // to_png renders the code as a 1-bit grayscale PNG, `scale` pixels per
+    // module.
+    pub fn to_png(&self, scale: usize) -> Vec<u8> {
+        let scale = scale.max(1);
+        let width = (self.size + 2 * QUIET_ZONE) * scale;
+        let row_len = 1 + width.div_ceil(8);
+
+        // each row is a filter type byte, 0 (none), then the pixels, 1 for
+        // white.
+        let mut raw = Vec::with_capacity(row_len * width);
+        for py in 0..width {
+            raw.push(0);
+            let mut row = vec![0u8; row_len - 1];
+            for px in 0..width {
+                let (x, y) = (px / scale, py / scale);
+                let dark = (QUIET_ZONE..QUIET_ZONE + self.size).contains(&x)
+                    && (QUIET_ZONE..QUIET_ZONE + self.size).contains(&y)
+                    && self.module(x - QUIET_ZONE, y - QUIET_ZONE);
+                if !dark {
+                    row[px / 8] |= 0x80 >> (px % 8);
+                }
+            }
+            raw.extend_from_slice(&row);
+        }
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+        // bit depth 1, grayscale, deflate, adaptive filtering, no interlace.
+        ihdr.extend_from_slice(&[1, 0, 0, 0, 0]);
+
+        let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+        png_chunk(&mut out, b"IHDR", &ihdr);
+        png_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// crc32 is the CRC of ISO 3309, which PNG uses.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & 0u32.wrapping_sub(crc & 1));
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &d in data {
+        a = (a + d as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+// zlib_stored wraps `data` in a zlib stream of uncompressed deflate blocks
+// (RFC 1950 and RFC 1951 section 3.2.4).
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut chunks = data.chunks(0xffff).peekable();
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        out.push(chunks.peek().is_none() as u8);
+        out.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(chunk.len() as u16)).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+impl Share {
+    // to_qr encodes the share's URI as a QR code.
+    pub fn to_qr(&self) -> Result<QrCode, SecretSharingError> {
+        QrCode::encode(self.to_uri().as_bytes())
+    }
+
+    // to_qr_svg renders the share's URI as an SVG QR code.
+    pub fn to_qr_svg(&self) -> Result<String, SecretSharingError> {
+        Ok(self.to_qr()?.to_svg())
+    }
+
+    // to_qr_png renders the share's URI as a PNG QR code, `scale` pixels per
+    // module.
+    pub fn to_qr_png(&self, scale: usize) -> Result<Vec<u8>, SecretSharingError> {
+        Ok(self.to_qr()?.to_png(scale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_tables() {
+        // level M data capacities of ISO/IEC 18004 table 7.
+        let capacity: Vec<usize> = [1, 2, 5, 10, 27, 40]
+            .iter()
+            .map(|&v| data_codewords(v))
+            .collect();
+        assert_eq!(capacity, [16, 28, 86, 216, 1128, 2334]);
+        assert_eq!(alignment_positions(2), [6, 18]);
+        assert_eq!(alignment_positions(7), [6, 22, 38]);
+        assert_eq!(alignment_positions(32), [6, 34, 60, 86, 112, 138]);
+    }
+    #[test]
+    fn test_reed_solomon() {
+        // "HELLO WORLD" at 1-M.
+        let data = [
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        assert_eq!(
+            rs_remainder(&data, &rs_divisor(10)),
+            [196, 35, 39, 119, 235, 215, 231, 226, 93, 23]
+        );
+    }
+    #[test]
+    fn test_format_and_version_bits() {
+        let format: Vec<u32> = (0..8).map(format_bits).collect();
+        assert_eq!(
+            format,
+            [
+                0b101010000010010,
+                0b101000100100101,
+                0b101111001111100,
+                0b101101101001011,
+                0b100010111111001,
+                0b100000011001110,
+                0b100111110010111,
+                0b100101010100000
+            ]
+        );
+        assert_eq!(version_bits(7), 0b000111110010010100);
+    }
+    #[test]
+    fn test_annex_i() {
+        // the 1-M symbol for "01234567" of ISO/IEC 18004 annex I, in numeric
+        // mode with the annex's mask 010. Only the data codewords are given,
+        // as the encoder itself only does byte mode.
+        let data = [
+            0x10, 0x20, 0x0c, 0x56, 0x61, 0x80, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11, 0xec, 0x11,
+            0xec, 0x11,
+        ];
+        let all = codewords(&data, 1);
+        assert_eq!(
+            all[16..],
+            [0xa5, 0x24, 0xd4, 0xc1, 0xed, 0x36, 0xc7, 0x87, 0x2c, 0x55]
+        );
+        let mut qr = QrCode::blank(1);
+        qr.draw_codewords(&all);
+        qr.apply_mask(2);
+        qr.draw_format(2);
+
+        let symbol = [
+            "#######..#.##.#######",
+            "#.....#..####.#.....#",
+            "#.###.#.#.....#.###.#",
+            "#.###.#.##....#.###.#",
+            "#.###.#.#.###.#.###.#",
+            "#.....#.#...#.#.....#",
+            "#######.#.#.#.#######",
+            "........#..##........",
+            "#.#####..#..#.#####..",
+            "...#.#.##.#.#..#.##..",
+            "..#...##.#.#.#..#####",
+            "....#....#.....####..",
+            "...######..#.#..#....",
+            "........#.#####..##..",
+            "#######..##.#.##.....",
+            "#.....#.#.#####...#.#",
+            "#.###.#.#...#..#.##..",
+            "#.###.#.##..#..#.....",
+            "#.###.#.#.##.#..#.#..",
+            "#.....#........##.##.",
+            "#######.####.#..#.#..",
+        ];
+        for (y, row) in symbol.iter().enumerate() {
+            let got: String = (0..21)
+                .map(|x| if qr.module(x, y) { '#' } else { '.' })
+                .collect();
+            assert_eq!(got, *row, "row {}", y);
+        }
+    }
+    #[test]
+    fn test_qr_share() {
+        let share = &construct_shares(2, 3, &[0x42; 32]).unwrap()[0];
+        let qr = share.to_qr().unwrap();
+        assert_eq!((qr.size() - 17) % 4, 0);
+        // the top left finder pattern.
+        for i in 0..7 {
+            assert!(qr.module(i, 0) && qr.module(0, i) && qr.module(6, i));
+        }
+        assert!(!qr.module(7, 0) && qr.module(3, 3) && !qr.module(1, 1));
+        assert!(qr.module(8, qr.size() - 8));
+
+        let svg = share.to_qr_svg().unwrap();
+        assert!(svg.contains("<svg") && svg.contains("M4,4h1v1h-1z"));
+
+        let png = share.to_qr_png(2).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let width = 2 * (qr.size() + 8) as u32;
+        assert_eq!(
+            &png[16..24],
+            &[width.to_be_bytes(), width.to_be_bytes()].concat()[..]
+        );
+        // IEND's CRC is fixed.
+        assert_eq!(&png[png.len() - 4..], &[0xae, 0x42, 0x60, 0x82]);
+
+        assert_eq!(
+            QrCode::encode(&[0; 2332]).err(),
+            Some(SecretSharingError::PayloadTooLarge { max: 2331 })
+        );
+        assert_eq!(QrCode::encode(&[0; 2331]).unwrap().size(), 177);
+    }
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+}
@@ -0,0 +1,342 @@
+// Ramp (packed) secret sharing. An ordinary (t, n) sharing gives every
+// participant a share as large as the secret; a (t, L, n) ramp sharing packs L
+// secret bytes into each polynomial, so shares are L times smaller. This is
+// what makes it practical to split multi-megabyte archives.
+//
+// The saving is paid for in security, which degrades gradually instead of all
+// at once:
+//
+//   - any t shares recover the secret;
+//   - any t-L shares or fewer reveal nothing about it;
+//   - t-L+m shares, for 0 < m < L, reveal m bytes' worth of information about
+//     every L-byte block of the secret. They don't necessarily reveal any
+//     particular byte, but they narrow each block down to 256^(L-m) candidates.
+//
+// So a (t, L, n) ramp sharing should be chosen where a coalition of t-L
+// participants is the one to guard against, and L = 1 is ordinary Shamir
+// sharing.
+//
+// The secret is cut into blocks of L bytes, the last padded with zeros. Each
+// block is shared with a random polynomial of degree t-1 over GF(2^8) that
+// takes the block's bytes at x = 255, 254, ..., 256-L, and random values at
+// the next t-L points down; participant i's share holds the polynomial's value
+// at x = i. Since all of those points are reserved, n may be at most 255-t.
+
+use crate::field::{lagrange_coefficients, wipe, Field};
+use crate::gf::GF256e;
+use crate::{Redacted, SecretSharingError};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+use zeroize::Zeroize;
+
+// RampShare is a participant's share of a ramp sharing.
+#[derive(Clone, Zeroize)]
+#[zeroize(drop)]
+pub struct RampShare {
+    x: GF256e,
+    y: Vec<GF256e>,
+    secret_len: usize,
+    packing: u8,
+    threshold: Option<u8>,
+}
+
+impl fmt::Debug for RampShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RampShare")
+            .field("x", &self.x)
+            .field("packing", &self.packing)
+            .field("data", &Redacted(self.y.len()))
+            .finish()
+    }
+}
+
+impl RampShare {
+    // new creates a ramp share from a previously persisted index, payload,
+    // secret length and packing, as returned by `index`, `as_bytes`,
+    // `secret_len` and `packing`.
+    pub fn new(index: u8, data: &[u8], secret_len: usize, packing: u8) -> RampShare {
+        RampShare {
+            x: index,
+            y: data.to_vec(),
+            secret_len,
+            packing,
+            threshold: None,
+        }
+    }
+
+    // with_threshold creates a share like `new`, additionally recording the
+    // threshold `t` it was created with, as returned by `threshold`, so that
+    // reconstruct_ramp can tell too few restored shares from enough.
+    pub fn with_threshold(
+        index: u8,
+        data: &[u8],
+        secret_len: usize,
+        packing: u8,
+        t: u8,
+    ) -> RampShare {
+        let mut share = RampShare::new(index, data, secret_len, packing);
+        share.threshold = Some(t);
+        share
+    }
+
+    pub fn index(&self) -> u8 {
+        self.x
+    }
+
+    pub fn threshold(&self) -> Option<u8> {
+        self.threshold
+    }
+
+    // packing returns the number of secret bytes packed into each byte of the
+    // share.
+    pub fn packing(&self) -> u8 {
+        self.packing
+    }
+
+    // secret_len returns the length in bytes of the shared secret.
+    pub fn secret_len(&self) -> usize {
+        self.secret_len
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.y
+    }
+}
+
+// secret_point returns the x-coordinate holding byte j of every block.
+fn secret_point(j: usize) -> GF256e {
+    (255 - j) as GF256e
+}
+
+// construct_shares_ramp splits `secret` into `n` shares, any `t` of which
+// recover it, packing `l` secret bytes into every share byte. See the module
+// comment for what fewer than t shares reveal. It returns TorNisZero if any
+// parameter is zero, ThresholdExceedsShares if t > n, PackingExceedsThreshold
+// if l > t and TooManyShares if n > 255-t.
+pub fn construct_shares_ramp(
+    t: u8,
+    l: u8,
+    n: u8,
+    secret: &[u8],
+) -> Result<Vec<RampShare>, SecretSharingError> {
+    construct_shares_ramp_with_rng(t, l, n, secret, &mut crate::default_rng())
+}
+
+// construct_shares_ramp_with_rng is like construct_shares_ramp, drawing the
+// random points from `rng`.
+pub fn construct_shares_ramp_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    l: u8,
+    n: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Vec<RampShare>, SecretSharingError> {
+    if t == 0 || l == 0 || n == 0 {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if t > n {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+    if l > t {
+        return Err(SecretSharingError::PackingExceedsThreshold);
+    }
+    if n as usize + t as usize > 255 {
+        return Err(SecretSharingError::TooManyShares);
+    }
+    let (t, l) = (t as usize, l as usize);
+
+    // every share byte is the same linear combination of the t values at the
+    // reserved points, so the lagrange basis is computed once per share.
+    let points: Vec<GF256e> = (0..t).map(secret_point).collect();
+    let bases: Vec<Vec<GF256e>> = (1..=n).map(|x| lagrange_coefficients(&points, x)).collect();
+
+    let blocks = secret.len().div_ceil(l);
+    let mut shares: Vec<RampShare> = (1..=n)
+        .map(|x| RampShare {
+            x,
+            y: Vec::with_capacity(blocks),
+            secret_len: secret.len(),
+            packing: l as u8,
+            threshold: Some(t as u8),
+        })
+        .collect();
+    let mut values = vec![GF256e::zero(); t];
+    for block in secret.chunks(l) {
+        values[..l].fill(GF256e::zero());
+        values[..block.len()].copy_from_slice(block);
+        for v in values[l..].iter_mut() {
+            *v = GF256e::random(rng);
+        }
+        for (share, basis) in shares.iter_mut().zip(bases.iter()) {
+            let y = basis
+                .iter()
+                .zip(values.iter())
+                .fold(GF256e::zero(), |y, (b, v)| y.add(b.mul(*v)));
+            share.y.push(y);
+        }
+    }
+    wipe(&mut values);
+    Ok(shares)
+}
+
+// reconstruct_ramp recovers the secret from at least t ramp shares. Like
+// reconstruct, it can't tell too few shares from enough unless they record
+// the threshold, and returns NotEnoughShares if they do.
+pub fn reconstruct_ramp(shares: &[RampShare]) -> Result<Vec<u8>, SecretSharingError> {
+    let first = shares.first().ok_or(SecretSharingError::NoSharesProvided)?;
+    if shares.iter().any(|s| s.y.len() != first.y.len()) {
+        let byte = shares.iter().map(|s| s.y.len()).min().unwrap_or(0);
+        return Err(SecretSharingError::MissingShareForByte { byte });
+    }
+    if shares
+        .iter()
+        .any(|s| s.secret_len != first.secret_len || s.packing != first.packing)
+    {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    let l = first.packing as usize;
+    if l == 0 || first.y.len() != first.secret_len.div_ceil(l) {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    for (i, share) in shares.iter().enumerate() {
+        if share.x == 0 || share.x as usize > 255 - l {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        if shares[..i].iter().any(|s| s.x == share.x) {
+            return Err(SecretSharingError::DuplicateShare { x: share.x.into() });
+        }
+    }
+    if let Some(t) = shares.iter().filter_map(|s| s.threshold).max() {
+        if shares.len() < t as usize {
+            return Err(SecretSharingError::NotEnoughShares {
+                have: shares.len(),
+                need: t as usize,
+            });
+        }
+    }
+
+    let xs: Vec<GF256e> = shares.iter().map(|s| s.x).collect();
+    let bases: Vec<Vec<GF256e>> = (0..l)
+        .map(|j| lagrange_coefficients(&xs, secret_point(j)))
+        .collect();
+    let mut secret = Vec::with_capacity(first.y.len() * l);
+    for i in 0..first.y.len() {
+        for basis in bases.iter() {
+            let v = shares
+                .iter()
+                .zip(basis.iter())
+                .fold(GF256e::zero(), |v, (s, b)| v.add(s.y[i].mul(*b)));
+            secret.push(v);
+        }
+    }
+    secret.truncate(first.secret_len);
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ramp_roundtrip() {
+        let secret: Vec<u8> = (0..1000u32).map(|i| (i * 7 + 3) as u8).collect();
+        let shares = construct_shares_ramp(6, 4, 10, &secret).unwrap();
+        assert_eq!(shares[0].as_bytes().len(), 250);
+        assert_eq!(reconstruct_ramp(&shares[2..8]).unwrap(), secret);
+        assert_eq!(reconstruct_ramp(&shares).unwrap(), secret);
+        assert_eq!(
+            reconstruct_ramp(&shares[..5]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 5, need: 6 })
+        );
+
+        // shares restored with new don't record the threshold.
+        let restored: Vec<RampShare> = [9, 0, 4, 5, 1, 7]
+            .iter()
+            .map(|&i| {
+                let s = &shares[i];
+                RampShare::new(s.index(), s.as_bytes(), s.secret_len(), s.packing())
+            })
+            .collect();
+        assert_eq!(reconstruct_ramp(&restored).unwrap(), secret);
+        assert_ne!(reconstruct_ramp(&restored[..5]).unwrap(), secret);
+
+        let restored: Vec<RampShare> = shares[..5]
+            .iter()
+            .map(|s| {
+                let t = s.threshold().unwrap();
+                RampShare::with_threshold(s.index(), s.as_bytes(), s.secret_len(), s.packing(), t)
+            })
+            .collect();
+        assert_eq!(restored[0].threshold(), Some(6));
+        assert_eq!(
+            reconstruct_ramp(&restored).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 5, need: 6 })
+        );
+    }
+    #[test]
+    fn test_ramp_packing() {
+        let secret = b"odd length secret";
+        for (t, l) in [(1, 1), (3, 1), (3, 2), (3, 3), (5, 5)] {
+            let shares = construct_shares_ramp(t, l, 7, secret).unwrap();
+            assert_eq!(
+                shares[0].as_bytes().len(),
+                secret.len().div_ceil(l as usize)
+            );
+            assert_eq!(reconstruct_ramp(&shares[7 - t as usize..]).unwrap(), secret);
+        }
+        assert_eq!(
+            reconstruct_ramp(&construct_shares_ramp(2, 2, 3, b"").unwrap()).unwrap(),
+            b""
+        );
+    }
+    #[test]
+    fn test_ramp_leakage() {
+        // with t = 2 and l = 2, one share reveals nothing: every block value
+        // is consistent with it, for some choice of the (absent) random point.
+        let mut rng = rand::thread_rng();
+        let share = &construct_shares_ramp_with_rng(2, 2, 3, b"ab", &mut rng).unwrap()[0];
+        let basis = lagrange_coefficients(&[secret_point(0), secret_point(1)], share.index());
+        let mut candidates = 0;
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                if basis[0].mul(a).add(basis[1].mul(b)) == share.as_bytes()[0] {
+                    candidates += 1;
+                }
+            }
+        }
+        assert_eq!(candidates, 256);
+    }
+    #[test]
+    fn test_ramp_errors() {
+        assert_eq!(
+            construct_shares_ramp(2, 0, 3, b"s").err(),
+            Some(SecretSharingError::TorNisZero)
+        );
+        assert_eq!(
+            construct_shares_ramp(4, 2, 3, b"s").err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        assert_eq!(
+            construct_shares_ramp(2, 3, 3, b"s").err(),
+            Some(SecretSharingError::PackingExceedsThreshold)
+        );
+        assert_eq!(
+            construct_shares_ramp(3, 2, 253, b"s").err(),
+            Some(SecretSharingError::TooManyShares)
+        );
+        assert_eq!(
+            reconstruct_ramp(&[]).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+        let shares = construct_shares_ramp(2, 2, 3, b"secret").unwrap();
+        assert_eq!(
+            reconstruct_ramp(&[shares[1].clone(), shares[1].clone()]).err(),
+            Some(SecretSharingError::DuplicateShare { x: 2 })
+        );
+        let other = RampShare::new(3, shares[2].as_bytes(), 6, 3);
+        assert_eq!(
+            reconstruct_ramp(&[shares[0].clone(), other]).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+    }
+}
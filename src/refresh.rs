@@ -0,0 +1,354 @@
+// Proactive share refresh. Adding the shares of a random polynomial with a
+// zero constant term to every share yields a fresh sharing of the same
+// secret. Old and new shares lie on different polynomials, so old copies
+// (for instance on a stolen backup) cannot be combined with refreshed ones.
+//
+// refresh_shares is the trusted dealer variant, where one party holding all
+// the shares refreshes them. In the dealerless variant every participant
+// contributes a zero-sharing with refresh_contribution, sending one
+// RefreshDelta to each participant, who applies the deltas addressed to it
+// with apply_refresh. The refresh is only secure if all participants apply
+// it; shares refreshed by a subset do not combine with the rest.
+
+use crate::gf::GfOps;
+use crate::message::{self, REFRESH_DELTA};
+use crate::{
+    check_identifiers, check_indices, check_lengths, Dealer, Redacted, SecretSharingError, Share,
+    Shares,
+};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+use zeroize::Zeroize;
+
+// RefreshDelta is a dealerless refresh message from participant `from` to
+// participant `to`.
+#[derive(Zeroize)]
+#[zeroize(drop)]
+pub struct RefreshDelta {
+    from: u8,
+    to: u8,
+    data: Vec<u8>,
+}
+
+impl fmt::Debug for RefreshDelta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RefreshDelta")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("data", &Redacted(self.data.len()))
+            .finish()
+    }
+}
+
+impl RefreshDelta {
+    pub fn sender(&self) -> u8 {
+        self.from
+    }
+
+    pub fn recipient(&self) -> u8 {
+        self.to
+    }
+
+    // to_bytes encodes the delta in the format described in message.rs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        message::encode(REFRESH_DELTA, &[self.from, self.to], &self.data)
+    }
+
+    // from_bytes decodes a delta produced by to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<RefreshDelta, SecretSharingError> {
+        let (fields, data) = message::decode(REFRESH_DELTA, 2, data)?;
+        if fields.contains(&0) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        Ok(RefreshDelta {
+            from: fields[0],
+            to: fields[1],
+            data: data.to_vec(),
+        })
+    }
+}
+
+// zero_sharing returns a dealer for a zero secret of length `len`, whose shares
+// are added to existing shares to refresh them.
+fn zero_sharing<R: CryptoRng + RngCore>(
+    t: u8,
+    len: usize,
+    rng: &mut R,
+) -> Result<Dealer, SecretSharingError> {
+    Dealer::with_rng(t, &vec![0; len], rng)
+}
+
+// check_threshold returns ThresholdExceedsShares if `t` is larger than the
+// `n` shares being refreshed, whose refreshed shares could then never be
+// combined, and InconsistentShares, naming them, if any of `shares` records a
+// threshold other than `t`: refreshing with a lower threshold would let fewer
+// shares than intended recover garbage without error.
+fn check_threshold<'a, I: IntoIterator<Item = &'a Share>>(
+    t: u8,
+    n: usize,
+    shares: I,
+) -> Result<(), SecretSharingError> {
+    if t as usize > n {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+    let suspects: Vec<u8> = shares
+        .into_iter()
+        .filter(|share| share.threshold.is_some_and(|st| st != t))
+        .map(|share| share.x)
+        .collect();
+    if !suspects.is_empty() {
+        return Err(SecretSharingError::InconsistentShares { suspects });
+    }
+    Ok(())
+}
+
+fn add_into(share: &mut Share, delta: &[u8]) {
+    for (y, d) in share.y.iter_mut().zip(delta.iter()) {
+        *y = y.add(*d);
+    }
+}
+
+// refresh_shares re-randomizes a complete set of shares created with threshold
+// `t`, returning new shares of the same secret at the same indices. Shares
+// recording their threshold must record `t`, which can't exceed the number of
+// shares. The old
+// shares should be destroyed once the new ones are distributed. The new shares
+// keep the split identifier of the old, as with apply_refresh, so it doesn't
+// tell old shares from new ones.
+pub fn refresh_shares(t: u8, shares: &[Share]) -> Result<Shares, SecretSharingError> {
+    refresh_shares_with_rng(t, shares, &mut crate::default_rng())
+}
+
+// refresh_shares_with_rng is like refresh_shares, drawing the refresh
+// polynomials from `rng`.
+pub fn refresh_shares_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    shares: &[Share],
+    rng: &mut R,
+) -> Result<Shares, SecretSharingError> {
+    let xs: Vec<u8> = shares.iter().map(|share| share.x).collect();
+    check_indices(&xs)?;
+    check_lengths(shares)?;
+    check_identifiers(shares)?;
+    check_threshold(t, shares.len(), shares)?;
+    let len = shares.first().map_or(0, |share| share.y.len());
+
+    let zero = zero_sharing(t, len, rng)?;
+    shares
+        .iter()
+        .map(|share| {
+            let mut refreshed = zero.issue(share.x)?;
+            add_into(&mut refreshed, &share.y);
+            refreshed.share_count = share.share_count;
+            refreshed.identifier = share.identifier;
+            refreshed.label = share.label.clone();
+            refreshed.owner = share.owner.clone();
+            Ok(refreshed)
+        })
+        .collect()
+}
+
+// refresh_contribution is this participant's part of a dealerless refresh
+// among `participants`, the indices of every share of the secret, with
+// threshold `t` checked as by refresh_shares. It returns one delta for each
+// participant (including this one).
+pub fn refresh_contribution<R: CryptoRng + RngCore>(
+    t: u8,
+    participants: &[u8],
+    share: &Share,
+    rng: &mut R,
+) -> Result<Vec<RefreshDelta>, SecretSharingError> {
+    check_indices(participants)?;
+    if !participants.contains(&share.x) {
+        return Err(SecretSharingError::UnexpectedMessage);
+    }
+    check_threshold(t, participants.len(), [share])?;
+
+    let zero = zero_sharing(t, share.y.len(), rng)?;
+    participants
+        .iter()
+        .map(|&to| {
+            let delta = zero.issue(to)?;
+            Ok(RefreshDelta {
+                from: share.x,
+                to,
+                data: delta.y.clone(),
+            })
+        })
+        .collect()
+}
+
+// apply_refresh applies the deltas addressed to `share`, one from every one of
+// `participants`, returning the refreshed share.
+pub fn apply_refresh(
+    share: &Share,
+    participants: &[u8],
+    deltas: &[RefreshDelta],
+) -> Result<Share, SecretSharingError> {
+    check_indices(participants)?;
+
+    let mut seen = Vec::with_capacity(deltas.len());
+    for delta in deltas {
+        if delta.to != share.x || !participants.contains(&delta.from) || seen.contains(&delta.from)
+        {
+            return Err(SecretSharingError::UnexpectedMessage);
+        }
+        if delta.data.len() != share.y.len() {
+            return Err(SecretSharingError::ShareLengthMismatch {
+                index: delta.from,
+                expected: share.y.len(),
+                actual: delta.data.len(),
+            });
+        }
+        seen.push(delta.from);
+    }
+    if seen.len() != participants.len() {
+        return Err(SecretSharingError::IncompleteProtocol);
+    }
+
+    let mut refreshed = Share {
+        x: share.x,
+        y: share.y.clone(),
+        threshold: share.threshold,
+        share_count: share.share_count,
+        identifier: share.identifier,
+        label: share.label.clone(),
+        owner: share.owner.clone(),
+    };
+    for delta in deltas {
+        add_into(&mut refreshed, &delta.data);
+    }
+    Ok(refreshed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{construct_shares, reconstruct};
+
+    #[test]
+    fn test_refresh_dealer() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let mut shares = construct_shares(3, 5, &secret).unwrap();
+        shares[0] = shares[0].clone().with_label("Safe deposit box").unwrap();
+        shares[1] = shares[1].clone().with_owner("Mom").unwrap();
+        let refreshed = refresh_shares(3, &shares).unwrap();
+
+        for (old, new) in shares.iter().zip(refreshed.iter()) {
+            assert_eq!(old.index(), new.index());
+            assert_ne!(old.as_bytes(), new.as_bytes());
+            assert_eq!((old.label(), old.owner()), (new.label(), new.owner()));
+            // like apply_refresh, the refresh keeps the split's identifier.
+            assert_eq!(old.identifier(), new.identifier());
+        }
+        assert_eq!(refreshed[0].label(), Some("Safe deposit box"));
+        assert_eq!(refreshed[1].owner(), Some("Mom"));
+        assert_eq!(reconstruct(&refreshed[2..]).unwrap(), secret);
+
+        // old and new shares no longer combine.
+        let mixed = vec![
+            Share::new(shares[0].index(), shares[0].as_bytes()),
+            Share::new(shares[1].index(), shares[1].as_bytes()),
+            Share::new(refreshed[2].index(), refreshed[2].as_bytes()),
+        ];
+        assert_ne!(reconstruct(&mixed).unwrap(), secret);
+    }
+    #[test]
+    fn test_refresh_threshold() {
+        let shares = construct_shares(3, 5, &[0xde, 0xad]).unwrap();
+        // a lower threshold than the shares were made with would let two
+        // refreshed shares "recover" a wrong secret.
+        assert_eq!(
+            refresh_shares(2, &shares).err(),
+            Some(SecretSharingError::InconsistentShares {
+                suspects: vec![1, 2, 3, 4, 5]
+            })
+        );
+        let bare: Shares = shares
+            .iter()
+            .map(|s| Share::new(s.index(), s.as_bytes()))
+            .collect();
+        assert_eq!(
+            refresh_shares(6, &bare).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        let refreshed = refresh_shares(3, &bare).unwrap();
+        assert_eq!(reconstruct(&refreshed[2..]).unwrap(), [0xde, 0xad]);
+
+        let mut rng = rand::thread_rng();
+        assert_eq!(
+            refresh_contribution(2, &[1, 2, 3], &shares[0], &mut rng).err(),
+            Some(SecretSharingError::InconsistentShares { suspects: vec![1] })
+        );
+        assert_eq!(
+            refresh_contribution(3, &[1, 2], &shares[0], &mut rng).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+    }
+    #[test]
+    fn test_refresh_dealerless() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let shares = construct_shares(2, 3, &secret).unwrap();
+        let participants = [1, 2, 3];
+        let mut rng = rand::thread_rng();
+
+        let mut deltas: Vec<RefreshDelta> = shares
+            .iter()
+            .flat_map(|share| refresh_contribution(2, &participants, share, &mut rng).unwrap())
+            .collect();
+        let mut refreshed = Shares::new();
+        for share in shares.iter() {
+            let (mine, rest): (Vec<RefreshDelta>, Vec<RefreshDelta>) = deltas
+                .into_iter()
+                .partition(|d| d.recipient() == share.index());
+            deltas = rest;
+            refreshed.push(apply_refresh(share, &participants, &mine).unwrap());
+        }
+
+        assert_ne!(refreshed[0].as_bytes(), shares[0].as_bytes());
+        assert_eq!(reconstruct(&refreshed[1..]).unwrap(), secret);
+    }
+    #[test]
+    fn test_apply_refresh_incomplete() {
+        let shares = construct_shares(2, 2, &[0x01]).unwrap();
+        let mut rng = rand::thread_rng();
+        let deltas = refresh_contribution(2, &[1, 2], &shares[0], &mut rng).unwrap();
+        let to_first: Vec<RefreshDelta> = deltas.into_iter().filter(|d| d.to == 1).collect();
+        assert_eq!(
+            apply_refresh(&shares[0], &[1, 2], &to_first).err(),
+            Some(SecretSharingError::IncompleteProtocol)
+        );
+        assert_eq!(
+            apply_refresh(&shares[1], &[1, 2], &to_first).err(),
+            Some(SecretSharingError::UnexpectedMessage)
+        );
+    }
+    #[test]
+    fn test_refresh_delta_encoding() {
+        let shares = construct_shares(2, 2, &[0x01, 0x02]).unwrap();
+        let mut rng = rand::thread_rng();
+        let deltas = refresh_contribution(2, &[1, 2], &shares[0], &mut rng).unwrap();
+        for delta in deltas.iter() {
+            let decoded = RefreshDelta::from_bytes(&delta.to_bytes()).unwrap();
+            assert_eq!((decoded.sender(), decoded.recipient()), (1, delta.to));
+            assert_eq!(decoded.data, delta.data);
+        }
+
+        let bytes = deltas[0].to_bytes();
+        assert_eq!(
+            RefreshDelta::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+        let mut corrupt = bytes.clone();
+        corrupt[12] ^= 1;
+        assert_eq!(
+            RefreshDelta::from_bytes(&corrupt).err(),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+        let to_zero = message::encode(REFRESH_DELTA, &[1, 0], &[0xaa, 0xbb]);
+        assert_eq!(
+            RefreshDelta::from_bytes(&to_zero).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+    }
+}
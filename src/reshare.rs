@@ -0,0 +1,345 @@
+// Resharing converts a sharing of a secret into a sharing with a different
+// threshold and share count, without the secret being reconstructed.
+//
+// A quorum of old shareholders each sub-shares its own share y_i with a fresh
+// (t', n') polynomial and sends sub-share j to new participant j. Since the
+// secret is sum_i l_i * y_i for the quorum's lagrange coefficients l_i at 0,
+// new participant j obtains its share of the secret as sum_i l_i * s_ij. Old
+// shares do not combine with new ones and should be destroyed afterwards.
+//
+// The new shares get a fresh split identifier, so they can't be mistaken for
+// old ones. Every old shareholder draws a random contribution to it and sends
+// the same contribution to every new participant, who derive the identifier
+// from all of them.
+
+use crate::gf::GfOps;
+use crate::message::{self, RESHARE_DELTA};
+use crate::{
+    check_indices, lagrange_coefficients, random_identifier, Dealer, Redacted, SecretSharingError,
+    Share,
+};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+use zeroize::Zeroize;
+
+// ReshareDelta is a sub-share sent by old shareholder `from` to new
+// participant `to`. It records the split identifier of the old share, if
+// known, and the sender's contribution to the new one.
+#[derive(Zeroize)]
+#[zeroize(drop)]
+pub struct ReshareDelta {
+    from: u8,
+    to: u8,
+    threshold: u8,
+    share_count: u8,
+    old_identifier: Option<u16>,
+    identifier: u16,
+    data: Vec<u8>,
+}
+
+impl fmt::Debug for ReshareDelta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReshareDelta")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("threshold", &self.threshold)
+            .field("share_count", &self.share_count)
+            .field("old_identifier", &self.old_identifier)
+            .field("data", &Redacted(self.data.len()))
+            .finish()
+    }
+}
+
+impl ReshareDelta {
+    pub fn sender(&self) -> u8 {
+        self.from
+    }
+
+    pub fn recipient(&self) -> u8 {
+        self.to
+    }
+
+    // to_bytes encodes the sub-share in the format described in message.rs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let old = self.old_identifier.unwrap_or(0).to_be_bytes();
+        let id = self.identifier.to_be_bytes();
+        let fields = [
+            self.from,
+            self.to,
+            self.threshold,
+            self.share_count,
+            old[0],
+            old[1],
+            id[0],
+            id[1],
+        ];
+        message::encode(RESHARE_DELTA, &fields, &self.data)
+    }
+
+    // from_bytes decodes a sub-share produced by to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<ReshareDelta, SecretSharingError> {
+        let (fields, data) = message::decode(RESHARE_DELTA, 8, data)?;
+        if fields[..4].contains(&0) || fields[2] > fields[3] || fields[1] > fields[3] {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        Ok(ReshareDelta {
+            from: fields[0],
+            to: fields[1],
+            threshold: fields[2],
+            share_count: fields[3],
+            old_identifier: Some(u16::from_be_bytes([fields[4], fields[5]])).filter(|&id| id != 0),
+            identifier: u16::from_be_bytes([fields[6], fields[7]]),
+            data: data.to_vec(),
+        })
+    }
+}
+
+// check_old_quorum ensures the old quorum `xs` are distinct, non-zero indices,
+// and at least as many as the old `threshold`, if known.
+fn check_old_quorum(xs: &[u8], threshold: Option<u8>) -> Result<(), SecretSharingError> {
+    check_indices(xs)?;
+    if let Some(t) = threshold {
+        if xs.len() < t as usize {
+            return Err(SecretSharingError::NotEnoughShares {
+                have: xs.len(),
+                need: t as usize,
+            });
+        }
+    }
+    Ok(())
+}
+
+// reshare_contribution is the part of old shareholder `share` in resharing to
+// a (new_t, new_n) sharing, where `old_quorum` lists the indices of all old
+// shareholders taking part. It returns one sub-share for each new participant
+// 1..new_n.
+pub fn reshare_contribution<R: CryptoRng + RngCore>(
+    share: &Share,
+    old_quorum: &[u8],
+    new_t: u8,
+    new_n: u8,
+    rng: &mut R,
+) -> Result<Vec<ReshareDelta>, SecretSharingError> {
+    if new_t == 0 || new_n == 0 {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if new_t > new_n {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+    check_old_quorum(old_quorum, share.threshold)?;
+    if !old_quorum.contains(&share.x) {
+        return Err(SecretSharingError::UnexpectedMessage);
+    }
+
+    let dealer = Dealer::with_rng(new_t, &share.y, rng)?;
+    let identifier = random_identifier(rng);
+    (1..=new_n)
+        .map(|to| {
+            let sub = dealer.issue(to)?;
+            Ok(ReshareDelta {
+                from: share.x,
+                to,
+                threshold: new_t,
+                share_count: new_n,
+                old_identifier: share.identifier,
+                identifier,
+                data: sub.y.clone(),
+            })
+        })
+        .collect()
+}
+
+// combine_reshare combines the sub-shares addressed to new participant
+// `new_x`, one from every member of `old_quorum`, into its new share.
+// Sub-shares from holders of shares of different splits are rejected with
+// InconsistentShares.
+pub fn combine_reshare(
+    new_x: u8,
+    old_quorum: &[u8],
+    deltas: &[ReshareDelta],
+) -> Result<Share, SecretSharingError> {
+    check_old_quorum(old_quorum, None)?;
+    let first = match deltas.first() {
+        Some(d) => d,
+        None => return Err(SecretSharingError::IncompleteProtocol),
+    };
+
+    let basis = lagrange_coefficients(old_quorum, 0);
+    let mut y = vec![0u8; first.data.len()];
+    let mut seen = Vec::with_capacity(deltas.len());
+    for delta in deltas {
+        let pos = old_quorum.iter().position(|&x| x == delta.from);
+        let pos = match pos {
+            Some(pos) if delta.to == new_x && !seen.contains(&delta.from) => pos,
+            _ => return Err(SecretSharingError::UnexpectedMessage),
+        };
+        if delta.threshold != first.threshold || delta.share_count != first.share_count {
+            return Err(SecretSharingError::UnexpectedMessage);
+        }
+        if first
+            .old_identifier
+            .zip(delta.old_identifier)
+            .is_some_and(|(a, b)| a != b)
+        {
+            return Err(SecretSharingError::InconsistentShares {
+                suspects: vec![delta.from],
+            });
+        }
+        if delta.data.len() != y.len() {
+            return Err(SecretSharingError::ShareLengthMismatch {
+                index: delta.from,
+                expected: y.len(),
+                actual: delta.data.len(),
+            });
+        }
+
+        for (y, d) in y.iter_mut().zip(delta.data.iter()) {
+            *y = y.add(d.mul(basis[pos]));
+        }
+        seen.push(delta.from);
+    }
+    if seen.len() != old_quorum.len() {
+        y.zeroize();
+        return Err(SecretSharingError::IncompleteProtocol);
+    }
+
+    // the contributions are summed into a non-zero identifier, which doesn't
+    // depend on the order the sub-shares arrive in.
+    let sum: u32 = deltas.iter().map(|d| d.identifier as u32).sum();
+    let identifier = (sum % u16::MAX as u32) as u16 + 1;
+
+    Ok(Share {
+        x: new_x,
+        y,
+        threshold: Some(first.threshold),
+        share_count: Some(first.share_count),
+        identifier: Some(identifier),
+        label: None,
+        owner: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{construct_shares, reconstruct, Shares};
+
+    fn reshare(shares: &[Share], old_quorum: &[u8], new_t: u8, new_n: u8) -> Shares {
+        let mut rng = rand::thread_rng();
+        let mut deltas: Vec<ReshareDelta> = shares
+            .iter()
+            .filter(|share| old_quorum.contains(&share.index()))
+            .flat_map(|share| {
+                reshare_contribution(share, old_quorum, new_t, new_n, &mut rng).unwrap()
+            })
+            .collect();
+
+        let mut reshared = Shares::new();
+        for new_x in 1..=new_n {
+            let (mine, rest): (Vec<ReshareDelta>, Vec<ReshareDelta>) =
+                deltas.into_iter().partition(|d| d.recipient() == new_x);
+            deltas = rest;
+            reshared.push(combine_reshare(new_x, old_quorum, &mine).unwrap());
+        }
+        reshared
+    }
+
+    #[test]
+    fn test_reshare_grow() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let shares = construct_shares(2, 3, &secret).unwrap();
+        let reshared = reshare(&shares, &[1, 3], 4, 7);
+
+        assert_eq!(reshared.len(), 7);
+        assert_eq!(reshared[0].threshold(), Some(4));
+        assert_eq!(reshared[0].share_count(), Some(7));
+        assert_eq!(reconstruct(&reshared[3..]).unwrap(), secret);
+        assert_eq!(
+            reconstruct(&reshared[4..]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 3, need: 4 })
+        );
+    }
+    #[test]
+    fn test_reshare_identifier() {
+        let shares = construct_shares(2, 3, &[0x01, 0x02]).unwrap();
+        let reshared = reshare(&shares, &[1, 2], 2, 3);
+        let id = reshared[0].identifier();
+        assert!(id.is_some());
+        assert!(reshared.iter().all(|s| s.identifier() == id));
+
+        // a sub-share from the holder of a share of another split.
+        let mut other = construct_shares(2, 3, &[0x01, 0x02]).unwrap();
+        while other[0].identifier() == shares[0].identifier() {
+            other = construct_shares(2, 3, &[0x01, 0x02]).unwrap();
+        }
+        let mut rng = rand::thread_rng();
+        let deltas: Vec<ReshareDelta> = [&shares[0], &other[1]]
+            .iter()
+            .flat_map(|share| reshare_contribution(share, &[1, 2], 2, 3, &mut rng).unwrap())
+            .filter(|d| d.recipient() == 1)
+            .collect();
+        assert_eq!(
+            combine_reshare(1, &[1, 2], &deltas).err(),
+            Some(SecretSharingError::InconsistentShares { suspects: vec![2] })
+        );
+    }
+    #[test]
+    fn test_reshare_shrink() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let shares = construct_shares(3, 5, &secret).unwrap();
+        let reshared = reshare(&shares, &[2, 4, 5], 2, 2);
+        assert_eq!(reconstruct(&reshared).unwrap(), secret);
+    }
+    #[test]
+    fn test_reshare_rejects_small_quorum() {
+        let shares = construct_shares(3, 5, &[0x01]).unwrap();
+        let mut rng = rand::thread_rng();
+        assert_eq!(
+            reshare_contribution(&shares[0], &[1, 2], 2, 3, &mut rng).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+        assert_eq!(
+            reshare_contribution(&shares[0], &[2, 3, 4], 2, 3, &mut rng).err(),
+            Some(SecretSharingError::UnexpectedMessage)
+        );
+    }
+    #[test]
+    fn test_reshare_delta_encoding() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let shares = construct_shares(2, 3, &secret).unwrap();
+        let mut rng = rand::thread_rng();
+        let mut deltas: Vec<Vec<u8>> = shares[..2]
+            .iter()
+            .flat_map(|share| reshare_contribution(share, &[1, 2], 3, 4, &mut rng).unwrap())
+            .map(|d| d.to_bytes())
+            .collect();
+        let mut reshared = Shares::new();
+        for new_x in 1..=4 {
+            let (mine, rest): (Vec<Vec<u8>>, Vec<Vec<u8>>) = deltas
+                .into_iter()
+                .partition(|d| ReshareDelta::from_bytes(d).unwrap().recipient() == new_x);
+            deltas = rest;
+            let mine: Vec<ReshareDelta> = mine
+                .iter()
+                .map(|d| ReshareDelta::from_bytes(d).unwrap())
+                .collect();
+            reshared.push(combine_reshare(new_x, &[1, 2], &mine).unwrap());
+        }
+        assert_eq!(reshared[0].threshold(), Some(3));
+        assert_eq!(reconstruct(&reshared[1..]).unwrap(), secret);
+
+        for fields in [[1, 2, 0, 4], [1, 2, 5, 4], [1, 5, 3, 4], [0, 2, 3, 4]] {
+            let fields = [fields[0], fields[1], fields[2], fields[3], 0, 7, 0, 9];
+            let bytes = message::encode(RESHARE_DELTA, &fields, &[0xaa]);
+            assert_eq!(
+                ReshareDelta::from_bytes(&bytes).err(),
+                Some(SecretSharingError::ShareMalformed)
+            );
+        }
+        let bytes = message::encode(RESHARE_DELTA, &[1, 2, 3, 4, 0, 7, 0, 9], &[0xaa]);
+        assert_eq!(
+            ReshareDelta::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+    }
+}
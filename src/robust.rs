@@ -0,0 +1,399 @@
+// Robust reconstruction. The shares of one secret byte are a Reed-Solomon
+// codeword: n evaluations of a polynomial of degree < t. Given n >= t + 2e
+// shares, up to e corrupted shares can be corrected with the Berlekamp-Welch
+// algorithm and identified by comparing them against the decoded polynomial.
+// reconstruct_consistent is a cheaper alternative that only detects
+// corruption, by comparing the secrets interpolated from several subsets.
+
+use crate::gf::{self, GfOps};
+use crate::{
+    check_identifiers, check_indices, check_lengths, evaluate, lagrange_coefficients,
+    SecretSharingError, Share,
+};
+use zeroize::{Zeroize, Zeroizing};
+
+// solve solves the linear system `m`, where each row holds the coefficients of
+// the unknowns followed by the constant term, by Gauss-Jordan elimination.
+// Free variables are set to zero. Returns None if the system is inconsistent.
+fn solve(mut m: Vec<Vec<gf::GF256e>>, unknowns: usize) -> Option<Vec<gf::GF256e>> {
+    let mut pivots = Vec::with_capacity(unknowns);
+    let mut row = 0;
+    for col in 0..unknowns {
+        let pivot = match (row..m.len()).find(|&r| m[r][col] != 0) {
+            Some(r) => r,
+            None => continue,
+        };
+        m.swap(row, pivot);
+
+        let inv = m[row][col].inv();
+        for v in m[row].iter_mut() {
+            *v = v.mul(inv);
+        }
+        let pivot_row = Zeroizing::new(m[row].clone());
+        for (r, other) in m.iter_mut().enumerate() {
+            let f = other[col];
+            if r != row && f != 0 {
+                for (v, p) in other.iter_mut().zip(pivot_row.iter()).skip(col) {
+                    *v = v.sub(p.mul(f));
+                }
+            }
+        }
+
+        pivots.push(col);
+        row += 1;
+    }
+
+    // any remaining row reads 0 = c, so it must have c = 0.
+    let consistent = m[row..].iter().all(|r| r[unknowns] == 0);
+    let mut solution = vec![0; unknowns];
+    if consistent {
+        for (r, &col) in pivots.iter().enumerate() {
+            solution[col] = m[r][unknowns];
+        }
+    }
+    for r in m.iter_mut() {
+        r.zeroize();
+    }
+    if !consistent {
+        return None;
+    }
+    Some(solution)
+}
+
+// divide divides polynomial `num` by `den` (coefficients constant term
+// first; `den` monic), returning the quotient if the remainder is zero.
+fn divide(num: &[gf::GF256e], den: &[gf::GF256e]) -> Option<Vec<gf::GF256e>> {
+    let mut rem = Zeroizing::new(num.to_vec());
+    let d = den.len() - 1;
+    if rem.len() <= d {
+        return if rem.iter().all(|&c| c == 0) {
+            Some(vec![0])
+        } else {
+            None
+        };
+    }
+
+    let mut quot = vec![0; rem.len() - d];
+    for i in (0..quot.len()).rev() {
+        let c = rem[i + d];
+        quot[i] = c;
+        for (j, dc) in den.iter().enumerate() {
+            rem[i + j] = rem[i + j].sub(c.mul(*dc));
+        }
+    }
+
+    if rem.iter().any(|&c| c != 0) {
+        return None;
+    }
+    Some(quot)
+}
+
+// decode returns the coefficients of the polynomial of degree < k agreeing with
+// all but at most (n-k)/2 of the points (xs, ys), if one exists.
+fn decode(xs: &[gf::GF256e], ys: &[gf::GF256e], k: usize) -> Option<Vec<gf::GF256e>> {
+    let e = (xs.len() - k) / 2;
+
+    // unknowns are Q's k+e coefficients followed by the e low coefficients of
+    // the monic error locator E. Each point gives Q(x) - y*E(x) = 0.
+    let unknowns = k + 2 * e;
+    let m = xs
+        .iter()
+        .zip(ys)
+        .map(|(&x, &y)| {
+            let mut row = Vec::with_capacity(unknowns + 1);
+            let mut p = 1 as gf::GF256e;
+            for _ in 0..k + e {
+                row.push(p);
+                p = p.mul(x);
+            }
+            let mut p = 1 as gf::GF256e;
+            for _ in 0..e {
+                row.push(y.mul(p));
+                p = p.mul(x);
+            }
+            // p is now x^e, the leading term of E.
+            row.push(y.mul(p));
+            row
+        })
+        .collect();
+
+    let solution = Zeroizing::new(solve(m, unknowns)?);
+    let q = &solution[..k + e];
+    let mut locator = Zeroizing::new(solution[k + e..].to_vec());
+    locator.push(1);
+
+    let mut p = divide(q, &locator)?;
+    if p.len() > k && p[k..].iter().any(|&c| c != 0) {
+        p.zeroize();
+        return None;
+    }
+    Some(p)
+}
+
+// check_shares validates that there are at least `t` shares of one split with
+// distinct, non-zero indices and equal lengths, returning their indices.
+// Shares of another split aren't corrupt shares to be corrected, and are
+// rejected.
+fn check_shares(t: u8, shares: &[Share]) -> Result<Vec<gf::GF256e>, SecretSharingError> {
+    let k = t as usize;
+    if t == 0 {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if shares.is_empty() {
+        return Err(SecretSharingError::NoSharesProvided);
+    }
+    if shares.len() < k {
+        return Err(SecretSharingError::NotEnoughShares {
+            have: shares.len(),
+            need: k,
+        });
+    }
+    let xs: Vec<gf::GF256e> = shares.iter().map(|share| share.x).collect();
+    check_indices(&xs)?;
+    check_identifiers(shares)?;
+    check_lengths(shares)?;
+    Ok(xs)
+}
+
+// reconstruct_robust reconstructs a secret shared with threshold `t` from
+// `shares`, correcting corrupted shares. With n shares, up to (n-t)/2 corrupt
+// shares can be corrected; it returns the secret together with the indices of
+// the shares found to be corrupt. If there are more corrupt shares than can
+// be corrected, TooManyCorruptShares is returned rather than a wrong secret,
+// as long as the corruption is detectable with the redundancy available.
+pub fn reconstruct_robust(
+    t: u8,
+    shares: &[Share],
+) -> Result<(Vec<u8>, Vec<u8>), SecretSharingError> {
+    let xs = check_shares(t, shares)?;
+    let k = t as usize;
+    let sz = shares[0].y.len();
+
+    // interpolating the first t shares and checking the rest against it is
+    // enough when nothing is corrupt, which is the common case.
+    let basis: Vec<Vec<gf::GF256e>> = xs[k..]
+        .iter()
+        .map(|&x| lagrange_coefficients(&xs[..k], x))
+        .collect();
+    let at_zero = lagrange_coefficients(&xs[..k], 0);
+
+    let mut corrupt = vec![false; shares.len()];
+    let mut secret = Zeroizing::new(Vec::with_capacity(sz));
+    let mut ys = Zeroizing::new(vec![0; shares.len()]);
+    for i in 0..sz {
+        for (y, share) in ys.iter_mut().zip(shares) {
+            *y = share.y[i];
+        }
+
+        let dot = |basis: &[gf::GF256e]| {
+            basis
+                .iter()
+                .zip(ys.iter())
+                .fold(0, |acc: gf::GF256e, (l, y)| acc.add(l.mul(*y)))
+        };
+        let consistent = basis.iter().zip(ys[k..].iter()).all(|(b, &y)| dot(b) == y);
+        if consistent {
+            secret.push(dot(&at_zero));
+            continue;
+        }
+
+        let p = match decode(&xs, &ys, k) {
+            Some(p) => Zeroizing::new(p),
+            None => return Err(SecretSharingError::TooManyCorruptShares { byte: i }),
+        };
+        for (j, (&x, &y)) in xs.iter().zip(ys.iter()).enumerate() {
+            if evaluate(&p, x) != y {
+                corrupt[j] = true;
+            }
+        }
+        secret.push(p[0]);
+    }
+
+    let corrupt = xs
+        .iter()
+        .zip(corrupt)
+        .filter(|&(_, c)| c)
+        .map(|(&x, _)| x)
+        .collect();
+    Ok((std::mem::take(&mut *secret), corrupt))
+}
+
+// reconstruct_consistent reconstructs a secret shared with threshold `t` from
+// more than t shares, checking that every share agrees with the others. The
+// secret is interpolated from the n cyclic windows of t consecutive shares,
+// which together use every share, and the results are compared. On
+// disagreement InconsistentShares is returned. If more than half of the
+// windows agree, it names the shares that appear in no agreeing window;
+// otherwise there is no result to trust and every share is named. A single
+// bad share is in t windows, so it can only be pinpointed when n > 2t.
+pub fn reconstruct_consistent(t: u8, shares: &[Share]) -> Result<Vec<u8>, SecretSharingError> {
+    let xs = check_shares(t, shares)?;
+    let k = t as usize;
+    let n = shares.len();
+    if n == k {
+        return Err(SecretSharingError::NotEnoughShares {
+            have: n,
+            need: k + 1,
+        });
+    }
+
+    let results: Vec<Zeroizing<Vec<u8>>> = (0..n)
+        .map(|w| {
+            let window: Vec<usize> = (w..w + k).map(|i| i % n).collect();
+            let wxs: Vec<gf::GF256e> = window.iter().map(|&i| xs[i]).collect();
+            let basis = lagrange_coefficients(&wxs, 0);
+            (0..shares[0].y.len())
+                .map(|b| {
+                    window
+                        .iter()
+                        .zip(basis.iter())
+                        .fold(0, |acc: gf::GF256e, (&i, l)| acc.add(l.mul(shares[i].y[b])))
+                })
+                .collect::<Vec<u8>>()
+                .into()
+        })
+        .collect();
+
+    if results.iter().all(|r| *r == results[0]) {
+        return Ok(results[0].to_vec());
+    }
+
+    // a result held by most windows is taken as the secret; shares that only
+    // ever appear in windows disagreeing with it are suspect.
+    let majority = match results
+        .iter()
+        .find(|r| 2 * results.iter().filter(|o| o == r).count() > n)
+    {
+        Some(majority) => majority,
+        None => {
+            return Err(SecretSharingError::InconsistentShares {
+                suspects: xs.to_vec(),
+            })
+        }
+    };
+    let mut vouched = vec![false; n];
+    for (w, r) in results.iter().enumerate() {
+        if r == majority {
+            for i in w..w + k {
+                vouched[i % n] = true;
+            }
+        }
+    }
+    let suspects = xs
+        .iter()
+        .zip(vouched)
+        .filter(|&(_, v)| !v)
+        .map(|(&x, _)| x)
+        .collect();
+    Err(SecretSharingError::InconsistentShares { suspects })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_no_corruption() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let shares = construct_shares(3, 7, &secret).unwrap();
+        let (recovered, corrupt) = reconstruct_robust(3, &shares).unwrap();
+        assert_eq!(recovered, secret);
+        assert!(corrupt.is_empty());
+    }
+    #[test]
+    fn test_corrects_errors() {
+        let secret: Vec<u8> = (0..64).collect();
+        let mut shares = construct_shares(3, 7, &secret).unwrap();
+        // n = 7, t = 3 corrects up to 2 corrupt shares.
+        shares[1].y[5] ^= 0x01;
+        shares[1].y[9] ^= 0xff;
+        shares[4].y[5] ^= 0x42;
+
+        let (recovered, corrupt) = reconstruct_robust(3, &shares).unwrap();
+        assert_eq!(recovered, secret);
+        assert_eq!(corrupt, vec![2, 5]);
+    }
+    #[test]
+    fn test_corrupt_first_shares() {
+        let secret = vec![0x55; 8];
+        let mut shares = construct_shares(2, 6, &secret).unwrap();
+        shares[0].y[0] ^= 0x10;
+        shares[1].y[0] ^= 0x20;
+
+        let (recovered, corrupt) = reconstruct_robust(2, &shares).unwrap();
+        assert_eq!(recovered, secret);
+        assert_eq!(corrupt, vec![1, 2]);
+    }
+    #[test]
+    fn test_too_many_errors() {
+        let secret = vec![0x01, 0x02];
+        let mut shares = construct_shares(3, 5, &secret).unwrap();
+        // n = 5, t = 3 can correct one error, and detects two.
+        shares[0].y[0] ^= 0x01;
+        shares[3].y[0] ^= 0x01;
+        assert_eq!(
+            reconstruct_robust(3, &shares).err(),
+            Some(SecretSharingError::TooManyCorruptShares { byte: 0 })
+        );
+    }
+    #[test]
+    fn test_consistent() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let mut shares = construct_shares(3, 7, &secret).unwrap();
+        assert_eq!(reconstruct_consistent(3, &shares).unwrap(), secret);
+
+        shares[3].y[2] ^= 0x80;
+        assert_eq!(
+            reconstruct_consistent(3, &shares).err(),
+            Some(SecretSharingError::InconsistentShares { suspects: vec![4] })
+        );
+        // with n = 6 only half of the windows avoid the bad share, so none
+        // can be trusted.
+        assert_eq!(
+            reconstruct_consistent(3, &shares[..6]).err(),
+            Some(SecretSharingError::InconsistentShares {
+                suspects: vec![1, 2, 3, 4, 5, 6]
+            })
+        );
+        assert_eq!(
+            reconstruct_consistent(3, &shares[..3]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 3, need: 4 })
+        );
+    }
+    #[test]
+    fn test_consistent_one_extra() {
+        // with n = t + 1 each window leaves out one share, so a bad share
+        // spoils every window but one and no result has a majority.
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let mut shares = construct_shares(3, 4, &secret).unwrap();
+        shares[0].y[1] ^= 0x01;
+        assert_eq!(
+            reconstruct_consistent(3, &shares).err(),
+            Some(SecretSharingError::InconsistentShares {
+                suspects: vec![1, 2, 3, 4]
+            })
+        );
+    }
+    #[test]
+    fn test_mixed_splits() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let mut shares = construct_shares(2, 5, &secret).unwrap();
+        let mut other = construct_shares(2, 5, &secret).unwrap();
+        while other[0].identifier() == shares[0].identifier() {
+            other = construct_shares(2, 5, &secret).unwrap();
+        }
+        shares[4] = other[4].clone();
+        let err = Some(SecretSharingError::InconsistentShares {
+            suspects: vec![1, 2, 3, 4, 5],
+        });
+        assert_eq!(reconstruct_robust(2, &shares).err(), err);
+        assert_eq!(reconstruct_consistent(2, &shares).err(), err);
+    }
+    #[test]
+    fn test_divide() {
+        // (x + 1)(x + 2) = x^2 + 3x + 2 in GF(2^8).
+        assert_eq!(divide(&[2, 3, 1], &[1, 1]), Some(vec![2, 1]));
+        assert_eq!(divide(&[3, 3, 1], &[1, 1]), None);
+    }
+}
@@ -0,0 +1,261 @@
+// Shares sealed to a recipient's X25519 public key, so that a dealer can hand
+// shares out over untrusted channels. Sealing is anonymous, in the style of a
+// sealed box: a fresh ephemeral key pair is generated per share, the
+// XChaCha20-Poly1305 key is derived with HKDF-SHA256 from the X25519 shared
+// secret and both public keys, and only the recipient's secret key can open
+// the result. The format:
+//
+//   offset  size  field
+//   0       4     magic, "SHMB"
+//   4       1     format version, currently 1
+//   5       32    ephemeral public key
+//   37      len   encrypted share, in the canonical binary format
+//   37+len  16    Poly1305 tag
+//
+// The header is authenticated as associated data. Since every key is used
+// once, the nonce is fixed at zero.
+
+use crate::aead::{self, NONCE_LEN, TAG_LEN};
+use crate::x25519::{x25519, BASE_POINT, KEY_LEN};
+use crate::{kdf, ConstantTimeEq, Redacted, SecretSharingError, Share};
+use rand::{CryptoRng, RngCore};
+use std::convert::TryInto;
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
+
+const MAGIC: [u8; 4] = *b"SHMB";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 5 + KEY_LEN;
+const SEAL_INFO: &[u8] = b"rust-shamir sealed share v1";
+
+// RecipientKey is a recipient's X25519 secret key. It is wiped when dropped.
+#[derive(Clone, Zeroize)]
+#[zeroize(drop)]
+pub struct RecipientKey([u8; KEY_LEN]);
+
+// RecipientPublicKey is the public key shares are sealed to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RecipientPublicKey([u8; KEY_LEN]);
+
+impl RecipientKey {
+    // generate returns a new random secret key.
+    pub fn generate() -> RecipientKey {
+        RecipientKey::generate_with_rng(&mut crate::default_rng())
+    }
+
+    // generate_with_rng is like generate, but draws the key from `rng`.
+    pub fn generate_with_rng<R: CryptoRng + RngCore>(rng: &mut R) -> RecipientKey {
+        let mut key = RecipientKey([0; KEY_LEN]);
+        rng.fill_bytes(&mut key.0);
+        key
+    }
+
+    // from_bytes restores a secret key saved with as_bytes.
+    pub fn from_bytes(key: [u8; KEY_LEN]) -> RecipientKey {
+        RecipientKey(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+
+    pub fn public_key(&self) -> RecipientPublicKey {
+        RecipientPublicKey(x25519(&self.0, &BASE_POINT))
+    }
+}
+
+impl fmt::Debug for RecipientKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("RecipientKey")
+            .field(&Redacted(KEY_LEN))
+            .finish()
+    }
+}
+
+impl RecipientPublicKey {
+    pub fn from_bytes(key: [u8; KEY_LEN]) -> RecipientPublicKey {
+        RecipientPublicKey(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+}
+
+// seal_key derives the encryption key from the shared secret and both public
+// keys, or returns None if the shared secret is zero, as it is for a public
+// key of small order.
+fn seal_key(
+    shared: &[u8; KEY_LEN],
+    ephemeral: &[u8; KEY_LEN],
+    recipient: &[u8; KEY_LEN],
+) -> Option<Zeroizing<[u8; KEY_LEN]>> {
+    if shared.ct_eq(&[0u8; KEY_LEN]) {
+        return None;
+    }
+    let mut salt = [0u8; 2 * KEY_LEN];
+    salt[..KEY_LEN].copy_from_slice(ephemeral);
+    salt[KEY_LEN..].copy_from_slice(recipient);
+    let mut prk = kdf::hkdf_extract(&salt, shared);
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    kdf::hkdf_expand(&prk, SEAL_INFO, &mut key[..]);
+    prk.zeroize();
+    Some(key)
+}
+
+// SealedShare is a share encrypted to a recipient's public key.
+#[derive(Clone, Debug)]
+pub struct SealedShare {
+    ephemeral: [u8; KEY_LEN],
+    sealed: Vec<u8>,
+}
+
+impl Share {
+    // seal_to encrypts the share to `recipient`. It returns InvalidPublicKey
+    // for a public key of small order, to which nothing can be sealed
+    // securely.
+    pub fn seal_to(
+        &self,
+        recipient: &RecipientPublicKey,
+    ) -> Result<SealedShare, SecretSharingError> {
+        self.seal_to_with_rng(recipient, &mut crate::default_rng())
+    }
+
+    // seal_to_with_rng is like seal_to, but draws the ephemeral key from
+    // `rng`.
+    pub fn seal_to_with_rng<R: CryptoRng + RngCore>(
+        &self,
+        recipient: &RecipientPublicKey,
+        rng: &mut R,
+    ) -> Result<SealedShare, SecretSharingError> {
+        let ephemeral = RecipientKey::generate_with_rng(rng);
+        let mut out = SealedShare {
+            ephemeral: ephemeral.public_key().0,
+            sealed: Vec::new(),
+        };
+        let shared = Zeroizing::new(x25519(&ephemeral.0, &recipient.0));
+        let key = seal_key(&shared, &out.ephemeral, &recipient.0)
+            .ok_or(SecretSharingError::InvalidPublicKey)?;
+        let plaintext = Zeroizing::new(self.to_bytes());
+        out.sealed = aead::seal(&key, &[0; NONCE_LEN], &out.header(), &plaintext);
+        Ok(out)
+    }
+}
+
+impl SealedShare {
+    fn header(&self) -> [u8; HEADER_LEN] {
+        let mut h = [0u8; HEADER_LEN];
+        h[..4].copy_from_slice(&MAGIC);
+        h[4] = VERSION;
+        h[5..].copy_from_slice(&self.ephemeral);
+        h
+    }
+
+    // open decrypts the share with the recipient's secret key, returning
+    // DecryptionFailed if it was sealed to someone else or tampered with.
+    pub fn open(&self, key: &RecipientKey) -> Result<Share, SecretSharingError> {
+        let shared = Zeroizing::new(x25519(&key.0, &self.ephemeral));
+        let key = seal_key(&shared, &self.ephemeral, &key.public_key().0)
+            .ok_or(SecretSharingError::DecryptionFailed)?;
+        let plaintext = aead::open(&key, &[0; NONCE_LEN], &self.header(), &self.sealed)
+            .ok_or(SecretSharingError::DecryptionFailed)?;
+        Share::from_bytes(&plaintext)
+    }
+
+    // to_bytes encodes the sealed share in the format described above.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.sealed.len());
+        out.extend_from_slice(&self.header());
+        out.extend_from_slice(&self.sealed);
+        out
+    }
+
+    // from_bytes decodes a sealed share produced by to_bytes.
+    pub fn from_bytes(data: &[u8]) -> Result<SealedShare, SecretSharingError> {
+        if data.len() < MAGIC.len() + 1 {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        if data[..MAGIC.len()] != MAGIC {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if data[4] != VERSION {
+            return Err(SecretSharingError::UnsupportedShareVersion(data[4]));
+        }
+        if data.len() < HEADER_LEN + TAG_LEN {
+            return Err(SecretSharingError::ShareTruncated);
+        }
+        Ok(SealedShare {
+            ephemeral: data[5..HEADER_LEN].try_into().unwrap(),
+            sealed: data[HEADER_LEN..].to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{construct_shares, reconstruct};
+
+    #[test]
+    fn test_seal_open() {
+        let secret = b"launch codes";
+        let shares = construct_shares(2, 3, secret).unwrap();
+        let keys: Vec<RecipientKey> = (0..3).map(|_| RecipientKey::generate()).collect();
+        let sealed: Vec<SealedShare> = shares
+            .iter()
+            .zip(keys.iter())
+            .map(|(s, k)| s.seal_to(&k.public_key()).unwrap())
+            .collect();
+
+        let opened: Vec<Share> = sealed
+            .iter()
+            .zip(keys.iter())
+            .skip(1)
+            .map(|(s, k)| {
+                let s = SealedShare::from_bytes(&s.to_bytes()).unwrap();
+                s.open(&RecipientKey::from_bytes(*k.as_bytes())).unwrap()
+            })
+            .collect();
+        assert_eq!(opened[0].index(), 2);
+        assert_eq!(reconstruct(&opened).unwrap(), secret);
+
+        assert_eq!(format!("{:?}", keys[0]), "RecipientKey([REDACTED; 32])");
+    }
+    #[test]
+    fn test_sealed_share_errors() {
+        let share = Share::with_metadata(1, b"key", 2, 3);
+        let key = RecipientKey::generate();
+        let sealed = share.seal_to(&key.public_key()).unwrap();
+        assert_eq!(
+            sealed.open(&RecipientKey::generate()).err(),
+            Some(SecretSharingError::DecryptionFailed)
+        );
+
+        let bytes = sealed.to_bytes();
+        for i in [5, HEADER_LEN, bytes.len() - 1] {
+            let mut bad = bytes.clone();
+            bad[i] ^= 1;
+            assert_eq!(
+                SealedShare::from_bytes(&bad).unwrap().open(&key).err(),
+                Some(SecretSharingError::DecryptionFailed)
+            );
+        }
+        let mut version = bytes.clone();
+        version[4] = 2;
+        assert_eq!(
+            SealedShare::from_bytes(&version).err(),
+            Some(SecretSharingError::UnsupportedShareVersion(2))
+        );
+        assert_eq!(
+            SealedShare::from_bytes(&bytes[..HEADER_LEN]).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+
+        assert_eq!(
+            share
+                .seal_to(&RecipientPublicKey::from_bytes([0; KEY_LEN]))
+                .err(),
+            Some(SecretSharingError::InvalidPublicKey)
+        );
+    }
+}
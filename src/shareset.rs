@@ -0,0 +1,228 @@
+// ShareSet collects shares one at a time, as they arrive from their holders,
+// and checks each against those already collected, so that a duplicate or a
+// share of another secret is caught when it is added rather than turning
+// into a wrong secret at the end. Shares recording the identifier of the
+// split they came from are only accepted from a single split. It tracks how
+// many more shares are needed and only combines them once there are enough.
+
+use crate::{reconstruct, SecretSharingError, Share, Shares};
+
+// Progress is the state of a ShareSet after adding a share.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Progress {
+    // NeedMore gives the number of shares still missing.
+    NeedMore(usize),
+    // Ready means the set holds enough shares to combine.
+    Ready,
+    // ThresholdUnknown means none of the shares record their threshold and
+    // none was given with ShareSet::with_threshold, so the set can't tell
+    // when it is complete.
+    ThresholdUnknown,
+}
+
+// ShareSet is a set of shares of one secret being collected for recovery.
+#[derive(Clone, Debug, Default)]
+pub struct ShareSet {
+    shares: Shares,
+    threshold: Option<u8>,
+}
+
+impl ShareSet {
+    // new returns an empty set, which learns the threshold from the first
+    // share that records it.
+    pub fn new() -> ShareSet {
+        ShareSet::default()
+    }
+
+    // with_threshold returns an empty set for shares with threshold `t`, for
+    // shares that don't record it.
+    pub fn with_threshold(t: u8) -> Result<ShareSet, SecretSharingError> {
+        if t == 0 {
+            return Err(SecretSharingError::TorNisZero);
+        }
+        Ok(ShareSet {
+            shares: Shares::new(),
+            threshold: Some(t),
+        })
+    }
+
+    // add adds `share` to the set and returns the progress made. A share
+    // whose index is already in the set is rejected with DuplicateShare, one
+    // of a different length with ShareLengthMismatch, and one recording a
+    // different threshold, share count or split with InconsistentShares. A
+    // rejected share leaves the set unchanged.
+    pub fn add(&mut self, share: Share) -> Result<Progress, SecretSharingError> {
+        if share.x == 0 {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        if self.shares.iter().any(|s| s.x == share.x) {
+            return Err(SecretSharingError::DuplicateShare { x: share.x.into() });
+        }
+        if let Some(first) = self.shares.first() {
+            if share.y.len() != first.y.len() {
+                return Err(SecretSharingError::ShareLengthMismatch {
+                    index: share.x,
+                    expected: first.y.len(),
+                    actual: share.y.len(),
+                });
+            }
+        }
+        let threshold_differs = self
+            .threshold
+            .zip(share.threshold)
+            .is_some_and(|(a, b)| a != b);
+        let count_differs = self
+            .shares
+            .iter()
+            .filter_map(|s| s.share_count)
+            .zip(share.share_count)
+            .any(|(a, b)| a != b);
+        let split_differs = self
+            .shares
+            .iter()
+            .filter_map(|s| s.identifier)
+            .zip(share.identifier)
+            .any(|(a, b)| a != b);
+        if threshold_differs || count_differs || split_differs {
+            return Err(SecretSharingError::InconsistentShares {
+                suspects: vec![share.x],
+            });
+        }
+
+        self.threshold = self.threshold.or(share.threshold);
+        self.shares.push(share);
+        Ok(self.progress())
+    }
+
+    // progress returns how close the set is to being ready to combine.
+    pub fn progress(&self) -> Progress {
+        match self.threshold {
+            None => Progress::ThresholdUnknown,
+            Some(t) if self.shares.len() >= t as usize => Progress::Ready,
+            Some(t) => Progress::NeedMore(t as usize - self.shares.len()),
+        }
+    }
+
+    pub fn threshold(&self) -> Option<u8> {
+        self.threshold
+    }
+
+    pub fn len(&self) -> usize {
+        self.shares.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shares.is_empty()
+    }
+
+    pub fn shares(&self) -> &[Share] {
+        &self.shares
+    }
+
+    // combine reconstructs the secret once the set is ready. Before then it
+    // returns NotEnoughShares, or ThresholdUnknown if the set can't tell.
+    pub fn combine(&self) -> Result<Vec<u8>, SecretSharingError> {
+        match self.progress() {
+            Progress::Ready => reconstruct(&self.shares),
+            Progress::NeedMore(_) => Err(SecretSharingError::NotEnoughShares {
+                have: self.shares.len(),
+                need: self.threshold.unwrap_or(0) as usize,
+            }),
+            Progress::ThresholdUnknown => Err(SecretSharingError::ThresholdUnknown),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_share_set() {
+        let secret = b"recover me";
+        let shares = construct_shares(3, 5, secret).unwrap();
+        let mut set = ShareSet::new();
+        assert_eq!(set.progress(), Progress::ThresholdUnknown);
+        assert_eq!(set.add(shares[4].clone()).unwrap(), Progress::NeedMore(2));
+        assert_eq!(
+            set.combine().err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 3 })
+        );
+        assert_eq!(set.add(shares[1].clone()).unwrap(), Progress::NeedMore(1));
+        assert_eq!(
+            set.add(shares[1].clone()).err(),
+            Some(SecretSharingError::DuplicateShare { x: 2 })
+        );
+        assert_eq!(set.add(shares[0].clone()).unwrap(), Progress::Ready);
+        assert_eq!(set.combine().unwrap(), secret);
+        assert_eq!(set.add(shares[2].clone()).unwrap(), Progress::Ready);
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.combine().unwrap(), secret);
+    }
+    #[test]
+    fn test_share_set_mismatch() {
+        let shares = construct_shares(2, 3, b"secret").unwrap();
+        let mut set = ShareSet::new();
+        set.add(shares[0].clone()).unwrap();
+
+        let other = construct_shares(3, 3, b"secret").unwrap();
+        assert_eq!(
+            set.add(other[1].clone()).err(),
+            Some(SecretSharingError::InconsistentShares { suspects: vec![2] })
+        );
+        let other = construct_shares(2, 4, b"secret").unwrap();
+        assert_eq!(
+            set.add(other[1].clone()).err(),
+            Some(SecretSharingError::InconsistentShares { suspects: vec![2] })
+        );
+        let other = construct_shares(2, 3, b"longer secret").unwrap();
+        assert_eq!(
+            set.add(other[1].clone()).err(),
+            Some(SecretSharingError::ShareLengthMismatch {
+                index: 2,
+                expected: 6,
+                actual: 13
+            })
+        );
+        let mut other = construct_shares(2, 3, b"secret").unwrap();
+        while other[0].identifier() == shares[0].identifier() {
+            other = construct_shares(2, 3, b"secret").unwrap();
+        }
+        assert_eq!(
+            set.add(other[1].clone()).err(),
+            Some(SecretSharingError::InconsistentShares { suspects: vec![2] })
+        );
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.add(shares[2].clone()).unwrap(), Progress::Ready);
+        assert_eq!(set.combine().unwrap(), b"secret");
+    }
+    #[test]
+    fn test_share_set_without_metadata() {
+        let shares = construct_shares(2, 3, b"bare").unwrap();
+        let bare: Vec<Share> = shares
+            .iter()
+            .map(|s| Share::new(s.index(), s.as_bytes()))
+            .collect();
+
+        let mut set = ShareSet::new();
+        assert_eq!(
+            set.add(bare[0].clone()).unwrap(),
+            Progress::ThresholdUnknown
+        );
+        set.add(bare[1].clone()).unwrap();
+        assert_eq!(
+            set.combine().err(),
+            Some(SecretSharingError::ThresholdUnknown)
+        );
+
+        let mut set = ShareSet::with_threshold(2).unwrap();
+        assert_eq!(set.add(bare[2].clone()).unwrap(), Progress::NeedMore(1));
+        assert_eq!(set.add(bare[0].clone()).unwrap(), Progress::Ready);
+        assert_eq!(set.combine().unwrap(), b"bare");
+        assert_eq!(
+            ShareSet::with_threshold(0).err(),
+            Some(SecretSharingError::TorNisZero)
+        );
+    }
+}
@@ -0,0 +1,254 @@
+// Dealer-signed shares. The dealer signs each share's canonical encoding with
+// Ed25519, and the signature travels with the share along with the dealer's
+// public key, so that recipients can check before a recovery that their
+// shares weren't swapped or forged. Unlike the HMAC tags of auth.rs, checking
+// a signature needs only the dealer's public key, so shareholders can verify
+// shares without being able to forge them.
+//
+// The encoding is the canonical binary format of the share, followed by the
+// 32-byte dealer public key and the 64-byte signature. The signature covers
+// SIGNATURE_CONTEXT and the share encoding exactly as it was signed: a share
+// decoded with from_bytes keeps the bytes it was decoded from, so signatures
+// made before a change to the share format stay valid.
+
+use crate::ed25519::{self, PUBLIC_KEY_LEN, SIGNATURE_LEN};
+use crate::{reconstruct, Redacted, SecretSharingError, Share, Shares};
+use rand::{CryptoRng, RngCore};
+use std::convert::TryInto;
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
+
+const SIGNATURE_CONTEXT: &[u8] = b"rust-shamir signed share v1";
+
+// DealerKey is a dealer's Ed25519 signing key. It is wiped when dropped.
+#[derive(Clone, Zeroize)]
+#[zeroize(drop)]
+pub struct DealerKey([u8; 32]);
+
+// DealerPublicKey is the key shareholders verify signed shares against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DealerPublicKey([u8; PUBLIC_KEY_LEN]);
+
+impl DealerKey {
+    // generate returns a new random signing key.
+    pub fn generate() -> DealerKey {
+        DealerKey::generate_with_rng(&mut crate::default_rng())
+    }
+
+    // generate_with_rng is like generate, but draws the key from `rng`.
+    pub fn generate_with_rng<R: CryptoRng + RngCore>(rng: &mut R) -> DealerKey {
+        let mut key = DealerKey([0; 32]);
+        rng.fill_bytes(&mut key.0);
+        key
+    }
+
+    // from_bytes restores a signing key saved with as_bytes.
+    pub fn from_bytes(key: [u8; 32]) -> DealerKey {
+        DealerKey(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn public_key(&self) -> DealerPublicKey {
+        DealerPublicKey(ed25519::public_key(&self.0))
+    }
+
+    // sign signs `share`.
+    pub fn sign(&self, share: Share) -> SignedShare {
+        let encoded = Zeroizing::new(share.to_bytes());
+        let signature = ed25519::sign(&self.0, &message(&encoded));
+        SignedShare {
+            share,
+            encoded,
+            dealer: self.public_key(),
+            signature,
+        }
+    }
+}
+
+impl fmt::Debug for DealerKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("DealerKey").field(&Redacted(32)).finish()
+    }
+}
+
+impl DealerPublicKey {
+    pub fn from_bytes(key: [u8; PUBLIC_KEY_LEN]) -> DealerPublicKey {
+        DealerPublicKey(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; PUBLIC_KEY_LEN] {
+        &self.0
+    }
+}
+
+// message returns the message signed for a share encoded as `encoded`.
+fn message(encoded: &[u8]) -> Vec<u8> {
+    let mut msg = SIGNATURE_CONTEXT.to_vec();
+    msg.extend_from_slice(encoded);
+    msg
+}
+
+// SignedShare is a share together with the dealer's signature over it.
+#[derive(Clone)]
+pub struct SignedShare {
+    share: Share,
+    // encoded is the encoding of share that the signature covers.
+    encoded: Zeroizing<Vec<u8>>,
+    dealer: DealerPublicKey,
+    signature: [u8; SIGNATURE_LEN],
+}
+
+impl fmt::Debug for SignedShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SignedShare")
+            .field("share", &self.share)
+            .field("dealer", &self.dealer)
+            .finish()
+    }
+}
+
+impl SignedShare {
+    // dealer returns the public key embedded in the share. It is only
+    // meaningful once verify has checked it against a trusted key.
+    pub fn dealer(&self) -> &DealerPublicKey {
+        &self.dealer
+    }
+
+    pub fn signature(&self) -> &[u8; SIGNATURE_LEN] {
+        &self.signature
+    }
+
+    // to_bytes encodes the share in the binary wire format it was signed in,
+    // followed by the dealer public key and the signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.encoded.to_vec();
+        out.extend_from_slice(&self.dealer.0);
+        out.extend_from_slice(&self.signature);
+        out
+    }
+
+    // from_bytes decodes a signed share encoded with to_bytes. It is not
+    // verified until `verify` is called.
+    pub fn from_bytes(data: &[u8]) -> Result<SignedShare, SecretSharingError> {
+        let share_len = data
+            .len()
+            .checked_sub(PUBLIC_KEY_LEN + SIGNATURE_LEN)
+            .ok_or(SecretSharingError::ShareTruncated)?;
+        let (share, rest) = data.split_at(share_len);
+        let (dealer, signature) = rest.split_at(PUBLIC_KEY_LEN);
+        Ok(SignedShare {
+            share: Share::from_bytes(share)?,
+            encoded: Zeroizing::new(share.to_vec()),
+            dealer: DealerPublicKey(dealer.try_into().unwrap()),
+            signature: signature.try_into().unwrap(),
+        })
+    }
+
+    // verify checks that the share was signed by `dealer`, returning the
+    // share if so and ShareTampered otherwise. The embedded public key must
+    // match `dealer`: a share re-signed by someone else is rejected.
+    pub fn verify(&self, dealer: &DealerPublicKey) -> Result<&Share, SecretSharingError> {
+        if self.dealer != *dealer
+            || !ed25519::verify(&dealer.0, &message(&self.encoded), &self.signature)
+        {
+            return Err(SecretSharingError::ShareTampered {
+                index: self.share.x,
+            });
+        }
+        Ok(&self.share)
+    }
+}
+
+// sign_shares signs every one of `shares` with the dealer `key`.
+pub fn sign_shares(shares: Shares, key: &DealerKey) -> Vec<SignedShare> {
+    shares.into_iter().map(|share| key.sign(share)).collect()
+}
+
+// reconstruct_signed verifies every share against the `dealer` public key and
+// reconstructs the secret only if all of them were signed by it.
+pub fn reconstruct_signed(
+    shares: &[SignedShare],
+    dealer: &DealerPublicKey,
+) -> Result<Vec<u8>, SecretSharingError> {
+    let verified = shares
+        .iter()
+        .map(|share| share.verify(dealer).cloned())
+        .collect::<Result<Shares, SecretSharingError>>()?;
+    reconstruct(&verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_signed_roundtrip() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef];
+        let key = DealerKey::generate();
+        let dealer = key.public_key();
+        let shares = sign_shares(construct_shares(2, 3, &secret).unwrap(), &key);
+        for share in shares.iter() {
+            let decoded = SignedShare::from_bytes(&share.to_bytes()).unwrap();
+            assert_eq!(decoded.dealer(), &dealer);
+            assert_eq!(
+                decoded.verify(&dealer).unwrap().index(),
+                share.share.index()
+            );
+        }
+        assert_eq!(reconstruct_signed(&shares[1..], &dealer).unwrap(), secret);
+        assert_eq!(DealerKey::from_bytes(*key.as_bytes()).public_key(), dealer);
+        assert_eq!(format!("{:?}", key), "DealerKey([REDACTED; 32])");
+    }
+    #[test]
+    fn test_signed_forged() {
+        let key = DealerKey::generate();
+        let dealer = key.public_key();
+        let mut shares = sign_shares(construct_shares(2, 3, b"secret").unwrap(), &key);
+
+        shares[1].share.y[0] ^= 0x01;
+        shares[1].encoded = Zeroizing::new(shares[1].share.to_bytes());
+        assert_eq!(
+            reconstruct_signed(&shares, &dealer).err(),
+            Some(SecretSharingError::ShareTampered { index: 2 })
+        );
+
+        // a share signed by another dealer, whatever key it embeds, is
+        // rejected.
+        let forger = DealerKey::generate();
+        let mut forged = forger.sign(shares[0].share.clone());
+        assert!(forged.verify(&forger.public_key()).is_ok());
+        assert!(forged.verify(&dealer).is_err());
+        forged.dealer = dealer;
+        assert_eq!(
+            forged.verify(&dealer).err(),
+            Some(SecretSharingError::ShareTampered { index: 1 })
+        );
+
+        let bytes = shares[0].to_bytes();
+        assert_eq!(
+            SignedShare::from_bytes(&bytes[..PUBLIC_KEY_LEN + SIGNATURE_LEN - 1]).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+    }
+    #[test]
+    fn test_signed_older_format() {
+        // a share signed in version 2 of the binary format, before split
+        // identifiers and labels were added to it.
+        let key = DealerKey::generate();
+        let dealer = key.public_key();
+        let mut bytes = b"SHMR\x02\x02\x03\x01\x00\x00\x00\x02\xaa\xbb".to_vec();
+        let sum = crate::sha256::digest(&bytes);
+        bytes.extend_from_slice(&sum[..4]);
+        let signature = ed25519::sign(key.as_bytes(), &message(&bytes));
+        bytes.extend_from_slice(dealer.as_bytes());
+        bytes.extend_from_slice(&signature);
+
+        let share = SignedShare::from_bytes(&bytes).unwrap();
+        assert_eq!(share.verify(&dealer).unwrap().as_bytes(), [0xaa, 0xbb]);
+        assert_eq!(share.to_bytes(), bytes);
+    }
+}
@@ -0,0 +1,298 @@
+// ShareStore is the storage backend interface for shares, so that
+// applications can keep shares wherever suits them (a directory, a cloud KMS,
+// a smartcard) behind one naming convention: shares are stored and looked up
+// by their index.
+//
+// DirShareStore keeps each share in its own file, share-NN.shamir, in the
+// canonical binary format; MemoryShareStore keeps them in memory.
+// split_to_dir and combine_from_paths wrap the directory layout for backup
+// scripts.
+
+use crate::{construct_shares, reconstruct, SecretSharingError, Share, Shares};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+pub trait ShareStore {
+    // put stores `share` under `index`, replacing any share already stored
+    // there. `index` must be the share's own index.
+    fn put(&mut self, index: u8, share: &Share) -> Result<(), SecretSharingError>;
+
+    // get returns the share stored under `index`, or None if there is none.
+    fn get(&self, index: u8) -> Result<Option<Share>, SecretSharingError>;
+
+    // list returns the indices of the stored shares, in increasing order.
+    fn list(&self) -> Result<Vec<u8>, SecretSharingError>;
+
+    // get_all returns every stored share, ready to be combined.
+    fn get_all(&self) -> Result<Shares, SecretSharingError> {
+        self.list()?
+            .into_iter()
+            .map(|index| {
+                self.get(index)?
+                    .ok_or(SecretSharingError::InvalidShareIndex)
+            })
+            .collect()
+    }
+}
+
+// check_index checks that `share` may be stored under `index`.
+fn check_index(index: u8, share: &Share) -> Result<(), SecretSharingError> {
+    if index == 0 || share.index() != index {
+        return Err(SecretSharingError::InvalidShareIndex);
+    }
+    Ok(())
+}
+
+// share_file_name returns the name a share with index `index` is stored under.
+pub(crate) fn share_file_name(index: u8) -> String {
+    format!("share-{:02}.shamir", index)
+}
+
+// parse_share_file_name returns the index of a file named by share_file_name.
+fn parse_share_file_name(name: &str) -> Option<u8> {
+    let digits = name.strip_prefix("share-")?.strip_suffix(".shamir")?;
+    if digits.len() < 2 || !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits
+        .parse()
+        .ok()
+        .filter(|&x| x != 0 && share_file_name(x) == name)
+}
+
+// DirShareStore stores shares as files in a directory, which is created on
+// the first put if it doesn't exist.
+#[derive(Clone, Debug)]
+pub struct DirShareStore {
+    dir: PathBuf,
+}
+
+impl DirShareStore {
+    pub fn new<P: AsRef<Path>>(dir: P) -> DirShareStore {
+        DirShareStore {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    // path returns the path of the file for share `index`.
+    pub fn path(&self, index: u8) -> PathBuf {
+        self.dir.join(share_file_name(index))
+    }
+}
+
+impl ShareStore for DirShareStore {
+    // put writes the share to a temporary file and renames it into place, so
+    // that a crash never leaves a partly written share. On Unix the file is
+    // readable by its owner only.
+    fn put(&mut self, index: u8, share: &Share) -> Result<(), SecretSharingError> {
+        check_index(index, share)?;
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(index);
+        let tmp = path.with_extension("shamir.tmp");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp)?;
+        file.write_all(&Zeroizing::new(share.to_bytes()))?;
+        file.sync_all()?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn get(&self, index: u8) -> Result<Option<Share>, SecretSharingError> {
+        let data = match fs::read(self.path(index)) {
+            Ok(data) => Zeroizing::new(data),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let share = Share::from_bytes(&data)?;
+        if share.index() != index {
+            return Err(SecretSharingError::InvalidShareIndex);
+        }
+        Ok(Some(share))
+    }
+
+    // list ignores files that aren't named like shares. A directory that
+    // doesn't exist holds no shares.
+    fn list(&self) -> Result<Vec<u8>, SecretSharingError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut indices = Vec::new();
+        for entry in entries {
+            if let Some(index) = entry?.file_name().to_str().and_then(parse_share_file_name) {
+                indices.push(index);
+            }
+        }
+        indices.sort_unstable();
+        Ok(indices)
+    }
+}
+
+// MemoryShareStore keeps shares in memory, for tests and for applications
+// that manage persistence themselves.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryShareStore {
+    shares: BTreeMap<u8, Share>,
+}
+
+impl MemoryShareStore {
+    pub fn new() -> MemoryShareStore {
+        MemoryShareStore::default()
+    }
+}
+
+impl ShareStore for MemoryShareStore {
+    fn put(&mut self, index: u8, share: &Share) -> Result<(), SecretSharingError> {
+        check_index(index, share)?;
+        self.shares.insert(index, share.clone());
+        Ok(())
+    }
+
+    fn get(&self, index: u8) -> Result<Option<Share>, SecretSharingError> {
+        Ok(self.shares.get(&index).cloned())
+    }
+
+    fn list(&self) -> Result<Vec<u8>, SecretSharingError> {
+        Ok(self.shares.keys().copied().collect())
+    }
+}
+
+// split_to_dir splits `secret` into n shares, t of which are required to
+// reconstruct it, and writes them to share-01.shamir ... share-NN.shamir in
+// `dir`, returning their paths. It returns Io(AlreadyExists) if `dir` already
+// holds shares, which may belong to another secret, rather than overwriting
+// them or mixing new shares in with them.
+pub fn split_to_dir<P: AsRef<Path>>(
+    secret: &[u8],
+    t: u8,
+    n: u8,
+    dir: P,
+) -> Result<Vec<PathBuf>, SecretSharingError> {
+    let shares = construct_shares(t, n, secret)?;
+    let mut store = DirShareStore::new(dir);
+    if !store.list()?.is_empty() {
+        return Err(SecretSharingError::Io(std::io::ErrorKind::AlreadyExists));
+    }
+    shares
+        .iter()
+        .map(|share| {
+            store.put(share.index(), share)?;
+            Ok(store.path(share.index()))
+        })
+        .collect()
+}
+
+// combine_from_paths reads shares in the canonical binary format from
+// `paths`, verifying their checksums, and reconstructs the secret.
+pub fn combine_from_paths<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<u8>, SecretSharingError> {
+    let shares = paths
+        .iter()
+        .map(|path| Share::from_bytes(&Zeroizing::new(fs::read(path)?)))
+        .collect::<Result<Shares, SecretSharingError>>()?;
+    reconstruct(&shares)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{construct_shares, reconstruct};
+
+    fn exercise<S: ShareStore>(store: &mut S) {
+        let secret = b"stored secret";
+        let shares = construct_shares(3, 5, secret).unwrap();
+        assert_eq!(store.list().unwrap(), Vec::<u8>::new());
+        for share in shares.iter().rev().take(3) {
+            store.put(share.index(), share).unwrap();
+        }
+        assert_eq!(store.list().unwrap(), vec![3, 4, 5]);
+        assert!(store.get(1).unwrap().is_none());
+        assert_eq!(
+            store.get(4).unwrap().unwrap().as_bytes(),
+            shares[3].as_bytes()
+        );
+        assert_eq!(reconstruct(&store.get_all().unwrap()).unwrap(), secret);
+
+        // putting a share under another index is refused.
+        assert_eq!(
+            store.put(1, &shares[1]).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+    }
+
+    #[test]
+    fn test_memory_store() {
+        exercise(&mut MemoryShareStore::new());
+    }
+    #[test]
+    fn test_dir_store() {
+        let dir = std::env::temp_dir().join(format!("share-store-{}", std::process::id()));
+        let mut store = DirShareStore::new(&dir);
+        exercise(&mut store);
+        assert!(store.path(3).ends_with("share-03.shamir"));
+
+        // stray files are ignored, and a share renamed to another index is
+        // rejected on load.
+        fs::write(dir.join("notes.txt"), b"hello").unwrap();
+        fs::write(dir.join("share-7.shamir"), b"").unwrap();
+        assert_eq!(store.list().unwrap(), vec![3, 4, 5]);
+        fs::rename(store.path(3), store.path(9)).unwrap();
+        assert_eq!(
+            store.get(9).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(parse_share_file_name("share-255.shamir"), Some(255));
+        assert_eq!(parse_share_file_name("share-00.shamir"), None);
+        assert_eq!(parse_share_file_name("share-007.shamir"), None);
+    }
+    #[test]
+    fn test_split_to_dir() {
+        let dir = std::env::temp_dir().join(format!("split-to-dir-{}", std::process::id()));
+        let secret = b"backup me";
+        let paths = split_to_dir(secret, 3, 5, &dir).unwrap();
+        let names: Vec<_> = paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "share-01.shamir",
+                "share-02.shamir",
+                "share-03.shamir",
+                "share-04.shamir",
+                "share-05.shamir"
+            ]
+        );
+        assert_eq!(combine_from_paths(&paths[2..]).unwrap(), secret);
+        assert_eq!(
+            combine_from_paths(&paths[..2]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+
+        assert_eq!(
+            split_to_dir(b"another secret", 2, 2, &dir).err(),
+            Some(SecretSharingError::Io(std::io::ErrorKind::AlreadyExists))
+        );
+        assert_eq!(combine_from_paths(&paths[2..]).unwrap(), secret);
+
+        fs::write(&paths[0], b"not a share").unwrap();
+        assert!(combine_from_paths(&paths[..3]).is_err());
+        assert_eq!(
+            combine_from_paths(&[dir.join("missing")]).err(),
+            Some(SecretSharingError::Io(std::io::ErrorKind::NotFound))
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,294 @@
+// Streaming sharing of secrets too large to hold in memory, such as disk
+// images. The secret is read in fixed-size chunks, each chunk is shared with
+// fresh polynomials, and each participant's share of it is written to that
+// participant's writer as a frame. All multi-byte integers are big-endian.
+//
+// A share stream starts with a header:
+//
+//   offset  size  field
+//   0       4     magic, "SHMS"
+//   4       1     format version, currently 1
+//   5       1     threshold t
+//   6       1     share count n
+//   7       1     share index (x-coordinate)
+//
+// followed by frames:
+//
+//   offset  size  field
+//   0       8     sequence number, counting from 0
+//   8       4     payload length in bytes
+//   12      len   payload (the share of this chunk)
+//   12+len  4     checksum: the first 4 bytes of SHA-256 over everything
+//                 above
+//
+// The last frame has an empty payload, so that a truncated stream is
+// detected rather than silently producing a truncated secret. Sequence
+// numbers let combine_stream detect frames that were reordered, dropped or
+// duplicated.
+
+use crate::{check_quorum, interpolate_at, sha256, Dealer, SecretSharingError, Share};
+use std::io::{self, Read, Write};
+use zeroize::{Zeroize, Zeroizing};
+
+const MAGIC: [u8; 4] = *b"SHMS";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 8;
+const FRAME_HEADER_LEN: usize = 12;
+const CHECKSUM_LEN: usize = 4;
+
+// CHUNK_LEN is the number of secret bytes shared per frame.
+const CHUNK_LEN: usize = 1 << 16;
+
+impl From<io::Error> for SecretSharingError {
+    fn from(e: io::Error) -> SecretSharingError {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => SecretSharingError::ShareTruncated,
+            kind => SecretSharingError::Io(kind),
+        }
+    }
+}
+
+// read_full reads into `buf` until it is full or the reader is exhausted,
+// returning the number of bytes read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(k) => n += k,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+fn write_frame<W: Write>(writer: &mut W, seq: u64, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Zeroizing::new(Vec::with_capacity(
+        FRAME_HEADER_LEN + payload.len() + CHECKSUM_LEN,
+    ));
+    frame.extend_from_slice(&seq.to_be_bytes());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    let checksum = sha256::digest(&frame);
+    frame.extend_from_slice(&checksum[..CHECKSUM_LEN]);
+    writer.write_all(&frame)
+}
+
+// read_frame reads the next frame, returning its sequence number and payload.
+fn read_frame<R: Read>(reader: &mut R) -> Result<(u64, Zeroizing<Vec<u8>>), SecretSharingError> {
+    let mut header = [0u8; FRAME_HEADER_LEN];
+    reader.read_exact(&mut header)?;
+    let mut seq = [0u8; 8];
+    seq.copy_from_slice(&header[..8]);
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&header[8..]);
+    let len = u32::from_be_bytes(len) as usize;
+    if len > CHUNK_LEN {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+
+    let mut rest = Zeroizing::new(vec![0u8; len + CHECKSUM_LEN]);
+    reader.read_exact(&mut rest)?;
+    let mut h = sha256::Sha256::new();
+    h.update(&header);
+    h.update(&rest[..len]);
+    if h.finalize()[..CHECKSUM_LEN] != rest[len..] {
+        return Err(SecretSharingError::ShareChecksumMismatch);
+    }
+    rest.truncate(len);
+    Ok((u64::from_be_bytes(seq), rest))
+}
+
+// split_stream shares everything read from `reader` among `writers`, one per
+// participant, with threshold `t`. Participant i (counting from 1) receives
+// the share written to writers[i-1]. It returns the number of secret bytes
+// shared.
+pub fn split_stream<R: Read, W: Write>(
+    mut reader: R,
+    writers: &mut [W],
+    t: u8,
+) -> Result<u64, SecretSharingError> {
+    if t == 0 || writers.is_empty() {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if writers.len() > 255 {
+        return Err(SecretSharingError::InvalidShareIndex);
+    }
+    if t as usize > writers.len() {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+    let n = writers.len() as u8;
+
+    for (i, w) in writers.iter_mut().enumerate() {
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&[VERSION, t, n, i as u8 + 1]);
+        w.write_all(&header)?;
+    }
+
+    let mut chunk = vec![0u8; CHUNK_LEN];
+    let mut seq = 0u64;
+    let mut total = 0u64;
+    loop {
+        let len = match read_full(&mut reader, &mut chunk) {
+            Ok(len) => len,
+            Err(e) => {
+                chunk.zeroize();
+                return Err(e.into());
+            }
+        };
+        if len == 0 {
+            break;
+        }
+
+        let dealer = Dealer::new(t, &chunk[..len]);
+        chunk[..len].zeroize();
+        let dealer = dealer?;
+        for (i, w) in writers.iter_mut().enumerate() {
+            let share = dealer.issue(i as u8 + 1)?;
+            write_frame(w, seq, &share.y)?;
+        }
+        seq += 1;
+        total += len as u64;
+        if len < CHUNK_LEN {
+            break;
+        }
+    }
+
+    for w in writers.iter_mut() {
+        write_frame(w, seq, &[])?;
+        w.flush()?;
+    }
+    Ok(total)
+}
+
+// combine_stream reconstructs a secret shared with split_stream from the
+// share streams in `readers`, writing it to `writer` a chunk at a time. At
+// least t streams from distinct participants are required. It returns the
+// number of secret bytes written. If an error is returned part of the secret
+// may already have been written.
+pub fn combine_stream<R: Read, W: Write>(
+    readers: &mut [R],
+    mut writer: W,
+) -> Result<u64, SecretSharingError> {
+    let mut meta = Vec::with_capacity(readers.len());
+    for r in readers.iter_mut() {
+        let mut header = [0u8; HEADER_LEN];
+        r.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if header[4] != VERSION {
+            return Err(SecretSharingError::UnsupportedShareVersion(header[4]));
+        }
+        if header[5] == 0 || header[6] == 0 || header[7] == 0 {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        meta.push((header[5], header[6], header[7]));
+    }
+    if meta.is_empty() {
+        return Err(SecretSharingError::NoSharesProvided);
+    }
+    let empty: Vec<Share> = meta
+        .iter()
+        .map(|&(t, n, x)| Share::with_metadata(x, &[], t, n))
+        .collect();
+    check_quorum(&empty)?;
+
+    let mut seq = 0u64;
+    let mut total = 0u64;
+    loop {
+        let mut shares = Vec::with_capacity(readers.len());
+        for (r, &(t, n, x)) in readers.iter_mut().zip(meta.iter()) {
+            let (got, payload) = read_frame(r)?;
+            if got != seq {
+                return Err(SecretSharingError::ChunkOutOfOrder { expected: seq, got });
+            }
+            shares.push(Share::with_metadata(x, &payload, t, n));
+        }
+        if shares.iter().all(|share| share.is_empty()) {
+            break;
+        }
+
+        let mut chunk = interpolate_at(&shares, 0)?;
+        let written = writer.write_all(&chunk);
+        chunk.zeroize();
+        written?;
+        total += shares[0].len() as u64;
+        seq += 1;
+    }
+
+    writer.flush()?;
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn split(secret: &[u8], t: u8, n: usize) -> Vec<Vec<u8>> {
+        let mut writers = vec![Vec::new(); n];
+        assert_eq!(
+            split_stream(secret, &mut writers, t).unwrap(),
+            secret.len() as u64
+        );
+        writers
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        let mut rng = rand::thread_rng();
+        for &len in &[0, 1, CHUNK_LEN - 1, CHUNK_LEN, 2 * CHUNK_LEN + 7] {
+            let secret: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let streams = split(&secret, 3, 5);
+
+            let mut readers: Vec<&[u8]> = streams[1..4].iter().map(|s| &s[..]).collect();
+            let mut out = Vec::new();
+            assert_eq!(combine_stream(&mut readers, &mut out).unwrap(), len as u64);
+            assert_eq!(out, secret);
+
+            let mut readers: Vec<&[u8]> = streams[..2].iter().map(|s| &s[..]).collect();
+            assert_eq!(
+                combine_stream(&mut readers, &mut Vec::new()).err(),
+                Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+            );
+        }
+    }
+    #[test]
+    fn test_stream_damage() {
+        let secret = vec![0x5a; 2 * CHUNK_LEN + 7];
+        let streams = split(&secret, 2, 2);
+        let frame_len = FRAME_HEADER_LEN + CHUNK_LEN + CHECKSUM_LEN;
+
+        // truncating a stream, even at a frame boundary, is detected.
+        let truncated = &streams[1][..HEADER_LEN + 2 * frame_len];
+        let mut readers = vec![&streams[0][..], truncated];
+        assert_eq!(
+            combine_stream(&mut readers, &mut Vec::new()).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+
+        // swapping the first two frames is detected.
+        let mut swapped = streams[1][..HEADER_LEN].to_vec();
+        swapped.extend_from_slice(&streams[1][HEADER_LEN + frame_len..HEADER_LEN + 2 * frame_len]);
+        swapped.extend_from_slice(&streams[1][HEADER_LEN..HEADER_LEN + frame_len]);
+        swapped.extend_from_slice(&streams[1][HEADER_LEN + 2 * frame_len..]);
+        let mut readers = vec![&streams[0][..], &swapped[..]];
+        assert_eq!(
+            combine_stream(&mut readers, &mut Vec::new()).err(),
+            Some(SecretSharingError::ChunkOutOfOrder {
+                expected: 0,
+                got: 1
+            })
+        );
+
+        let mut corrupt = streams[1].clone();
+        corrupt[HEADER_LEN + FRAME_HEADER_LEN] ^= 1;
+        let mut readers = vec![&streams[0][..], &corrupt[..]];
+        assert_eq!(
+            combine_stream(&mut readers, &mut Vec::new()).err(),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+// Known-answer test vectors. Each vector fixes a secret, (t, n) and a seed for
+// a ChaCha20 RNG (rand_chacha's ChaCha20Rng), and lists the shares
+// insecure_deterministic_split produces from them. The coefficients come from a
+// single fill_bytes call of len*t bytes: the polynomial of secret byte i takes
+// bytes i*t to (i+1)*t-1 as its coefficients, constant term first, with the
+// constant term then replaced by the secret byte. Share x is at index x, over
+// GF(2^8) with reduction polynomial 0x11b.
+//
+// The vectors let other implementations, and future versions of this crate,
+// check byte-exact compatibility. They are generated by
+// `cargo run --example test_vectors`; changing them is a breaking change.
+
+use crate::{insecure_deterministic_split, SecretSharingError, Shares};
+
+// TestVector is a secret, its sharing parameters and the expected shares, as
+// (index, payload) pairs.
+pub struct TestVector {
+    pub secret: &'static [u8],
+    pub threshold: u8,
+    pub share_count: u8,
+    pub seed: [u8; 32],
+    pub shares: &'static [(u8, &'static [u8])],
+}
+
+// generate splits `secret` with threshold `t` into `n` shares, drawing the
+// coefficients from a ChaCha20 RNG seeded with `seed`.
+pub fn generate(t: u8, n: u8, secret: &[u8], seed: [u8; 32]) -> Result<Shares, SecretSharingError> {
+    insecure_deterministic_split(seed, t, n, secret)
+}
+
+pub const TEST_VECTORS: &[TestVector] = &[
+    TestVector {
+        secret: &[],
+        threshold: 2,
+        share_count: 3,
+        seed: [0; 32],
+        shares: &[(1, &[]), (2, &[]), (3, &[])],
+    },
+    TestVector {
+        secret: &[0x00],
+        threshold: 1,
+        share_count: 1,
+        seed: [0; 32],
+        shares: &[(1, &[0x00])],
+    },
+    TestVector {
+        secret: &[0xff],
+        threshold: 2,
+        share_count: 2,
+        seed: [1; 32],
+        shares: &[(1, &[0xc0]), (2, &[0x81])],
+    },
+    TestVector {
+        secret: &[
+            0x72, 0x75, 0x73, 0x74, 0x2d, 0x73, 0x68, 0x61, 0x6d, 0x69, 0x72,
+        ],
+        threshold: 2,
+        share_count: 3,
+        seed: [2; 32],
+        shares: &[
+            (
+                1,
+                &[
+                    0xd3, 0xdd, 0xb0, 0x12, 0x39, 0xbf, 0x1e, 0x00, 0xec, 0x08, 0x1f,
+                ],
+            ),
+            (
+                2,
+                &[
+                    0x2b, 0x3e, 0xee, 0xb8, 0x05, 0xf0, 0x84, 0xa3, 0x74, 0xab, 0xa8,
+                ],
+            ),
+            (
+                3,
+                &[
+                    0x8a, 0x96, 0x2d, 0xde, 0x11, 0x3c, 0xf2, 0xc2, 0xf5, 0xca, 0xc5,
+                ],
+            ),
+        ],
+    },
+    TestVector {
+        secret: &[
+            0x63, 0x6f, 0x72, 0x72, 0x65, 0x63, 0x74, 0x20, 0x68, 0x6f, 0x72, 0x73, 0x65, 0x20,
+            0x62, 0x61, 0x74, 0x74, 0x65, 0x72, 0x79, 0x20, 0x73, 0x74, 0x61, 0x70, 0x6c, 0x65,
+        ],
+        threshold: 3,
+        share_count: 5,
+        seed: [3; 32],
+        shares: &[
+            (
+                1,
+                &[
+                    0x65, 0xee, 0xbe, 0x6f, 0x44, 0x69, 0x74, 0x5a, 0x22, 0xaf, 0x19, 0x45, 0x4e,
+                    0xd3, 0x07, 0xcf, 0xd8, 0x0e, 0x17, 0x18, 0x31, 0x66, 0xfb, 0xd5, 0xd7, 0xfd,
+                    0x6b, 0xfb,
+                ],
+            ),
+            (
+                2,
+                &[
+                    0x9e, 0x97, 0x17, 0xbc, 0xf5, 0xc8, 0x2b, 0xcc, 0x27, 0x66, 0xe9, 0xe8, 0xb5,
+                    0x7a, 0x76, 0x4b, 0xc5, 0x57, 0xb1, 0x29, 0x9b, 0x17, 0x37, 0x27, 0x68, 0x4a,
+                    0x2a, 0xe7,
+                ],
+            ),
+            (
+                3,
+                &[
+                    0x98, 0x16, 0xdb, 0xa1, 0xd4, 0xc2, 0x2b, 0xb6, 0x6d, 0xa6, 0x82, 0xde, 0x9e,
+                    0x89, 0x13, 0xe5, 0x69, 0x2d, 0xc3, 0x43, 0xd3, 0x51, 0xbf, 0x86, 0xde, 0xc7,
+                    0x2d, 0x79,
+                ],
+            ),
+            (
+                4,
+                &[
+                    0x6b, 0x2d, 0x0d, 0x08, 0x3b, 0xe4, 0xad, 0x83, 0xb7, 0x03, 0x70, 0xaf, 0xf0,
+                    0x3e, 0x1f, 0x9a, 0xe8, 0x43, 0x16, 0xce, 0x75, 0x94, 0xdc, 0xfa, 0x90, 0xe8,
+                    0xdb, 0xd8,
+                ],
+            ),
+            (
+                5,
+                &[
+                    0x6d, 0xac, 0xc1, 0x15, 0x1a, 0xee, 0xad, 0xf9, 0xfd, 0xc3, 0x1b, 0x99, 0xdb,
+                    0xcd, 0x7a, 0x34, 0x44, 0x39, 0x64, 0xa4, 0x3d, 0xd2, 0x54, 0x5b, 0x26, 0x65,
+                    0xdc, 0x46,
+                ],
+            ),
+        ],
+    },
+    TestVector {
+        secret: &[
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f,
+        ],
+        threshold: 5,
+        share_count: 8,
+        seed: [4; 32],
+        shares: &[
+            (
+                1,
+                &[
+                    0x45, 0xcf, 0x2d, 0xe3, 0x2f, 0x39, 0xf1, 0xbc, 0x73, 0x71, 0xaa, 0x91, 0x99,
+                    0xf0, 0x67, 0x2f, 0xd0, 0x3b, 0x54, 0x33, 0x7e, 0xd3, 0x6d, 0x53, 0xbb, 0xdc,
+                    0xfe, 0x8e, 0x6e, 0x0c, 0x6f, 0xfc,
+                ],
+            ),
+            (
+                2,
+                &[
+                    0x47, 0xe0, 0xc8, 0x67, 0x97, 0x4a, 0x83, 0x94, 0x6f, 0xa9, 0xc2, 0x23, 0x03,
+                    0x4a, 0xf0, 0x92, 0x61, 0x68, 0x3a, 0xa5, 0x83, 0x20, 0x57, 0xc2, 0xb1, 0x98,
+                    0x52, 0xd4, 0x34, 0xbf, 0xf7, 0x16,
+                ],
+            ),
+            (
+                3,
+                &[
+                    0x52, 0x1f, 0x98, 0x17, 0x1b, 0x4a, 0x2d, 0x23, 0xce, 0xf0, 0x95, 0x49, 0xa3,
+                    0x48, 0x84, 0x19, 0x4e, 0xcf, 0xf1, 0x01, 0xa8, 0xc4, 0xbf, 0x68, 0x31, 0x8a,
+                    0x80, 0xd0, 0xde, 0x4a, 0xe3, 0x0a,
+                ],
+            ),
+            (
+                4,
+                &[
+                    0x86, 0x5e, 0x32, 0xe9, 0xa4, 0xdf, 0x0e, 0x01, 0x05, 0xed, 0xaf, 0x79, 0x59,
+                    0x96, 0x64, 0x14, 0x69, 0x19, 0x74, 0x06, 0x9f, 0xd1, 0x10, 0x92, 0xf7, 0x6c,
+                    0xd3, 0x8d, 0x9d, 0xfc, 0x47, 0x4d,
+                ],
+            ),
+            (
+                5,
+                &[
+                    0x38, 0x36, 0x04, 0x44, 0x70, 0x6b, 0x34, 0x92, 0x94, 0x53, 0x0b, 0xf5, 0x72,
+                    0x5f, 0x43, 0xe3, 0xfd, 0x30, 0x31, 0x13, 0x68, 0xdb, 0x2c, 0x84, 0x9e, 0x6d,
+                    0x83, 0x53, 0x92, 0x83, 0x73, 0x9a,
+                ],
+            ),
+            (
+                6,
+                &[
+                    0x77, 0x2c, 0x2d, 0x61, 0x78, 0x6b, 0x75, 0xf2, 0xe8, 0x5e, 0x9e, 0x90, 0xe5,
+                    0x68, 0x72, 0xa6, 0x21, 0x64, 0x58, 0xfc, 0x36, 0xef, 0xa5, 0x76, 0x5d, 0x0f,
+                    0x30, 0xa6, 0x19, 0x3f, 0xab, 0xfd,
+                ],
+            ),
+            (
+                7,
+                &[
+                    0x99, 0x75, 0x64, 0x5c, 0x0b, 0xe3, 0x16, 0x6d, 0xa3, 0xc1, 0xcd, 0xec, 0xfb,
+                    0x5e, 0x48, 0xfa, 0x5a, 0xc0, 0x90, 0x6d, 0x80, 0xc7, 0x0a, 0x8e, 0x17, 0xd9,
+                    0x56, 0xe9, 0x8e, 0xa4, 0xfa, 0xd5,
+                ],
+            ),
+            (
+                8,
+                &[
+                    0xb1, 0x41, 0x06, 0x3c, 0x65, 0xc1, 0x09, 0xdd, 0x7c, 0x7b, 0x8f, 0x26, 0x15,
+                    0xf3, 0xaf, 0x35, 0xa8, 0xb8, 0x35, 0x8b, 0xf6, 0xac, 0xea, 0x01, 0xf0, 0xa7,
+                    0x8b, 0x35, 0xfb, 0x36, 0x23, 0x9f,
+                ],
+            ),
+        ],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reconstruct;
+
+    #[test]
+    fn test_known_answers() {
+        assert!(!TEST_VECTORS.is_empty());
+        for v in TEST_VECTORS {
+            let shares = generate(v.threshold, v.share_count, v.secret, v.seed).unwrap();
+            assert_eq!(shares.len(), v.shares.len());
+            for (share, &(x, y)) in shares.iter().zip(v.shares) {
+                assert_eq!(share.index(), x);
+                assert_eq!(share.as_bytes(), y);
+            }
+            let t = v.threshold as usize;
+            assert_eq!(reconstruct(&shares[shares.len() - t..]).unwrap(), v.secret);
+        }
+    }
+}
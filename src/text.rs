@@ -0,0 +1,239 @@
+// Textual encodings of a Share, for pasting into config files, password
+// managers and chat messages. All of them encode the canonical binary format
+// of format.rs, so they carry its checksum and metadata:
+//
+//   to_hex      lowercase hex
+//   to_base64   standard base64 (RFC 4648) with padding
+//   Display     "shamir1-<index>-<hex>", e.g. shamir1-3-53484d52...
+//
+// FromStr accepts any of the three, the bech32m encoding of bech32.rs and the
+// URI of uri.rs, telling them apart by their prefix: the binary format starts
+// with the magic "SHMR", which is "53484d52" in hex and "U0hN" in base64,
+// bech32m strings start with "shamir1" but, unlike Display, no dash, and URIs
+// with "shamir:". In the prefixed form, the index must match the one in the
+// encoded share.
+
+use crate::format::MAGIC;
+use crate::uri::SCHEME;
+use crate::{SecretSharingError, Share};
+use std::fmt;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+// PREFIX starts the Display form; the 1 is the version of the text format.
+const PREFIX: &str = "shamir1-";
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn hex_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(2 * data.len());
+    for b in data {
+        out.push(HEX[(b >> 4) as usize] as char);
+        out.push(HEX[(b & 0xf) as usize] as char);
+    }
+    out
+}
+
+pub(crate) fn hex_decode(s: &str) -> Result<Zeroizing<Vec<u8>>, SecretSharingError> {
+    let digit = |c: u8| match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(SecretSharingError::ShareMalformed),
+    };
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(2) {
+        return Err(SecretSharingError::ShareTruncated);
+    }
+    let mut out = Zeroizing::new(Vec::with_capacity(s.len() / 2));
+    for pair in s.chunks(2) {
+        out.push(digit(pair[0])? << 4 | digit(pair[1])?);
+    }
+    Ok(out)
+}
+
+pub(crate) fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+pub(crate) fn base64_decode(s: &str) -> Result<Zeroizing<Vec<u8>>, SecretSharingError> {
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(4) {
+        return Err(SecretSharingError::ShareTruncated);
+    }
+    let mut out = Zeroizing::new(Vec::with_capacity(s.len() / 4 * 3));
+    for (i, chunk) in s.chunks(4).enumerate() {
+        let last = i == s.len() / 4 - 1;
+        let pad = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if pad > 2 || (pad > 0 && !last) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - pad] {
+            let v = BASE64
+                .iter()
+                .position(|&b| b == c)
+                .ok_or(SecretSharingError::ShareMalformed)?;
+            n = n << 6 | v as u32;
+        }
+        n <<= 6 * pad;
+        out.extend_from_slice(&[(n >> 16) as u8, (n >> 8) as u8, n as u8][..3 - pad]);
+    }
+    Ok(out)
+}
+
+impl Share {
+    // to_hex encodes the share as lowercase hex.
+    pub fn to_hex(&self) -> String {
+        hex_encode(&Zeroizing::new(self.to_bytes()))
+    }
+
+    // to_base64 encodes the share as standard, padded base64.
+    pub fn to_base64(&self) -> String {
+        base64_encode(&Zeroizing::new(self.to_bytes()))
+    }
+}
+
+impl fmt::Display for Share {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}-{}", PREFIX, self.x, self.to_hex())
+    }
+}
+
+impl FromStr for Share {
+    type Err = SecretSharingError;
+
+    // from_str parses a share encoded by to_hex, to_base64, to_bech32, to_uri
+    // or Display.
+    fn from_str(s: &str) -> Result<Share, SecretSharingError> {
+        let s = s.trim();
+        if let Some(rest) = s.strip_prefix(PREFIX) {
+            let (index, hex) = rest
+                .split_once('-')
+                .ok_or(SecretSharingError::ShareMalformed)?;
+            let index: u8 = index
+                .parse()
+                .map_err(|_| SecretSharingError::ShareMalformed)?;
+            let share = Share::from_bytes(&hex_decode(hex)?)?;
+            if share.x != index {
+                return Err(SecretSharingError::ShareMalformed);
+            }
+            return Ok(share);
+        }
+
+        if s.get(..SCHEME.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(SCHEME))
+        {
+            return Share::from_uri(s);
+        }
+        if s.get(..PREFIX.len() - 1)
+            .is_some_and(|p| p.eq_ignore_ascii_case(&PREFIX[..PREFIX.len() - 1]))
+        {
+            return Share::from_bech32(s);
+        }
+
+        let magic = s.get(..2 * MAGIC.len()).unwrap_or("");
+        if magic.eq_ignore_ascii_case(&hex_encode(&MAGIC)) {
+            return Share::from_bytes(&hex_decode(s)?);
+        }
+        if s.starts_with(&base64_encode(&MAGIC[..3])) {
+            return Share::from_bytes(&base64_decode(s)?);
+        }
+        Err(SecretSharingError::ShareMalformed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_base64() {
+        // RFC 4648 section 10.
+        for (plain, encoded) in &[
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64_encode(plain.as_bytes()), *encoded);
+            assert_eq!(&base64_decode(encoded).unwrap()[..], plain.as_bytes());
+        }
+        assert!(base64_decode("Zg=").is_err());
+        assert!(base64_decode("Zg==Zm8=").is_err());
+        assert!(base64_decode("Z!==").is_err());
+    }
+    #[test]
+    fn test_text_roundtrip() {
+        let shares = construct_shares(2, 3, b"correct horse").unwrap();
+        for share in shares.iter() {
+            let display = share.to_string();
+            assert!(display.starts_with(&format!("shamir1-{}-53484d52", share.index())));
+            for text in &[
+                display,
+                share.to_hex(),
+                share.to_base64(),
+                share.to_bech32(),
+                share.to_uri(),
+            ] {
+                let parsed: Share = text.parse().unwrap();
+                assert_eq!(parsed.index(), share.index());
+                assert_eq!(parsed.threshold(), Some(2));
+                assert_eq!(parsed.as_bytes(), share.as_bytes());
+            }
+        }
+        let upper: Share = shares[0].to_hex().to_uppercase().parse().unwrap();
+        assert_eq!(upper.as_bytes(), shares[0].as_bytes());
+    }
+    #[test]
+    fn test_text_errors() {
+        let share = Share::with_metadata(3, &[1, 2, 3], 2, 3);
+        let display = share.to_string();
+        assert_eq!(
+            display.replacen("-3-", "-4-", 1).parse::<Share>().err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        let typo = display.replacen("010203", "010208", 1);
+        assert_eq!(
+            typo.parse::<Share>().err(),
+            Some(SecretSharingError::ShareChecksumMismatch)
+        );
+        assert_eq!(
+            "shamir1-3-ü".parse::<Share>().err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        assert_eq!(
+            "ü53484d52".parse::<Share>().err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        assert_eq!(
+            "hello".parse::<Share>().err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+        assert_eq!(
+            share.to_hex()[..20].parse::<Share>().err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+    }
+}
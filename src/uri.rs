@@ -0,0 +1,145 @@
+// A compact URI for a Share, for QR codes and links:
+//
+//   shamir:?v=1&x=3&t=2&d=U0hNUgICAwMAAAAH...
+//
+// v is the version of the URI scheme, x the share index, t the threshold if
+// known, and d the canonical binary format of format.rs in unpadded base64url
+// (RFC 4648 section 5), so a misread code fails its checksum rather than
+// yielding a wrong share. x and t repeat the binary format for people and
+// tools that only look at the query, and must agree with it. Unknown
+// parameters are ignored.
+
+use crate::text::{base64_decode, base64_encode};
+use crate::{SecretSharingError, Share};
+use zeroize::Zeroizing;
+
+pub(crate) const SCHEME: &str = "shamir:";
+const VERSION: u8 = 1;
+
+impl Share {
+    // to_uri encodes the share as a shamir: URI.
+    pub fn to_uri(&self) -> String {
+        let d = Zeroizing::new(base64_encode(&Zeroizing::new(self.to_bytes())));
+        let mut out = format!("{}?v={}&x={}", SCHEME, VERSION, self.x);
+        if let Some(t) = self.threshold {
+            out.push_str(&format!("&t={}", t));
+        }
+        out.push_str("&d=");
+        out.extend(d.trim_end_matches('=').chars().map(|c| match c {
+            '+' => '-',
+            '/' => '_',
+            c => c,
+        }));
+        out
+    }
+
+    // from_uri decodes a share encoded by to_uri. The scheme is
+    // case-insensitive, as QR scanners may upper-case it.
+    pub fn from_uri(uri: &str) -> Result<Share, SecretSharingError> {
+        let uri = uri.trim();
+        let query = uri
+            .get(..SCHEME.len())
+            .filter(|s| s.eq_ignore_ascii_case(SCHEME))
+            .map(|_| &uri[SCHEME.len()..])
+            .ok_or(SecretSharingError::ShareMalformed)?;
+        let query = query.strip_prefix('?').unwrap_or(query);
+
+        let (mut v, mut x, mut t, mut d) = (None, None, None, None);
+        for param in query.split('&') {
+            let (key, value) = param
+                .split_once('=')
+                .ok_or(SecretSharingError::ShareMalformed)?;
+            let slot = match key {
+                "v" => &mut v,
+                "x" => &mut x,
+                "t" => &mut t,
+                "d" => &mut d,
+                _ => continue,
+            };
+            if slot.replace(value).is_some() {
+                return Err(SecretSharingError::ShareMalformed);
+            }
+        }
+
+        let number = |v: &str| {
+            v.parse::<u8>()
+                .map_err(|_| SecretSharingError::ShareMalformed)
+        };
+        match v.map(number).transpose()? {
+            Some(VERSION) => {}
+            Some(v) => return Err(SecretSharingError::UnsupportedShareVersion(v)),
+            None => return Err(SecretSharingError::ShareMalformed),
+        }
+        let d = d.ok_or(SecretSharingError::ShareMalformed)?;
+        if d.contains(['+', '/', '=']) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        let mut padded: Zeroizing<String> = Zeroizing::new(
+            d.chars()
+                .map(|c| match c {
+                    '-' => '+',
+                    '_' => '/',
+                    c => c,
+                })
+                .collect(),
+        );
+        while !padded.len().is_multiple_of(4) {
+            padded.push('=');
+        }
+
+        let share = Share::from_bytes(&base64_decode(&padded)?)?;
+        if x.map(number).transpose()? != Some(share.x) {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        if let Some(t) = t.map(number).transpose()? {
+            if share.threshold != Some(t) {
+                return Err(SecretSharingError::ShareMalformed);
+            }
+        }
+        Ok(share)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::construct_shares;
+
+    #[test]
+    fn test_uri_roundtrip() {
+        for share in construct_shares(3, 5, &[0xfb; 31]).unwrap() {
+            let uri = share.to_uri();
+            assert!(uri.starts_with(&format!("shamir:?v=1&x={}&t=3&d=U0hNUg", share.index())));
+            assert!(!uri.split("&d=").nth(1).unwrap().contains(['+', '/', '=']));
+            for s in &[uri.clone(), uri.replacen("shamir:", "SHAMIR:", 1)] {
+                let parsed = Share::from_uri(s).unwrap();
+                assert_eq!(parsed.index(), share.index());
+                assert_eq!(parsed.threshold(), Some(3));
+                assert_eq!(parsed.as_bytes(), share.as_bytes());
+            }
+        }
+        let share = Share::new(7, b"no metadata");
+        assert!(!share.to_uri().contains("&t="));
+        assert_eq!(Share::from_uri(&share.to_uri()).unwrap().index(), 7);
+    }
+    #[test]
+    fn test_uri_errors() {
+        let uri = Share::with_metadata(3, b"key", 2, 3).to_uri();
+        for (from, to, err) in [
+            ("x=3", "x=4", SecretSharingError::ShareMalformed),
+            ("t=2", "t=3", SecretSharingError::ShareMalformed),
+            ("v=1", "v=2", SecretSharingError::UnsupportedShareVersion(2)),
+            ("v=1", "v=1&v=1", SecretSharingError::ShareMalformed),
+            ("&d=", "&e=", SecretSharingError::ShareMalformed),
+            ("shamir:", "mailto:", SecretSharingError::ShareMalformed),
+        ] {
+            assert_eq!(
+                Share::from_uri(&uri.replacen(from, to, 1)).err(),
+                Some(err),
+                "{}",
+                to
+            );
+        }
+        assert!(Share::from_uri(&uri[..uri.len() - 2]).is_err());
+    }
+}
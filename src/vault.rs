@@ -0,0 +1,173 @@
+// Interoperability with HashiCorp Vault's unseal keys. Vault's shamir package
+// shares over GF(2^8) with the AES polynomial, like this crate, but encodes a
+// part as its y-values followed by a single trailing x byte, and assigns the
+// parts distinct random x-coordinates rather than 1..n. `vault operator init`
+// prints the parts in base64 and, with -format=json, also in hex; `vault
+// operator unseal` accepts either.
+//
+// vault_split produces parts Vault can combine, vault_combine combines parts
+// Vault produced, and Share::to_vault and Share::from_vault convert between
+// the two representations so Vault parts can be used with the rest of the
+// crate, e.g. to rotate or recover one.
+
+use crate::text::{base64_decode, base64_encode, hex_decode};
+use crate::{reconstruct, Dealer, SecretSharingError, Share, Shares};
+use rand::seq::SliceRandom;
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+impl Share {
+    // to_vault encodes the share as a Vault part: the payload followed by the
+    // index.
+    pub fn to_vault(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.y.len() + 1);
+        out.extend_from_slice(&self.y);
+        out.push(self.x);
+        out
+    }
+
+    // to_vault_base64 encodes the share as a Vault unseal key, in the base64
+    // form `vault operator init` prints.
+    pub fn to_vault_base64(&self) -> String {
+        base64_encode(&Zeroizing::new(self.to_vault()))
+    }
+
+    // from_vault decodes a Vault part.
+    pub fn from_vault(part: &[u8]) -> Result<Share, SecretSharingError> {
+        match part.split_last() {
+            Some((&0, _)) => Err(SecretSharingError::InvalidShareIndex),
+            Some((&x, y)) if !y.is_empty() => Ok(Share::new(x, y)),
+            _ => Err(SecretSharingError::ShareTruncated),
+        }
+    }
+
+    // from_vault_key decodes a Vault unseal key in hex or base64. Like
+    // `vault operator unseal`, it tries hex first.
+    pub fn from_vault_key(key: &str) -> Result<Share, SecretSharingError> {
+        let key = key.trim();
+        let part = hex_decode(key).or_else(|_| base64_decode(key))?;
+        Share::from_vault(&part)
+    }
+}
+
+// vault_split shares `secret` the way Vault does: into `n` parts at distinct
+// random x-coordinates, `t` of which are required to reconstruct it. Vault
+// requires at least two parts to combine, so t must be at least 2.
+pub fn vault_split(t: u8, n: u8, secret: &[u8]) -> Result<Shares, SecretSharingError> {
+    vault_split_with_rng(t, n, secret, &mut crate::default_rng())
+}
+
+// vault_split_with_rng is like vault_split, but draws the coefficients and
+// x-coordinates from `rng`.
+pub fn vault_split_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    n: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Shares, SecretSharingError> {
+    if t == 0 || n == 0 {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if t > n {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+    if t < 2 {
+        return Err(SecretSharingError::NotEnoughShares { have: 1, need: 2 });
+    }
+    if secret.is_empty() {
+        return Err(SecretSharingError::ShareTruncated);
+    }
+
+    let dealer = Dealer::with_rng(t, secret, rng)?;
+    let mut xs: Vec<u8> = (1..=255).collect();
+    xs.shuffle(rng);
+    xs[..n as usize]
+        .iter()
+        .map(|&x| {
+            let mut share = dealer.issue(x)?;
+            share.share_count = Some(n);
+            Ok(share)
+        })
+        .collect()
+}
+
+// vault_combine reconstructs the secret from Vault parts, as Vault's
+// shamir.Combine does. Like Vault, it requires at least two parts; the
+// threshold isn't recorded in them, so passing fewer than it returns a wrong
+// secret rather than an error.
+pub fn vault_combine(parts: &[&[u8]]) -> Result<Vec<u8>, SecretSharingError> {
+    if parts.len() < 2 {
+        return Err(SecretSharingError::NotEnoughShares {
+            have: parts.len(),
+            need: 2,
+        });
+    }
+    let shares = parts
+        .iter()
+        .map(|p| Share::from_vault(p))
+        .collect::<Result<Shares, _>>()?;
+    reconstruct(&shares)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_vector() {
+        // 0x42 + x over GF(2^8): 0x43 at x = 1 and 0x40 at x = 2.
+        let parts: [&[u8]; 2] = [&[0x43, 0x01], &[0x40, 0x02]];
+        assert_eq!(vault_combine(&parts).unwrap(), vec![0x42]);
+        let share = Share::from_vault_key("QAI=").unwrap();
+        assert_eq!((share.index(), share.as_bytes()), (2, &[0x40][..]));
+        assert_eq!(share.to_vault_base64(), "QAI=");
+        assert_eq!(Share::from_vault_key("4301").unwrap().index(), 1);
+    }
+    #[test]
+    fn test_vault_roundtrip() {
+        let secret = b"vault master key material 32 by";
+        let shares = vault_split(3, 5, secret).unwrap();
+        let mut xs: Vec<u8> = shares.iter().map(|s| s.index()).collect();
+        xs.sort_unstable();
+        xs.dedup();
+        assert_eq!(xs.len(), 5);
+
+        let parts: Vec<Vec<u8>> = shares.iter().map(|s| s.to_vault()).collect();
+        assert!(parts.iter().all(|p| p.len() == secret.len() + 1));
+        let quorum: Vec<&[u8]> = parts[1..4].iter().map(|p| &p[..]).collect();
+        assert_eq!(vault_combine(&quorum).unwrap(), secret);
+
+        let keys: Vec<Share> = shares
+            .iter()
+            .map(|s| Share::from_vault_key(&s.to_vault_base64()).unwrap())
+            .collect();
+        assert_eq!(reconstruct(&keys[2..]).unwrap(), secret);
+    }
+    #[test]
+    fn test_vault_errors() {
+        assert_eq!(
+            vault_split(1, 3, b"key").err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 2 })
+        );
+        assert_eq!(
+            vault_split(2, 3, b"").err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+        assert_eq!(
+            vault_combine(&[&[0x43, 0x01]]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 2 })
+        );
+        assert_eq!(
+            vault_combine(&[&[0x43, 0x01], &[0x40, 0x01]]).err(),
+            Some(SecretSharingError::DuplicateShare { x: 1 })
+        );
+        assert_eq!(
+            Share::from_vault(&[0x43, 0x00]).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+        assert_eq!(
+            Share::from_vault(&[0x01]).err(),
+            Some(SecretSharingError::ShareTruncated)
+        );
+    }
+}
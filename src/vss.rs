@@ -0,0 +1,533 @@
+// Feldman verifiable secret sharing. The dealer shares a scalar of the
+// Ed25519 field with a random polynomial f(x) = a_0 + a_1 x + ... of degree
+// t-1, as field::split does, and publishes commitments C_j = [a_j]B to its
+// coefficients in the prime-order subgroup of edwards25519. A participant
+// holding f(i) checks it against them before accepting it, since
+//
+//   [f(i)]B = sum_j [i^j]C_j
+//
+// holds only for points on the committed polynomial. A dealer therefore
+// can't hand out shares of different secrets, or shares that don't combine.
+//
+// C_0 = [a_0]B is the public key of the secret: sharing an Ed25519 private
+// key's scalar publishes its public key. The commitments hide the secret only
+// computationally, and not at all if it can be guessed, since a guess can be
+// checked against C_0.
+//
+// Pedersen verifiable secret sharing (split_pedersen) hides the secret
+// unconditionally instead. The dealer shares a second, random polynomial g
+// along with f and commits to C_j = [a_j]B + [b_j]H, where b_j are the
+// coefficients of g and H is a second generator whose discrete logarithm to
+// B nobody knows. Participant i holds f(i) and g(i) and checks
+//
+//   [f(i)]B + [g(i)]H = sum_j [i^j]C_j.
+//
+// For every secret there is a g giving the same commitments, so they reveal
+// nothing about it even to an unbounded adversary; a dealer who knew the
+// logarithm of H could open them to other shares, which is why H is derived
+// from a hash (see pedersen_generator) rather than chosen.
+//
+// Commitments of both kinds are encoded as the 32-byte compressed points
+// (RFC 8032 section 5.1.2), C_0 first.
+
+use crate::ed25519::Point;
+use crate::field::{combine, evaluate, participant, wipe, Field, FieldShare};
+use crate::prime::Ed25519Scalar;
+use crate::sha512::Sha512;
+use crate::SecretSharingError;
+use rand::{CryptoRng, RngCore};
+use std::convert::TryInto;
+use std::fmt;
+use zeroize::Zeroize;
+
+const POINT_LEN: usize = 32;
+const GENERATOR_DOMAIN: &[u8] = b"rust-shamir pedersen generator";
+
+// Commitments are a dealer's published commitments to the coefficients of
+// its sharing polynomial.
+#[derive(Clone)]
+pub struct Commitments(pub(crate) Vec<Point>);
+
+impl fmt::Debug for Commitments {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Commitments")
+            .field("threshold", &self.threshold())
+            .field("public_key", &self.public_key())
+            .finish()
+    }
+}
+
+impl PartialEq for Commitments {
+    fn eq(&self, other: &Commitments) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+// PedersenCommitments are a dealer's published Pedersen commitments to the
+// coefficients of its two sharing polynomials.
+#[derive(Clone, PartialEq)]
+pub struct PedersenCommitments(Commitments);
+
+impl fmt::Debug for PedersenCommitments {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PedersenCommitments")
+            .field("threshold", &self.threshold())
+            .finish()
+    }
+}
+
+impl Commitments {
+    // threshold returns the number of shares needed to reconstruct the
+    // secret, which is the number of coefficients committed to.
+    pub fn threshold(&self) -> u8 {
+        self.0.len() as u8
+    }
+
+    // public_key returns C_0 = [secret]B, the Ed25519 public key of the
+    // shared scalar.
+    pub fn public_key(&self) -> [u8; POINT_LEN] {
+        self.0[0].compress()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|c| c.compress()).collect()
+    }
+
+    // from_bytes decodes commitments produced by to_bytes. It returns
+    // ShareMalformed for the encoding of anything but 1 to 255 points of the
+    // prime-order subgroup: a dealer could otherwise hide small-order
+    // components in them that some participants' checks cancel out.
+    pub fn from_bytes(data: &[u8]) -> Result<Commitments, SecretSharingError> {
+        if data.is_empty()
+            || !data.len().is_multiple_of(POINT_LEN)
+            || data.len() > u8::MAX as usize * POINT_LEN
+        {
+            return Err(SecretSharingError::ShareMalformed);
+        }
+        data.chunks_exact(POINT_LEN)
+            .map(|c| Point::decompress(c.try_into().unwrap()).filter(Point::is_torsion_free))
+            .collect::<Option<Vec<Point>>>()
+            .map(Commitments)
+            .ok_or(SecretSharingError::ShareMalformed)
+    }
+
+    // expected returns sum_j [x^j]C_j, the commitment to f(x), by Horner's
+    // rule.
+    pub(crate) fn expected(&self, x: Ed25519Scalar) -> Point {
+        let x = scalar_bytes(&x);
+        self.0
+            .iter()
+            .rev()
+            .fold(Point::IDENTITY, |acc, c| acc.mul(&x).add(c))
+    }
+}
+
+impl PedersenCommitments {
+    // threshold returns the number of shares needed to reconstruct the
+    // secret.
+    pub fn threshold(&self) -> u8 {
+        self.0.threshold()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    // from_bytes decodes commitments produced by to_bytes, checking them as
+    // Commitments::from_bytes does.
+    pub fn from_bytes(data: &[u8]) -> Result<PedersenCommitments, SecretSharingError> {
+        Commitments::from_bytes(data).map(PedersenCommitments)
+    }
+}
+
+pub(crate) fn scalar_bytes(s: &Ed25519Scalar) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    s.to_bytes(&mut out);
+    out
+}
+
+// mul returns [s]p, wiping the encoding of s afterwards.
+pub(crate) fn mul(p: &Point, s: &Ed25519Scalar) -> Point {
+    let mut b = scalar_bytes(s);
+    let q = p.mul(&b);
+    b.zeroize();
+    q
+}
+
+// base_mul returns [s]B.
+pub(crate) fn base_mul(s: &Ed25519Scalar) -> Point {
+    mul(&Point::base(), s)
+}
+
+// pedersen_generator returns the second generator H of Pedersen commitments.
+// It hashes GENERATOR_DOMAIN and a counter byte with SHA-512, counting up
+// from 0 until the first 32 bytes of the digest decode to a point, and
+// multiplies that point by the cofactor 8 to bring it into the prime-order
+// subgroup. Nobody knows its discrete logarithm to B.
+pub(crate) fn pedersen_generator() -> Point {
+    let mut cofactor = [0u8; 32];
+    cofactor[0] = 8;
+    (0..=u8::MAX)
+        .find_map(|counter| {
+            let mut h = Sha512::new();
+            h.update(GENERATOR_DOMAIN);
+            h.update(&[counter]);
+            let digest = h.finalize();
+            Point::decompress(digest[..32].try_into().unwrap())
+                .map(|p| p.mul(&cofactor))
+                .filter(|p| p.compress() != Point::IDENTITY.compress())
+        })
+        .unwrap()
+}
+
+// check_parameters checks the threshold and share count of a dealing.
+pub(crate) fn check_parameters(t: u8, n: u8) -> Result<(), SecretSharingError> {
+    if t == 0 || n == 0 {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    if t > n {
+        return Err(SecretSharingError::ThresholdExceedsShares);
+    }
+    Ok(())
+}
+
+// random_polynomial returns the coefficients of a random polynomial of
+// degree t-1 with constant term `c`, constant term first.
+pub(crate) fn random_polynomial<R: CryptoRng + RngCore>(
+    t: u8,
+    c: Ed25519Scalar,
+    rng: &mut R,
+) -> Vec<Ed25519Scalar> {
+    let mut coeff = Vec::with_capacity(t as usize);
+    coeff.push(c);
+    coeff.extend((1..t).map(|_| Ed25519Scalar::random(rng)));
+    coeff
+}
+
+// x_coordinate returns the x-coordinate of participant `i`.
+pub(crate) fn x_coordinate(i: u8) -> Ed25519Scalar {
+    Ed25519Scalar::from_u64(i as u64).unwrap()
+}
+
+// tampered returns ShareTampered for the share at `x`.
+pub(crate) fn tampered(x: Ed25519Scalar) -> SecretSharingError {
+    SecretSharingError::ShareTampered {
+        index: participant(x).try_into().unwrap_or(0),
+    }
+}
+
+// reconstruct_checked verifies every one of `shares` with `verify` and
+// interpolates them, returning the first element of the secret. Shares
+// verified against commitments to polynomials of degree t-1 number at least
+// `t`.
+fn reconstruct_checked<V>(
+    t: u8,
+    shares: &[FieldShare<Ed25519Scalar>],
+    verify: V,
+) -> Result<Ed25519Scalar, SecretSharingError>
+where
+    V: Fn(&FieldShare<Ed25519Scalar>) -> Result<(), SecretSharingError>,
+{
+    shares.iter().try_for_each(verify)?;
+    if shares.len() < t as usize {
+        return Err(SecretSharingError::NotEnoughShares {
+            have: shares.len(),
+            need: t as usize,
+        });
+    }
+    let mut secret = combine(shares)?;
+    let s = secret[0];
+    wipe(&mut secret);
+    Ok(s)
+}
+
+// split_verifiable shares `secret` among `n` participants, any `t` of whom
+// can reconstruct it, returning the commitments to publish and the shares of
+// participants 1..n, each holding a single y-value.
+pub fn split_verifiable(
+    t: u8,
+    n: u8,
+    secret: &Ed25519Scalar,
+) -> Result<(Commitments, Vec<FieldShare<Ed25519Scalar>>), SecretSharingError> {
+    split_verifiable_with_rng(t, n, secret, &mut crate::default_rng())
+}
+
+// split_verifiable_with_rng is like split_verifiable, but draws the
+// polynomial coefficients from `rng`.
+pub fn split_verifiable_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    n: u8,
+    secret: &Ed25519Scalar,
+    rng: &mut R,
+) -> Result<(Commitments, Vec<FieldShare<Ed25519Scalar>>), SecretSharingError> {
+    check_parameters(t, n)?;
+    let mut coeff = random_polynomial(t, *secret, rng);
+    let commitments = Commitments(coeff.iter().map(base_mul).collect());
+    let shares = (1..=n)
+        .map(|i| {
+            let x = x_coordinate(i);
+            FieldShare {
+                x,
+                y: vec![evaluate(&coeff, x)],
+            }
+        })
+        .collect();
+    wipe(&mut coeff);
+
+    Ok((commitments, shares))
+}
+
+// verify_share checks that `share` lies on the polynomial committed to by
+// `commitments`. It returns ShareTampered, with the participant number, if
+// it doesn't, and ShareMalformed for a share of other than one y-value.
+pub fn verify_share(
+    commitments: &Commitments,
+    share: &FieldShare<Ed25519Scalar>,
+) -> Result<(), SecretSharingError> {
+    if share.x == Ed25519Scalar::zero() {
+        return Err(SecretSharingError::InvalidShareIndex);
+    }
+    let y = match share.y.as_slice() {
+        [y] => y,
+        _ => return Err(SecretSharingError::ShareMalformed),
+    };
+    if base_mul(y).compress() != commitments.expected(share.x).compress() {
+        return Err(tampered(share.x));
+    }
+    Ok(())
+}
+
+// reconstruct_verified verifies every one of `shares` against `commitments`,
+// as verify_share does, and reconstructs the secret from them. As the
+// commitments record the threshold, too few shares are NotEnoughShares rather
+// than a wrong secret.
+pub fn reconstruct_verified(
+    commitments: &Commitments,
+    shares: &[FieldShare<Ed25519Scalar>],
+) -> Result<Ed25519Scalar, SecretSharingError> {
+    reconstruct_checked(commitments.threshold(), shares, |share| {
+        verify_share(commitments, share)
+    })
+}
+
+// split_pedersen shares `secret` among `n` participants, any `t` of whom can
+// reconstruct it, returning Pedersen commitments to publish and the shares of
+// participants 1..n. Each share holds two y-values: f(i), then g(i).
+pub fn split_pedersen(
+    t: u8,
+    n: u8,
+    secret: &Ed25519Scalar,
+) -> Result<(PedersenCommitments, Vec<FieldShare<Ed25519Scalar>>), SecretSharingError> {
+    split_pedersen_with_rng(t, n, secret, &mut crate::default_rng())
+}
+
+// split_pedersen_with_rng is like split_pedersen, but draws the polynomial
+// coefficients from `rng`.
+pub fn split_pedersen_with_rng<R: CryptoRng + RngCore>(
+    t: u8,
+    n: u8,
+    secret: &Ed25519Scalar,
+    rng: &mut R,
+) -> Result<(PedersenCommitments, Vec<FieldShare<Ed25519Scalar>>), SecretSharingError> {
+    check_parameters(t, n)?;
+    let mut f = random_polynomial(t, *secret, rng);
+    let blinding = Ed25519Scalar::random(rng);
+    let mut g = random_polynomial(t, blinding, rng);
+    let h = pedersen_generator();
+    let commitments = f
+        .iter()
+        .zip(g.iter())
+        .map(|(a, b)| base_mul(a).add(&mul(&h, b)))
+        .collect();
+    let shares = (1..=n)
+        .map(|i| {
+            let x = x_coordinate(i);
+            FieldShare {
+                x,
+                y: vec![evaluate(&f, x), evaluate(&g, x)],
+            }
+        })
+        .collect();
+    wipe(&mut f);
+    wipe(&mut g);
+
+    Ok((PedersenCommitments(Commitments(commitments)), shares))
+}
+
+// verify_pedersen_share checks that `share` lies on the polynomials
+// committed to by `commitments`. It returns ShareTampered, with the
+// participant number, if it doesn't, and ShareMalformed for a share of other
+// than two y-values.
+pub fn verify_pedersen_share(
+    commitments: &PedersenCommitments,
+    share: &FieldShare<Ed25519Scalar>,
+) -> Result<(), SecretSharingError> {
+    if share.x == Ed25519Scalar::zero() {
+        return Err(SecretSharingError::InvalidShareIndex);
+    }
+    let (y, blinding) = match share.y.as_slice() {
+        [y, blinding] => (y, blinding),
+        _ => return Err(SecretSharingError::ShareMalformed),
+    };
+    let actual = base_mul(y).add(&mul(&pedersen_generator(), blinding));
+    if actual.compress() != commitments.0.expected(share.x).compress() {
+        return Err(tampered(share.x));
+    }
+    Ok(())
+}
+
+// reconstruct_pedersen verifies every one of `shares` against `commitments`,
+// as verify_pedersen_share does, and reconstructs the secret from them,
+// returning NotEnoughShares for fewer than the threshold.
+pub fn reconstruct_pedersen(
+    commitments: &PedersenCommitments,
+    shares: &[FieldShare<Ed25519Scalar>],
+) -> Result<Ed25519Scalar, SecretSharingError> {
+    reconstruct_checked(commitments.threshold(), shares, |share| {
+        verify_pedersen_share(commitments, share)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::hex_decode;
+
+    fn bytes(hex: &str) -> [u8; 32] {
+        hex_decode(hex).unwrap()[..].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_vss() {
+        // the scalar of the RFC 8032 test 1 key, whose public key is C_0.
+        let h = bytes("357c83864f2833cb427a2ef1c00a013cfdff2768d980c0a3a520f006904de90f");
+        let secret = Ed25519Scalar::from_clamped(&h);
+        let (commitments, shares) = split_verifiable(3, 5, &secret).unwrap();
+        assert_eq!(
+            commitments.public_key(),
+            bytes("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+        );
+        assert_eq!(commitments.threshold(), 3);
+        for share in shares.iter() {
+            verify_share(&commitments, share).unwrap();
+        }
+        assert_eq!(
+            reconstruct_verified(&commitments, &shares[2..]).unwrap(),
+            secret
+        );
+        assert_eq!(
+            reconstruct_verified(&commitments, &shares[3..]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+        assert_eq!(
+            split_verifiable(3, 2, &secret).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+    }
+    #[test]
+    fn test_vss_rejects_bad_shares() {
+        let secret = Ed25519Scalar::from_u64(42).unwrap();
+        let (commitments, mut shares) = split_verifiable(2, 3, &secret).unwrap();
+
+        // a share off the committed polynomial.
+        shares[1].y[0] = shares[1].y[0].add(Ed25519Scalar::one());
+        assert_eq!(
+            verify_share(&commitments, &shares[1]).err(),
+            Some(SecretSharingError::ShareTampered { index: 2 })
+        );
+        assert_eq!(
+            reconstruct_verified(&commitments, &shares).err(),
+            Some(SecretSharingError::ShareTampered { index: 2 })
+        );
+
+        // a valid share of another dealing.
+        let (_, other) = split_verifiable(2, 3, &secret).unwrap();
+        assert_eq!(
+            verify_share(&commitments, &other[0]).err(),
+            Some(SecretSharingError::ShareTampered { index: 1 })
+        );
+
+        let wide = FieldShare {
+            x: shares[0].x,
+            y: vec![shares[0].y[0]; 2],
+        };
+        assert_eq!(
+            verify_share(&commitments, &wide).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+    }
+    #[test]
+    fn test_pedersen_generator() {
+        let h = pedersen_generator();
+        assert!(h.is_torsion_free());
+        assert_ne!(h.compress(), Point::base().compress());
+        assert_ne!(h.compress(), Point::IDENTITY.compress());
+    }
+    #[test]
+    fn test_pedersen() {
+        let secret = Ed25519Scalar::from_u64(42).unwrap();
+        let (commitments, mut shares) = split_pedersen(3, 4, &secret).unwrap();
+        assert_eq!(commitments.threshold(), 3);
+        for share in shares.iter() {
+            verify_pedersen_share(&commitments, share).unwrap();
+        }
+        assert_eq!(
+            reconstruct_pedersen(&commitments, &shares[1..]).unwrap(),
+            secret
+        );
+        assert_eq!(
+            reconstruct_pedersen(&commitments, &shares[2..]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+        let decoded = PedersenCommitments::from_bytes(&commitments.to_bytes()).unwrap();
+        assert_eq!(decoded, commitments);
+
+        // the same secret dealt again is committed to differently.
+        let (again, _) = split_pedersen(3, 4, &secret).unwrap();
+        assert_ne!(again.to_bytes()[..32], commitments.to_bytes()[..32]);
+
+        // shares with a wrong value or blinding, or without a blinding.
+        shares[0].y[1] = shares[0].y[1].add(Ed25519Scalar::one());
+        assert_eq!(
+            reconstruct_pedersen(&commitments, &shares).err(),
+            Some(SecretSharingError::ShareTampered { index: 1 })
+        );
+        shares[3].y[0] = shares[3].y[0].add(Ed25519Scalar::one());
+        assert_eq!(
+            verify_pedersen_share(&commitments, &shares[3]).err(),
+            Some(SecretSharingError::ShareTampered { index: 4 })
+        );
+        let (_, feldman) = split_verifiable(3, 4, &secret).unwrap();
+        assert_eq!(
+            verify_pedersen_share(&commitments, &feldman[0]).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+    }
+    #[test]
+    fn test_commitments_encoding() {
+        let secret = Ed25519Scalar::from_u64(1).unwrap();
+        let (commitments, _) = split_verifiable(2, 2, &secret).unwrap();
+        let encoded = commitments.to_bytes();
+        assert_eq!(encoded.len(), 64);
+        // [1]B is the base point.
+        assert_eq!(
+            encoded[..32],
+            bytes("5866666666666666666666666666666666666666666666666666666666666666")
+        );
+        assert_eq!(Commitments::from_bytes(&encoded).unwrap(), commitments);
+
+        for bad in [&[][..], &encoded[..31], &[0xff; 32][..]] {
+            assert_eq!(
+                Commitments::from_bytes(bad).err(),
+                Some(SecretSharingError::ShareMalformed)
+            );
+        }
+        // y = 0 is a point of order 4, outside the prime-order subgroup.
+        let mut small = encoded.clone();
+        small[32..].copy_from_slice(&[0; 32]);
+        assert_eq!(
+            Commitments::from_bytes(&small).err(),
+            Some(SecretSharingError::ShareMalformed)
+        );
+    }
+}
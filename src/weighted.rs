@@ -0,0 +1,166 @@
+// Weighted threshold sharing. Each participant is given a weight, such as 3
+// for a CEO and 1 for an engineer, and receives that many ordinary shares of
+// a single (t, total weight) sharing, so any group of participants whose
+// weights add up to t can reconstruct the secret. Participant keeps a
+// participant's shares together under their name.
+//
+// Every share takes one of the 255 share indices, so the weights may add up
+// to at most 255.
+
+use crate::{construct_shares_with_rng, reconstruct, SecretSharingError, Share, Shares};
+use rand::{CryptoRng, RngCore};
+
+// Participant is a named participant and the shares they hold. Their weight is
+// the number of shares.
+#[derive(Clone, Debug)]
+pub struct Participant {
+    name: String,
+    shares: Shares,
+}
+
+impl Participant {
+    // from_shares regroups shares issued by split_weighted to `name`, such as
+    // after loading them from storage.
+    pub fn from_shares(name: &str, shares: Shares) -> Participant {
+        Participant {
+            name: name.to_string(),
+            shares,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn weight(&self) -> usize {
+        self.shares.len()
+    }
+
+    pub fn shares(&self) -> &[Share] {
+        &self.shares
+    }
+}
+
+// split_weighted splits `secret` among `participants`, given as names and
+// weights, so that participants whose weights add up to `threshold` can
+// reconstruct it. Participants receive consecutive share indices in the
+// order given. It returns TorNisZero if the threshold or any weight is zero,
+// ThresholdExceedsShares if the weights add up to less than the threshold
+// and TooManyShares if they add up to more than 255.
+pub fn split_weighted(
+    secret: &[u8],
+    threshold: u8,
+    participants: &[(&str, u8)],
+) -> Result<Vec<Participant>, SecretSharingError> {
+    split_weighted_with_rng(secret, threshold, participants, &mut crate::default_rng())
+}
+
+// split_weighted_with_rng is like split_weighted, but draws the polynomial
+// coefficients from `rng`.
+pub fn split_weighted_with_rng<R: CryptoRng + RngCore>(
+    secret: &[u8],
+    threshold: u8,
+    participants: &[(&str, u8)],
+    rng: &mut R,
+) -> Result<Vec<Participant>, SecretSharingError> {
+    if participants.iter().any(|&(_, weight)| weight == 0) {
+        return Err(SecretSharingError::TorNisZero);
+    }
+    let total: usize = participants
+        .iter()
+        .map(|&(_, weight)| weight as usize)
+        .sum();
+    if total > u8::MAX as usize {
+        return Err(SecretSharingError::TooManyShares);
+    }
+    let mut shares = construct_shares_with_rng(threshold, total as u8, secret, rng)?.into_iter();
+    Ok(participants
+        .iter()
+        .map(|&(name, weight)| Participant {
+            name: name.to_string(),
+            shares: shares.by_ref().take(weight as usize).collect(),
+        })
+        .collect())
+}
+
+// reconstruct_weighted reconstructs the secret from the shares of
+// `participants`. If their weights add up to less than the threshold, it
+// returns NotEnoughShares, counting weight.
+pub fn reconstruct_weighted(participants: &[Participant]) -> Result<Vec<u8>, SecretSharingError> {
+    let shares: Shares = participants
+        .iter()
+        .flat_map(|p| p.shares.iter().cloned())
+        .collect();
+    if shares.is_empty() {
+        return Err(SecretSharingError::NoSharesProvided);
+    }
+    reconstruct(&shares)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted() {
+        let secret = b"treasury key";
+        let people = split_weighted(
+            secret,
+            4,
+            &[("ceo", 3), ("cfo", 2), ("alice", 1), ("bob", 1)],
+        )
+        .unwrap();
+        assert_eq!(
+            people.iter().map(Participant::weight).collect::<Vec<_>>(),
+            [3, 2, 1, 1]
+        );
+        assert_eq!(people[1].name(), "cfo");
+        assert_eq!(
+            people[1]
+                .shares()
+                .iter()
+                .map(Share::index)
+                .collect::<Vec<_>>(),
+            [4, 5]
+        );
+
+        for quorum in [&[0, 2][..], &[1, 2, 3], &[0, 1]] {
+            let group: Vec<Participant> = quorum.iter().map(|&i| people[i].clone()).collect();
+            assert_eq!(reconstruct_weighted(&group).unwrap(), secret);
+        }
+        assert_eq!(
+            reconstruct_weighted(&[people[0].clone()]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 3, need: 4 })
+        );
+        // the same participant twice adds no weight.
+        assert_eq!(
+            reconstruct_weighted(&[people[1].clone(), people[1].clone()]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 4 })
+        );
+
+        let regrouped = Participant::from_shares("alice", people[2].shares().to_vec());
+        assert_eq!(
+            reconstruct_weighted(&[people[0].clone(), regrouped]).unwrap(),
+            secret
+        );
+    }
+    #[test]
+    fn test_weighted_errors() {
+        assert_eq!(
+            split_weighted(b"s", 2, &[("a", 1), ("b", 0)]).err(),
+            Some(SecretSharingError::TorNisZero)
+        );
+        assert_eq!(
+            split_weighted(b"s", 4, &[("a", 2), ("b", 1)]).err(),
+            Some(SecretSharingError::ThresholdExceedsShares)
+        );
+        assert_eq!(
+            split_weighted(b"s", 2, &[("a", 200), ("b", 56)]).err(),
+            Some(SecretSharingError::TooManyShares)
+        );
+        assert_eq!(
+            reconstruct_weighted(&[]).err(),
+            Some(SecretSharingError::NoSharesProvided)
+        );
+    }
+}
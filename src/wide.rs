@@ -0,0 +1,204 @@
+// Wide shares support up to 65535 participants by sharing over GF(2^16)
+// instead of GF(2^8). The secret is packed into 16-bit elements (big-endian,
+// with odd-length secrets padded by a zero byte), so a wide share is about as
+// large as the secret.
+
+use crate::field;
+use crate::gf65536::GF65536e;
+use crate::{Redacted, SecretSharingError};
+use rand::{CryptoRng, RngCore};
+use std::fmt;
+use zeroize::Zeroize;
+
+// WideShare is a participant's share of a secret shared over GF(2^16).
+#[derive(Clone, Zeroize)]
+#[zeroize(drop)]
+pub struct WideShare {
+    x: GF65536e,
+    y: Vec<GF65536e>,
+    secret_len: usize,
+    threshold: Option<u16>,
+}
+
+impl fmt::Debug for WideShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WideShare")
+            .field("x", &self.x)
+            .field("data", &Redacted(self.secret_len))
+            .finish()
+    }
+}
+
+impl WideShare {
+    // new creates a wide share from a previously persisted index, payload and
+    // secret length, as returned by `index`, `as_bytes` and `secret_len`.
+    pub fn new(index: u16, data: &[u8], secret_len: usize) -> WideShare {
+        WideShare {
+            x: index,
+            y: data
+                .chunks(2)
+                .map(|c| GF65536e::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]))
+                .collect(),
+            secret_len,
+            threshold: None,
+        }
+    }
+
+    // with_threshold creates a share like `new`, additionally recording the
+    // threshold `t` it was created with, as returned by `threshold`, so that
+    // reconstruct_wide can tell too few restored shares from enough.
+    pub fn with_threshold(index: u16, data: &[u8], secret_len: usize, t: u16) -> WideShare {
+        let mut share = WideShare::new(index, data, secret_len);
+        share.threshold = Some(t);
+        share
+    }
+
+    pub fn index(&self) -> u16 {
+        self.x
+    }
+
+    pub fn threshold(&self) -> Option<u16> {
+        self.threshold
+    }
+
+    // secret_len returns the length in bytes of the shared secret.
+    pub fn secret_len(&self) -> usize {
+        self.secret_len
+    }
+
+    // as_bytes returns the share payload, two big-endian bytes per element.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        self.y
+            .iter()
+            .flat_map(|y| y.to_be_bytes().to_vec())
+            .collect()
+    }
+}
+
+// construct_shares_wide is like construct_shares, but supports up to 65535
+// shares.
+pub fn construct_shares_wide(
+    t: u16,
+    n: u16,
+    secret: &[u8],
+) -> Result<Vec<WideShare>, SecretSharingError> {
+    construct_shares_wide_with_rng(t, n, secret, &mut crate::default_rng())
+}
+
+// construct_shares_wide_with_rng is like construct_shares_wide, drawing the
+// polynomial coefficients from `rng`.
+pub fn construct_shares_wide_with_rng<R: CryptoRng + RngCore>(
+    t: u16,
+    n: u16,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Vec<WideShare>, SecretSharingError> {
+    let mut packed: Vec<GF65536e> = secret
+        .chunks(2)
+        .map(|c| GF65536e::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]))
+        .collect();
+    let shares = field::split(t as usize, n as usize, &packed, rng);
+    packed.zeroize();
+
+    Ok(shares?
+        .into_iter()
+        .map(|mut share| WideShare {
+            x: share.x,
+            y: std::mem::take(&mut share.y),
+            secret_len: secret.len(),
+            threshold: Some(t),
+        })
+        .collect())
+}
+
+// reconstruct_wide reconstructs a secret from wide shares.
+pub fn reconstruct_wide(shares: &[WideShare]) -> Result<Vec<u8>, SecretSharingError> {
+    let first = match shares.first() {
+        Some(share) => share,
+        None => return Err(SecretSharingError::NoSharesProvided),
+    };
+    if shares.iter().any(|s| s.y.len() != first.y.len()) {
+        let byte = shares.iter().map(|s| 2 * s.y.len()).min().unwrap_or(0);
+        return Err(SecretSharingError::MissingShareForByte { byte });
+    }
+    if shares.iter().any(|s| s.secret_len != first.secret_len) {
+        return Err(SecretSharingError::ShareMalformed);
+    }
+    if let Some(t) = shares.iter().filter_map(|share| share.threshold).max() {
+        let mut xs: Vec<u16> = shares.iter().map(|share| share.x).collect();
+        xs.sort_unstable();
+        xs.dedup();
+        if xs.len() < t as usize {
+            return Err(SecretSharingError::NotEnoughShares {
+                have: xs.len(),
+                need: t as usize,
+            });
+        }
+    }
+
+    let xs: Vec<GF65536e> = shares.iter().map(|share| share.x).collect();
+    let ys: Vec<&[GF65536e]> = shares.iter().map(|share| &share.y[..]).collect();
+    let mut packed = field::interpolate(&xs, &ys)?;
+    let mut secret: Vec<u8> = packed
+        .iter()
+        .flat_map(|e| e.to_be_bytes().to_vec())
+        .collect();
+    packed.zeroize();
+    secret.truncate(first.secret_len);
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_roundtrip() {
+        let secret = vec![0xde, 0xad, 0xbe, 0xef, 0x01];
+        let shares = construct_shares_wide(3, 1000, &secret).unwrap();
+        assert_eq!(shares.len(), 1000);
+        assert_eq!(shares[999].index(), 1000);
+        assert_eq!(reconstruct_wide(&shares[500..503]).unwrap(), secret);
+        assert_eq!(
+            reconstruct_wide(&shares[..2]).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 2, need: 3 })
+        );
+    }
+    #[test]
+    fn test_wide_persist() {
+        let secret = vec![0xca, 0xfe, 0xba];
+        let shares = construct_shares_wide(2, 300, &secret).unwrap();
+        let restored: Vec<WideShare> = shares[298..]
+            .iter()
+            .map(|s| WideShare::new(s.index(), &s.as_bytes(), s.secret_len()))
+            .collect();
+        assert_eq!(reconstruct_wide(&restored).unwrap(), secret);
+
+        let restored: Vec<WideShare> = shares[299..]
+            .iter()
+            .map(|s| {
+                let t = s.threshold().unwrap();
+                WideShare::with_threshold(s.index(), &s.as_bytes(), s.secret_len(), t)
+            })
+            .collect();
+        assert_eq!(restored[0].threshold(), Some(2));
+        assert_eq!(
+            reconstruct_wide(&restored).err(),
+            Some(SecretSharingError::NotEnoughShares { have: 1, need: 2 })
+        );
+    }
+    #[test]
+    fn test_wide_invalid_index() {
+        let shares = construct_shares_wide(2, 300, b"wide").unwrap();
+        let repeated = [shares[299].clone(), shares[0].clone(), shares[299].clone()];
+        assert_eq!(
+            reconstruct_wide(&repeated).err(),
+            Some(SecretSharingError::DuplicateShare { x: 300 })
+        );
+        let zero = WideShare::new(0, &shares[1].as_bytes(), 4);
+        assert_eq!(
+            reconstruct_wide(&[shares[0].clone(), zero]).err(),
+            Some(SecretSharingError::InvalidShareIndex)
+        );
+    }
+}
@@ -0,0 +1,266 @@
+// X25519 (RFC 7748): Diffie-Hellman on Curve25519 with the Montgomery ladder,
+// in constant time. Field elements mod 2^255 - 19 are held in five 51-bit
+// limbs; ed25519.rs shares them. The tests check the vectors of RFC 7748
+// sections 5.2 and 6.1.
+
+use std::convert::TryInto;
+use zeroize::Zeroize;
+
+pub const KEY_LEN: usize = 32;
+
+// BASE_POINT is the u-coordinate of the Curve25519 generator, 9.
+pub const BASE_POINT: [u8; KEY_LEN] = {
+    let mut b = [0u8; KEY_LEN];
+    b[0] = 9;
+    b
+};
+
+const M51: u64 = (1 << 51) - 1;
+
+#[derive(Clone, Copy, Default, Zeroize)]
+pub(crate) struct Fe(pub(crate) [u64; 5]);
+
+impl Fe {
+    pub(crate) const ZERO: Fe = Fe([0; 5]);
+    pub(crate) const ONE: Fe = Fe([1, 0, 0, 0, 0]);
+    const A24: Fe = Fe([121_665, 0, 0, 0, 0]);
+
+    // from_bytes decodes a little-endian field element, ignoring the top bit
+    // as RFC 7748 requires.
+    pub(crate) fn from_bytes(b: &[u8; KEY_LEN]) -> Fe {
+        let w = |i: usize| u64::from_le_bytes(b[8 * i..8 * i + 8].try_into().unwrap());
+        let (l0, l1, l2, l3) = (w(0), w(1), w(2), w(3));
+        Fe([
+            l0 & M51,
+            ((l0 >> 51) | (l1 << 13)) & M51,
+            ((l1 >> 38) | (l2 << 26)) & M51,
+            ((l2 >> 25) | (l3 << 39)) & M51,
+            (l3 >> 12) & M51,
+        ])
+    }
+
+    // carry propagates the limbs down to 51 bits, folding the top carry back
+    // in times 19.
+    fn carry(mut h: [u128; 5]) -> Fe {
+        for i in 0..4 {
+            h[i + 1] += h[i] >> 51;
+            h[i] &= M51 as u128;
+        }
+        h[0] += (h[4] >> 51) * 19;
+        h[4] &= M51 as u128;
+        h[1] += h[0] >> 51;
+        h[0] &= M51 as u128;
+        Fe([
+            h[0] as u64,
+            h[1] as u64,
+            h[2] as u64,
+            h[3] as u64,
+            h[4] as u64,
+        ])
+    }
+
+    pub(crate) fn to_bytes(self) -> [u8; KEY_LEN] {
+        let mut h = Fe::carry(self.0.map(|l| l as u128)).0;
+        // h is now below 2^255 + a little; subtract p if h >= p, which is when
+        // h + 19 carries out of bit 255.
+        let mut q = (h[0] + 19) >> 51;
+        for l in &h[1..] {
+            q = (l + q) >> 51;
+        }
+        h[0] += 19 * q;
+        for i in 0..4 {
+            h[i + 1] += h[i] >> 51;
+            h[i] &= M51;
+        }
+        h[4] &= M51;
+
+        let mut out = [0u8; KEY_LEN];
+        out[..8].copy_from_slice(&(h[0] | (h[1] << 51)).to_le_bytes());
+        out[8..16].copy_from_slice(&((h[1] >> 13) | (h[2] << 38)).to_le_bytes());
+        out[16..24].copy_from_slice(&((h[2] >> 26) | (h[3] << 25)).to_le_bytes());
+        out[24..].copy_from_slice(&((h[3] >> 39) | (h[4] << 12)).to_le_bytes());
+        h.zeroize();
+        out
+    }
+
+    pub(crate) fn add(self, b: Fe) -> Fe {
+        let (a, b) = (self.0, b.0);
+        Fe([
+            a[0] + b[0],
+            a[1] + b[1],
+            a[2] + b[2],
+            a[3] + b[3],
+            a[4] + b[4],
+        ])
+    }
+
+    // sub returns self - b, adding 2p first so that no limb goes negative.
+    pub(crate) fn sub(self, b: Fe) -> Fe {
+        let (a, b) = (self.0, b.0);
+        Fe::carry([
+            (a[0] + 0xf_ffff_ffff_ffda - b[0]) as u128,
+            (a[1] + 0xf_ffff_ffff_fffe - b[1]) as u128,
+            (a[2] + 0xf_ffff_ffff_fffe - b[2]) as u128,
+            (a[3] + 0xf_ffff_ffff_fffe - b[3]) as u128,
+            (a[4] + 0xf_ffff_ffff_fffe - b[4]) as u128,
+        ])
+    }
+
+    pub(crate) fn mul(self, b: Fe) -> Fe {
+        let (a, b) = (self.0, b.0);
+        let m = |x: u64, y: u64| x as u128 * y as u128;
+        let b19 = b.map(|l| l * 19);
+        Fe::carry([
+            m(a[0], b[0]) + m(a[1], b19[4]) + m(a[2], b19[3]) + m(a[3], b19[2]) + m(a[4], b19[1]),
+            m(a[0], b[1]) + m(a[1], b[0]) + m(a[2], b19[4]) + m(a[3], b19[3]) + m(a[4], b19[2]),
+            m(a[0], b[2]) + m(a[1], b[1]) + m(a[2], b[0]) + m(a[3], b19[4]) + m(a[4], b19[3]),
+            m(a[0], b[3]) + m(a[1], b[2]) + m(a[2], b[1]) + m(a[3], b[0]) + m(a[4], b19[4]),
+            m(a[0], b[4]) + m(a[1], b[3]) + m(a[2], b[2]) + m(a[3], b[1]) + m(a[4], b[0]),
+        ])
+    }
+
+    pub(crate) fn square(self) -> Fe {
+        self.mul(self)
+    }
+
+    // invert returns self^(p-2). The exponent is public, so the
+    // square-and-multiply takes the same time for every input.
+    pub(crate) fn invert(self) -> Fe {
+        // p - 2 = 2^255 - 21: bits 254..5 set, then 01011.
+        let mut r = Fe::ONE;
+        for i in (0..255).rev() {
+            r = r.square();
+            if i >= 5 || (0b01011 >> i) & 1 == 1 {
+                r = r.mul(self);
+            }
+        }
+        r
+    }
+
+    // pow22523 returns self^((p-5)/8), for square roots.
+    pub(crate) fn pow22523(self) -> Fe {
+        // (p - 5) / 8 = 2^252 - 3: bits 251..2 set, then 01.
+        let mut r = Fe::ONE;
+        for i in (0..252).rev() {
+            r = r.square();
+            if i != 1 {
+                r = r.mul(self);
+            }
+        }
+        r
+    }
+
+    pub(crate) fn neg(self) -> Fe {
+        Fe::ZERO.sub(self)
+    }
+
+    // is_negative returns whether the canonical encoding is odd, the sign
+    // convention of RFC 8032.
+    pub(crate) fn is_negative(self) -> bool {
+        self.to_bytes()[0] & 1 == 1
+    }
+
+    pub(crate) fn ct_eq(self, other: Fe) -> bool {
+        use crate::ConstantTimeEq;
+        self.to_bytes().ct_eq(&other.to_bytes())
+    }
+
+    // cswap swaps a and b if `swap` is 1, without branching on it.
+    pub(crate) fn cswap(swap: u64, a: &mut Fe, b: &mut Fe) {
+        let mask = 0u64.wrapping_sub(swap);
+        for (a, b) in a.0.iter_mut().zip(b.0.iter_mut()) {
+            let t = mask & (*a ^ *b);
+            *a ^= t;
+            *b ^= t;
+        }
+    }
+}
+
+// x25519 returns the X25519 function of the scalar `k` and the u-coordinate
+// `u`. The result is all zeros if `u` is a point of small order.
+pub fn x25519(k: &[u8; KEY_LEN], u: &[u8; KEY_LEN]) -> [u8; KEY_LEN] {
+    let mut k = *k;
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+
+    let x1 = Fe::from_bytes(u);
+    let (mut x2, mut z2, mut x3, mut z3) = (Fe::ONE, Fe::default(), x1, Fe::ONE);
+    let mut swap = 0;
+    for t in (0..255).rev() {
+        let bit = ((k[t / 8] >> (t % 8)) & 1) as u64;
+        swap ^= bit;
+        Fe::cswap(swap, &mut x2, &mut x3);
+        Fe::cswap(swap, &mut z2, &mut z3);
+        swap = bit;
+
+        let a = x2.add(z2);
+        let aa = a.square();
+        let b = x2.sub(z2);
+        let bb = b.square();
+        let e = aa.sub(bb);
+        let c = x3.add(z3);
+        let d = x3.sub(z3);
+        let da = d.mul(a);
+        let cb = c.mul(b);
+        x3 = da.add(cb).square();
+        z3 = x1.mul(da.sub(cb).square());
+        x2 = aa.mul(bb);
+        z2 = e.mul(aa.add(Fe::A24.mul(e)));
+    }
+    Fe::cswap(swap, &mut x2, &mut x3);
+    Fe::cswap(swap, &mut z2, &mut z3);
+
+    let out = x2.mul(z2.invert()).to_bytes();
+    k.zeroize();
+    x2.zeroize();
+    z2.zeroize();
+    x3.zeroize();
+    z3.zeroize();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::hex_decode;
+
+    fn hex(s: &str) -> [u8; KEY_LEN] {
+        hex_decode(s).unwrap()[..].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_rfc7748_vectors() {
+        // section 5.2
+        assert_eq!(
+            x25519(
+                &hex("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4"),
+                &hex("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c")
+            ),
+            hex("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552")
+        );
+
+        // section 6.1
+        let alice = hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob = hex("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        let alice_pub = x25519(&alice, &BASE_POINT);
+        let bob_pub = x25519(&bob, &BASE_POINT);
+        assert_eq!(
+            alice_pub,
+            hex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+        );
+        assert_eq!(
+            bob_pub,
+            hex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+        );
+        let shared = x25519(&alice, &bob_pub);
+        assert_eq!(shared, x25519(&bob, &alice_pub));
+        assert_eq!(
+            shared,
+            hex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742")
+        );
+
+        // a point of small order gives the all-zero output.
+        assert_eq!(x25519(&alice, &[0; KEY_LEN]), [0; KEY_LEN]);
+    }
+}